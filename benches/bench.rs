@@ -0,0 +1,314 @@
+// Criterion benchmarks for each stage of running a program, on inputs generated below so
+// that every run measures the same thing. To compare a change against the tree before it,
+// run `cargo bench --bench bench -- --save-baseline before` first and `cargo bench --bench
+// bench -- --baseline before` after; criterion then reports the difference for each.
+//
+// Measured when these were added, as a rough guide to what to expect:
+//
+//   lex                   65 µs     lex_scaling/100000    6.6 ms (15 Melem/s)
+//   lex_flat/100000       14 ms     parse                 210 µs
+//   parse_nested          128 µs    interpret             29 µs
+//   nested                5.9 µs    wide_sum              520 µs
+//   evaluate              314 µs    vm                    18 µs
+//   wide_sum_arena        321 µs    parse_eval/tree       4.1 ms
+//   parse_eval/arena      4.4 ms    evaluate_batch/1      240 ms (420 Kelem/s)
+//   memoize/off           7.7 ms    memoize/on            3.5 ms
+//   compiled_expr/1000000 43 ms (23 Melem/s)
+//
+// evaluate_batch was measured on a machine with one core, where more threads cannot help;
+// with a core for each, the time for n threads should approach a nth of that for one.
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+
+use interpreter::compiler;
+use interpreter::interpreter::{Interpreter, OverflowMode};
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::vm::Vm;
+use interpreter::{evaluate, evaluate_batch_on, CompiledExpr};
+
+// `1 + 2 - 3 * 4 + ...`, 500 numbers and 499 operators.
+fn arithmetic() -> String {
+    let mut source = String::from("1");
+    for i in 1..500 {
+        let op = ["+", "-", "*"][i % 3];
+        source.push_str(&format!(" {} {}", op, i % 9 + 1));
+    }
+    source
+}
+
+// `(((1 + 2) * 3) + 4) ...`, 100 levels deep.
+fn nested() -> String {
+    let mut source = String::from("1");
+    for i in 2..=101 {
+        let op = if i % 2 == 0 { "+" } else { "*" };
+        source = format!("({} {} {})", source, op, i);
+    }
+    source
+}
+
+// `count` tokens of every common kind, including strings with multi-byte characters.
+fn mixed_tokens(count: usize) -> String {
+    let kinds = [
+        "name",
+        "42",
+        "3.14",
+        "+",
+        "\"héllo\"",
+        "*",
+        "(",
+        ")",
+        "café",
+        "-",
+    ];
+    (0..count)
+        .map(|i| kinds[i % kinds.len()])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// `1 + 2 - 3 * ...` with `terms` numbers, for lexing only: the parser limits how long a
+// chain of operators may be.
+fn flat(terms: usize) -> String {
+    let mut source = String::from("1");
+    for i in 1..terms {
+        let op = ["+", "-", "*", "/"][i % 4];
+        source.push_str(&format!(" {} {}", op, i % 97 + 1));
+    }
+    source
+}
+
+// `((((1))))`, `depth` parentheses deep.
+fn parens(depth: usize) -> String {
+    format!("{}1{}", "(".repeat(depth), ")".repeat(depth))
+}
+
+// `(1 + 2 + ... + 100) + (101 + ...) + ...` up to `terms`, grouped so that no chain of
+// operators is longer than the parser allows.
+fn wide_sum(terms: usize) -> String {
+    let numbers: Vec<String> = (1..=terms).map(|i| i.to_string()).collect();
+    numbers
+        .chunks(100)
+        .map(|group| format!("({})", group.join(" + ")))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+// Functions, a lambda, and built-ins over a range, as a script computing a table would.
+fn formula() -> String {
+    "fn f(x) { 3 * x ^ 2 - 2 * x + 1 }; \
+     fn hypot(x, y) { sqrt(x * x + y * y) }; \
+     sum(map(|i| f(i / 10) + hypot(i, 2) * sin(i), 0..100))"
+        .to_string()
+}
+
+fn bench_lex(c: &mut Criterion) {
+    let source = arithmetic();
+    c.bench_function("lex", |b| {
+        b.iter(|| Lexer::new(black_box(source.clone())).count())
+    });
+}
+
+// Each advance is constant time, so the time per token should stay flat as the input
+// grows.
+fn bench_lex_scaling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lex_scaling");
+    for count in [1_000, 10_000, 100_000] {
+        let source = mixed_tokens(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &source, |b, source| {
+            b.iter(|| Lexer::new(black_box(source.clone())).count())
+        });
+    }
+    group.finish();
+}
+
+fn bench_lex_flat(c: &mut Criterion) {
+    let source = flat(100_000);
+    let mut group = c.benchmark_group("lex_flat");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("100000", |b| {
+        b.iter(|| Lexer::new(black_box(source.clone())).count())
+    });
+    group.finish();
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let source = arithmetic();
+    c.bench_function("parse", |b| {
+        b.iter(|| {
+            Parser::new(Lexer::new(black_box(source.clone())))
+                .parse()
+                .unwrap()
+        })
+    });
+}
+
+// Just inside the default nesting limit of 500.
+fn bench_parse_nested(c: &mut Criterion) {
+    let source = parens(450);
+    c.bench_function("parse_nested", |b| {
+        b.iter(|| {
+            Parser::new(Lexer::new(black_box(source.clone())))
+                .parse()
+                .unwrap()
+        })
+    });
+}
+
+fn bench_interpret(c: &mut Criterion) {
+    let ast = Parser::new(Lexer::new(arithmetic())).parse().unwrap();
+    c.bench_function("interpret", |b| {
+        b.iter(|| Interpreter::new().interpret(black_box(&ast)).unwrap())
+    });
+}
+
+// The product outgrows an integer long before the end, so overflow promotes to float.
+fn bench_nested(c: &mut Criterion) {
+    let ast = Parser::new(Lexer::new(nested())).parse().unwrap();
+    c.bench_function("nested", |b| {
+        b.iter(|| {
+            let mut interpreter = Interpreter::new();
+            interpreter.overflow = OverflowMode::Promote;
+            interpreter.interpret(black_box(&ast)).unwrap()
+        })
+    });
+}
+
+fn bench_wide_sum(c: &mut Criterion) {
+    let ast = Parser::new(Lexer::new(wide_sum(10_000))).parse().unwrap();
+    c.bench_function("wide_sum", |b| {
+        b.iter(|| Interpreter::new().interpret(black_box(&ast)).unwrap())
+    });
+}
+
+// Parsing, evaluating and dropping a large expression, with the tree evaluated as parsed
+// and moved into an arena first.
+fn bench_parse_eval(c: &mut Criterion) {
+    let source = wide_sum(10_000);
+    let mut group = c.benchmark_group("parse_eval");
+    group.bench_function("tree", |b| {
+        b.iter(|| {
+            let ast = Parser::new(Lexer::new(black_box(source.clone())))
+                .parse()
+                .unwrap();
+            Interpreter::new().interpret(&ast).unwrap()
+        })
+    });
+    group.bench_function("arena", |b| {
+        b.iter(|| {
+            let ast = Parser::new(Lexer::new(black_box(source.clone())))
+                .parse_ast()
+                .unwrap();
+            Interpreter::new().interpret_ast(&ast).unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn bench_wide_sum_arena(c: &mut Criterion) {
+    let ast = Parser::new(Lexer::new(wide_sum(10_000)))
+        .parse_ast()
+        .unwrap();
+    c.bench_function("wide_sum_arena", |b| {
+        b.iter(|| Interpreter::new().interpret_ast(black_box(&ast)).unwrap())
+    });
+}
+
+// Lexing, parsing and evaluating together, through the library's entry point.
+fn bench_evaluate(c: &mut Criterion) {
+    let source = formula();
+    c.bench_function("evaluate", |b| {
+        b.iter(|| evaluate(black_box(&source)).unwrap())
+    });
+}
+
+// A hundred thousand trivial formulas on one thread and on more, to show how a batch
+// scales with the threads it has.
+fn bench_evaluate_batch(c: &mut Criterion) {
+    let inputs: Vec<String> = (0..100_000).map(|i| format!("{} * 2 + 1", i)).collect();
+    let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+    let mut group = c.benchmark_group("evaluate_batch");
+    group.throughput(Throughput::Elements(inputs.len() as u64));
+    group.sample_size(10);
+    for threads in [1, 2, 4, 8] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| b.iter(|| evaluate_batch_on(black_box(&inputs), threads)),
+        );
+    }
+    group.finish();
+}
+
+// A loop over a formula that uses the same expensive subexpression five times, with and
+// without `with_memoization`.
+fn bench_memoize(c: &mut Criterion) {
+    let term = "sqrt(a * a + b * b + sin(a) * cos(b) + ln(a + b) * exp(a / b))";
+    let source = format!(
+        "let t = 0; let b = 2.5; for a in 1..500 {{ t += {0} + {0} * 2 - {0} / 3 + {0} ^ 2 - {0} }}; t",
+        term
+    );
+    let mut group = c.benchmark_group("memoize");
+    for (name, enabled) in [("off", false), ("on", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || Parser::new(Lexer::new(source.clone())).parse().unwrap(),
+                |ast| {
+                    Interpreter::new()
+                        .with_memoization(enabled)
+                        .run(ast)
+                        .unwrap()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+// A million evaluations of a formula compiled once, as a plotter evaluates it at each x.
+fn bench_compiled_expr(c: &mut Criterion) {
+    let f = CompiledExpr::compile("x ^ 2 + 3 * x - 1", &["x"]).unwrap();
+    let mut group = c.benchmark_group("compiled_expr");
+    group.throughput(Throughput::Elements(1_000_000));
+    group.bench_function("1000000", |b| {
+        b.iter(|| {
+            let mut total = 0.0;
+            for i in 0..1_000_000 {
+                total += f.eval(black_box(&[i as f64 * 0.001])).unwrap();
+            }
+            total
+        })
+    });
+    group.finish();
+}
+
+fn bench_vm(c: &mut Criterion) {
+    let ast = Parser::new(Lexer::new(arithmetic())).parse().unwrap();
+    let code = compiler::compile(&ast).unwrap();
+    c.bench_function("vm", |b| {
+        b.iter(|| Vm::new().run(black_box(&code)).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_lex,
+    bench_lex_scaling,
+    bench_lex_flat,
+    bench_parse,
+    bench_parse_nested,
+    bench_interpret,
+    bench_nested,
+    bench_wide_sum,
+    bench_wide_sum_arena,
+    bench_parse_eval,
+    bench_evaluate,
+    bench_evaluate_batch,
+    bench_memoize,
+    bench_compiled_expr,
+    bench_vm
+);
+criterion_main!(benches);
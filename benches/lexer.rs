@@ -0,0 +1,81 @@
+// Lexes a megabyte of generated script, counting the allocations the lexer makes along
+// with the time it takes. Only names and strings should need one. `cargo bench --bench
+// lexer` lexes it ten times and reports the fastest; under `cargo test` a smaller input
+// is lexed once.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use interpreter::lexer::{Lexer, Token};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// Statements of keywords, names, numbers in every form, operators and strings, repeated
+// to `len` bytes.
+fn script(len: usize) -> String {
+    let statement = "let total_1 = 1_000 + 3.25 * (count - 42) / 7; \
+                     if total_1 >= 10 and not done or x == null { fn(a, b) { a ^ b } } \
+                     else { \"héllo\" }; for i in 0..100 by 5 { true } while false { 1.5 };\n";
+    statement.repeat(len / statement.len() + 1)[..len].to_string()
+}
+
+fn main() {
+    let bench = std::env::args().any(|arg| arg == "--bench");
+    let (len, runs) = if bench { (1 << 20, 10) } else { (1 << 14, 1) };
+    let source = script(len);
+    // Cut at a statement boundary so the input lexes without error.
+    let source = &source[..=source.rfind('\n').unwrap()];
+
+    let mut tokens = 0;
+    let mut dynamic = 0;
+    let mut fastest = Duration::MAX;
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..runs {
+        let lexer = Lexer::new(source.to_string());
+        let start = Instant::now();
+        for token in lexer {
+            match token.unwrap().token {
+                Token::Ident(_) | Token::Str(_) => dynamic += 1,
+                _ => {}
+            }
+            tokens += 1;
+        }
+        fastest = fastest.min(start.elapsed());
+    }
+    // Less the copy of the source made for each run.
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before - runs;
+
+    println!("{} bytes, {} tokens per run", source.len(), tokens / runs);
+    println!("allocations: {} per run", allocations / runs);
+    println!(
+        "             {:.3} per token, {:.3} per name or string",
+        allocations as f64 / tokens as f64,
+        allocations as f64 / dynamic as f64
+    );
+    println!("time:        {:?}", fastest);
+    println!(
+        "             {:.1} MB/s",
+        source.len() as f64 / fastest.as_secs_f64() / 1e6
+    );
+}
@@ -0,0 +1,94 @@
+// Times the tree-walking interpreter against the VM on one long counting loop, and on a
+// formula evaluated once per sample of `x` as a plotter would. `cargo bench` runs
+// millions of iterations; under `cargo test` a short run only checks that both agree.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use interpreter::ast::{ASTNode, NodeKind};
+use interpreter::compiler;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::{Lexer, Span};
+use interpreter::parser::Parser;
+use interpreter::vm::{Compiled, Vm};
+use interpreter::Value;
+
+fn main() {
+    let bench = std::env::args().any(|arg| arg == "--bench");
+    counting_loop(if bench { 10_000_000 } else { 1_000 });
+    sampled_formula(if bench { 1_000_000 } else { 1_000 });
+}
+
+fn counting_loop(iterations: usize) {
+    let source = format!(
+        "let i = 0; let sum = 0; while i < {} {{ sum = sum + i; i = i + 1 }}; sum",
+        iterations
+    );
+    let ast = Parser::new(Lexer::new(source)).parse().unwrap();
+
+    let start = Instant::now();
+    let expected = Interpreter::new().interpret(&ast).unwrap();
+    let tree_walker = start.elapsed();
+
+    let code = compiler::compile(&ast).unwrap();
+    let start = Instant::now();
+    let result = Vm::new().run(&code).unwrap();
+    let vm = start.elapsed();
+
+    assert_eq!(result, expected);
+    report("loop iterations", iterations, tree_walker, vm);
+}
+
+// The formula is parsed and compiled once; only `x` changes between runs.
+fn sampled_formula(samples: usize) {
+    let source = "3 * x ^ 2 - x / 2 + 1".to_string();
+    let ast = Parser::new(Lexer::new(source)).parse().unwrap();
+    let xs: Vec<f64> = (0..samples).map(|i| i as f64 / samples as f64).collect();
+
+    let mut interpreter = Interpreter::new();
+    let start = Instant::now();
+    let expected: Vec<Value> = xs
+        .iter()
+        .map(|&x| {
+            interpreter.interpret(&let_x(x)).unwrap();
+            interpreter.interpret(&ast).unwrap()
+        })
+        .collect();
+    let tree_walker = start.elapsed();
+
+    let compiled = Compiled::new(&ast).unwrap();
+    let mut env = HashMap::new();
+    let start = Instant::now();
+    let results: Vec<f64> = xs
+        .iter()
+        .map(|&x| {
+            env.insert("x".to_string(), Value::Float(x));
+            compiled.eval(&env).unwrap()
+        })
+        .collect();
+    let vm = start.elapsed();
+
+    assert_eq!(
+        results.into_iter().map(Value::Float).collect::<Vec<_>>(),
+        expected
+    );
+    report("formula samples", samples, tree_walker, vm);
+}
+
+// `let x = <x>`, built directly so that parsing is not part of the timing.
+fn let_x(x: f64) -> ASTNode {
+    let value = ASTNode::new(NodeKind::Number(x), Span::default());
+    ASTNode::new(
+        NodeKind::Let("x".to_string(), Box::new(value)),
+        Span::default(),
+    )
+}
+
+fn report(what: &str, count: usize, tree_walker: Duration, vm: Duration) {
+    println!("{} {}", count, what);
+    println!("interpreter: {:?}", tree_walker);
+    println!("vm:          {:?}", vm);
+    println!(
+        "speedup:     {:.2}x",
+        tree_walker.as_secs_f64() / vm.as_secs_f64()
+    );
+}
@@ -0,0 +1,15 @@
+#![no_main]
+
+// Evaluation may fail, but must never panic. Programs that loop forever show up as
+// timeouts rather than crashes.
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let source = String::from_utf8_lossy(data).into_owned();
+    if let Ok(ast) = Parser::new(Lexer::new(source)).parse() {
+        let _ = Interpreter::new().run(ast);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+// Lexing and parsing may reject any input, but must never panic.
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let source = String::from_utf8_lossy(data).into_owned();
+    let _ = Lexer::new(source.clone()).get_tokens();
+    let _ = Parser::new(Lexer::new(source)).parse();
+});
@@ -0,0 +1,510 @@
+use std::ops::Index;
+use std::rc::Rc;
+
+use crate::ast::{ASTNode, FStrNode, NodeKind, Param, Pattern};
+use crate::lexer::{Span, Token};
+
+// A syntax tree stored as one vector of nodes that refer to their children by index,
+// instead of a separate allocation per node. Children come before their parents, so the
+// root is last. Function bodies stay boxed trees: closures outlive the `Ast` made them.
+pub struct Ast {
+    nodes: Vec<Node>,
+    root: NodeId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(u32);
+
+pub struct Node {
+    pub kind: Kind,
+    pub span: Span,
+    // Where the nodes below this one start; they run from there up to it.
+    pub(crate) first: u32,
+    // For a node with only literals, variables and unary and binary operators below it,
+    // how many levels deep it goes. Otherwise 0.
+    pub(crate) height: u32,
+    // Whether this is an operator with another just above or below it, which
+    // `eval_operators` would evaluate without recursing.
+    pub(crate) chained: bool,
+}
+
+// `NodeKind` with `NodeId`s for children; see there for what each variant means.
+pub enum Kind {
+    Number(f64),
+    Integer(i64),
+    Bool(bool),
+    Null,
+    Str(String),
+    FStr(Vec<Part>),
+    VarRef(String),
+    Let(String, NodeId),
+    DestructureLet(Vec<String>, NodeId),
+    Const(String, NodeId),
+    Assign(String, NodeId),
+    Global(Vec<String>),
+    Import(String),
+    Sequence(Vec<NodeId>),
+    Block(Vec<NodeId>),
+    If {
+        cond: NodeId,
+        then_branch: NodeId,
+        else_branch: Option<NodeId>,
+    },
+    While {
+        cond: NodeId,
+        body: NodeId,
+    },
+    For {
+        var: String,
+        iterable: NodeId,
+        body: NodeId,
+    },
+    Match {
+        subject: NodeId,
+        arms: Vec<(Arm, NodeId)>,
+    },
+    Try {
+        body: NodeId,
+        binding: Option<String>,
+        handler: Option<NodeId>,
+        finally: Option<NodeId>,
+    },
+    Throw(NodeId),
+    UnaryOp(Token, NodeId),
+    Membership {
+        item: NodeId,
+        container: NodeId,
+        negated: bool,
+    },
+    BinaryOp(NodeId, Token, NodeId),
+    Logical(NodeId, Token, NodeId),
+    Call(NodeId, Vec<NodeId>),
+    Pipe(NodeId, NodeId),
+    FnDef {
+        name: Option<String>,
+        params: Rc<Vec<Param>>,
+        rest_param: Option<String>,
+        body: Rc<ASTNode>,
+    },
+    StructDef {
+        name: String,
+        fields: Vec<String>,
+    },
+    StructLit {
+        name: String,
+        fields: Vec<(String, NodeId)>,
+    },
+    FieldAccess(NodeId, String),
+    SafeFieldAccess(NodeId, String),
+    Map(Vec<(NodeId, NodeId)>),
+    Array(Vec<NodeId>),
+    Tuple(Vec<NodeId>),
+    Index(NodeId, NodeId),
+    Range {
+        start: NodeId,
+        end: NodeId,
+        step: Option<NodeId>,
+        inclusive: bool,
+    },
+}
+
+// `FStrNode`, with the expressions in the arena.
+pub enum Part {
+    Literal(String),
+    Expr(NodeId),
+}
+
+// `Pattern`, with literals in the arena.
+pub enum Arm {
+    Literal(NodeId),
+    Wildcard,
+    Ident(String),
+}
+
+impl Ast {
+    // Moves every node of `tree` into the arena. Like the tree's own drop, this recurses
+    // once per level, which the parser's nesting limit keeps shallow.
+    pub fn from_tree(tree: ASTNode) -> Ast {
+        let mut ast = Ast {
+            nodes: Vec::new(),
+            root: NodeId(0),
+        };
+        ast.root = ast.add(tree);
+        ast
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // The whole tree as boxed nodes, the same as the one the arena was made from.
+    pub fn to_tree(&self) -> ASTNode {
+        self.subtree(self.root)
+    }
+
+    // The tree below `id`, as boxed nodes.
+    pub fn subtree(&self, id: NodeId) -> ASTNode {
+        let tree = |id: &NodeId| Box::new(self.subtree(*id));
+        let trees = |ids: &[NodeId]| ids.iter().map(|id| self.subtree(*id)).collect();
+        let node = &self[id];
+        let kind = match &node.kind {
+            Kind::Number(n) => NodeKind::Number(*n),
+            Kind::Integer(n) => NodeKind::Integer(*n),
+            Kind::Bool(b) => NodeKind::Bool(*b),
+            Kind::Null => NodeKind::Null,
+            Kind::Str(s) => NodeKind::Str(s.clone()),
+            Kind::FStr(parts) => NodeKind::FStr(
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        Part::Literal(s) => FStrNode::Literal(s.clone()),
+                        Part::Expr(expr) => FStrNode::Expr(self.subtree(*expr)),
+                    })
+                    .collect(),
+            ),
+            Kind::VarRef(name) => NodeKind::VarRef(name.clone()),
+            Kind::Let(name, value) => NodeKind::Let(name.clone(), tree(value)),
+            Kind::DestructureLet(names, value) => {
+                NodeKind::DestructureLet(names.clone(), tree(value))
+            }
+            Kind::Const(name, value) => NodeKind::Const(name.clone(), tree(value)),
+            Kind::Assign(name, value) => NodeKind::Assign(name.clone(), tree(value)),
+            Kind::Global(names) => NodeKind::Global(names.clone()),
+            Kind::Import(path) => NodeKind::Import(path.clone()),
+            Kind::Sequence(statements) => NodeKind::Sequence(trees(statements)),
+            Kind::Block(statements) => NodeKind::Block(trees(statements)),
+            Kind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => NodeKind::If {
+                cond: tree(cond),
+                then_branch: tree(then_branch),
+                else_branch: else_branch.as_ref().map(tree),
+            },
+            Kind::While { cond, body } => NodeKind::While {
+                cond: tree(cond),
+                body: tree(body),
+            },
+            Kind::For {
+                var,
+                iterable,
+                body,
+            } => NodeKind::For {
+                var: var.clone(),
+                iterable: tree(iterable),
+                body: tree(body),
+            },
+            Kind::Match { subject, arms } => NodeKind::Match {
+                subject: tree(subject),
+                arms: arms
+                    .iter()
+                    .map(|(arm, body)| {
+                        let pattern = match arm {
+                            Arm::Literal(literal) => Pattern::PatLiteral(self.subtree(*literal)),
+                            Arm::Wildcard => Pattern::PatWildcard,
+                            Arm::Ident(name) => Pattern::PatIdent(name.clone()),
+                        };
+                        (pattern, self.subtree(*body))
+                    })
+                    .collect(),
+            },
+            Kind::Try {
+                body,
+                binding,
+                handler,
+                finally,
+            } => NodeKind::Try {
+                body: tree(body),
+                binding: binding.clone(),
+                handler: handler.as_ref().map(tree),
+                finally: finally.as_ref().map(tree),
+            },
+            Kind::Throw(value) => NodeKind::Throw(tree(value)),
+            Kind::UnaryOp(op, operand) => NodeKind::UnaryOp(op.clone(), tree(operand)),
+            Kind::Membership {
+                item,
+                container,
+                negated,
+            } => NodeKind::Membership {
+                item: tree(item),
+                container: tree(container),
+                negated: *negated,
+            },
+            Kind::BinaryOp(left, op, right) => {
+                NodeKind::BinaryOp(tree(left), op.clone(), tree(right))
+            }
+            Kind::Logical(left, op, right) => {
+                NodeKind::Logical(tree(left), op.clone(), tree(right))
+            }
+            Kind::Call(callee, args) => NodeKind::Call(tree(callee), trees(args)),
+            Kind::Pipe(value, function) => NodeKind::Pipe(tree(value), tree(function)),
+            Kind::FnDef {
+                name,
+                params,
+                rest_param,
+                body,
+            } => NodeKind::FnDef {
+                name: name.clone(),
+                params: Rc::clone(params),
+                rest_param: rest_param.clone(),
+                body: Rc::clone(body),
+            },
+            Kind::StructDef { name, fields } => NodeKind::StructDef {
+                name: name.clone(),
+                fields: fields.clone(),
+            },
+            Kind::StructLit { name, fields } => NodeKind::StructLit {
+                name: name.clone(),
+                fields: fields
+                    .iter()
+                    .map(|(field, value)| (field.clone(), self.subtree(*value)))
+                    .collect(),
+            },
+            Kind::FieldAccess(target, field) => NodeKind::FieldAccess(tree(target), field.clone()),
+            Kind::SafeFieldAccess(target, field) => {
+                NodeKind::SafeFieldAccess(tree(target), field.clone())
+            }
+            Kind::Map(entries) => NodeKind::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (self.subtree(*key), self.subtree(*value)))
+                    .collect(),
+            ),
+            Kind::Array(items) => NodeKind::Array(trees(items)),
+            Kind::Tuple(items) => NodeKind::Tuple(trees(items)),
+            Kind::Index(target, index) => NodeKind::Index(tree(target), tree(index)),
+            Kind::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => NodeKind::Range {
+                start: tree(start),
+                end: tree(end),
+                step: step.as_ref().map(tree),
+                inclusive: *inclusive,
+            },
+        };
+        ASTNode::new(kind, node.span)
+    }
+
+    fn add(&mut self, node: ASTNode) -> NodeId {
+        let first = self.nodes.len() as u32;
+        let span = node.span;
+        let kind = match node.into_kind() {
+            NodeKind::Number(n) => Kind::Number(n),
+            NodeKind::Integer(n) => Kind::Integer(n),
+            NodeKind::Bool(b) => Kind::Bool(b),
+            NodeKind::Null => Kind::Null,
+            NodeKind::Str(s) => Kind::Str(s),
+            NodeKind::FStr(parts) => Kind::FStr(
+                parts
+                    .into_iter()
+                    .map(|part| match part {
+                        FStrNode::Literal(s) => Part::Literal(s),
+                        FStrNode::Expr(expr) => Part::Expr(self.add(expr)),
+                    })
+                    .collect(),
+            ),
+            NodeKind::VarRef(name) => Kind::VarRef(name),
+            NodeKind::Let(name, value) => Kind::Let(name, self.add(*value)),
+            NodeKind::DestructureLet(names, value) => Kind::DestructureLet(names, self.add(*value)),
+            NodeKind::Const(name, value) => Kind::Const(name, self.add(*value)),
+            NodeKind::Assign(name, value) => Kind::Assign(name, self.add(*value)),
+            NodeKind::Global(names) => Kind::Global(names),
+            NodeKind::Import(path) => Kind::Import(path),
+            NodeKind::Sequence(statements) => Kind::Sequence(self.add_all(statements)),
+            NodeKind::Block(statements) => Kind::Block(self.add_all(statements)),
+            NodeKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => Kind::If {
+                cond: self.add(*cond),
+                then_branch: self.add(*then_branch),
+                else_branch: else_branch.map(|branch| self.add(*branch)),
+            },
+            NodeKind::While { cond, body } => Kind::While {
+                cond: self.add(*cond),
+                body: self.add(*body),
+            },
+            NodeKind::For {
+                var,
+                iterable,
+                body,
+            } => Kind::For {
+                var,
+                iterable: self.add(*iterable),
+                body: self.add(*body),
+            },
+            NodeKind::Match { subject, arms } => Kind::Match {
+                subject: self.add(*subject),
+                arms: arms
+                    .into_iter()
+                    .map(|(pattern, body)| {
+                        let arm = match pattern {
+                            Pattern::PatLiteral(literal) => Arm::Literal(self.add(literal)),
+                            Pattern::PatWildcard => Arm::Wildcard,
+                            Pattern::PatIdent(name) => Arm::Ident(name),
+                        };
+                        (arm, self.add(body))
+                    })
+                    .collect(),
+            },
+            NodeKind::Try {
+                body,
+                binding,
+                handler,
+                finally,
+            } => Kind::Try {
+                body: self.add(*body),
+                binding,
+                handler: handler.map(|handler| self.add(*handler)),
+                finally: finally.map(|finally| self.add(*finally)),
+            },
+            NodeKind::Throw(value) => Kind::Throw(self.add(*value)),
+            NodeKind::UnaryOp(op, operand) => Kind::UnaryOp(op, self.add(*operand)),
+            NodeKind::Membership {
+                item,
+                container,
+                negated,
+            } => Kind::Membership {
+                item: self.add(*item),
+                container: self.add(*container),
+                negated,
+            },
+            NodeKind::BinaryOp(left, op, right) => {
+                let left = self.add(*left);
+                Kind::BinaryOp(left, op, self.add(*right))
+            }
+            NodeKind::Logical(left, op, right) => {
+                let left = self.add(*left);
+                Kind::Logical(left, op, self.add(*right))
+            }
+            NodeKind::Call(callee, args) => {
+                let callee = self.add(*callee);
+                Kind::Call(callee, self.add_all(args))
+            }
+            NodeKind::Pipe(value, function) => {
+                let value = self.add(*value);
+                Kind::Pipe(value, self.add(*function))
+            }
+            NodeKind::FnDef {
+                name,
+                params,
+                rest_param,
+                body,
+            } => Kind::FnDef {
+                name,
+                params,
+                rest_param,
+                body,
+            },
+            NodeKind::StructDef { name, fields } => Kind::StructDef { name, fields },
+            NodeKind::StructLit { name, fields } => Kind::StructLit {
+                name,
+                fields: fields
+                    .into_iter()
+                    .map(|(field, value)| (field, self.add(value)))
+                    .collect(),
+            },
+            NodeKind::FieldAccess(target, field) => Kind::FieldAccess(self.add(*target), field),
+            NodeKind::SafeFieldAccess(target, field) => {
+                Kind::SafeFieldAccess(self.add(*target), field)
+            }
+            NodeKind::Map(entries) => Kind::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (self.add(key), self.add(value)))
+                    .collect(),
+            ),
+            NodeKind::Array(items) => Kind::Array(self.add_all(items)),
+            NodeKind::Tuple(items) => Kind::Tuple(self.add_all(items)),
+            NodeKind::Index(target, index) => {
+                let target = self.add(*target);
+                Kind::Index(target, self.add(*index))
+            }
+            NodeKind::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => Kind::Range {
+                start: self.add(*start),
+                end: self.add(*end),
+                step: step.map(|step| self.add(*step)),
+                inclusive,
+            },
+        };
+        let height = match &kind {
+            Kind::Number(_)
+            | Kind::Integer(_)
+            | Kind::Bool(_)
+            | Kind::Null
+            | Kind::Str(_)
+            | Kind::VarRef(_) => 1,
+            Kind::UnaryOp(_, operand) => match self[*operand].height {
+                0 => 0,
+                height => height + 1,
+            },
+            Kind::BinaryOp(left, _, right) => match (self[*left].height, self[*right].height) {
+                (0, _) | (_, 0) => 0,
+                (left, right) => left.max(right) + 1,
+            },
+            _ => 0,
+        };
+        let operands = match &kind {
+            Kind::UnaryOp(_, operand) => [Some(*operand), None],
+            Kind::BinaryOp(left, _, right) | Kind::Logical(left, _, right) => {
+                [Some(*left), Some(*right)]
+            }
+            _ => [None, None],
+        };
+        let mut chained = false;
+        for operand in operands.into_iter().flatten() {
+            let operand = &mut self.nodes[operand.0 as usize];
+            if matches!(
+                operand.kind,
+                Kind::UnaryOp(..) | Kind::BinaryOp(..) | Kind::Logical(..)
+            ) {
+                operand.chained = true;
+                chained = true;
+            }
+        }
+        let id = NodeId(u32::try_from(self.nodes.len()).expect("fewer than 2^32 nodes"));
+        self.nodes.push(Node {
+            kind,
+            span,
+            first,
+            height,
+            chained,
+        });
+        id
+    }
+
+    // `id` and the nodes below it, children before parents.
+    pub(crate) fn below(&self, id: NodeId) -> &[Node] {
+        &self.nodes[self[id].first as usize..=id.0 as usize]
+    }
+
+    fn add_all(&mut self, nodes: Vec<ASTNode>) -> Vec<NodeId> {
+        nodes.into_iter().map(|node| self.add(node)).collect()
+    }
+}
+
+impl Index<NodeId> for Ast {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+}
@@ -0,0 +1,500 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::lexer::{Span, Token};
+
+// A node of the syntax tree: what it is, and the source text it was parsed from.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ASTNode {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub kind: NodeKind,
+    pub span: Span,
+}
+
+impl ASTNode {
+    pub fn new(kind: NodeKind, span: Span) -> Self {
+        ASTNode { kind, span }
+    }
+
+    // The node's kind, which can't be moved out of it directly as nodes have a `Drop`.
+    pub fn into_kind(mut self) -> NodeKind {
+        std::mem::replace(&mut self.kind, NodeKind::Null)
+    }
+
+    // How many nodes the longest path from this one down to a leaf has, found without
+    // recursing, so that it can be asked of a tree too deep to walk recursively.
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack = vec![(self, 1)];
+        let mut children = Vec::new();
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+            node.kind.children(&mut children);
+            stack.extend(children.drain(..).map(|child| (child, depth + 1)));
+        }
+        height
+    }
+}
+
+// Dropping a node the default way would recurse through its children, so a tree deeper
+// than the stack allows, like a long chain of operators, takes them out and drops them
+// one at a time.
+impl Drop for ASTNode {
+    fn drop(&mut self) {
+        let mut nodes = Vec::new();
+        self.kind.take_children(&mut nodes);
+        while let Some(mut node) = nodes.pop() {
+            node.kind.take_children(&mut nodes);
+        }
+    }
+}
+
+// Spans are left out so that printed trees stay readable.
+impl fmt::Debug for ASTNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+// A parameter name and the default used when its argument is left out. The default is
+// evaluated at each such call, in the function's scope, so it can use earlier parameters.
+pub type Param = (String, Option<ASTNode>);
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum NodeKind {
+    Number(f64),
+    Integer(i64),
+    Bool(bool),
+    Null,
+    Str(String),
+    FStr(Vec<FStrNode>),
+    VarRef(String),
+    Let(String, Box<ASTNode>),
+    DestructureLet(Vec<String>, Box<ASTNode>),
+    Const(String, Box<ASTNode>),
+    // `name = value`; compound forms like `+=` are desugared by the parser.
+    Assign(String, Box<ASTNode>),
+    // `global a, b` inside a function makes assignments to those names reach the session.
+    Global(Vec<String>),
+    // `import "path"` runs a script file and brings its top-level bindings into scope.
+    Import(String),
+    Sequence(Vec<ASTNode>),
+    Block(Vec<ASTNode>),
+    If {
+        cond: Box<ASTNode>,
+        then_branch: Box<ASTNode>,
+        else_branch: Option<Box<ASTNode>>,
+    },
+    While {
+        cond: Box<ASTNode>,
+        body: Box<ASTNode>,
+    },
+    // `for name in iterable { ... }` over the elements of an array or tuple, or the keys
+    // of a map.
+    For {
+        var: String,
+        iterable: Box<ASTNode>,
+        body: Box<ASTNode>,
+    },
+    Match {
+        subject: Box<ASTNode>,
+        arms: Vec<(Pattern, ASTNode)>,
+    },
+    // `try { ... } catch err { ... } finally { ... }`; the binding is optional, and so is
+    // either clause as long as there is one.
+    Try {
+        body: Box<ASTNode>,
+        binding: Option<String>,
+        handler: Option<Box<ASTNode>>,
+        finally: Option<Box<ASTNode>>,
+    },
+    // `throw value`, which raises any value as an error.
+    Throw(Box<ASTNode>),
+    UnaryOp(Token, Box<ASTNode>),
+    Membership {
+        item: Box<ASTNode>,
+        container: Box<ASTNode>,
+        negated: bool,
+    },
+    BinaryOp(Box<ASTNode>, Token, Box<ASTNode>),
+    // `and` / `or` / `??`, kept apart from BinaryOp because the right side may not be
+    // evaluated.
+    Logical(Box<ASTNode>, Token, Box<ASTNode>),
+    Call(Box<ASTNode>, Vec<ASTNode>),
+    // `value |> function`, a call with the left side as its only argument.
+    Pipe(Box<ASTNode>, Box<ASTNode>),
+    // The parameters and body are shared with every closure created from this definition.
+    // Arguments past `params` are collected into an array bound to `rest_param`, if there
+    // is one.
+    FnDef {
+        name: Option<String>,
+        params: Rc<Vec<Param>>,
+        rest_param: Option<String>,
+        body: Rc<ASTNode>,
+    },
+    StructDef {
+        name: String,
+        fields: Vec<String>,
+    },
+    StructLit {
+        name: String,
+        fields: Vec<(String, ASTNode)>,
+    },
+    FieldAccess(Box<ASTNode>, String),
+    // `target?.field`: null when the target is null.
+    SafeFieldAccess(Box<ASTNode>, String),
+    Map(Vec<(ASTNode, ASTNode)>),
+    Array(Vec<ASTNode>),
+    Tuple(Vec<ASTNode>),
+    Index(Box<ASTNode>, Box<ASTNode>),
+    Range {
+        start: Box<ASTNode>,
+        end: Box<ASTNode>,
+        step: Option<Box<ASTNode>>,
+        inclusive: bool,
+    },
+}
+
+impl NodeKind {
+    // The variant name, for diagnostics and tooling.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeKind::Number(_) => "Number",
+            NodeKind::Integer(_) => "Integer",
+            NodeKind::Bool(_) => "Bool",
+            NodeKind::Null => "Null",
+            NodeKind::Str(_) => "Str",
+            NodeKind::FStr(_) => "FStr",
+            NodeKind::VarRef(_) => "VarRef",
+            NodeKind::Let(..) => "Let",
+            NodeKind::DestructureLet(..) => "DestructureLet",
+            NodeKind::Const(..) => "Const",
+            NodeKind::Assign(..) => "Assign",
+            NodeKind::Global(_) => "Global",
+            NodeKind::Import(_) => "Import",
+            NodeKind::Sequence(_) => "Sequence",
+            NodeKind::Block(_) => "Block",
+            NodeKind::If { .. } => "If",
+            NodeKind::While { .. } => "While",
+            NodeKind::For { .. } => "For",
+            NodeKind::Match { .. } => "Match",
+            NodeKind::Try { .. } => "Try",
+            NodeKind::Throw(_) => "Throw",
+            NodeKind::UnaryOp(..) => "UnaryOp",
+            NodeKind::Membership { .. } => "Membership",
+            NodeKind::BinaryOp(..) => "BinaryOp",
+            NodeKind::Logical(..) => "Logical",
+            NodeKind::Call(..) => "Call",
+            NodeKind::Pipe(..) => "Pipe",
+            NodeKind::FnDef { .. } => "FnDef",
+            NodeKind::StructDef { .. } => "StructDef",
+            NodeKind::StructLit { .. } => "StructLit",
+            NodeKind::FieldAccess(..) => "FieldAccess",
+            NodeKind::SafeFieldAccess(..) => "SafeFieldAccess",
+            NodeKind::Map(_) => "Map",
+            NodeKind::Array(_) => "Array",
+            NodeKind::Tuple(_) => "Tuple",
+            NodeKind::Index(..) => "Index",
+            NodeKind::Range { .. } => "Range",
+        }
+    }
+
+    // Adds the nodes directly below this one to `out`.
+    fn children<'a>(&'a self, out: &mut Vec<&'a ASTNode>) {
+        match self {
+            NodeKind::Number(_)
+            | NodeKind::Integer(_)
+            | NodeKind::Bool(_)
+            | NodeKind::Null
+            | NodeKind::Str(_)
+            | NodeKind::VarRef(_)
+            | NodeKind::Global(_)
+            | NodeKind::Import(_)
+            | NodeKind::StructDef { .. } => {}
+            NodeKind::FStr(parts) => out.extend(parts.iter().filter_map(|part| match part {
+                FStrNode::Expr(expr) => Some(expr),
+                FStrNode::Literal(_) => None,
+            })),
+            NodeKind::Let(_, value)
+            | NodeKind::DestructureLet(_, value)
+            | NodeKind::Const(_, value)
+            | NodeKind::Assign(_, value)
+            | NodeKind::Throw(value)
+            | NodeKind::UnaryOp(_, value)
+            | NodeKind::FieldAccess(value, _)
+            | NodeKind::SafeFieldAccess(value, _) => out.push(value),
+            NodeKind::Sequence(nodes)
+            | NodeKind::Block(nodes)
+            | NodeKind::Array(nodes)
+            | NodeKind::Tuple(nodes) => out.extend(nodes),
+            NodeKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                out.extend([&**cond, then_branch]);
+                out.extend(else_branch.as_deref());
+            }
+            NodeKind::While { cond: first, body }
+            | NodeKind::For {
+                iterable: first,
+                body,
+                ..
+            }
+            | NodeKind::Membership {
+                item: first,
+                container: body,
+                ..
+            }
+            | NodeKind::BinaryOp(first, _, body)
+            | NodeKind::Logical(first, _, body)
+            | NodeKind::Pipe(first, body)
+            | NodeKind::Index(first, body) => out.extend([&**first, body]),
+            NodeKind::Match { subject, arms } => {
+                out.push(subject);
+                for (pattern, body) in arms {
+                    if let Pattern::PatLiteral(literal) = pattern {
+                        out.push(literal);
+                    }
+                    out.push(body);
+                }
+            }
+            NodeKind::Try {
+                body,
+                handler,
+                finally,
+                ..
+            } => {
+                out.push(body);
+                out.extend(handler.as_deref());
+                out.extend(finally.as_deref());
+            }
+            NodeKind::Call(callee, args) => {
+                out.push(callee);
+                out.extend(args);
+            }
+            NodeKind::FnDef { params, body, .. } => {
+                out.extend(params.iter().filter_map(|(_, default)| default.as_ref()));
+                out.push(body);
+            }
+            NodeKind::StructLit { fields, .. } => out.extend(fields.iter().map(|(_, value)| value)),
+            NodeKind::Map(entries) => {
+                for (key, value) in entries {
+                    out.extend([key, value]);
+                }
+            }
+            NodeKind::Range {
+                start, end, step, ..
+            } => {
+                out.extend([&**start, end]);
+                out.extend(step.as_deref());
+            }
+        }
+    }
+
+    // Moves the nodes directly below this one to `out`, leaving placeholders. A function's
+    // body is left alone, as closures may share it.
+    fn take_children(&mut self, out: &mut Vec<ASTNode>) {
+        fn take(node: &mut ASTNode) -> ASTNode {
+            std::mem::replace(node, ASTNode::new(NodeKind::Null, Span::default()))
+        }
+        match self {
+            NodeKind::Number(_)
+            | NodeKind::Integer(_)
+            | NodeKind::Bool(_)
+            | NodeKind::Null
+            | NodeKind::Str(_)
+            | NodeKind::VarRef(_)
+            | NodeKind::Global(_)
+            | NodeKind::Import(_)
+            | NodeKind::FnDef { .. }
+            | NodeKind::StructDef { .. } => {}
+            NodeKind::FStr(parts) => out.extend(parts.drain(..).filter_map(|part| match part {
+                FStrNode::Expr(expr) => Some(expr),
+                FStrNode::Literal(_) => None,
+            })),
+            NodeKind::Let(_, value)
+            | NodeKind::DestructureLet(_, value)
+            | NodeKind::Const(_, value)
+            | NodeKind::Assign(_, value)
+            | NodeKind::Throw(value)
+            | NodeKind::UnaryOp(_, value)
+            | NodeKind::FieldAccess(value, _)
+            | NodeKind::SafeFieldAccess(value, _) => out.push(take(value)),
+            NodeKind::Sequence(nodes)
+            | NodeKind::Block(nodes)
+            | NodeKind::Array(nodes)
+            | NodeKind::Tuple(nodes) => out.append(nodes),
+            NodeKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                out.extend([take(cond), take(then_branch)]);
+                out.extend(else_branch.as_deref_mut().map(take));
+            }
+            NodeKind::While { cond: first, body }
+            | NodeKind::For {
+                iterable: first,
+                body,
+                ..
+            }
+            | NodeKind::Membership {
+                item: first,
+                container: body,
+                ..
+            }
+            | NodeKind::BinaryOp(first, _, body)
+            | NodeKind::Logical(first, _, body)
+            | NodeKind::Pipe(first, body)
+            | NodeKind::Index(first, body) => out.extend([take(first), take(body)]),
+            NodeKind::Match { subject, arms } => {
+                out.push(take(subject));
+                for (pattern, body) in arms.drain(..) {
+                    if let Pattern::PatLiteral(literal) = pattern {
+                        out.push(literal);
+                    }
+                    out.push(body);
+                }
+            }
+            NodeKind::Try {
+                body,
+                handler,
+                finally,
+                ..
+            } => {
+                out.push(take(body));
+                out.extend(handler.as_deref_mut().map(take));
+                out.extend(finally.as_deref_mut().map(take));
+            }
+            NodeKind::Call(callee, args) => {
+                out.push(take(callee));
+                out.append(args);
+            }
+            NodeKind::StructLit { fields, .. } => {
+                out.extend(fields.drain(..).map(|(_, value)| value))
+            }
+            NodeKind::Map(entries) => {
+                for (key, value) in entries.drain(..) {
+                    out.extend([key, value]);
+                }
+            }
+            NodeKind::Range {
+                start, end, step, ..
+            } => {
+                out.extend([take(start), take(end)]);
+                out.extend(step.as_deref_mut().map(take));
+            }
+        }
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum Pattern {
+    PatLiteral(ASTNode),
+    PatWildcard,
+    // Matches anything and binds it to the name inside the arm.
+    PatIdent(String),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum FStrNode {
+    Literal(String),
+    Expr(ASTNode),
+}
+
+// Each node is an object whose `type` names the variant, whose `value` holds its fields
+// and whose `span` locates it; purely internal tagging can't represent the tuple variants.
+#[cfg(feature = "serde")]
+pub fn ast_to_json(node: &ASTNode) -> String {
+    serde_json::to_string(node).expect("an AST always serializes")
+}
+
+#[cfg(feature = "serde")]
+pub fn ast_from_json(s: &str) -> Result<ASTNode, serde_json::Error> {
+    serde_json::from_str(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(kind: NodeKind, start: usize, end: usize) -> ASTNode {
+        ASTNode::new(kind, Span { start, end })
+    }
+
+    #[test]
+    fn a_clone_keeps_the_spans_debug_leaves_out() {
+        let sum = node(
+            NodeKind::BinaryOp(
+                Box::new(node(NodeKind::Integer(1), 0, 1)),
+                Token::Plus,
+                Box::new(node(NodeKind::VarRef("x".to_string()), 4, 5)),
+            ),
+            0,
+            5,
+        );
+        let copy = sum.clone();
+        assert_eq!(
+            format!("{:?}", copy),
+            "BinaryOp(Integer(1), Plus, VarRef(\"x\"))"
+        );
+        let NodeKind::BinaryOp(_, _, right) = &copy.kind else {
+            panic!("{:?}", copy);
+        };
+        assert_eq!(
+            (copy.span, right.span),
+            (sum.span, Span { start: 4, end: 5 })
+        );
+    }
+
+    #[test]
+    fn cloned_functions_share_their_body() {
+        let body = Rc::new(node(NodeKind::Null, 0, 4));
+        let def = node(
+            NodeKind::FnDef {
+                name: None,
+                params: Rc::new(Vec::new()),
+                rest_param: None,
+                body: body.clone(),
+            },
+            0,
+            4,
+        );
+        let copy = def.clone();
+        let NodeKind::FnDef { body: copied, .. } = &copy.kind else {
+            panic!("{:?}", copy);
+        };
+        assert!(Rc::ptr_eq(copied, &body));
+    }
+
+    #[test]
+    fn height_counts_the_longest_path_to_a_leaf() {
+        let sum = node(
+            NodeKind::BinaryOp(
+                Box::new(node(NodeKind::Integer(1), 0, 1)),
+                Token::Plus,
+                Box::new(node(
+                    NodeKind::Call(
+                        Box::new(node(NodeKind::VarRef("f".to_string()), 4, 5)),
+                        vec![node(NodeKind::Null, 6, 10)],
+                    ),
+                    4,
+                    11,
+                )),
+            ),
+            0,
+            11,
+        );
+        assert_eq!(sum.height(), 3);
+        assert_eq!(node(NodeKind::Null, 0, 4).height(), 1);
+    }
+}
@@ -0,0 +1,7 @@
+// Built-in functions with more to them than fits in an arm of `call_builtin`.
+pub mod format;
+pub mod hash;
+pub mod number;
+#[cfg(feature = "regex")]
+pub mod regex;
+pub mod string;
@@ -0,0 +1,138 @@
+use crate::error::EvalError;
+use crate::interpreter::Value;
+
+// A subset of Python's format spec mini-language, `[sign][0][width][,][.precision][type]`:
+//
+//   sign       `+` shows a sign on every number, ` ` a space before positive ones and
+//              `-`, the default, only a minus on negative ones
+//   0, width   pads to at least `width` characters, with zeros after the sign if the
+//              0 is there and with spaces before it otherwise; padding zeros are not
+//              separated into thousands
+//   ,          separates thousands in the whole part with commas
+//   precision  how many decimals `f`, `e` and `%` show; two unless given
+//   type       `f` fixed point, `e` scientific, `%` a percentage, `d` an integer as it
+//              is, or nothing for the number as it prints, or in fixed point with a
+//              precision
+//
+// Exponents are written as Rust writes them, so `format(1000, ".1e")` is `1.0e3`. A width
+// or precision over `MAX_WIDTH` is refused, so that no spec builds an enormous string.
+pub fn format(value: &Value, spec: &str) -> Result<String, EvalError> {
+    let invalid = || EvalError::InvalidFormatSpec(spec.to_string());
+    let spec = Spec::parse(spec).ok_or_else(invalid)?;
+    let (negative, digits) = match (value, spec.kind) {
+        (Value::Integer(n), None | Some('d')) if spec.precision.is_none() => {
+            (*n < 0, n.unsigned_abs().to_string())
+        }
+        (Value::Integer(_) | Value::Float(_), Some('d')) => return Err(invalid()),
+        (Value::Integer(n), kind) => digits(*n as f64, kind, spec.precision),
+        (Value::Float(x), kind) => digits(*x, kind, spec.precision),
+        (other, _) => {
+            return Err(EvalError::TypeMismatch(format!(
+                "format() expects a number, found {}",
+                other.type_name()
+            )))
+        }
+    };
+    let digits = if spec.grouping {
+        grouped(&digits)
+    } else {
+        digits
+    };
+    let sign = match (negative, spec.sign) {
+        (true, _) => "-",
+        (false, '+') => "+",
+        (false, ' ') => " ",
+        (false, _) => "",
+    };
+    let padding = spec
+        .width
+        .saturating_sub(sign.chars().count() + digits.chars().count());
+    Ok(if spec.zero {
+        format!("{}{}{}", sign, "0".repeat(padding), digits)
+    } else {
+        format!("{}{}{}", " ".repeat(padding), sign, digits)
+    })
+}
+
+const MAX_WIDTH: usize = 1_000;
+
+struct Spec {
+    sign: char,
+    zero: bool,
+    width: usize,
+    grouping: bool,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+impl Spec {
+    // None for anything outside the subset, including characters left over at the end.
+    fn parse(spec: &str) -> Option<Spec> {
+        let mut chars = spec.chars().peekable();
+        let sign = chars
+            .next_if(|c| matches!(c, '+' | '-' | ' '))
+            .unwrap_or('-');
+        let zero = chars.next_if_eq(&'0').is_some();
+        let width = number(&mut chars).unwrap_or(0);
+        let grouping = chars.next_if_eq(&',').is_some();
+        let precision = match chars.next_if_eq(&'.') {
+            Some(_) => Some(number(&mut chars)?),
+            None => None,
+        };
+        let kind = chars.next_if(|c| matches!(c, 'f' | 'e' | '%' | 'd'));
+        if chars.next().is_some() || width > MAX_WIDTH || precision > Some(MAX_WIDTH) {
+            return None;
+        }
+        Some(Spec {
+            sign,
+            zero,
+            width,
+            grouping,
+            precision,
+            kind,
+        })
+    }
+}
+
+fn number(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits.parse().ok()
+}
+
+// Whether `x` is negative, and how its magnitude is written. NaN has no sign.
+fn digits(x: f64, kind: Option<char>, precision: Option<usize>) -> (bool, String) {
+    let negative = x.is_sign_negative() && !x.is_nan();
+    let x = x.abs();
+    let decimals = precision.unwrap_or(2);
+    let digits = match kind {
+        Some('f') => format!("{:.*}", decimals, x),
+        Some('e') => format!("{:.*e}", decimals, x),
+        Some('%') => format!("{:.*}%", decimals, x * 100.0),
+        _ => match precision {
+            Some(decimals) => format!("{:.*}", decimals, x),
+            None => x.to_string(),
+        },
+    };
+    (negative, digits)
+}
+
+// Puts a comma between each group of three digits in the whole part, leaving anything
+// that is not a run of digits, such as `inf`, as it is.
+fn grouped(digits: &str) -> String {
+    let whole = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    let (whole_part, rest) = digits.split_at(whole);
+    let mut grouped = String::with_capacity(digits.len() + whole_part.len() / 3);
+    for (i, c) in whole_part.chars().enumerate() {
+        if i > 0 && (whole_part.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped.push_str(rest);
+    grouped
+}
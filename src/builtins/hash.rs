@@ -0,0 +1,64 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use crate::error::EvalError;
+use crate::interpreter::Value;
+
+// A hash of `value` keyed by `state`, which each interpreter draws when it is created: the
+// same value hashes the same throughout a session, but differently from one run to the
+// next, so nothing should store it. Values that `==` holds equal hash equal, so an integer
+// and a float with the same value do. Maps, structs and functions, alone or inside a list
+// or tuple, cannot be hashed.
+pub fn hash(state: &RandomState, value: &Value) -> Result<Value, EvalError> {
+    let mut hasher = state.build_hasher();
+    write(&mut hasher, value)?;
+    Ok(Value::Integer(hasher.finish() as i64))
+}
+
+fn write(hasher: &mut impl Hasher, value: &Value) -> Result<(), EvalError> {
+    // Each kind is written after a tag of its own, so that `"1"` and `1` or a list and a
+    // tuple of the same items cannot collide by construction.
+    match value {
+        Value::Null => hasher.write_u8(0),
+        Value::Bool(b) => {
+            hasher.write_u8(1);
+            b.hash(hasher);
+        }
+        Value::Integer(n) => {
+            hasher.write_u8(2);
+            n.hash(hasher);
+        }
+        Value::Float(x) if x.fract() == 0.0 && *x >= i64::MIN as f64 && *x < i64::MAX as f64 => {
+            hasher.write_u8(2);
+            (*x as i64).hash(hasher);
+        }
+        Value::Float(x) => {
+            hasher.write_u8(3);
+            x.to_bits().hash(hasher);
+        }
+        Value::Str(s) => {
+            hasher.write_u8(4);
+            s.hash(hasher);
+        }
+        Value::Array(items) | Value::Tuple(items) => {
+            hasher.write_u8(if matches!(value, Value::Array(_)) {
+                5
+            } else {
+                6
+            });
+            hasher.write_usize(items.len());
+            for item in items {
+                write(hasher, item)?;
+            }
+        }
+        Value::Error { message, kind } => {
+            hasher.write_u8(7);
+            message.hash(hasher);
+            kind.hash(hasher);
+        }
+        Value::Map(_) | Value::Struct { .. } | Value::Function(_) => {
+            return Err(EvalError::Unhashable(value.type_name().to_string()))
+        }
+    }
+    Ok(())
+}
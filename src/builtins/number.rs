@@ -0,0 +1,61 @@
+use crate::error::EvalError;
+use crate::interpreter::Value;
+
+// `n` written in `base`, with lowercase digits unless `upper` is set. A negative number is
+// its magnitude's digits after a minus, so `to_hex(-255)` is `-ff`, as `from_base` reads
+// it back, rather than a two's complement.
+pub fn to_base(name: &str, n: &Value, base: u32, upper: bool) -> Result<Value, EvalError> {
+    let Value::Integer(n) = n else {
+        // Floats are numbers too, so the value says more than the type's name.
+        let found = match n {
+            Value::Float(x) => format!("the float {:?}", x),
+            _ => n.type_name().to_string(),
+        };
+        return Err(EvalError::TypeMismatch(format!(
+            "{}() expects an integer, found {}",
+            name, found
+        )));
+    };
+    let magnitude = n.unsigned_abs();
+    let digits = match (base, upper) {
+        (16, false) => format!("{:x}", magnitude),
+        (16, true) => format!("{:X}", magnitude),
+        (8, _) => format!("{:o}", magnitude),
+        _ => format!("{:b}", magnitude),
+    };
+    Ok(Value::Str(match *n < 0 {
+        true => format!("-{}", digits),
+        false => digits,
+    }))
+}
+
+// The integer `s` writes in `base`, from 2 to 36. Digits past 9 are letters of either
+// case, and a leading `+` or `-` gives the sign; nothing else, whitespace or a `0x`
+// included, is allowed.
+pub fn from_base(s: &Value, base: &Value) -> Result<Value, EvalError> {
+    let (Value::Str(s), Value::Integer(base)) = (s, base) else {
+        return Err(EvalError::TypeMismatch(format!(
+            "from_base() expects a string and an integer, found {} and {}",
+            s.type_name(),
+            base.type_name()
+        )));
+    };
+    if !(2..=36).contains(base) {
+        return Err(EvalError::InvalidArgument(format!(
+            "from_base() base must be from 2 to 36, found {}",
+            base
+        )));
+    }
+    match i64::from_str_radix(s, *base as u32) {
+        Ok(n) => Ok(Value::Integer(n)),
+        Err(err) => match err.kind() {
+            std::num::IntErrorKind::PosOverflow | std::num::IntErrorKind::NegOverflow => {
+                Err(EvalError::Overflow(format!("from_base({:?}, {})", s, base)))
+            }
+            _ => Err(EvalError::ParseError(format!(
+                "{:?} is not a number in base {}",
+                s, base
+            ))),
+        },
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use ::regex::Regex;
+
+use crate::error::EvalError;
+use crate::interpreter::Value;
+
+// Patterns compiled so far, so that a `regex_` call in a loop compiles its pattern once.
+// Once `CAPACITY` patterns are held the cache starts over, which bounds it when patterns
+// are built at run time.
+#[derive(Default)]
+pub struct Regexes {
+    compiled: HashMap<String, Regex>,
+}
+
+const CAPACITY: usize = 64;
+
+impl Regexes {
+    fn get(&mut self, pattern: &str) -> Result<&Regex, EvalError> {
+        if !self.compiled.contains_key(pattern) {
+            let regex =
+                Regex::new(pattern).map_err(|err| EvalError::RegexError(err.to_string()))?;
+            if self.compiled.len() >= CAPACITY {
+                self.compiled.clear();
+            }
+            self.compiled.insert(pattern.to_string(), regex);
+        }
+        Ok(&self.compiled[pattern])
+    }
+
+    // Whether `pattern` matches anywhere in `text`; anchor it with `^` and `$` to match
+    // the whole of it.
+    pub fn is_match(&mut self, pattern: &str, text: &str) -> Result<Value, EvalError> {
+        Ok(Value::Bool(self.get(pattern)?.is_match(text)))
+    }
+
+    pub fn find(&mut self, pattern: &str, text: &str) -> Result<Value, EvalError> {
+        Ok(self
+            .get(pattern)?
+            .find(text)
+            .map_or(Value::Null, |found| Value::Str(found.as_str().to_string())))
+    }
+
+    pub fn find_all(&mut self, pattern: &str, text: &str) -> Result<Value, EvalError> {
+        Ok(Value::Array(
+            self.get(pattern)?
+                .find_iter(text)
+                .map(|found| Value::Str(found.as_str().to_string()))
+                .collect(),
+        ))
+    }
+
+    // Only the first match is replaced. `$1` or `${name}` in `replacement` stands for
+    // what a group matched, and `$$` for a dollar sign.
+    pub fn replace(
+        &mut self,
+        pattern: &str,
+        text: &str,
+        replacement: &str,
+    ) -> Result<Value, EvalError> {
+        Ok(Value::Str(
+            self.get(pattern)?.replace(text, replacement).into_owned(),
+        ))
+    }
+}
@@ -0,0 +1,56 @@
+use crate::error::EvalError;
+use crate::interpreter::Value;
+
+// The byte offset of the first `needle` in `haystack`, or null. The empty string is found
+// at 0.
+pub fn find(haystack: &Value, needle: &Value) -> Result<Value, EvalError> {
+    let [haystack, needle] = strings("find", [haystack, needle])?;
+    Ok(haystack
+        .find(needle)
+        .map_or(Value::Null, |offset| Value::Integer(offset as i64)))
+}
+
+// `s` with its first `from`, or every one when `all` is set, replaced by `to`. Replacing
+// the empty string leaves `s` as it is, rather than putting `to` between every character.
+pub fn replace(
+    name: &str,
+    s: &Value,
+    from: &Value,
+    to: &Value,
+    all: bool,
+) -> Result<Value, EvalError> {
+    let [s, from, to] = strings(name, [s, from, to])?;
+    Ok(Value::Str(match (from.is_empty(), all) {
+        (true, _) => s.to_string(),
+        (false, true) => s.replace(from, to),
+        (false, false) => s.replacen(from, to, 1),
+    }))
+}
+
+pub fn starts_with(s: &Value, prefix: &Value) -> Result<Value, EvalError> {
+    let [s, prefix] = strings("starts_with", [s, prefix])?;
+    Ok(Value::Bool(s.starts_with(prefix)))
+}
+
+pub fn ends_with(s: &Value, suffix: &Value) -> Result<Value, EvalError> {
+    let [s, suffix] = strings("ends_with", [s, suffix])?;
+    Ok(Value::Bool(s.ends_with(suffix)))
+}
+
+fn strings<'a, const N: usize>(
+    name: &str,
+    args: [&'a Value; N],
+) -> Result<[&'a str; N], EvalError> {
+    let mut strings = [""; N];
+    for (string, arg) in strings.iter_mut().zip(args) {
+        let Value::Str(s) = arg else {
+            return Err(EvalError::TypeMismatch(format!(
+                "{}() expects strings, found {}",
+                name,
+                arg.type_name()
+            )));
+        };
+        *string = s;
+    }
+    Ok(strings)
+}
@@ -0,0 +1,297 @@
+use std::rc::Rc;
+
+use crate::ast::{ASTNode, NodeKind};
+use crate::error::CompileError;
+use crate::interpreter::{Function, Value};
+use crate::lexer::Token;
+
+// One step of a compiled program; see `vm::Vm` for what each does to the stack. Jump
+// targets are indexes into the instruction list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    LoadConst(Value),
+    LoadVar(String),
+    // `let`: binds the value on top of the stack in the innermost scope, leaving it there.
+    DefineVar(String),
+    // `=`: updates the nearest existing binding with the value on top of the stack.
+    StoreVar(String),
+    BinaryOp(OpCode),
+    UnaryOp(OpCode),
+    // Fails unless the top of the stack is a bool, naming the `and` or `or` it belongs to.
+    CheckBool(OpCode),
+    Jump(usize),
+    JumpIfFalse(usize),
+    // Calls the value below the top `argc` arguments. A compiled function runs in a new
+    // call frame until its `Return`.
+    Call(usize),
+    EnterScope,
+    ExitScope,
+    Return,
+    Pop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpCode {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Neg,
+    Not,
+    And,
+    Or,
+}
+
+impl OpCode {
+    fn from_token(token: &Token) -> Option<OpCode> {
+        Some(match token {
+            Token::Plus => OpCode::Add,
+            Token::Minus => OpCode::Sub,
+            Token::Mul => OpCode::Mul,
+            Token::Div => OpCode::Div,
+            Token::Mod => OpCode::Mod,
+            Token::Pow => OpCode::Pow,
+            Token::Eq => OpCode::Eq,
+            Token::NotEq => OpCode::NotEq,
+            Token::Lt => OpCode::Lt,
+            Token::Le => OpCode::Le,
+            Token::Gt => OpCode::Gt,
+            Token::Ge => OpCode::Ge,
+            Token::Not => OpCode::Not,
+            Token::And => OpCode::And,
+            Token::Or => OpCode::Or,
+            _ => return None,
+        })
+    }
+
+    // The operator token the interpreter evaluates this opcode as.
+    pub fn token(self) -> Token {
+        match self {
+            OpCode::Add => Token::Plus,
+            OpCode::Sub | OpCode::Neg => Token::Minus,
+            OpCode::Mul => Token::Mul,
+            OpCode::Div => Token::Div,
+            OpCode::Mod => Token::Mod,
+            OpCode::Pow => Token::Pow,
+            OpCode::Eq => Token::Eq,
+            OpCode::NotEq => Token::NotEq,
+            OpCode::Lt => Token::Lt,
+            OpCode::Le => Token::Le,
+            OpCode::Gt => Token::Gt,
+            OpCode::Ge => Token::Ge,
+            OpCode::Not => Token::Not,
+            OpCode::And => Token::And,
+            OpCode::Or => Token::Or,
+        }
+    }
+}
+
+// A function body lowered to its own instructions, which end in `Return`. Its jump
+// targets index into `code`.
+#[derive(Debug)]
+pub struct CompiledFunction {
+    pub name: Option<String>,
+    pub params: Vec<String>,
+    pub code: Vec<Instruction>,
+}
+
+// Lowers a program to instructions that leave its value on the stack and then `Return`.
+// Nodes the VM cannot run yet are reported rather than compiled approximately.
+pub fn compile(node: &ASTNode) -> Result<Vec<Instruction>, CompileError> {
+    let mut compiler = Compiler::default();
+    compiler.compile(node)?;
+    compiler.emit(Instruction::Return);
+    Ok(compiler.code)
+}
+
+#[derive(Default)]
+struct Compiler {
+    code: Vec<Instruction>,
+    // How many blocks or function bodies enclose the node being compiled.
+    depth: usize,
+}
+
+impl Compiler {
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.code.push(instruction);
+        self.code.len() - 1
+    }
+
+    // Points the jump at `at` to the next instruction to be emitted.
+    fn patch(&mut self, at: usize) {
+        let target = self.code.len();
+        match &mut self.code[at] {
+            Instruction::Jump(to) | Instruction::JumpIfFalse(to) => *to = target,
+            other => unreachable!("patching {:?}", other),
+        }
+    }
+
+    fn compile(&mut self, node: &ASTNode) -> Result<(), CompileError> {
+        match &node.kind {
+            NodeKind::Number(n) => {
+                self.emit(Instruction::LoadConst(Value::Float(*n)));
+            }
+            NodeKind::Integer(n) => {
+                self.emit(Instruction::LoadConst(Value::Integer(*n)));
+            }
+            NodeKind::Bool(b) => {
+                self.emit(Instruction::LoadConst(Value::Bool(*b)));
+            }
+            NodeKind::Null => {
+                self.emit(Instruction::LoadConst(Value::Null));
+            }
+            NodeKind::Str(s) => {
+                self.emit(Instruction::LoadConst(Value::Str(s.clone())));
+            }
+            NodeKind::VarRef(name) => {
+                self.emit(Instruction::LoadVar(name.clone()));
+            }
+            NodeKind::Let(name, value) => {
+                self.compile(value)?;
+                self.emit(Instruction::DefineVar(name.clone()));
+            }
+            NodeKind::Assign(name, value) => {
+                self.compile(value)?;
+                self.emit(Instruction::StoreVar(name.clone()));
+            }
+            NodeKind::Sequence(statements) => self.statements(statements)?,
+            // A block that declares nothing needs no scope of its own.
+            NodeKind::Block(statements) => {
+                let declares = statements
+                    .iter()
+                    .any(|statement| matches!(statement.kind, NodeKind::Let(..)));
+                if declares {
+                    self.emit(Instruction::EnterScope);
+                }
+                self.depth += 1;
+                self.statements(statements)?;
+                self.depth -= 1;
+                if declares {
+                    self.emit(Instruction::ExitScope);
+                }
+            }
+            NodeKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.compile(cond)?;
+                let to_else = self.emit(Instruction::JumpIfFalse(0));
+                self.compile(then_branch)?;
+                let to_end = self.emit(Instruction::Jump(0));
+                self.patch(to_else);
+                match else_branch {
+                    Some(else_branch) => self.compile(else_branch)?,
+                    None => {
+                        self.emit(Instruction::LoadConst(Value::Null));
+                    }
+                }
+                self.patch(to_end);
+            }
+            NodeKind::While { cond, body } => {
+                let start = self.code.len();
+                self.compile(cond)?;
+                let to_end = self.emit(Instruction::JumpIfFalse(0));
+                self.compile(body)?;
+                self.emit(Instruction::Pop);
+                self.emit(Instruction::Jump(start));
+                self.patch(to_end);
+                self.emit(Instruction::LoadConst(Value::Null));
+            }
+            NodeKind::UnaryOp(op, operand) => {
+                self.compile(operand)?;
+                let op = match op {
+                    Token::Minus => OpCode::Neg,
+                    op => self.opcode(op, node)?,
+                };
+                self.emit(Instruction::UnaryOp(op));
+            }
+            NodeKind::BinaryOp(left, op, right) => {
+                self.compile(left)?;
+                self.compile(right)?;
+                let op = self.opcode(op, node)?;
+                self.emit(Instruction::BinaryOp(op));
+            }
+            // `a and b` jumps to `false` as soon as an operand is false; `a or b` to `true`
+            // as soon as one is true.
+            NodeKind::Logical(left, op, right) => {
+                let op = self.opcode(op, node)?;
+                self.compile(left)?;
+                self.emit(Instruction::CheckBool(op));
+                if op == OpCode::Or {
+                    self.emit(Instruction::UnaryOp(OpCode::Not));
+                }
+                let short_circuit = self.emit(Instruction::JumpIfFalse(0));
+                self.compile(right)?;
+                self.emit(Instruction::CheckBool(op));
+                let to_end = self.emit(Instruction::Jump(0));
+                self.patch(short_circuit);
+                self.emit(Instruction::LoadConst(Value::Bool(op == OpCode::Or)));
+                self.patch(to_end);
+            }
+            NodeKind::Call(callee, args) => {
+                self.compile(callee)?;
+                for arg in args {
+                    self.compile(arg)?;
+                }
+                self.emit(Instruction::Call(args.len()));
+            }
+            // Compiled functions do not capture variables, so they may only be defined where
+            // everything they can see is a session variable.
+            // The VM binds a fixed number of parameters, so a rest parameter or a default is
+            // unsupported.
+            NodeKind::FnDef {
+                name,
+                params,
+                rest_param: None,
+                body,
+            } if self.depth == 0 && params.iter().all(|(_, default)| default.is_none()) => {
+                let mut compiler = Compiler {
+                    code: Vec::new(),
+                    depth: 1,
+                };
+                compiler.compile(body)?;
+                compiler.emit(Instruction::Return);
+                let function = CompiledFunction {
+                    name: name.clone(),
+                    params: params.iter().map(|(param, _)| param.clone()).collect(),
+                    code: compiler.code,
+                };
+                self.emit(Instruction::LoadConst(Value::Function(Function::Compiled(
+                    Rc::new(function),
+                ))));
+                if let Some(name) = name {
+                    self.emit(Instruction::DefineVar(name.clone()));
+                }
+            }
+            other => return Err(CompileError::Unsupported(other.name())),
+        }
+        Ok(())
+    }
+
+    // Every statement leaves a value; all but the last are popped. An empty list gives null.
+    fn statements(&mut self, statements: &[ASTNode]) -> Result<(), CompileError> {
+        if statements.is_empty() {
+            self.emit(Instruction::LoadConst(Value::Null));
+        }
+        for (i, statement) in statements.iter().enumerate() {
+            if i > 0 {
+                self.emit(Instruction::Pop);
+            }
+            self.compile(statement)?;
+        }
+        Ok(())
+    }
+
+    fn opcode(&self, op: &Token, node: &ASTNode) -> Result<OpCode, CompileError> {
+        OpCode::from_token(op).ok_or(CompileError::Unsupported(node.kind.name()))
+    }
+}
@@ -0,0 +1,55 @@
+use unicode_width::UnicodeWidthChar;
+
+use crate::lexer::Span;
+
+// Columns between tab stops when a tab is echoed as spaces.
+const TAB_WIDTH: usize = 4;
+
+// The line of `source` that `span` starts on, a line of carets under the span, and then
+// `msg`. Carets follow the width the text takes on screen, so a wide character gets two
+// and a tab is expanded in both lines alike. A span that runs past the end of its line
+// is underlined up to the line end; an empty one, or one at the very end of a line, gets
+// a single caret where it points.
+pub fn render_diagnostic(source: &str, span: Span, msg: &str) -> String {
+    let start = char_boundary(source, span.start);
+    let end = char_boundary(source, span.end).max(start);
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |i| start + i);
+
+    let mut echoed = String::new();
+    let mut underline = String::new();
+    let mut column = 0;
+    for (i, c) in source[line_start..line_end].char_indices() {
+        let offset = line_start + i;
+        let width = match c {
+            '\t' => TAB_WIDTH - column % TAB_WIDTH,
+            _ => c.width().unwrap_or(0),
+        };
+        match c {
+            '\t' => echoed.push_str(&" ".repeat(width)),
+            '\r' => {}
+            _ => echoed.push(c),
+        }
+        if offset < start {
+            underline.push_str(&" ".repeat(width));
+        } else if offset < end {
+            underline.push_str(&"^".repeat(width));
+        }
+        column += width;
+    }
+    if !underline.contains('^') {
+        underline.push('^');
+    }
+    format!("  {}\n  {}\n{}", echoed, underline, msg)
+}
+
+// `offset` clamped to `source` and moved back to the start of the character it falls in.
+fn char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
@@ -0,0 +1,63 @@
+use std::fmt::Write;
+
+use crate::compiler::Instruction;
+use crate::interpreter::{Function, Value};
+
+// A listing of `bytecode`, one instruction per line as `offset  MNEMONIC operand`. Jumps
+// show their target as both an index and a `->offset` label. The bodies of compiled
+// functions follow the code that loads them.
+pub fn disassemble(bytecode: &[Instruction]) -> String {
+    let mut out = String::new();
+    let mut functions = Vec::new();
+    for (offset, instruction) in bytecode.iter().enumerate() {
+        let (mnemonic, operand) = match instruction {
+            Instruction::LoadConst(value) => {
+                if let Value::Function(Function::Compiled(function)) = value {
+                    functions.push(function);
+                }
+                ("LOAD_CONST", constant(value))
+            }
+            Instruction::LoadVar(name) => ("LOAD_VAR", format!("{:?}", name)),
+            Instruction::DefineVar(name) => ("DEFINE_VAR", format!("{:?}", name)),
+            Instruction::StoreVar(name) => ("STORE_VAR", format!("{:?}", name)),
+            Instruction::BinaryOp(op) => ("BINARY_OP", format!("{:?}", op)),
+            Instruction::UnaryOp(op) => ("UNARY_OP", format!("{:?}", op)),
+            Instruction::CheckBool(op) => ("CHECK_BOOL", format!("{:?}", op)),
+            Instruction::Jump(target) => ("JUMP", format!("{} (->{:04})", target, target)),
+            Instruction::JumpIfFalse(target) => {
+                ("JUMP_IF_FALSE", format!("{} (->{:04})", target, target))
+            }
+            Instruction::Call(argc) => ("CALL", argc.to_string()),
+            Instruction::EnterScope => ("ENTER_SCOPE", String::new()),
+            Instruction::ExitScope => ("EXIT_SCOPE", String::new()),
+            Instruction::Return => ("RETURN", String::new()),
+            Instruction::Pop => ("POP", String::new()),
+        };
+        let _ = if operand.is_empty() {
+            writeln!(out, "{:04}  {}", offset, mnemonic)
+        } else {
+            writeln!(out, "{:04}  {} {}", offset, mnemonic, operand)
+        };
+    }
+    for function in functions {
+        let params = function.params.join(", ");
+        let _ = writeln!(out);
+        match &function.name {
+            Some(name) => {
+                let _ = writeln!(out, "fn {}({}):", name, params);
+            }
+            None => {
+                let _ = writeln!(out, "fn({}):", params);
+            }
+        }
+        out.push_str(&disassemble(&function.code));
+    }
+    out
+}
+
+fn constant(value: &Value) -> String {
+    match value {
+        Value::Str(s) => format!("{:?}", s),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,467 @@
+use std::fmt;
+
+use crate::interpreter::Value;
+use crate::lexer::{Span, SpannedToken, Token};
+
+// `offset` is the byte offset in the source where the problem starts, on the 1-based
+// `line` and `col`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub offset: usize,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LexErrorKind {
+    UnexpectedChar(char),
+    UnterminatedString,
+    // A number run into a name, as in `1x`.
+    InvalidLiteral(String),
+    InvalidNumber(String),
+    // A number with a decimal point, as in `1.5` or `.5`, while decimal commas are on.
+    DecimalPoint(String),
+    IntegerOutOfRange(String),
+    UnknownEscape(char),
+    UnmatchedBrace,
+    // The input was longer than the lexer's limit in bytes, or had more tokens than its
+    // limit; each holds the limit.
+    InputTooLong(usize),
+    TooManyTokens(usize),
+    // The reader a lexer was reading from failed, for this reason.
+    ReadFailed(String),
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedChar(c) => write!(f, "unexpected character `{}`", c),
+            LexErrorKind::UnterminatedString => write!(f, "unterminated string literal"),
+            LexErrorKind::InvalidLiteral(literal) => write!(f, "invalid literal `{}`", literal),
+            LexErrorKind::InvalidNumber(literal) => {
+                write!(f, "invalid number literal `{}`", literal)
+            }
+            LexErrorKind::DecimalPoint(literal) => {
+                let leading_zero = if literal.starts_with('.') { "0" } else { "" };
+                write!(
+                    f,
+                    "`{}` has a decimal point, but decimal commas are on: write `{}{}`",
+                    literal,
+                    leading_zero,
+                    literal.replace('.', ",")
+                )
+            }
+            LexErrorKind::IntegerOutOfRange(literal) => {
+                write!(f, "integer literal `{}` is out of range", literal)
+            }
+            LexErrorKind::UnknownEscape(c) => write!(f, "unknown escape sequence `\\{}`", c),
+            LexErrorKind::UnmatchedBrace => write!(f, "unmatched `}}` in f-string"),
+            LexErrorKind::InputTooLong(limit) => {
+                write!(f, "input exceeds limit of {} bytes", limit)
+            }
+            LexErrorKind::TooManyTokens(limit) => {
+                write!(f, "input exceeds limit of {} tokens", limit)
+            }
+            LexErrorKind::ReadFailed(reason) => write!(f, "cannot read the input: {}", reason),
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, col {}", self.kind, self.line, self.col)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    UndefinedVariable(String),
+    Overflow(String),
+    TypeMismatch(String),
+    UnknownFunction(String),
+    InvalidArgument(String),
+    KeyNotFound(String),
+    IndexOutOfBounds(i64, usize),
+    DestructureArity(usize, usize),
+    NonExhaustiveMatch(String),
+    NotCallable(String),
+    ArityMismatch(usize, usize),
+    Callback {
+        function: &'static str,
+        index: usize,
+        error: Box<EvalError>,
+    },
+    InvalidRange(String),
+    UnknownStruct(String),
+    NoSuchField(String, String),
+    MissingField(String, String),
+    AssertionFailed(Option<String>),
+    // Raised by `throw value`, with the value's text. A caught error thrown again keeps
+    // its kind; anything else is of kind `Thrown`.
+    Thrown {
+        message: String,
+        kind: String,
+    },
+    // The constant's name, and where it was declared unless it is a built-in one.
+    ConstReassignment(String, Option<Span>),
+    UndefinedGlobal(String),
+    // Holds the offending expression, e.g. `7 % 0`.
+    DivisionByZero(String),
+    // A NaN or infinite float, with the operation that produced it if there was one.
+    NonFinite(Option<String>, f64),
+    // Evaluation nested deeper than the interpreter's limit, which it holds.
+    TooDeep(usize),
+    // `for` was given a value that is not an array, tuple or map.
+    NotIterable(String),
+    // The canonical path of a file that imports itself, directly or not.
+    CircularImport(String),
+    // The path of a file that could not be read or parsed, and why.
+    ImportFailed(String, String),
+    // A file built-in failed; says which call and why.
+    IoError(String),
+    // The path given to a file built-in, which leaves the working directory.
+    PermissionDenied(String),
+    // What was wrong with the text given to `json_parse`.
+    JsonParseError(String),
+    // Which value `json_stringify` was given that JSON cannot represent.
+    JsonSerializeError(String),
+    // A pattern given to one of the `regex_` built-ins does not compile, for this reason.
+    RegexError(String),
+    // The spec given to `format`, which it does not understand.
+    InvalidFormatSpec(String),
+    // Text given to `from_base` that is not a number in its base, and which base.
+    ParseError(String),
+    // The type of a value given to `hash` that cannot be hashed.
+    Unhashable(String),
+    // Bytecode given to the VM that the compiler would not have produced, and what is
+    // wrong with it.
+    InvalidBytecode(String),
+}
+
+impl EvalError {
+    // The variant's name, which a caught error holds as its `kind`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EvalError::UndefinedVariable(..) => "UndefinedVariable",
+            EvalError::Overflow(..) => "Overflow",
+            EvalError::TypeMismatch(..) => "TypeMismatch",
+            EvalError::UnknownFunction(..) => "UnknownFunction",
+            EvalError::InvalidArgument(..) => "InvalidArgument",
+            EvalError::KeyNotFound(..) => "KeyNotFound",
+            EvalError::IndexOutOfBounds(..) => "IndexOutOfBounds",
+            EvalError::DestructureArity(..) => "DestructureArity",
+            EvalError::NonExhaustiveMatch(..) => "NonExhaustiveMatch",
+            EvalError::NotCallable(..) => "NotCallable",
+            EvalError::ArityMismatch(..) => "ArityMismatch",
+            EvalError::Callback { .. } => "Callback",
+            EvalError::InvalidRange(..) => "InvalidRange",
+            EvalError::UnknownStruct(..) => "UnknownStruct",
+            EvalError::NoSuchField(..) => "NoSuchField",
+            EvalError::MissingField(..) => "MissingField",
+            EvalError::AssertionFailed(..) => "AssertionFailed",
+            EvalError::Thrown { .. } => "Thrown",
+            EvalError::ConstReassignment(..) => "ConstReassignment",
+            EvalError::UndefinedGlobal(..) => "UndefinedGlobal",
+            EvalError::DivisionByZero(..) => "DivisionByZero",
+            EvalError::NonFinite(..) => "NonFinite",
+            EvalError::TooDeep(..) => "TooDeep",
+            EvalError::NotIterable(..) => "NotIterable",
+            EvalError::CircularImport(..) => "CircularImport",
+            EvalError::ImportFailed(..) => "ImportFailed",
+            EvalError::IoError(..) => "IoError",
+            EvalError::PermissionDenied(..) => "PermissionDenied",
+            EvalError::JsonParseError(..) => "JsonParseError",
+            EvalError::RegexError(..) => "RegexError",
+            EvalError::JsonSerializeError(..) => "JsonSerializeError",
+            EvalError::InvalidFormatSpec(..) => "InvalidFormatSpec",
+            EvalError::ParseError(..) => "ParseError",
+            EvalError::Unhashable(..) => "Unhashable",
+            EvalError::InvalidBytecode(..) => "InvalidBytecode",
+        }
+    }
+
+    // What `throw value` raises.
+    pub fn thrown(value: Value) -> EvalError {
+        match value {
+            Value::Error { message, kind } => EvalError::Thrown { message, kind },
+            value => EvalError::Thrown {
+                message: value.to_string(),
+                kind: "Thrown".to_string(),
+            },
+        }
+    }
+
+    // What `catch` binds: an error value with this error's message and kind.
+    pub fn to_value(&self) -> Value {
+        let kind = match self {
+            EvalError::Thrown { kind, .. } => kind.clone(),
+            err => err.kind().to_string(),
+        };
+        Value::Error {
+            message: self.to_string(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::UndefinedVariable(name) => write!(f, "undefined variable: {}", name),
+            EvalError::Overflow(expr) => write!(f, "integer overflow in `{}`", expr),
+            EvalError::TypeMismatch(msg) => write!(f, "type mismatch: {}", msg),
+            EvalError::UnknownFunction(name) => write!(f, "unknown function: {}", name),
+            EvalError::InvalidArgument(msg) => write!(f, "{}", msg),
+            EvalError::KeyNotFound(key) => write!(f, "key not found: {:?}", key),
+            EvalError::IndexOutOfBounds(index, len) => {
+                write!(
+                    f,
+                    "index {} out of bounds for list of length {}",
+                    index, len
+                )
+            }
+            EvalError::DestructureArity(expected, found) => write!(
+                f,
+                "cannot destructure a tuple of {} values into {} names",
+                found, expected
+            ),
+            EvalError::NonExhaustiveMatch(value) => write!(f, "no match arm matches {}", value),
+            EvalError::NotCallable(value) => write!(f, "{} is not callable", value),
+            EvalError::ArityMismatch(expected, found) => {
+                write!(f, "expected {} arguments, found {}", expected, found)
+            }
+            EvalError::Callback {
+                function,
+                index,
+                error,
+            } => write!(f, "{} failed at element {}: {}", function, index, error),
+            EvalError::InvalidRange(msg) => write!(f, "invalid range: {}", msg),
+            EvalError::UnknownStruct(name) => write!(f, "unknown struct: {}", name),
+            EvalError::NoSuchField(type_name, field) => {
+                write!(f, "struct {} has no field {}", type_name, field)
+            }
+            EvalError::MissingField(type_name, field) => {
+                write!(f, "missing field {} in {} literal", field, type_name)
+            }
+            EvalError::AssertionFailed(Some(msg)) => write!(f, "assertion failed: {}", msg),
+            EvalError::AssertionFailed(None) => write!(f, "assertion failed"),
+            EvalError::Thrown { message, .. } => write!(f, "{}", message),
+            EvalError::ConstReassignment(name, None) => {
+                write!(f, "cannot assign to constant `{}`", name)
+            }
+            EvalError::ConstReassignment(name, Some(span)) => write!(
+                f,
+                "cannot assign to constant `{}` declared at {}..{}",
+                name, span.start, span.end
+            ),
+            EvalError::UndefinedGlobal(name) => {
+                write!(f, "`global {}`: no session variable named {}", name, name)
+            }
+            EvalError::DivisionByZero(expr) => write!(f, "division by zero in `{}`", expr),
+            EvalError::NonFinite(Some(expr), n) => {
+                write!(f, "result is not a finite number: `{}` gives {}", expr, n)
+            }
+            EvalError::NonFinite(None, n) => write!(f, "result is not a finite number: {}", n),
+            EvalError::TooDeep(limit) => write!(
+                f,
+                "evaluation nested more than {} levels deep, as in runaway recursion",
+                limit
+            ),
+            EvalError::NotIterable(value) => write!(f, "{} is not iterable", value),
+            EvalError::CircularImport(path) => write!(f, "circular import of {}", path),
+            EvalError::ImportFailed(path, reason) => {
+                write!(f, "cannot import {}: {}", path, reason)
+            }
+            EvalError::IoError(msg) => write!(f, "{}", msg),
+            EvalError::PermissionDenied(path) => {
+                write!(
+                    f,
+                    "permission denied: {} is outside the working directory",
+                    path
+                )
+            }
+            EvalError::JsonParseError(msg) => write!(f, "invalid JSON: {}", msg),
+            EvalError::RegexError(msg) => write!(f, "invalid regex: {}", msg),
+            EvalError::JsonSerializeError(msg) => write!(f, "cannot convert to JSON: {}", msg),
+            EvalError::InvalidFormatSpec(spec) => write!(f, "invalid format spec: {:?}", spec),
+            EvalError::ParseError(msg) => write!(f, "cannot parse: {}", msg),
+            EvalError::Unhashable(type_name) => write!(f, "cannot hash a {}", type_name),
+            EvalError::InvalidBytecode(msg) => write!(f, "invalid bytecode: {}", msg),
+        }
+    }
+}
+
+// `expected` describes what the grammar allowed at `span`, where `found` was read.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub expected: String,
+    pub found: Token,
+    pub span: Span,
+    // The innermost bracket still open when the input ended early.
+    pub unclosed: Option<Box<SpannedToken>>,
+    // Set when the input went past one of the parser's limits.
+    pub limit: Option<ParseLimit>,
+    // Set when the tokens were all in place but broke a rule the grammar alone does not
+    // state.
+    pub kind: Option<ParseErrorKind>,
+    // The 1-based line and column of `offset()`.
+    pub line: usize,
+    pub col: usize,
+}
+
+// A limit on the parser's input, with its value.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseLimit {
+    // How deeply expressions may nest.
+    Nesting(usize),
+    // How many nodes the syntax tree may have.
+    Nodes(usize),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseErrorKind {
+    // `fn f(a = 1, b)`, found at `b`.
+    RequiredParamAfterDefault,
+}
+
+// What the parser expected of input with no tokens in it at all.
+pub(crate) const PROGRAM: &str = "a program";
+
+impl ParseError {
+    // A complete program was read but more tokens followed it, as in `1 2` or `(1+2))`.
+    pub fn is_trailing_input(&self) -> bool {
+        self.found != Token::EOF && self.expected == Token::EOF.to_string()
+    }
+
+    // The input was empty or held only whitespace.
+    pub fn is_empty_input(&self) -> bool {
+        self.found == Token::EOF && self.expected == PROGRAM
+    }
+
+    // Where a diagnostic should point: at the bracket left open, if that is the problem.
+    pub fn offset(&self) -> usize {
+        match &self.unclosed {
+            Some(bracket) => bracket.span.start,
+            None => self.span.start,
+        }
+    }
+
+    // The message with `position` (say `line 1, col 5`) naming `offset()`.
+    pub fn describe(&self, position: &str) -> String {
+        if self.is_empty_input() {
+            return "empty input".to_string();
+        }
+        match self.limit {
+            Some(ParseLimit::Nesting(limit)) => {
+                return format!(
+                    "expression too deeply nested at {} (the limit is {} levels)",
+                    position, limit
+                )
+            }
+            Some(ParseLimit::Nodes(limit)) => {
+                return format!(
+                    "input exceeds limit of {} syntax tree nodes at {}",
+                    limit, position
+                )
+            }
+            None => {}
+        }
+        if let Some(ParseErrorKind::RequiredParamAfterDefault) = self.kind {
+            return format!(
+                "required parameter `{}` follows one with a default value at {}",
+                self.found, position
+            );
+        }
+        if let Some(bracket) = &self.unclosed {
+            return format!("unclosed `{}` opened at {}", bracket.token, position);
+        }
+        if self.is_trailing_input() {
+            return match self.found {
+                Token::RParen | Token::RBracket | Token::RBrace => {
+                    format!("unmatched `{}` at {}", self.found, position)
+                }
+                _ => format!(
+                    "unexpected trailing input starting at {}: `{}`",
+                    position, self.found
+                ),
+            };
+        }
+        match &self.found {
+            Token::EOF => format!(
+                "expected {}, found end of input at {}",
+                self.expected, position
+            ),
+            token => format!(
+                "expected {}, found `{}` at {}",
+                self.expected, token, position
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let position = format!("line {}, col {}", self.line, self.col);
+        write!(f, "{}", self.describe(&position))
+    }
+}
+
+// A program `compiler::compile` cannot lower to bytecode.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CompileError {
+    // Holds the kind of node, e.g. `FnDef`.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CompileError::Unsupported(kind) => {
+                write!(f, "the bytecode compiler does not support {} nodes", kind)
+            }
+        }
+    }
+}
+
+// Any failure from running source text through the lexer, parser and interpreter.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    Lex(LexError),
+    Parse(ParseError),
+    Runtime(EvalError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Lex(err) => write!(f, "{}", err),
+            Error::Parse(err) => write!(f, "{}", err),
+            Error::Runtime(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<LexError> for Error {
+    fn from(err: LexError) -> Self {
+        Error::Lex(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<EvalError> for Error {
+    fn from(err: EvalError) -> Self {
+        Error::Runtime(err)
+    }
+}
+
+impl std::error::Error for LexError {}
+impl std::error::Error for ParseError {}
+impl std::error::Error for EvalError {}
+impl std::error::Error for CompileError {}
+impl std::error::Error for Error {}
@@ -0,0 +1,274 @@
+use crate::ast::{ASTNode, NodeKind};
+use crate::error::{Error, EvalError};
+use crate::interpreter::{Interpreter, Value};
+use crate::lexer::{Lexer, Token};
+use crate::optimizer;
+use crate::parser::Parser;
+use crate::pretty::pretty_print;
+use crate::visitor::AstVisitor;
+
+// A numeric expression compiled once for evaluating many times, as a plotter or an
+// integrator does. Its variables are given in order at compile time and become slots,
+// so `eval` is a loop over a flat program with no lookups, allocation or parsing:
+//
+//   let f = CompiledExpr::compile("x ^ 2 + 3 * x - 1", &["x"])?;
+//   f.eval(&[2.0])  // Ok(9.0)
+//
+// Numbers, the variables, arithmetic, unary minus and the math built-ins `abs`, `sqrt`,
+// `floor`, `ceil`, `round`, `sin`, `cos`, `tan`, `ln`, `exp`, `min` and `max` can be
+// used. Every part that does not mention a variable is evaluated by the interpreter when
+// compiling, so `2 ^ 70` fails there as it would when run and `pi` is the built-in
+// constant. What is left gives what `Interpreter::new` would give with the variables bound
+// to the same floats, dividing by zero included.
+#[derive(Debug, Clone)]
+pub struct CompiledExpr {
+    params: Vec<String>,
+    program: Vec<Op>,
+    // The most values `program` holds on its stack at once.
+    stack: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Const(f64),
+    Slot(usize),
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Apply(fn(f64) -> f64),
+    // `min` or `max` of this many values.
+    Min(usize),
+    Max(usize),
+}
+
+// Programs that need no more stack than this evaluate without allocating.
+const INLINE_STACK: usize = 32;
+
+impl CompiledExpr {
+    pub fn compile(source: &str, params: &[&str]) -> Result<CompiledExpr, Error> {
+        let ast = Parser::new(Lexer::new(source.to_string())).parse()?;
+        Ok(Self::from_ast(optimizer::fold_constants(ast), params)?)
+    }
+
+    // A variable not in `params`, or anything else that is not a number, is an error here
+    // rather than when evaluating.
+    pub fn from_ast(ast: ASTNode, params: &[&str]) -> Result<CompiledExpr, EvalError> {
+        let mut compiler = Compiler {
+            params,
+            interpreter: Interpreter::new(),
+            program: Vec::new(),
+            depth: 0,
+            stack: 0,
+            max_stack: 0,
+        };
+        compiler.expression(&ast)?;
+        Ok(CompiledExpr {
+            params: params.iter().map(|param| param.to_string()).collect(),
+            program: compiler.program,
+            stack: compiler.max_stack,
+        })
+    }
+
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    // `args` gives each parameter's value, in the order they were compiled with.
+    pub fn eval(&self, args: &[f64]) -> Result<f64, EvalError> {
+        if args.len() != self.params.len() {
+            return Err(EvalError::ArityMismatch(self.params.len(), args.len()));
+        }
+        if self.stack <= INLINE_STACK {
+            self.run(args, &mut [0.0; INLINE_STACK])
+        } else {
+            self.run(args, &mut vec![0.0; self.stack])
+        }
+    }
+
+    fn run(&self, args: &[f64], stack: &mut [f64]) -> Result<f64, EvalError> {
+        let mut top = 0;
+        for op in &self.program {
+            match *op {
+                Op::Const(value) => {
+                    stack[top] = value;
+                    top += 1;
+                }
+                Op::Slot(slot) => {
+                    stack[top] = args[slot];
+                    top += 1;
+                }
+                Op::Neg => stack[top - 1] = -stack[top - 1],
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Mod | Op::Pow => {
+                    top -= 1;
+                    let (l, r) = (stack[top - 1], stack[top]);
+                    stack[top - 1] = match op {
+                        Op::Add => l + r,
+                        Op::Sub => l - r,
+                        Op::Mul => l * r,
+                        Op::Div | Op::Mod if r == 0.0 => return Err(division_by_zero(op, l, r)),
+                        Op::Div => l / r,
+                        Op::Mod => l % r,
+                        _ => l.powf(r),
+                    };
+                }
+                Op::Apply(f) => stack[top - 1] = f(stack[top - 1]),
+                // As the built-ins do, keeping the first of equal values and any NaN that
+                // comes first.
+                Op::Min(count) | Op::Max(count) => {
+                    top -= count;
+                    let mut best = stack[top];
+                    for &x in &stack[top + 1..top + count] {
+                        let better = match op {
+                            Op::Min(_) => x < best,
+                            _ => x > best,
+                        };
+                        if better {
+                            best = x;
+                        }
+                    }
+                    stack[top] = best;
+                    top += 1;
+                }
+            }
+        }
+        Ok(stack[0])
+    }
+}
+
+struct Compiler<'a> {
+    params: &'a [&'a str],
+    // Evaluates the parts that do not mention a parameter.
+    interpreter: Interpreter,
+    program: Vec<Op>,
+    // How deeply nested the node being compiled is, limited as evaluation is.
+    depth: usize,
+    stack: usize,
+    max_stack: usize,
+}
+
+impl Compiler<'_> {
+    fn expression(&mut self, node: &ASTNode) -> Result<(), EvalError> {
+        if self.depth == self.interpreter.max_depth {
+            return Err(EvalError::TooDeep(self.interpreter.max_depth));
+        }
+        self.depth += 1;
+        let result = self.node(node);
+        self.depth -= 1;
+        result
+    }
+
+    fn node(&mut self, node: &ASTNode) -> Result<(), EvalError> {
+        if !self.mentions_param(node) {
+            let value = match self.interpreter.interpret(node)? {
+                Value::Integer(n) => n as f64,
+                Value::Float(x) => x,
+                _ => return Err(unsupported(node)),
+            };
+            self.emit(Op::Const(value), 1, 0);
+            return Ok(());
+        }
+        match &node.kind {
+            NodeKind::VarRef(name) => {
+                let slot = self.params.iter().position(|param| param == name);
+                self.emit(Op::Slot(slot.expect("mentions a parameter")), 1, 0);
+            }
+            NodeKind::UnaryOp(Token::Minus, operand) => {
+                self.expression(operand)?;
+                self.emit(Op::Neg, 1, 1);
+            }
+            NodeKind::BinaryOp(left, op, right) => {
+                let op = match op {
+                    Token::Plus => Op::Add,
+                    Token::Minus => Op::Sub,
+                    Token::Mul => Op::Mul,
+                    Token::Div => Op::Div,
+                    Token::Mod => Op::Mod,
+                    Token::Pow => Op::Pow,
+                    _ => return Err(unsupported(node)),
+                };
+                self.expression(left)?;
+                self.expression(right)?;
+                self.emit(op, 1, 2);
+            }
+            NodeKind::Call(callee, args) => {
+                // A parameter named like a built-in hides it, as a variable would.
+                let NodeKind::VarRef(name) = &callee.kind else {
+                    return Err(unsupported(node));
+                };
+                if self.params.contains(&name.as_str()) {
+                    return Err(unsupported(node));
+                }
+                let op = match (name.as_str(), args.len()) {
+                    ("min", 1..) => Op::Min(args.len()),
+                    ("max", 1..) => Op::Max(args.len()),
+                    ("abs", 1) => Op::Apply(f64::abs),
+                    ("sqrt", 1) => Op::Apply(f64::sqrt),
+                    ("floor", 1) => Op::Apply(f64::floor),
+                    ("ceil", 1) => Op::Apply(f64::ceil),
+                    ("round", 1) => Op::Apply(f64::round),
+                    ("sin", 1) => Op::Apply(f64::sin),
+                    ("cos", 1) => Op::Apply(f64::cos),
+                    ("tan", 1) => Op::Apply(f64::tan),
+                    ("ln", 1) => Op::Apply(f64::ln),
+                    ("exp", 1) => Op::Apply(f64::exp),
+                    _ => return Err(unsupported(node)),
+                };
+                for arg in args {
+                    self.expression(arg)?;
+                }
+                self.emit(op, 1, args.len());
+            }
+            _ => return Err(unsupported(node)),
+        }
+        Ok(())
+    }
+
+    // Appends `op`, which takes `pops` values off the stack and pushes `pushes`.
+    fn emit(&mut self, op: Op, pushes: usize, pops: usize) {
+        self.program.push(op);
+        self.stack = self.stack - pops + pushes;
+        self.max_stack = self.max_stack.max(self.stack);
+    }
+
+    fn mentions_param(&self, node: &ASTNode) -> bool {
+        let mut mentions = Mentions {
+            params: self.params,
+            found: false,
+        };
+        mentions.visit(node);
+        mentions.found
+    }
+}
+
+struct Mentions<'a> {
+    params: &'a [&'a str],
+    found: bool,
+}
+
+impl AstVisitor for Mentions<'_> {
+    fn visit_var_ref(&mut self, name: &str) {
+        self.found |= self.params.contains(&name);
+    }
+}
+
+fn unsupported(node: &ASTNode) -> EvalError {
+    EvalError::TypeMismatch(format!(
+        "`{}` is not a numeric expression that can be compiled",
+        pretty_print(node)
+    ))
+}
+
+// The error the interpreter gives for `l / r` or `l % r` with `r` zero.
+fn division_by_zero(op: &Op, l: f64, r: f64) -> EvalError {
+    let symbol = if matches!(op, Op::Div) { "/" } else { "%" };
+    EvalError::DivisionByZero(format!(
+        "{} {} {}",
+        Value::Float(l),
+        symbol,
+        Value::Float(r)
+    ))
+}
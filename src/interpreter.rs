@@ -0,0 +1,2530 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, PoisonError};
+#[cfg(feature = "time")]
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+mod arena;
+mod memo;
+
+use self::memo::Memo;
+use crate::arena::Ast;
+use crate::ast::{ASTNode, FStrNode, NodeKind, Param, Pattern};
+#[cfg(feature = "regex")]
+use crate::builtins::regex::Regexes;
+use crate::builtins::{format, hash, number, string};
+use crate::compiler::CompiledFunction;
+use crate::error::{Error, EvalError};
+#[cfg(feature = "json")]
+use crate::json;
+use crate::lexer::{Lexer, LexerOptions, Span, Token, DEFAULT_MAX_INPUT_LEN, DEFAULT_MAX_TOKENS};
+use crate::parser::{Parser, DEFAULT_MAX_NODES};
+#[cfg(feature = "rand")]
+use crate::random::Rng;
+use crate::{lint, optimizer};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Array(Vec<Value>),
+    Tuple(Vec<Value>),
+    Map(HashMap<String, Value>),
+    Struct {
+        type_name: String,
+        fields: HashMap<String, Value>,
+    },
+    Function(Function),
+    // A caught error: its message and the kind of error it was, such as `DivisionByZero`.
+    // Throwing one raises it again unchanged.
+    Error {
+        message: String,
+        kind: String,
+    },
+    Null,
+}
+
+#[derive(Clone)]
+pub enum Function {
+    Builtin(&'static str),
+    User(Rc<Closure>),
+    // Defined by bytecode; only `vm::Vm` can call it.
+    Compiled(Rc<CompiledFunction>),
+}
+
+pub struct Closure {
+    name: Option<String>,
+    params: Rc<Vec<Param>>,
+    rest_param: Option<String>,
+    body: Rc<ASTNode>,
+    env: Environment,
+}
+
+// Closures can capture the environment that holds them, so never print the body or scope.
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Value::Function(self.clone()))
+    }
+}
+
+impl PartialEq for Function {
+    fn eq(&self, other: &Function) -> bool {
+        match (self, other) {
+            (Function::Builtin(a), Function::Builtin(b)) => a == b,
+            (Function::User(a), Function::User(b)) => Rc::ptr_eq(a, b),
+            (Function::Compiled(a), Function::Compiled(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Value {
+    // The name reported by `type()`; the `is_*` builtins are defined in terms of it.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) | Value::Float(_) => "number",
+            Value::Str(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Array(_) => "list",
+            Value::Tuple(_) => "tuple",
+            Value::Map(_) => "map",
+            Value::Struct { .. } => "struct",
+            Value::Function(_) => "function",
+            Value::Error { .. } => "error",
+            Value::Null => "null",
+        }
+    }
+
+    // Strings nested inside collections are shown quoted.
+    fn fmt_nested(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Str(s) => write!(f, "{:?}", s),
+            _ if f.alternate() => write!(f, "{:#}", self),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+// The alternate form, `{:#}`, writes `3,14` decimals and `; ` between items to match the
+// decimal comma input mode.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let separator = if f.alternate() { "; " } else { ", " };
+        match self {
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Float(n) if f.alternate() => write!(f, "{}", n.to_string().replace('.', ",")),
+            Value::Float(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    item.fmt_nested(f)?;
+                }
+                write!(f, "]")
+            }
+            Value::Tuple(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    item.fmt_nested(f)?;
+                }
+                write!(f, ")")
+            }
+            Value::Map(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                write!(f, "{{")?;
+                for (i, key) in keys.into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    write!(f, "{:?}: ", key)?;
+                    map[key].fmt_nested(f)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Struct { type_name, fields } => {
+                let mut names: Vec<&String> = fields.keys().collect();
+                names.sort();
+                write!(f, "{} {{ ", type_name)?;
+                for (i, name) in names.into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{}", separator)?;
+                    }
+                    write!(f, "{}: ", name)?;
+                    fields[name].fmt_nested(f)?;
+                }
+                write!(f, " }}")
+            }
+            Value::Error { message, .. } => write!(f, "{}", message),
+            Value::Null => write!(f, "null"),
+            Value::Function(Function::Builtin(name)) => write!(f, "<builtin {}>", name),
+            Value::Function(Function::User(closure)) => match &closure.name {
+                Some(name) => write!(f, "<fn {}>", name),
+                None => write!(f, "<fn>"),
+            },
+            Value::Function(Function::Compiled(function)) => match &function.name {
+                Some(name) => write!(f, "<fn {}>", name),
+                None => write!(f, "<fn>"),
+            },
+        }
+    }
+}
+
+// Ranges are materialized eagerly, so cap their length to avoid exhausting memory.
+const MAX_RANGE_LEN: usize = 10_000_000;
+
+// A constant in each script's top-level scope holding the script's canonical path.
+const FILE: &str = "__file__";
+
+// How deeply evaluation may nest by default; see `max_depth`.
+const DEFAULT_MAX_DEPTH: usize = 2_000;
+
+// The height of the tallest tree `run` folds and checks before evaluating it.
+const MAX_PASS_HEIGHT: usize = DEFAULT_MAX_DEPTH;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverflowMode {
+    Error,
+    // Redo the overflowing operation in floating point.
+    Promote,
+}
+
+// What `/` and `%` give when the divisor is zero, for integers and floats alike.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DivByZero {
+    Error,
+    // Divide as IEEE floats: `1 / 0` is inf, while `0 / 0` and `1 % 0` are NaN.
+    Ieee,
+    // Give this value instead.
+    Value(f64),
+}
+
+// A handle to a scope; cloning it shares the scope, which is how closures capture.
+#[derive(Clone, Default)]
+pub struct Environment {
+    scope: Rc<RefCell<Scope>>,
+}
+
+// Struct definitions live beside variables and follow the same scoping rules.
+#[derive(Default)]
+struct Scope {
+    vars: HashMap<String, Binding>,
+    structs: HashMap<String, Rc<Vec<String>>>,
+    parent: Option<Environment>,
+    // Set on the scope of a function call. Assigning to a name not bound inside the
+    // call creates a local here instead of reaching the caller's variables.
+    function: bool,
+    // Names opted into the session scope with `global`, mapped to that scope.
+    globals: HashMap<String, Environment>,
+}
+
+struct Binding {
+    value: Value,
+    mutable: bool,
+    // Where a constant was declared; None for the built-in ones and for variables.
+    declared: Option<Span>,
+}
+
+impl Environment {
+    fn child(&self) -> Environment {
+        Environment {
+            scope: Rc::new(RefCell::new(Scope {
+                parent: Some(self.clone()),
+                ..Scope::default()
+            })),
+        }
+    }
+
+    fn function_child(&self) -> Environment {
+        let env = self.child();
+        env.scope.borrow_mut().function = true;
+        env
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        let scope = self.scope.borrow();
+        if let Some(binding) = scope.vars.get(name) {
+            return Some(binding.value.clone());
+        }
+        match scope.globals.get(name) {
+            Some(globals) => globals.get(name),
+            None => scope.parent.as_ref().and_then(|parent| parent.get(name)),
+        }
+    }
+
+    fn is_const(&self, name: &str) -> bool {
+        let scope = self.scope.borrow();
+        match scope.vars.get(name) {
+            Some(binding) => !binding.mutable,
+            None => scope
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_const(name)),
+        }
+    }
+
+    // Where the constant `name` refers to was declared, if it refers to one declared
+    // by a program.
+    fn declared(&self, name: &str) -> Option<Span> {
+        let scope = self.scope.borrow();
+        match scope.vars.get(name) {
+            Some(binding) => binding.declared,
+            None => scope
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.declared(name)),
+        }
+    }
+
+    fn define(&self, name: &str, value: Value) {
+        self.bind(name, value, true, None);
+    }
+
+    fn define_const(&self, name: &str, value: Value, declared: Option<Span>) {
+        self.bind(name, value, false, declared);
+    }
+
+    fn bind(&self, name: &str, value: Value, mutable: bool, declared: Option<Span>) {
+        self.scope.borrow_mut().vars.insert(
+            name.to_string(),
+            Binding {
+                value,
+                mutable,
+                declared,
+            },
+        );
+    }
+
+    // A `let` or `const` may shadow a constant from an enclosing scope, but not one
+    // declared in this scope.
+    fn check_redeclare(&self, name: &str) -> Result<(), EvalError> {
+        match self.scope.borrow().vars.get(name) {
+            Some(binding) if !binding.mutable => Err(EvalError::ConstReassignment(
+                name.to_string(),
+                binding.declared,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Updates the nearest binding of `name`. Inside a function, a name that would
+    // otherwise resolve to the session scope (`globals`) becomes a new local of the call
+    // unless it was declared `global`; variables of enclosing functions are shared.
+    fn assign(&self, name: &str, value: Value, globals: &Environment) -> Result<(), EvalError> {
+        let mut env = self.clone();
+        let mut frame = None;
+        loop {
+            if Rc::ptr_eq(&env.scope, &globals.scope) && frame.is_some() {
+                break;
+            }
+            let next = {
+                let mut scope = env.scope.borrow_mut();
+                match scope.vars.get_mut(name) {
+                    Some(binding) if !binding.mutable => {
+                        return Err(EvalError::ConstReassignment(
+                            name.to_string(),
+                            binding.declared,
+                        ))
+                    }
+                    Some(binding) => {
+                        binding.value = value;
+                        return Ok(());
+                    }
+                    None => {}
+                }
+                if scope.globals.contains_key(name) {
+                    drop(scope);
+                    return globals.assign(name, value, globals);
+                }
+                if scope.function && frame.is_none() {
+                    frame = Some(env.clone());
+                }
+                scope.parent.clone()
+            };
+            match next {
+                Some(parent) => env = parent,
+                None => break,
+            }
+        }
+        match frame {
+            Some(_) if globals.is_const(name) => Err(EvalError::ConstReassignment(
+                name.to_string(),
+                globals.declared(name),
+            )),
+            Some(frame) => {
+                frame.define(name, value);
+                Ok(())
+            }
+            None => Err(EvalError::UndefinedVariable(name.to_string())),
+        }
+    }
+
+    // Makes `name` refer to the session variable for the rest of the enclosing call.
+    // Outside any function it already does.
+    fn declare_global(&self, name: &str, globals: &Environment) -> Result<(), EvalError> {
+        if globals.get(name).is_none() {
+            return Err(EvalError::UndefinedGlobal(name.to_string()));
+        }
+        let mut env = self.clone();
+        loop {
+            let parent = {
+                let mut scope = env.scope.borrow_mut();
+                if scope.function {
+                    scope.globals.insert(name.to_string(), globals.clone());
+                    return Ok(());
+                }
+                scope.parent.clone()
+            };
+            match parent {
+                Some(parent) => env = parent,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    // Copies the bindings and structs of this scope into `target`, as `import` does. A
+    // file imported again only fills in names `target` lacks, so the second import
+    // changes nothing the first one bound.
+    fn merge_into(&self, target: &Environment, again: bool) -> Result<(), EvalError> {
+        let scope = self.scope.borrow();
+        for (name, binding) in &scope.vars {
+            let bound = target
+                .scope
+                .borrow()
+                .vars
+                .get(name)
+                .is_some_and(|existing| again || existing.value == binding.value);
+            if name == FILE || bound {
+                continue;
+            }
+            target.check_redeclare(name)?;
+            target.bind(
+                name,
+                binding.value.clone(),
+                binding.mutable,
+                binding.declared,
+            );
+        }
+        for (name, fields) in &scope.structs {
+            target
+                .scope
+                .borrow_mut()
+                .structs
+                .insert(name.clone(), Rc::clone(fields));
+        }
+        Ok(())
+    }
+
+    fn get_struct(&self, name: &str) -> Option<Rc<Vec<String>>> {
+        let scope = self.scope.borrow();
+        match scope.structs.get(name) {
+            Some(fields) => Some(Rc::clone(fields)),
+            None => scope
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.get_struct(name)),
+        }
+    }
+
+    fn define_struct(&self, name: &str, fields: Vec<String>) {
+        self.scope
+            .borrow_mut()
+            .structs
+            .insert(name.to_string(), Rc::new(fields));
+    }
+}
+
+pub struct Interpreter {
+    env: Environment,
+    // The session scope that `global` declarations refer to.
+    globals: Environment,
+    pub overflow: OverflowMode,
+    pub div_by_zero: DivByZero,
+    // Makes any operator that gives NaN or infinity fail, where otherwise only a final
+    // result that is one gets a warning; see `run`.
+    pub strict_float: bool,
+    // Lets `SQRT` or `Pi` name a built-in function or constant. User-defined names stay
+    // case-sensitive.
+    pub case_insensitive: bool,
+    // Source read by `parse()` uses `3,14` decimals; see `LexerOptions`.
+    pub decimal_comma: bool,
+    // Source read by `parser` longer than this many bytes, or with more tokens or syntax
+    // tree nodes than these, is rejected before any of it runs. `usize::MAX` turns a
+    // limit off.
+    pub max_input_len: usize,
+    pub max_tokens: usize,
+    pub max_nodes: usize,
+    // Whether `run` prints lint warnings to stderr; see `lint`.
+    pub warnings: bool,
+    // What `args()` returns: the arguments given to a script after `--`.
+    pub args: Vec<String>,
+    // Receives a line before and after every node evaluated; see `with_trace`.
+    trace: Option<Box<dyn Write>>,
+    // Where `input()` reads lines from, and where its prompt and `print` output go; see
+    // `with_io`.
+    input_reader: Box<dyn BufRead>,
+    output_writer: Box<dyn Write>,
+    // What `rand()` and `rand_int` draw from, until `seed(n)` replaces it.
+    #[cfg(feature = "rand")]
+    rng: Rng,
+    // When the interpreter was created, which `elapsed_ms()` counts from.
+    #[cfg(feature = "time")]
+    start: Instant,
+    // The patterns the `regex_` built-ins have compiled.
+    #[cfg(feature = "regex")]
+    regexes: Regexes,
+    // What `hash` keys its hashes with; see `builtins::hash`.
+    hash_state: RandomState,
+    // How many nodes are being evaluated, each inside the last. Operators that
+    // `eval_operators` evaluates without recursing aren't counted.
+    depth: usize,
+    // How many of those operators are being evaluated, which trace lines are indented by
+    // as well.
+    trace_offset: usize,
+    // Evaluation recurses once per nested node and function call, so a program nested
+    // deeper than this fails with `TooDeep` rather than overflowing the stack.
+    pub max_depth: usize,
+    // Whether `run` folds constant expressions before evaluating; see `optimizer`.
+    pub optimize: bool,
+    // Whether `run` moves the tree into an `arena::Ast` and evaluates that.
+    pub arena: bool,
+    // Whether `run` remembers the values of pure subtrees it repeats; see `memo`.
+    pub memoize: bool,
+    memo: Memo,
+    memo_hits: usize,
+    // The innermost node the last uncaught error came from; see `error_span`.
+    error_span: Option<Span>,
+    // The script being run, which `import` paths are relative to; see `set_script`.
+    file: Option<PathBuf>,
+    // The top-level scope of every file imported so far, by canonical path.
+    import_cache: HashMap<PathBuf, Environment>,
+    // The files being run, outermost first: the script, then each import in progress.
+    importing: Vec<PathBuf>,
+}
+
+// The interpreter's settings; its scopes, streams and caches are left out.
+impl fmt::Debug for Interpreter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Interpreter")
+            .field("overflow", &self.overflow)
+            .field("div_by_zero", &self.div_by_zero)
+            .field("strict_float", &self.strict_float)
+            .field("max_depth", &self.max_depth)
+            .field("optimize", &self.optimize)
+            .field("arena", &self.arena)
+            .field("memoize", &self.memoize)
+            .field("traced", &self.trace.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+// The writer `make_testable` gives an interpreter, which its caller keeps a handle on.
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut output = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        output.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Interpreter {
+    /// An interpreter with nothing bound but the built-ins, evaluating with the default
+    /// options and limits.
+    pub fn new() -> Self {
+        // Built-in constants live in a scope of their own, so a top-level `let pi = 3`
+        // shadows them in the usual way.
+        let prelude = Environment::default();
+        for (name, value) in CONSTANTS {
+            prelude.define_const(name, Value::Float(*value), None);
+        }
+        let globals = prelude.child();
+        Interpreter {
+            env: globals.clone(),
+            globals,
+            overflow: OverflowMode::Error,
+            div_by_zero: DivByZero::Error,
+            strict_float: false,
+            case_insensitive: false,
+            decimal_comma: false,
+            max_input_len: DEFAULT_MAX_INPUT_LEN,
+            max_tokens: DEFAULT_MAX_TOKENS,
+            max_nodes: DEFAULT_MAX_NODES,
+            warnings: false,
+            args: Vec::new(),
+            trace: None,
+            // A one-byte buffer takes no more from stdin than each line, which leaves the
+            // rest for the prompt's own reads.
+            input_reader: Box::new(BufReader::with_capacity(1, io::stdin())),
+            output_writer: Box::new(io::stdout()),
+            #[cfg(feature = "rand")]
+            rng: Rng::new(),
+            #[cfg(feature = "time")]
+            start: Instant::now(),
+            #[cfg(feature = "regex")]
+            regexes: Regexes::default(),
+            hash_state: RandomState::new(),
+            depth: 0,
+            trace_offset: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            optimize: true,
+            arena: false,
+            memoize: false,
+            memo: Memo::default(),
+            memo_hits: 0,
+            error_span: None,
+            file: None,
+            import_cache: HashMap::new(),
+            importing: Vec::new(),
+        }
+    }
+
+    // Records that the source about to be run was read from `path`: imports are looked
+    // up beside it, and `__file__` holds its canonical path. Without a script, imports
+    // are relative to the working directory.
+    pub fn set_script(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.globals
+            .define_const(FILE, Value::Str(path.display().to_string()), None);
+        self.importing = vec![path.clone()];
+        self.file = Some(path);
+    }
+
+    /// An interpreter that writes `[depth=N] Evaluating: <node>` before each node and
+    /// `[depth=N] => <value>` after it, indented by depth.
+    pub fn with_trace(writer: Box<dyn Write>) -> Self {
+        Interpreter {
+            trace: Some(writer),
+            ..Interpreter::new()
+        }
+    }
+
+    /// Reads `input()` lines from `reader` and writes its prompts and `print` output to
+    /// `writer` in place of stdin and stdout.
+    pub fn with_io(mut self, reader: Box<dyn BufRead>, writer: Box<dyn Write>) -> Self {
+        self.input_reader = reader;
+        self.output_writer = writer;
+        self
+    }
+
+    /// An interpreter that reads `input()` lines from `input` and writes what it would have
+    /// written to stdout into the returned buffer, for tests.
+    pub fn make_testable(input: &str) -> (Interpreter, Arc<Mutex<Vec<u8>>>) {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let interpreter = Interpreter::new().with_io(
+            Box::new(io::Cursor::new(input.to_string())),
+            Box::new(SharedBuffer(Arc::clone(&output))),
+        );
+        (interpreter, output)
+    }
+
+    /// Turns the constant-folding pass in `run` on or off. It is on by default.
+    pub fn with_optimization(mut self, enabled: bool) -> Self {
+        self.optimize = enabled;
+        self
+    }
+
+    // Evaluates programs given to `run` through `interpret_ast`. It is off by default.
+    pub fn with_arena(mut self, enabled: bool) -> Self {
+        self.arena = enabled;
+        self
+    }
+
+    // Computes each pure subtree that a program repeats once for as long as the variables
+    // in it keep their values, within one `run`. It is off by default, and does nothing
+    // with `with_arena` or a trace.
+    pub fn with_memoization(mut self, enabled: bool) -> Self {
+        self.memoize = enabled;
+        self
+    }
+
+    // How source evaluated by this interpreter should be lexed.
+    pub fn lexer_options(&self) -> LexerOptions {
+        LexerOptions {
+            case_insensitive: self.case_insensitive,
+            decimal_comma: self.decimal_comma,
+            max_input_len: self.max_input_len,
+            max_tokens: self.max_tokens,
+        }
+    }
+
+    // A parser for source to be run by this interpreter, with its options and limits.
+    pub fn parser(&self, source: String) -> Parser {
+        Parser::new(Lexer::with_options(source, self.lexer_options()))
+            .with_max_nodes(self.max_nodes)
+            .with_lexer_options(self.lexer_options())
+    }
+
+    // The session's variables, sorted by name.
+    pub fn variables(&self) -> Vec<(String, Value)> {
+        let mut vars: Vec<(String, Value)> = self
+            .globals
+            .scope
+            .borrow()
+            .vars
+            .iter()
+            .map(|(name, binding)| (name.clone(), binding.value.clone()))
+            .collect();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        vars
+    }
+
+    // Returns to the session scope after evaluation was abandoned part-way, as by a panic.
+    pub(crate) fn recover(&mut self) {
+        self.env = self.globals.clone();
+        self.depth = 0;
+        self.trace_offset = 0;
+    }
+
+    // Drops every session binding and struct; built-ins and settings are kept.
+    pub fn reset(&mut self) {
+        let fresh = Interpreter::new();
+        self.env = fresh.env;
+        self.globals = fresh.globals;
+    }
+
+    // Whether the nearest binding of `name` is a constant, such as the built-in `pi`.
+    pub(crate) fn is_const(&self, name: &str) -> bool {
+        self.env.is_const(name)
+    }
+
+    // Resolves a name that no binding covers to a built-in constant or function.
+    pub(crate) fn builtin(&self, name: &str) -> Option<Value> {
+        let matches = |builtin: &str| {
+            builtin == name || (self.case_insensitive && builtin.eq_ignore_ascii_case(name))
+        };
+        if let Some((constant, _)) = CONSTANTS.iter().find(|(constant, _)| matches(constant)) {
+            return self.env.get(constant);
+        }
+        BUILTINS
+            .iter()
+            .find(|builtin| matches(builtin))
+            .map(|builtin| Value::Function(Function::Builtin(builtin)))
+    }
+
+    // Evaluates a freshly parsed program, folding its constants first unless optimization
+    // has been turned off and reporting lint warnings if they are enabled.
+    pub fn run(&mut self, ast: ASTNode) -> Result<Value, EvalError> {
+        // Folding, linting, memoizing and moving into an arena all recurse through the
+        // tree, so a deeper one, which only evaluation can take, goes straight to it.
+        let shallow = ast.height() <= MAX_PASS_HEIGHT;
+        let ast = if self.optimize && shallow {
+            optimizer::fold_constants(ast)
+        } else {
+            ast
+        };
+        if self.warnings && shallow {
+            for warning in lint::lint(&ast) {
+                eprintln!("Warning: {}", warning);
+            }
+        }
+        self.error_span = None;
+        let span = ast.span;
+        let value = if !shallow {
+            self.interpret(&ast)?
+        } else if self.arena {
+            self.interpret_ast(&Ast::from_tree(ast))?
+        } else if self.memoize && self.trace.is_none() {
+            self.with_memo(&ast, |interpreter| interpreter.interpret(&ast))?
+        } else {
+            self.interpret(&ast)?
+        };
+        match value {
+            Value::Float(n) if !n.is_finite() && self.strict_float => {
+                self.error_span = Some(span);
+                Err(EvalError::NonFinite(None, n))
+            }
+            Value::Float(n) if !n.is_finite() => {
+                if self.warnings {
+                    eprintln!("Warning: result is not a finite number: {}", n);
+                }
+                Ok(Value::Float(n))
+            }
+            value => Ok(value),
+        }
+    }
+
+    // Where in its source the error last returned by `run` was raised. A function's body
+    // keeps the spans of the source that defined it, which may not be the one being run.
+    pub fn error_span(&self) -> Option<Span> {
+        self.error_span
+    }
+
+    pub fn interpret(&mut self, node: &ASTNode) -> Result<Value, EvalError> {
+        if !self.memo.is_empty() {
+            if let Some(id) = self.memo.id(node) {
+                return self.memoized(id, node);
+            }
+        }
+        self.evaluate(node)
+    }
+
+    fn evaluate(&mut self, node: &ASTNode) -> Result<Value, EvalError> {
+        let result = if self.depth == self.max_depth {
+            Err(EvalError::TooDeep(self.max_depth))
+        } else if nests_operators(node) {
+            self.eval_operators(node)
+        } else if self.trace.is_none() {
+            self.depth += 1;
+            let result = self.eval_node(node);
+            self.depth -= 1;
+            result
+        } else {
+            self.trace_line(&format!("Evaluating: {:?}", node));
+            self.depth += 1;
+            let result = self.eval_node(node);
+            self.depth -= 1;
+            match &result {
+                Ok(value) => self.trace_line(&format!("=> {}", quoted(value))),
+                Err(err) => self.trace_line(&format!("=> error: {}", err)),
+            }
+            result
+        };
+        if result.is_err() && self.error_span.is_none() {
+            self.error_span = Some(node.span);
+        }
+        result
+    }
+
+    fn trace_line(&mut self, line: &str) {
+        self.trace_at(self.depth + self.trace_offset, line);
+    }
+
+    fn trace_at(&mut self, depth: usize, line: &str) {
+        if let Some(writer) = &mut self.trace {
+            // A failing trace writer should not stop evaluation.
+            let _ = writeln!(writer, "{}[depth={}] {}", "  ".repeat(depth), depth, line);
+        }
+    }
+
+    // Evaluates a tree of operators with explicit stacks instead of recursion, so a chain
+    // like `1 + 1 + ... + 1` of any length fits on the Rust stack. Each operator gives the
+    // same value, error and trace lines as if `interpret` had evaluated it; anything else
+    // in the tree is handed back to `interpret`. Each step holds the depth its trace lines
+    // are at. The steps take no stack, so only what is handed back counts against the
+    // depth limit.
+    fn eval_operators(&mut self, root: &ASTNode) -> Result<Value, EvalError> {
+        let offset = self.trace_offset;
+        let mut steps = vec![Step::Eval(root, self.depth + offset)];
+        let mut values = Vec::new();
+        let result = loop {
+            let Some(step) = steps.pop() else {
+                break Ok(values.pop().expect("the root leaves one value"));
+            };
+            let (node, depth, result) = match step {
+                Step::Eval(node, depth) => {
+                    // The root is being evaluated for its memo entry, if it has one.
+                    let remembered = !self.memo.is_empty()
+                        && !std::ptr::eq(node, root)
+                        && self.memo.id(node).is_some();
+                    match &node.kind {
+                        _ if remembered => {
+                            self.trace_offset = depth - self.depth;
+                            match self.interpret(node) {
+                                Ok(value) => values.push(value),
+                                Err(err) => break Err(self.unwind(&steps, err)),
+                            }
+                            continue;
+                        }
+                        NodeKind::BinaryOp(left, _, right) => {
+                            steps.push(Step::Apply(node, depth));
+                            steps.push(Step::Eval(right, depth + 1));
+                            steps.push(Step::Eval(left, depth + 1));
+                        }
+                        NodeKind::UnaryOp(_, operand) => {
+                            steps.push(Step::Apply(node, depth));
+                            steps.push(Step::Eval(operand, depth + 1));
+                        }
+                        NodeKind::Logical(left, _, _) => {
+                            steps.push(Step::Branch(node, depth));
+                            steps.push(Step::Eval(left, depth + 1));
+                        }
+                        _ => {
+                            self.trace_offset = depth - self.depth;
+                            let result = self.interpret(node);
+                            match result {
+                                Ok(value) => values.push(value),
+                                Err(err) => break Err(self.unwind(&steps, err)),
+                            }
+                            continue;
+                        }
+                    }
+                    if self.trace.is_some() {
+                        self.trace_at(depth, &format!("Evaluating: {:?}", node));
+                    }
+                    continue;
+                }
+                Step::Apply(node, depth) => {
+                    let result = match &node.kind {
+                        NodeKind::BinaryOp(_, op, _) => {
+                            let right = values.pop().expect("two operands");
+                            let left = values.pop().expect("two operands");
+                            self.binary_op(op, left, right)
+                        }
+                        NodeKind::UnaryOp(op, _) => {
+                            let operand = values.pop().expect("one operand");
+                            self.unary_op(op, operand)
+                        }
+                        _ => unreachable!("only operators are applied"),
+                    };
+                    (node, depth, result)
+                }
+                // `and` stops at the first false operand, `or` at the first true one and
+                // `??` at the first that is not null.
+                Step::Branch(node, depth) => {
+                    let NodeKind::Logical(_, op, right) = &node.kind else {
+                        unreachable!("only logical operators branch");
+                    };
+                    let left = values.pop().expect("a left operand");
+                    let result = match (op, left) {
+                        (Token::NullCoalesce, Value::Null) => None,
+                        (Token::NullCoalesce, left) => Some(Ok(left)),
+                        (op, left) => match logical_value(op, left) {
+                            Ok(left) if left == (*op == Token::Or) => Some(Ok(Value::Bool(left))),
+                            Ok(_) => None,
+                            Err(err) => Some(Err(err)),
+                        },
+                    };
+                    let Some(result) = result else {
+                        steps.push(Step::Check(node, depth));
+                        steps.push(Step::Eval(right, depth + 1));
+                        continue;
+                    };
+                    (node, depth, result)
+                }
+                Step::Check(node, depth) => {
+                    let NodeKind::Logical(_, op, _) = &node.kind else {
+                        unreachable!("only logical operators are checked");
+                    };
+                    let right = values.pop().expect("a right operand");
+                    let result = match op {
+                        Token::NullCoalesce => Ok(right),
+                        op => logical_value(op, right).map(Value::Bool),
+                    };
+                    (node, depth, result)
+                }
+            };
+            match result {
+                Ok(value) => {
+                    if self.trace.is_some() {
+                        self.trace_at(depth, &format!("=> {}", quoted(&value)));
+                    }
+                    values.push(value);
+                }
+                Err(err) => {
+                    if self.error_span.is_none() {
+                        self.error_span = Some(node.span);
+                    }
+                    if self.trace.is_some() {
+                        self.trace_at(depth, &format!("=> error: {}", err));
+                    }
+                    break Err(self.unwind(&steps, err));
+                }
+            }
+        };
+        self.trace_offset = offset;
+        result
+    }
+
+    // Traces `err` leaving each operator still waiting on operands, innermost first.
+    fn unwind(&mut self, steps: &[Step], err: EvalError) -> EvalError {
+        if self.trace.is_some() {
+            for step in steps.iter().rev() {
+                if let Step::Apply(_, depth) | Step::Branch(_, depth) | Step::Check(_, depth) = step
+                {
+                    self.trace_at(*depth, &format!("=> error: {}", err));
+                }
+            }
+        }
+        err
+    }
+
+    fn eval_node(&mut self, node: &ASTNode) -> Result<Value, EvalError> {
+        match &node.kind {
+            NodeKind::Number(n) => Ok(Value::Float(*n)),
+            NodeKind::Integer(n) => Ok(Value::Integer(*n)),
+            NodeKind::Bool(b) => Ok(Value::Bool(*b)),
+            NodeKind::Null => Ok(Value::Null),
+            NodeKind::Str(s) => Ok(Value::Str(s.clone())),
+            NodeKind::FStr(parts) => {
+                let mut result = String::new();
+                for part in parts {
+                    match part {
+                        FStrNode::Literal(s) => result.push_str(s),
+                        FStrNode::Expr(expr) => result.push_str(&self.interpret(expr)?.to_string()),
+                    }
+                }
+                Ok(Value::Str(result))
+            }
+            NodeKind::VarRef(name) => self.variable(name),
+            NodeKind::Let(name, value) => {
+                let value = self.interpret(value)?;
+                self.env.check_redeclare(name)?;
+                self.env.define(name, value.clone());
+                Ok(value)
+            }
+            NodeKind::Const(name, value) => {
+                let value = self.interpret(value)?;
+                self.env.check_redeclare(name)?;
+                self.env.define_const(name, value.clone(), Some(node.span));
+                Ok(value)
+            }
+            NodeKind::Global(names) => {
+                for name in names {
+                    self.env.declare_global(name, &self.globals)?;
+                }
+                Ok(Value::Null)
+            }
+            NodeKind::Import(path) => {
+                self.import(path)?;
+                Ok(Value::Null)
+            }
+            NodeKind::Assign(name, value) => {
+                let value = self.interpret(value)?;
+                self.env.assign(name, value.clone(), &self.globals)?;
+                Ok(value)
+            }
+            NodeKind::DestructureLet(names, value) => {
+                let value = self.interpret(value)?;
+                self.destructure(names, value)
+            }
+            NodeKind::Sequence(statements) => {
+                let mut result = None;
+                for statement in statements {
+                    result = Some(self.interpret(statement)?);
+                }
+                Ok(result.expect("sequence has at least two statements"))
+            }
+            NodeKind::Block(statements) => {
+                let env = self.env.child();
+                let saved = std::mem::replace(&mut self.env, env);
+                let result = self.block(statements);
+                self.env = saved;
+                result
+            }
+            NodeKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                if self.condition(cond)? {
+                    self.interpret(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.interpret(else_branch)
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            NodeKind::While { cond, body } => {
+                while self.condition(cond)? {
+                    self.interpret(body)?;
+                }
+                Ok(Value::Null)
+            }
+            NodeKind::For {
+                var,
+                iterable,
+                body,
+            } => {
+                let items = items_of(self.interpret(iterable)?)?;
+                // Each pass gets a scope of its own, so closures made in the body keep
+                // the element they saw.
+                for item in items {
+                    let env = self.env.child();
+                    env.define(var, item);
+                    let saved = std::mem::replace(&mut self.env, env);
+                    let result = self.interpret(body);
+                    self.env = saved;
+                    result?;
+                }
+                Ok(Value::Null)
+            }
+            NodeKind::Match { subject, arms } => {
+                let subject = self.interpret(subject)?;
+                for (pattern, body) in arms {
+                    let binding = match pattern {
+                        Pattern::PatWildcard => None,
+                        Pattern::PatIdent(name) => Some(name),
+                        Pattern::PatLiteral(literal) => {
+                            if !values_equal(&self.interpret(literal)?, &subject) {
+                                continue;
+                            }
+                            None
+                        }
+                    };
+                    let env = self.env.child();
+                    if let Some(name) = binding {
+                        env.define(name, subject.clone());
+                    }
+                    let saved = std::mem::replace(&mut self.env, env);
+                    let result = self.interpret(body);
+                    self.env = saved;
+                    return result;
+                }
+                Err(EvalError::NonExhaustiveMatch(subject.to_string()))
+            }
+            NodeKind::Try {
+                body,
+                binding,
+                handler,
+                finally,
+            } => {
+                let mut result = self.interpret(body);
+                if let (Err(err), Some(handler)) = (&result, handler) {
+                    self.error_span = None;
+                    let env = self.env.child();
+                    if let Some(name) = binding {
+                        env.define(name, err.to_value());
+                    }
+                    let saved = std::mem::replace(&mut self.env, env);
+                    result = self.interpret(handler);
+                    self.env = saved;
+                }
+                // An error from `finally` replaces the result; otherwise its value is dropped.
+                if let Some(finally) = finally {
+                    let error_span = self.error_span.take();
+                    self.interpret(finally)?;
+                    self.error_span = error_span;
+                }
+                result
+            }
+            NodeKind::Throw(value) => Err(EvalError::thrown(self.interpret(value)?)),
+            NodeKind::UnaryOp(op, operand) => {
+                let value = self.interpret(operand)?;
+                self.unary_op(op, value)
+            }
+            NodeKind::Membership {
+                item,
+                container,
+                negated,
+            } => {
+                let item = self.interpret(item)?;
+                let container = self.interpret(container)?;
+                Ok(Value::Bool(contains(&container, &item)? != *negated))
+            }
+            NodeKind::BinaryOp(left, op, right) => {
+                let left_val = self.interpret(left)?;
+                let right_val = self.interpret(right)?;
+                self.binary_op(op, left_val, right_val)
+            }
+            NodeKind::StructDef { name, fields } => {
+                self.env.define_struct(name, fields.clone());
+                Ok(Value::Null)
+            }
+            NodeKind::StructLit { name, fields } => {
+                let declared = self
+                    .env
+                    .get_struct(name)
+                    .ok_or_else(|| EvalError::UnknownStruct(name.clone()))?;
+                let mut values = HashMap::new();
+                for (field, value) in fields {
+                    if !declared.contains(field) {
+                        return Err(EvalError::NoSuchField(name.clone(), field.clone()));
+                    }
+                    let value = self.interpret(value)?;
+                    values.insert(field.clone(), value);
+                }
+                if let Some(missing) = declared.iter().find(|field| !values.contains_key(*field)) {
+                    return Err(EvalError::MissingField(name.clone(), missing.clone()));
+                }
+                Ok(Value::Struct {
+                    type_name: name.clone(),
+                    fields: values,
+                })
+            }
+            NodeKind::FieldAccess(target, field) => {
+                let target = self.interpret(target)?;
+                field_of(target, field)
+            }
+            NodeKind::SafeFieldAccess(target, field) => match self.interpret(target)? {
+                Value::Null => Ok(Value::Null),
+                target => field_of(target, field),
+            },
+            NodeKind::Logical(left, Token::NullCoalesce, right) => match self.interpret(left)? {
+                Value::Null => self.interpret(right),
+                left => Ok(left),
+            },
+            NodeKind::Logical(left, op, right) => {
+                let left = self.logical_operand(op, left)?;
+                // `and` stops at the first false operand and `or` at the first true one.
+                if left == (*op == Token::Or) {
+                    return Ok(Value::Bool(left));
+                }
+                Ok(Value::Bool(self.logical_operand(op, right)?))
+            }
+            NodeKind::Map(entries) => {
+                let mut map = HashMap::new();
+                for (key, value) in entries {
+                    let key = self.map_key(key)?;
+                    let value = self.interpret(value)?;
+                    map.insert(key, value);
+                }
+                Ok(Value::Map(map))
+            }
+            NodeKind::Array(items) => Ok(Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.interpret(item))
+                    .collect::<Result<Vec<Value>, EvalError>>()?,
+            )),
+            NodeKind::Tuple(items) => Ok(Value::Tuple(
+                items
+                    .iter()
+                    .map(|item| self.interpret(item))
+                    .collect::<Result<Vec<Value>, EvalError>>()?,
+            )),
+            NodeKind::Index(target, index) => {
+                let target = self.interpret(target)?;
+                let index = self.interpret(index)?;
+                index_into(target, index)
+            }
+            NodeKind::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => {
+                let start = self.interpret(start)?;
+                let end = self.interpret(end)?;
+                let step = match step {
+                    Some(step) => self.interpret(step)?,
+                    None => Value::Integer(1),
+                };
+                make_range(start, end, step, *inclusive)
+            }
+            NodeKind::Call(callee, args) => {
+                let callee = self.interpret(callee)?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.interpret(arg))
+                    .collect::<Result<Vec<Value>, EvalError>>()?;
+                self.call_function(&callee, args)
+            }
+            NodeKind::Pipe(value, function) => {
+                let value = self.interpret(value)?;
+                let function = self.interpret(function)?;
+                self.call_function(&function, vec![value])
+            }
+            NodeKind::FnDef {
+                name,
+                params,
+                rest_param,
+                body,
+            } => Ok(self.define_function(name, params, rest_param, body)),
+        }
+    }
+
+    // A closure over the current scope, which a named function is also defined in.
+    fn define_function(
+        &mut self,
+        name: &Option<String>,
+        params: &Rc<Vec<Param>>,
+        rest_param: &Option<String>,
+        body: &Rc<ASTNode>,
+    ) -> Value {
+        let function = Value::Function(Function::User(Rc::new(Closure {
+            name: name.clone(),
+            params: Rc::clone(params),
+            rest_param: rest_param.clone(),
+            body: Rc::clone(body),
+            env: self.env.clone(),
+        })));
+        if let Some(name) = name {
+            self.env.define(name, function.clone());
+        }
+        function
+    }
+
+    fn variable(&self, name: &str) -> Result<Value, EvalError> {
+        match self.env.get(name) {
+            Some(value) => Ok(value),
+            None => self
+                .builtin(name)
+                .ok_or_else(|| EvalError::UndefinedVariable(name.to_string())),
+        }
+    }
+
+    fn block(&mut self, statements: &[ASTNode]) -> Result<Value, EvalError> {
+        let mut result = Value::Null;
+        for statement in statements {
+            result = self.interpret(statement)?;
+        }
+        Ok(result)
+    }
+
+    fn destructure(&mut self, names: &[String], value: Value) -> Result<Value, EvalError> {
+        let items = match &value {
+            Value::Tuple(items) => items,
+            other => {
+                return Err(EvalError::TypeMismatch(format!(
+                    "cannot destructure {} as a tuple",
+                    other
+                )))
+            }
+        };
+        if items.len() != names.len() {
+            return Err(EvalError::DestructureArity(names.len(), items.len()));
+        }
+        for (name, item) in names.iter().zip(items) {
+            self.env.define(name, item.clone());
+        }
+        Ok(value)
+    }
+
+    fn condition(&mut self, cond: &ASTNode) -> Result<bool, EvalError> {
+        condition_value(self.interpret(cond)?)
+    }
+
+    pub(crate) fn call_function(
+        &mut self,
+        callee: &Value,
+        args: Vec<Value>,
+    ) -> Result<Value, EvalError> {
+        let closure = match callee {
+            Value::Function(Function::Builtin(name)) => return self.call_builtin(name, args),
+            Value::Function(Function::User(closure)) => Rc::clone(closure),
+            Value::Function(Function::Compiled(_)) => {
+                return Err(EvalError::InvalidArgument(format!(
+                    "{} is compiled to bytecode and can only be called by the VM",
+                    callee
+                )))
+            }
+            other => return Err(EvalError::NotCallable(other.to_string())),
+        };
+        // Every parameter up to the last one without a default needs an argument.
+        let required = closure
+            .params
+            .iter()
+            .rposition(|(_, default)| default.is_none())
+            .map_or(0, |last| last + 1);
+        let arity = closure.params.len();
+        if args.len() < required {
+            return Err(EvalError::ArityMismatch(required, args.len()));
+        }
+        if args.len() > arity && closure.rest_param.is_none() {
+            return Err(EvalError::ArityMismatch(arity, args.len()));
+        }
+        let env = closure.env.function_child();
+        let saved = std::mem::replace(&mut self.env, env);
+        let result = self
+            .bind_params(&closure, args)
+            .and_then(|()| self.interpret(&closure.body));
+        self.env = saved;
+        result
+    }
+
+    // Binds `args` in the current scope, evaluating a default there, after the parameters
+    // before it, for each argument left out.
+    fn bind_params(&mut self, closure: &Closure, args: Vec<Value>) -> Result<(), EvalError> {
+        let mut args = args.into_iter();
+        for (param, default) in closure.params.iter() {
+            let value = match (args.next(), default) {
+                (Some(arg), _) => arg,
+                (None, Some(default)) => self.interpret(default)?,
+                (None, None) => unreachable!("checked by call_function"),
+            };
+            self.env.define(param, value);
+        }
+        if let Some(rest_param) = &closure.rest_param {
+            self.env.define(rest_param, Value::Array(args.collect()));
+        }
+        Ok(())
+    }
+
+    // Writes the prompt, if there is one, and reads a line without its line ending. At the
+    // end of the input there is no line, so the result is null.
+    fn read_input(&mut self, prompt: Option<&Value>) -> Result<Value, EvalError> {
+        let failed = |err: io::Error| EvalError::InvalidArgument(format!("input(): {}", err));
+        if let Some(prompt) = prompt {
+            write!(self.output_writer, "{}", prompt).map_err(failed)?;
+            self.output_writer.flush().map_err(failed)?;
+        }
+        let mut line = String::new();
+        if self.input_reader.read_line(&mut line).map_err(failed)? == 0 {
+            return Ok(Value::Null);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Value::Str(line))
+    }
+
+    // Writes `args` separated by spaces and then `end`. Strings are written without quotes.
+    fn print(&mut self, name: &str, args: &[Value], end: &str) -> Result<Value, EvalError> {
+        let failed = |err: io::Error| EvalError::InvalidArgument(format!("{}(): {}", name, err));
+        let line = args
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(self.output_writer, "{}{}", line, end).map_err(failed)?;
+        self.output_writer.flush().map_err(failed)?;
+        Ok(Value::Null)
+    }
+
+    // Applies `f` to each element for map/filter, tagging errors with the element index.
+    fn call_each(
+        &mut self,
+        function: &'static str,
+        f: &Value,
+        items: &[Value],
+    ) -> Result<Vec<Value>, EvalError> {
+        if !matches!(f, Value::Function(_)) {
+            return Err(EvalError::NotCallable(f.to_string()));
+        }
+        let mut results = Vec::with_capacity(items.len());
+        for (index, item) in items.iter().enumerate() {
+            let result =
+                self.call_function(f, vec![item.clone()])
+                    .map_err(|error| EvalError::Callback {
+                        function,
+                        index,
+                        error: Box::new(error),
+                    })?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    // Logical operators take booleans only, like conditions; there is no truthiness.
+    fn logical_operand(&mut self, op: &Token, operand: &ASTNode) -> Result<bool, EvalError> {
+        logical_value(op, self.interpret(operand)?)
+    }
+
+    pub(crate) fn unary_op(&self, op: &Token, value: Value) -> Result<Value, EvalError> {
+        match (op, value) {
+            (Token::Minus, Value::Integer(n)) => match n.checked_neg() {
+                Some(n) => Ok(Value::Integer(n)),
+                None if self.overflow == OverflowMode::Promote => Ok(Value::Float(-(n as f64))),
+                None => Err(EvalError::Overflow(format!("-({})", n))),
+            },
+            (Token::Minus, Value::Float(n)) => Ok(Value::Float(-n)),
+            (Token::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (op, value) => Err(EvalError::TypeMismatch(format!(
+                "unsupported operand for unary {}: {}",
+                op.symbol(),
+                value
+            ))),
+        }
+    }
+
+    pub(crate) fn binary_op(
+        &self,
+        op: &Token,
+        left: Value,
+        right: Value,
+    ) -> Result<Value, EvalError> {
+        if let Token::Eq | Token::NotEq | Token::Lt | Token::Le | Token::Gt | Token::Ge = op {
+            return compare(op, &left, &right);
+        }
+        let numbers = matches!(
+            (&left, &right),
+            (
+                Value::Integer(_) | Value::Float(_),
+                Value::Integer(_) | Value::Float(_)
+            )
+        );
+        if !self.strict_float || !numbers {
+            return self.arithmetic(op, left, right);
+        }
+        // Operands are shown as plain numbers, since the error already says what NaN is.
+        let number = |value: &Value| match value {
+            Value::Float(n) => n.to_string(),
+            value => value.to_string(),
+        };
+        let expr = format!("{} {} {}", number(&left), op.symbol(), number(&right));
+        match self.arithmetic(op, left, right)? {
+            Value::Float(n) if !n.is_finite() => Err(EvalError::NonFinite(Some(expr), n)),
+            value => Ok(value),
+        }
+    }
+
+    fn arithmetic(&self, op: &Token, left: Value, right: Value) -> Result<Value, EvalError> {
+        if let (Token::Div | Token::Mod, Value::Integer(0) | Value::Float(0.0)) = (op, &right) {
+            let l = match left {
+                Value::Integer(l) => l as f64,
+                Value::Float(l) => l,
+                _ => return self.mismatch(op, left, right),
+            };
+            return match self.div_by_zero {
+                DivByZero::Error => Err(EvalError::DivisionByZero(format!(
+                    "{} {} {}",
+                    left,
+                    op.symbol(),
+                    right
+                ))),
+                DivByZero::Ieee => Ok(Value::Float(float_op(op, l, 0.0))),
+                DivByZero::Value(value) => Ok(Value::Float(value)),
+            };
+        }
+        match (left, right) {
+            (Value::Integer(l), Value::Integer(r)) => self.integer_op(op, l, r),
+            (Value::Integer(l), Value::Float(r)) => Ok(Value::Float(float_op(op, l as f64, r))),
+            (Value::Float(l), Value::Integer(r)) => Ok(Value::Float(float_op(op, l, r as f64))),
+            (Value::Float(l), Value::Float(r)) => Ok(Value::Float(float_op(op, l, r))),
+            (l, r) => self.mismatch(op, l, r),
+        }
+    }
+
+    fn mismatch(&self, op: &Token, left: Value, right: Value) -> Result<Value, EvalError> {
+        Err(EvalError::TypeMismatch(format!(
+            "unsupported operands for {}: {} and {}",
+            op.symbol(),
+            left,
+            right
+        )))
+    }
+
+    fn integer_op(&self, op: &Token, l: i64, r: i64) -> Result<Value, EvalError> {
+        let result = match op {
+            Token::Plus => l.checked_add(r),
+            Token::Minus => l.checked_sub(r),
+            Token::Mul => l.checked_mul(r),
+            Token::Div => return Ok(Value::Float(l as f64 / r as f64)),
+            // Only `i64::MIN % -1` overflows, though the remainder it has is 0.
+            Token::Mod if r == -1 => Some(0),
+            Token::Mod => l.checked_rem(r),
+            Token::Pow if r < 0 => return Ok(Value::Float((l as f64).powf(r as f64))),
+            // These bases can be raised to any power, however large.
+            Token::Pow if r > 0 && (l == 0 || l == 1) => Some(l),
+            Token::Pow if l == -1 => Some(if r % 2 == 0 { 1 } else { -1 }),
+            Token::Pow => u32::try_from(r).ok().and_then(|r| l.checked_pow(r)),
+            _ => unreachable!(),
+        };
+        match result {
+            Some(n) => Ok(Value::Integer(n)),
+            None if self.overflow == OverflowMode::Promote => {
+                Ok(Value::Float(float_op(op, l as f64, r as f64)))
+            }
+            None => Err(EvalError::Overflow(format!("{} {} {}", l, op.symbol(), r))),
+        }
+    }
+
+    fn map_key(&mut self, node: &ASTNode) -> Result<String, EvalError> {
+        match self.interpret(node)? {
+            Value::Str(s) => Ok(s),
+            other => Err(EvalError::TypeMismatch(format!(
+                "map keys must be strings, found {}",
+                other
+            ))),
+        }
+    }
+
+    // Maps are values: map_set and map_delete return an updated copy.
+    fn call_builtin(&mut self, name: &str, args: Vec<Value>) -> Result<Value, EvalError> {
+        match (name, args.as_slice()) {
+            ("map_get", [Value::Map(map), Value::Str(key)]) => match map.get(key) {
+                Some(value) => Ok(value.clone()),
+                None => Err(EvalError::KeyNotFound(key.clone())),
+            },
+            ("map_set", [Value::Map(map), Value::Str(key), value]) => {
+                let mut map = map.clone();
+                map.insert(key.clone(), value.clone());
+                Ok(Value::Map(map))
+            }
+            ("map_delete", [Value::Map(map), Value::Str(key)]) => {
+                let mut map = map.clone();
+                map.remove(key);
+                Ok(Value::Map(map))
+            }
+            ("map_has", [Value::Map(map), Value::Str(key)]) => {
+                Ok(Value::Bool(map.contains_key(key)))
+            }
+            ("map_keys", [Value::Map(map)]) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                Ok(Value::Array(
+                    keys.into_iter().map(|k| Value::Str(k.clone())).collect(),
+                ))
+            }
+            ("dedent", [Value::Str(s)]) => Ok(Value::Str(dedent(s))),
+            ("trim", [Value::Str(s)]) => Ok(Value::Str(s.trim().to_string())),
+            ("to_upper", [Value::Str(s)]) => Ok(Value::Str(s.to_uppercase())),
+            ("to_lower", [Value::Str(s)]) => Ok(Value::Str(s.to_lowercase())),
+            ("find", [haystack, needle]) => string::find(haystack, needle),
+            ("replace", [s, from, to]) => string::replace(name, s, from, to, false),
+            ("replace_all", [s, from, to]) => string::replace(name, s, from, to, true),
+            ("starts_with", [s, prefix]) => string::starts_with(s, prefix),
+            ("ends_with", [s, suffix]) => string::ends_with(s, suffix),
+            ("to_hex", [n]) => number::to_base(name, n, 16, false),
+            ("to_hex", [n, Value::Bool(upper)]) => number::to_base(name, n, 16, *upper),
+            ("to_bin", [n]) => number::to_base(name, n, 2, false),
+            ("to_oct", [n]) => number::to_base(name, n, 8, false),
+            ("from_base", [s, base]) => number::from_base(s, base),
+            ("len", [Value::Str(s)]) => Ok(Value::Integer(s.chars().count() as i64)),
+            ("len", [Value::Array(items) | Value::Tuple(items)]) => {
+                Ok(Value::Integer(items.len() as i64))
+            }
+            ("len", [Value::Map(map)]) => Ok(Value::Integer(map.len() as i64)),
+            ("int", [value]) => to_int(value),
+            ("float", [value]) => to_float(value),
+            ("str", [value]) => Ok(Value::Str(value.to_string())),
+            ("format", [value, Value::Str(spec)]) => format::format(value, spec).map(Value::Str),
+            ("parse", [Value::Str(source)]) => self.parse_and_eval(source),
+            ("input", []) => self.read_input(None),
+            ("input", [prompt]) => self.read_input(Some(prompt)),
+            ("print", _) => self.print(name, &args, ""),
+            ("println", _) => self.print(name, &args, "\n"),
+            #[cfg(feature = "io")]
+            ("read_file", [Value::Str(path)]) => fs::read_to_string(sandboxed(path)?)
+                .map(Value::Str)
+                .map_err(io_failed(name, path)),
+            #[cfg(feature = "io")]
+            ("write_file", [Value::Str(path), Value::Str(content)]) => {
+                fs::write(sandboxed(path)?, content)
+                    .map(|()| Value::Null)
+                    .map_err(io_failed(name, path))
+            }
+            #[cfg(feature = "io")]
+            ("append_file", [Value::Str(path), Value::Str(content)]) => fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(sandboxed(path)?)
+                .and_then(|mut file| file.write_all(content.as_bytes()))
+                .map(|()| Value::Null)
+                .map_err(io_failed(name, path)),
+            #[cfg(feature = "io")]
+            ("file_exists", [Value::Str(path)]) => Ok(Value::Bool(sandboxed(path)?.exists())),
+            #[cfg(feature = "rand")]
+            ("rand", []) => Ok(Value::Float(self.rng.float())),
+            #[cfg(feature = "rand")]
+            ("rand_int", [Value::Integer(min), Value::Integer(max)]) => {
+                if min > max {
+                    return Err(EvalError::InvalidRange(format!(
+                        "rand_int({}, {}) has no integers to choose from",
+                        min, max
+                    )));
+                }
+                Ok(Value::Integer(self.rng.int_in(*min, *max)))
+            }
+            #[cfg(feature = "rand")]
+            ("seed", [Value::Integer(n)]) => {
+                self.rng = Rng::seeded(*n as u64);
+                Ok(Value::Null)
+            }
+            #[cfg(feature = "time")]
+            ("now", []) => Ok(Value::Float(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs_f64(),
+            )),
+            #[cfg(feature = "time")]
+            ("sleep", [seconds @ (Value::Integer(_) | Value::Float(_))]) => {
+                let duration = Duration::try_from_secs_f64(as_float(seconds)?).map_err(|_| {
+                    EvalError::InvalidArgument(format!(
+                        "sleep(): cannot sleep for {} seconds",
+                        seconds
+                    ))
+                })?;
+                std::thread::sleep(duration);
+                Ok(Value::Null)
+            }
+            #[cfg(feature = "time")]
+            ("elapsed_ms", []) => Ok(Value::Float(self.start.elapsed().as_secs_f64() * 1000.0)),
+            #[cfg(feature = "json")]
+            ("json_parse", [Value::Str(source)]) => json::parse(source),
+            #[cfg(feature = "json")]
+            ("json_stringify", [value]) => json::stringify(value).map(Value::Str),
+            #[cfg(feature = "regex")]
+            ("regex_match", [Value::Str(pattern), Value::Str(text)]) => {
+                self.regexes.is_match(pattern, text)
+            }
+            #[cfg(feature = "regex")]
+            ("regex_find", [Value::Str(pattern), Value::Str(text)]) => {
+                self.regexes.find(pattern, text)
+            }
+            #[cfg(feature = "regex")]
+            ("regex_find_all", [Value::Str(pattern), Value::Str(text)]) => {
+                self.regexes.find_all(pattern, text)
+            }
+            #[cfg(feature = "regex")]
+            ("regex_replace", [Value::Str(pattern), Value::Str(text), Value::Str(replacement)]) => {
+                self.regexes.replace(pattern, text, replacement)
+            }
+            ("args", []) => Ok(Value::Array(
+                self.args.iter().cloned().map(Value::Str).collect(),
+            )),
+            ("assert", [cond] | [cond, Value::Str(_)]) => match (cond, args.get(1)) {
+                (Value::Bool(true), _) => Ok(Value::Null),
+                (Value::Bool(false), Some(Value::Str(msg))) => {
+                    Err(EvalError::AssertionFailed(Some(msg.clone())))
+                }
+                (Value::Bool(false), _) => Err(EvalError::AssertionFailed(None)),
+                (other, _) => Err(EvalError::TypeMismatch(format!(
+                    "assert expects a boolean, found {}",
+                    other
+                ))),
+            },
+            ("assert_eq", [left, right] | [left, right, Value::Str(_)]) => {
+                if values_equal(left, right) {
+                    return Ok(Value::Null);
+                }
+                let mut msg = format!("left: {}, right: {}", quoted(left), quoted(right));
+                if let Some(Value::Str(message)) = args.get(2) {
+                    msg = format!("{} ({})", message, msg);
+                }
+                Err(EvalError::AssertionFailed(Some(msg)))
+            }
+            ("type", [value]) => Ok(Value::Str(value.type_name().to_string())),
+            ("hash", [value]) => hash::hash(&self.hash_state, value),
+            ("is_number", [value]) => Ok(Value::Bool(value.type_name() == "number")),
+            ("is_string", [value]) => Ok(Value::Bool(value.type_name() == "string")),
+            ("is_list", [value]) => Ok(Value::Bool(value.type_name() == "list")),
+            ("is_fn", [value]) => Ok(Value::Bool(value.type_name() == "function")),
+            ("sum", _) => self.sum(aggregate_args(&args)?),
+            ("product", _) => self.product(aggregate_args(&args)?),
+            ("avg", _) => {
+                let values = aggregate_args(&args)?;
+                if values.is_empty() {
+                    return Err(EvalError::InvalidArgument(
+                        "avg() requires at least one value".to_string(),
+                    ));
+                }
+                let count = values.len() as f64;
+                Ok(Value::Float(as_float(&self.sum(values)?)? / count))
+            }
+            ("map", [f, Value::Array(items) | Value::Tuple(items)]) => {
+                Ok(Value::Array(self.call_each("map", f, items)?))
+            }
+            ("filter", [f, Value::Array(items) | Value::Tuple(items)]) => {
+                let keep = self.call_each("filter", f, items)?;
+                let mut results = Vec::new();
+                for (index, (item, keep)) in items.iter().zip(keep).enumerate() {
+                    match keep {
+                        Value::Bool(true) => results.push(item.clone()),
+                        Value::Bool(false) => {}
+                        other => {
+                            return Err(EvalError::Callback {
+                                function: "filter",
+                                index,
+                                error: Box::new(EvalError::TypeMismatch(format!(
+                                    "predicate returned {}, expected a boolean",
+                                    other
+                                ))),
+                            })
+                        }
+                    }
+                }
+                Ok(Value::Array(results))
+            }
+            ("reduce", [f, init, Value::Array(items) | Value::Tuple(items)]) => {
+                if !matches!(f, Value::Function(_)) {
+                    return Err(EvalError::NotCallable(f.to_string()));
+                }
+                let mut acc = init.clone();
+                for (index, item) in items.iter().enumerate() {
+                    acc = self
+                        .call_function(f, vec![acc, item.clone()])
+                        .map_err(|error| EvalError::Callback {
+                            function: "reduce",
+                            index,
+                            error: Box::new(error),
+                        })?;
+                }
+                Ok(acc)
+            }
+            ("sort", [Value::Array(items) | Value::Tuple(items)]) => {
+                self.sort(items, None).map(Value::Array)
+            }
+            ("sort", [Value::Array(items) | Value::Tuple(items), f]) => {
+                if !matches!(f, Value::Function(_)) {
+                    return Err(EvalError::NotCallable(f.to_string()));
+                }
+                self.sort(items, Some(f)).map(Value::Array)
+            }
+            ("zip", [Value::Array(xs) | Value::Tuple(xs), Value::Array(ys) | Value::Tuple(ys)]) => {
+                Ok(Value::Array(
+                    xs.iter()
+                        .zip(ys)
+                        .map(|(x, y)| Value::Tuple(vec![x.clone(), y.clone()]))
+                        .collect(),
+                ))
+            }
+            ("range", [start, stop] | [start, stop, _]) => {
+                let step = args.get(2).cloned().unwrap_or(Value::Integer(1));
+                for value in [start, stop, &step] {
+                    if !matches!(value, Value::Integer(_)) {
+                        return Err(EvalError::TypeMismatch(format!(
+                            "range expects integers, found {}",
+                            quoted(value)
+                        )));
+                    }
+                }
+                if step == Value::Integer(0) {
+                    let call = format!("range({}, {}, 0)", start, stop);
+                    return Err(EvalError::DivisionByZero(call));
+                }
+                make_range(start.clone(), stop.clone(), step, false)
+            }
+            ("count", _) => Ok(Value::Integer(aggregate_items(&args).len() as i64)),
+            ("min" | "max", _) => {
+                let values = aggregate_args(&args)?;
+                let mut best: Option<&Value> = None;
+                for value in &values {
+                    let x = as_float(value)?;
+                    let better = match best {
+                        None => true,
+                        Some(b) if name == "min" => x < as_float(b)?,
+                        Some(b) => x > as_float(b)?,
+                    };
+                    if better {
+                        best = Some(value);
+                    }
+                }
+                best.cloned().ok_or_else(|| {
+                    EvalError::InvalidArgument(format!("{}() requires at least one value", name))
+                })
+            }
+            ("abs", [Value::Integer(n)]) => match n.checked_abs() {
+                Some(n) => Ok(Value::Integer(n)),
+                None if self.overflow == OverflowMode::Promote => {
+                    Ok(Value::Float((*n as f64).abs()))
+                }
+                None => Err(EvalError::Overflow(format!("abs({})", n))),
+            },
+            (
+                "abs" | "sqrt" | "floor" | "ceil" | "round" | "sin" | "cos" | "tan" | "ln" | "exp",
+                [x @ (Value::Integer(_) | Value::Float(_))],
+            ) => {
+                let x = as_float(x)?;
+                Ok(Value::Float(match name {
+                    "abs" => x.abs(),
+                    "sqrt" => x.sqrt(),
+                    "floor" => x.floor(),
+                    "ceil" => x.ceil(),
+                    "round" => x.round(),
+                    "sin" => x.sin(),
+                    "cos" => x.cos(),
+                    "tan" => x.tan(),
+                    "ln" => x.ln(),
+                    _ => x.exp(),
+                }))
+            }
+            _ if BUILTINS.contains(&name) => Err(EvalError::InvalidArgument(format!(
+                "invalid arguments to {}",
+                name
+            ))),
+            _ => Err(EvalError::UnknownFunction(name.to_string())),
+        }
+    }
+
+    // A stable sort, by `compare(a, b)` when given, which returns a negative number,
+    // zero or a positive number as `a` goes before, with or after `b`, and by `<`
+    // otherwise. The first error stops the comparisons that follow from calling back.
+    fn sort(&mut self, items: &[Value], compare: Option<&Value>) -> Result<Vec<Value>, EvalError> {
+        merge_sort(items.to_vec(), &mut |a, b| match compare {
+            Some(f) => self
+                .call_function(f, vec![a.clone(), b.clone()])
+                .and_then(|result| comparator_ordering(&result)),
+            None => value_ordering(a, b)
+                .map(|ordering| ordering.unwrap_or(Ordering::Equal))
+                .ok_or_else(|| {
+                    EvalError::TypeMismatch(format!("sort() cannot compare {} and {}", a, b))
+                }),
+        })
+    }
+
+    fn sum(&self, values: Vec<Value>) -> Result<Value, EvalError> {
+        if values.iter().all(|v| matches!(v, Value::Integer(_))) {
+            return values.into_iter().try_fold(Value::Integer(0), |acc, v| {
+                self.binary_op(&Token::Plus, acc, v)
+            });
+        }
+        // Neumaier's variant of Kahan summation keeps the rounding error of long sums bounded.
+        let mut total = 0.0;
+        let mut compensation = 0.0;
+        for value in &values {
+            let x = as_float(value)?;
+            let t = total + x;
+            if total.abs() >= x.abs() {
+                compensation += (total - t) + x;
+            } else {
+                compensation += (x - t) + total;
+            }
+            total = t;
+        }
+        Ok(Value::Float(total + compensation))
+    }
+
+    fn product(&self, values: Vec<Value>) -> Result<Value, EvalError> {
+        values
+            .into_iter()
+            .try_fold(Value::Integer(1), |acc, v| match v {
+                Value::Integer(_) | Value::Float(_) => self.binary_op(&Token::Mul, acc, v),
+                other => Err(EvalError::TypeMismatch(format!(
+                    "expected a number, found {}",
+                    other
+                ))),
+            })
+    }
+}
+
+impl Interpreter {
+    // Runs the file at `path` once, then binds its top-level names in the current scope.
+    fn import(&mut self, path: &str) -> Result<(), EvalError> {
+        let base = match &self.file {
+            Some(file) => file.parent().unwrap_or(Path::new("")),
+            None => Path::new(""),
+        };
+        let canonical = fs::canonicalize(base.join(path))
+            .map_err(|err| EvalError::ImportFailed(path.to_string(), err.to_string()))?;
+        if self.importing.contains(&canonical) {
+            return Err(EvalError::CircularImport(canonical.display().to_string()));
+        }
+        if let Some(module) = self.import_cache.get(&canonical) {
+            return module.merge_into(&self.env, true);
+        }
+        let module = self.run_module(&canonical)?;
+        self.import_cache.insert(canonical, module.clone());
+        module.merge_into(&self.env, false)
+    }
+
+    // Runs a file in a scope of its own, which sees only the built-ins, and returns it.
+    fn run_module(&mut self, path: &Path) -> Result<Environment, EvalError> {
+        let failed = |err: &dyn fmt::Display| {
+            EvalError::ImportFailed(path.display().to_string(), err.to_string())
+        };
+        let source = fs::read_to_string(path).map_err(|err| failed(&err))?;
+        let prelude = self.globals.scope.borrow().parent.clone();
+        let module = prelude.unwrap_or_default().child();
+        module.define_const(FILE, Value::Str(path.display().to_string()), None);
+        let ast = match self.parser(source).parse() {
+            Ok(ast) => ast,
+            Err(Error::Parse(err)) if err.is_empty_input() => return Ok(module),
+            Err(err) => return Err(failed(&err)),
+        };
+        let env = std::mem::replace(&mut self.env, module.clone());
+        let file = self.file.replace(path.to_path_buf());
+        self.importing.push(path.to_path_buf());
+        let result = self.interpret(&ast);
+        self.importing.pop();
+        self.env = env;
+        self.file = file;
+        // The error's span is in the imported file; point at the `import` instead.
+        if result.is_err() {
+            self.error_span = None;
+        }
+        result.map(|_| module)
+    }
+
+    // Backs `parse(source)`: the source runs in the caller's scope, so its bindings stay visible.
+    // It is evaluated as it is, inside the run that called `parse`, and an error in it is
+    // blamed on the call, since its spans are offsets into `source`.
+    fn parse_and_eval(&mut self, source: &str) -> Result<Value, EvalError> {
+        let ast = self
+            .parser(source.to_string())
+            .parse()
+            .map_err(|err| EvalError::InvalidArgument(format!("parse({:?}): {}", source, err)))?;
+        let error_span = self.error_span;
+        let result = self.interpret(&ast);
+        self.error_span = error_span;
+        result
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const CONSTANTS: &[(&str, f64)] = &[
+    ("pi", std::f64::consts::PI),
+    ("π", std::f64::consts::PI),
+    ("e", std::f64::consts::E),
+];
+
+const BUILTINS: &[&str] = &[
+    "map_get",
+    "map_set",
+    "map_delete",
+    "map_has",
+    "map_keys",
+    "dedent",
+    "trim",
+    "to_upper",
+    "to_lower",
+    "find",
+    "replace",
+    "replace_all",
+    "starts_with",
+    "ends_with",
+    "to_hex",
+    "to_bin",
+    "to_oct",
+    "from_base",
+    "len",
+    "int",
+    "float",
+    "str",
+    "format",
+    "parse",
+    "args",
+    "input",
+    "print",
+    "println",
+    #[cfg(feature = "io")]
+    "read_file",
+    #[cfg(feature = "io")]
+    "write_file",
+    #[cfg(feature = "io")]
+    "append_file",
+    #[cfg(feature = "io")]
+    "file_exists",
+    #[cfg(feature = "rand")]
+    "rand",
+    #[cfg(feature = "rand")]
+    "rand_int",
+    #[cfg(feature = "rand")]
+    "seed",
+    #[cfg(feature = "time")]
+    "now",
+    #[cfg(feature = "time")]
+    "sleep",
+    #[cfg(feature = "time")]
+    "elapsed_ms",
+    #[cfg(feature = "json")]
+    "json_parse",
+    #[cfg(feature = "json")]
+    "json_stringify",
+    #[cfg(feature = "regex")]
+    "regex_match",
+    #[cfg(feature = "regex")]
+    "regex_find",
+    #[cfg(feature = "regex")]
+    "regex_find_all",
+    #[cfg(feature = "regex")]
+    "regex_replace",
+    "assert",
+    "assert_eq",
+    "type",
+    "hash",
+    "is_number",
+    "is_string",
+    "is_list",
+    "is_fn",
+    "sum",
+    "product",
+    "avg",
+    "count",
+    "min",
+    "max",
+    "abs",
+    "sqrt",
+    "floor",
+    "ceil",
+    "round",
+    "sin",
+    "cos",
+    "tan",
+    "ln",
+    "exp",
+    "map",
+    "filter",
+    "reduce",
+    "sort",
+    "zip",
+    "range",
+];
+
+// The file built-ins take paths relative to the working directory that stay inside it, so
+// absolute paths and any `..` are refused.
+#[cfg(feature = "io")]
+fn sandboxed(path: &str) -> Result<&Path, EvalError> {
+    use std::path::Component;
+    let inside = Path::new(path)
+        .components()
+        .all(|part| matches!(part, Component::Normal(_) | Component::CurDir));
+    if !inside {
+        return Err(EvalError::PermissionDenied(path.to_string()));
+    }
+    Ok(Path::new(path))
+}
+
+#[cfg(feature = "io")]
+fn io_failed<'a>(name: &'a str, path: &'a str) -> impl Fn(io::Error) -> EvalError + 'a {
+    move |err| EvalError::IoError(format!("{}({:?}): {}", name, path, err))
+}
+
+fn to_int(value: &Value) -> Result<Value, EvalError> {
+    match value {
+        Value::Integer(n) => Ok(Value::Integer(*n)),
+        // `i64::MAX as f64` rounds up to 2^63, which is itself out of range.
+        Value::Float(f) if f.is_finite() && *f >= i64::MIN as f64 && *f < i64::MAX as f64 => {
+            Ok(Value::Integer(f.trunc() as i64))
+        }
+        Value::Str(s) => s
+            .trim()
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| conversion_error(value, "int")),
+        _ => Err(conversion_error(value, "int")),
+    }
+}
+
+fn to_float(value: &Value) -> Result<Value, EvalError> {
+    match value {
+        Value::Integer(n) => Ok(Value::Float(*n as f64)),
+        Value::Float(f) => Ok(Value::Float(*f)),
+        Value::Str(s) => s
+            .trim()
+            .parse::<f64>()
+            .map(Value::Float)
+            .map_err(|_| conversion_error(value, "float")),
+        _ => Err(conversion_error(value, "float")),
+    }
+}
+
+fn conversion_error(value: &Value, target: &str) -> EvalError {
+    EvalError::InvalidArgument(format!("cannot convert {} to {}", quoted(value), target))
+}
+
+// Shows strings with quotes so they can't be mistaken for other values in messages.
+fn quoted(value: &Value) -> String {
+    match value {
+        Value::Str(s) => format!("{:?}", s),
+        other => other.to_string(),
+    }
+}
+
+// Aggregates accept either a single list (`sum(1..10)`) or the values themselves (`sum(1, 2)`).
+fn aggregate_items(args: &[Value]) -> Vec<Value> {
+    match args {
+        [Value::Array(items)] => items.clone(),
+        _ => args.to_vec(),
+    }
+}
+
+// The values of a numeric aggregate, which must all be numbers.
+fn aggregate_args(args: &[Value]) -> Result<Vec<Value>, EvalError> {
+    let values = aggregate_items(args);
+    for value in &values {
+        as_float(value)?;
+    }
+    Ok(values)
+}
+
+// Builds the elements of `start..end by step`. A range whose step points away from
+// `end` (e.g. `10..1`) is empty; counting down needs an explicit negative step.
+fn make_range(start: Value, end: Value, step: Value, inclusive: bool) -> Result<Value, EvalError> {
+    match (start, end, step) {
+        (Value::Integer(start), Value::Integer(end), Value::Integer(step)) => {
+            if step == 0 {
+                return Err(EvalError::InvalidRange("step must not be zero".to_string()));
+            }
+            let (start, end, step) = (start as i128, end as i128, step as i128);
+            let span = if inclusive {
+                end - start + step.signum()
+            } else {
+                end - start
+            };
+            let len = if span.signum() == step.signum() {
+                (span + step - step.signum()) / step
+            } else {
+                0
+            };
+            check_range_len(len as f64)?;
+            Ok(Value::Array(
+                (0..len)
+                    .map(|i| Value::Integer((start + i * step) as i64))
+                    .collect(),
+            ))
+        }
+        (start, end, step) => {
+            let (start, end, step) = (as_float(&start)?, as_float(&end)?, as_float(&step)?);
+            if step == 0.0 || !step.is_finite() {
+                return Err(EvalError::InvalidRange(
+                    "step must be a non-zero number".to_string(),
+                ));
+            }
+            let count = ((end - start) / step).floor().max(-1.0) + 1.0;
+            check_range_len(count)?;
+            let mut items = Vec::new();
+            for i in 0..count as usize {
+                let value = start + i as f64 * step;
+                if value == end && !inclusive {
+                    break;
+                }
+                items.push(Value::Float(value));
+            }
+            Ok(Value::Array(items))
+        }
+    }
+}
+
+fn check_range_len(len: f64) -> Result<(), EvalError> {
+    if len > MAX_RANGE_LEN as f64 {
+        return Err(EvalError::InvalidRange(format!(
+            "range has more than {} elements",
+            MAX_RANGE_LEN
+        )));
+    }
+    Ok(())
+}
+
+fn as_float(value: &Value) -> Result<f64, EvalError> {
+    match value {
+        Value::Integer(n) => Ok(*n as f64),
+        Value::Float(n) => Ok(*n),
+        other => Err(EvalError::TypeMismatch(format!(
+            "expected a number, found {}",
+            other
+        ))),
+    }
+}
+
+// Removes the leading whitespace shared by every non-blank line, for tidying
+// indented triple-quoted strings.
+fn dedent(s: &str) -> String {
+    let indent = s
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut dedented = s
+        .lines()
+        .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+        .collect::<Vec<&str>>()
+        .join("\n");
+    if s.ends_with('\n') {
+        dedented.push('\n');
+    }
+    dedented
+}
+
+// `==` and `!=` work on any values, treating integers and floats as numbers;
+// the ordered comparisons are only defined between numbers and between strings.
+fn compare(op: &Token, left: &Value, right: &Value) -> Result<Value, EvalError> {
+    let result = match op {
+        Token::Eq => values_equal(left, right),
+        Token::NotEq => !values_equal(left, right),
+        _ => {
+            let ordering = value_ordering(left, right).ok_or_else(|| {
+                EvalError::TypeMismatch(format!(
+                    "cannot compare {} {} {}",
+                    left,
+                    op.symbol(),
+                    right
+                ))
+            })?;
+            match ordering {
+                Some(ordering) => match op {
+                    Token::Lt => ordering.is_lt(),
+                    Token::Le => ordering.is_le(),
+                    Token::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+                None => false,
+            }
+        }
+    };
+    Ok(Value::Bool(result))
+}
+
+// The ordering used by `<`, `<=`, `>` and `>=`: `None` when the operands cannot be ordered
+// and `Some(None)` when they can but are unordered (NaN). Strings compare lexicographically
+// by Unicode code point, which for UTF-8 is the same as comparing bytes, so `"B" < "a"`.
+fn value_ordering(left: &Value, right: &Value) -> Option<Option<Ordering>> {
+    match (left, right) {
+        (Value::Integer(l), Value::Integer(r)) => Some(Some(l.cmp(r))),
+        (Value::Integer(_) | Value::Float(_), Value::Integer(_) | Value::Float(_)) => {
+            Some(as_float(left).ok()?.partial_cmp(&as_float(right).ok()?))
+        }
+        (Value::Str(l), Value::Str(r)) => Some(Some(l.cmp(r))),
+        _ => None,
+    }
+}
+
+// `slice::sort_by` may panic when the comparison is not a total order, which neither a
+// user's comparator nor NaN has to be; a merge sort only ever ends up in some order.
+fn merge_sort(
+    mut items: Vec<Value>,
+    compare: &mut impl FnMut(&Value, &Value) -> Result<Ordering, EvalError>,
+) -> Result<Vec<Value>, EvalError> {
+    if items.len() < 2 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let mut left = merge_sort(items, compare)?.into_iter().peekable();
+    let mut right = merge_sort(right, compare)?.into_iter().peekable();
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Only an item that goes strictly before is taken from the right, which keeps
+        // the sort stable.
+        let next = match compare(b, a)? {
+            Ordering::Less => right.next(),
+            _ => left.next(),
+        };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+// What a `sort` comparator's result says about its arguments. NaN says they are equal.
+fn comparator_ordering(result: &Value) -> Result<Ordering, EvalError> {
+    match result {
+        Value::Integer(n) => Ok(n.cmp(&0)),
+        Value::Float(x) => Ok(x.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+        other => Err(EvalError::TypeMismatch(format!(
+            "sort() comparator returned {}, expected a number",
+            quoted(other)
+        ))),
+    }
+}
+
+// Lists and tuples test their elements, strings test for a substring and maps test their keys.
+fn contains(container: &Value, item: &Value) -> Result<bool, EvalError> {
+    match (container, item) {
+        (Value::Array(items) | Value::Tuple(items), item) => {
+            Ok(items.iter().any(|x| values_equal(x, item)))
+        }
+        (Value::Str(haystack), Value::Str(needle)) => Ok(haystack.contains(needle.as_str())),
+        (Value::Map(map), Value::Str(key)) => Ok(map.contains_key(key)),
+        (container, item) => Err(EvalError::TypeMismatch(format!(
+            "cannot test whether {} is in {}",
+            item, container
+        ))),
+    }
+}
+
+// Work for `eval_operators`, with the trace depth of the node it is for.
+enum Step<'a> {
+    Eval(&'a ASTNode, usize),
+    // A unary or binary operator whose operands are on top of the value stack.
+    Apply(&'a ASTNode, usize),
+    // A logical operator whose left operand is on top of the value stack.
+    Branch(&'a ASTNode, usize),
+    // A logical operator whose right operand is on top of the value stack.
+    Check(&'a ASTNode, usize),
+}
+
+fn is_operator(node: &ASTNode) -> bool {
+    matches!(
+        node.kind,
+        NodeKind::BinaryOp(..) | NodeKind::UnaryOp(..) | NodeKind::Logical(..)
+    )
+}
+
+// Whether `node` is an operator with another operator as an operand, which is where
+// evaluation could nest deeply enough to need `eval_operators`.
+fn nests_operators(node: &ASTNode) -> bool {
+    match &node.kind {
+        NodeKind::BinaryOp(left, _, right) | NodeKind::Logical(left, _, right) => {
+            is_operator(left) || is_operator(right)
+        }
+        NodeKind::UnaryOp(_, operand) => is_operator(operand),
+        _ => false,
+    }
+}
+
+// What a `for` loop over `value` visits: a map's keys, in order.
+fn items_of(value: Value) -> Result<Vec<Value>, EvalError> {
+    match value {
+        Value::Array(items) | Value::Tuple(items) => Ok(items),
+        Value::Map(map) => {
+            let mut keys: Vec<String> = map.into_keys().collect();
+            keys.sort();
+            Ok(keys.into_iter().map(Value::Str).collect())
+        }
+        other => Err(EvalError::NotIterable(other.to_string())),
+    }
+}
+
+fn index_into(target: Value, index: Value) -> Result<Value, EvalError> {
+    match (target, index) {
+        (Value::Array(items) | Value::Tuple(items), Value::Integer(i)) => usize::try_from(i)
+            .ok()
+            .and_then(|idx| items.get(idx).cloned())
+            .ok_or(EvalError::IndexOutOfBounds(i, items.len())),
+        (target, index) => Err(EvalError::TypeMismatch(format!(
+            "cannot index {} with {}",
+            target, index
+        ))),
+    }
+}
+
+fn field_of(target: Value, field: &str) -> Result<Value, EvalError> {
+    match target {
+        Value::Struct { type_name, fields } => fields
+            .get(field)
+            .cloned()
+            .ok_or(EvalError::NoSuchField(type_name, field.to_string())),
+        Value::Error { message, .. } if field == "message" => Ok(Value::Str(message)),
+        Value::Error { kind, .. } if field == "kind" => Ok(Value::Str(kind)),
+        other => Err(EvalError::TypeMismatch(format!(
+            "cannot access field {} of {}",
+            field, other
+        ))),
+    }
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Integer(l), Value::Float(r)) | (Value::Float(r), Value::Integer(l)) => {
+            *l as f64 == *r
+        }
+        (Value::Array(l), Value::Array(r)) | (Value::Tuple(l), Value::Tuple(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| values_equal(l, r))
+        }
+        (Value::Map(l), Value::Map(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .all(|(key, l)| r.get(key).is_some_and(|r| values_equal(l, r)))
+        }
+        (
+            Value::Struct {
+                type_name: l_type,
+                fields: l,
+            },
+            Value::Struct {
+                type_name: r_type,
+                fields: r,
+            },
+        ) => {
+            l_type == r_type
+                && l.len() == r.len()
+                && l.iter()
+                    .all(|(key, l)| r.get(key).is_some_and(|r| values_equal(l, r)))
+        }
+        _ => left == right,
+    }
+}
+
+// A number as a float, for callers that only want numbers.
+pub(crate) fn number_value(value: Value) -> Result<f64, EvalError> {
+    match value {
+        Value::Integer(n) => Ok(n as f64),
+        Value::Float(x) => Ok(x),
+        other => Err(EvalError::TypeMismatch(format!(
+            "expected a number, found {}",
+            other.type_name()
+        ))),
+    }
+}
+
+pub(crate) fn condition_value(value: Value) -> Result<bool, EvalError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(EvalError::TypeMismatch(format!(
+            "condition must be a boolean, found {}",
+            other
+        ))),
+    }
+}
+
+// An operand of `and` or `or`.
+pub(crate) fn logical_value(op: &Token, value: Value) -> Result<bool, EvalError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(EvalError::TypeMismatch(format!(
+            "`{}` expects booleans, found {}",
+            op.symbol(),
+            other
+        ))),
+    }
+}
+
+fn float_op(op: &Token, l: f64, r: f64) -> f64 {
+    match op {
+        Token::Plus => l + r,
+        Token::Minus => l - r,
+        Token::Mul => l * r,
+        Token::Div => l / r,
+        Token::Mod => l % r,
+        Token::Pow => l.powf(r),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parse(source: &str) -> ASTNode {
+        Parser::new(Lexer::new(source.to_string())).parse().unwrap()
+    }
+
+    #[test]
+    fn float_operators_follow_ieee() {
+        assert_eq!(float_op(&Token::Div, 1.0, 4.0), 0.25);
+        assert_eq!(float_op(&Token::Mod, -7.0, 3.0), -1.0);
+        assert_eq!(float_op(&Token::Pow, 2.0, 0.5), 2f64.sqrt());
+        assert!(float_op(&Token::Div, 0.0, 0.0).is_nan());
+    }
+
+    #[test]
+    fn int_truncates_floats_in_range() {
+        assert_eq!(to_int(&Value::Float(-2.9)), Ok(Value::Integer(-2)));
+        assert_eq!(
+            to_int(&Value::Str(" 42 ".to_string())),
+            Ok(Value::Integer(42))
+        );
+        for value in [
+            Value::Float(i64::MAX as f64),
+            Value::Float(f64::NAN),
+            Value::Null,
+        ] {
+            assert!(to_int(&value).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn integers_and_floats_are_equal_by_value() {
+        assert!(values_equal(&Value::Integer(2), &Value::Float(2.0)));
+        assert!(values_equal(
+            &Value::Array(vec![Value::Integer(1)]),
+            &Value::Array(vec![Value::Float(1.0)])
+        ));
+        assert!(!values_equal(
+            &Value::Float(f64::NAN),
+            &Value::Float(f64::NAN)
+        ));
+        assert!(!values_equal(
+            &Value::Integer(1),
+            &Value::Str("1".to_string())
+        ));
+    }
+
+    #[test]
+    fn a_failed_run_leaves_the_session_scope_and_depth_as_they_were() {
+        let mut interpreter = Interpreter::new();
+        interpreter.run(parse("let x = 1")).unwrap();
+        let result = interpreter.run(parse("fn f(n) { { 1 + (n * undefined) } }; f(2)"));
+        assert_eq!(
+            result,
+            Err(EvalError::UndefinedVariable("undefined".to_string()))
+        );
+        assert_eq!(interpreter.depth, 0);
+        assert!(interpreter.env.get("x").is_some());
+        assert!(interpreter.env.get("n").is_none());
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+
+use super::{
+    condition_value, contains, field_of, index_into, items_of, logical_value, make_range, quoted,
+    values_equal, Interpreter, Value,
+};
+use crate::arena::{Arm, Ast, Kind, NodeId, Part};
+use crate::error::EvalError;
+use crate::lexer::Token;
+
+// `interpret` and `eval_node` over an `Ast`, giving the same values, errors, error spans
+// and trace lines. Unlike `eval_operators`, this recurses through chains of operators,
+// though they count against the depth limit no more than there; `run` only makes an `Ast`
+// of a tree shallow enough to recurse through. A function defined here
+// keeps its body as a tree, and each call evaluates that with `interpret`.
+impl Interpreter {
+    pub fn interpret_ast(&mut self, ast: &Ast) -> Result<Value, EvalError> {
+        self.eval_at(ast, ast.root())
+    }
+
+    fn eval_at(&mut self, ast: &Ast, id: NodeId) -> Result<Value, EvalError> {
+        let (height, chained) = (ast[id].height as usize, ast[id].chained);
+        // A chained subtree's leaves are evaluated where it is, and a lone operator's a
+        // level further down.
+        if height > 1
+            && self.trace.is_none()
+            && self.depth < self.max_depth
+            && (chained || height <= self.max_depth - self.depth)
+        {
+            return self.eval_flat(ast, id);
+        }
+        let result = if self.depth == self.max_depth {
+            Err(EvalError::TooDeep(self.max_depth))
+        } else {
+            if self.trace.is_some() {
+                self.trace_line(&format!("Evaluating: {:?}", ast.subtree(id)));
+            }
+            if chained {
+                self.trace_offset += 1;
+            } else {
+                self.depth += 1;
+            }
+            let result = self.eval_arena_node(ast, id);
+            if chained {
+                self.trace_offset -= 1;
+            } else {
+                self.depth -= 1;
+            }
+            if self.trace.is_some() {
+                match &result {
+                    Ok(value) => self.trace_line(&format!("=> {}", quoted(value))),
+                    Err(err) => self.trace_line(&format!("=> error: {}", err)),
+                }
+            }
+            result
+        };
+        if result.is_err() && self.error_span.is_none() {
+            self.error_span = Some(ast[id].span);
+        }
+        result
+    }
+
+    // Evaluates a subtree of literals, variables and arithmetic in the order its nodes are
+    // stored, which is the order `eval_at` would visit them in, with operands on a stack
+    // instead of recursing. `eval_at` only calls this when none of it is too deep.
+    fn eval_flat(&mut self, ast: &Ast, id: NodeId) -> Result<Value, EvalError> {
+        let mut values = Vec::with_capacity(ast[id].height as usize);
+        for node in ast.below(id) {
+            let result = match &node.kind {
+                Kind::Number(n) => Ok(Value::Float(*n)),
+                Kind::Integer(n) => Ok(Value::Integer(*n)),
+                Kind::Bool(b) => Ok(Value::Bool(*b)),
+                Kind::Null => Ok(Value::Null),
+                Kind::Str(s) => Ok(Value::Str(s.clone())),
+                Kind::VarRef(name) => self.variable(name),
+                Kind::UnaryOp(op, _) => {
+                    let operand = values.pop().expect("one operand");
+                    self.unary_op(op, operand)
+                }
+                Kind::BinaryOp(_, op, _) => {
+                    let right = values.pop().expect("two operands");
+                    let left = values.pop().expect("two operands");
+                    self.binary_op(op, left, right)
+                }
+                _ => unreachable!("only flat subtrees are scanned"),
+            };
+            match result {
+                Ok(value) => values.push(value),
+                Err(err) => {
+                    if self.error_span.is_none() {
+                        self.error_span = Some(node.span);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(values.pop().expect("the subtree leaves one value"))
+    }
+
+    // Literals and arithmetic, which most nodes are, kept apart from the rest so that
+    // each level of an expression needs only a small stack frame.
+    fn eval_arena_node(&mut self, ast: &Ast, id: NodeId) -> Result<Value, EvalError> {
+        match &ast[id].kind {
+            Kind::Number(n) => Ok(Value::Float(*n)),
+            Kind::Integer(n) => Ok(Value::Integer(*n)),
+            Kind::Bool(b) => Ok(Value::Bool(*b)),
+            Kind::Null => Ok(Value::Null),
+            Kind::UnaryOp(op, operand) => {
+                let value = self.eval_at(ast, *operand)?;
+                self.unary_op(op, value)
+            }
+            Kind::BinaryOp(left, op, right) => {
+                let left = self.eval_at(ast, *left)?;
+                let right = self.eval_at(ast, *right)?;
+                self.binary_op(op, left, right)
+            }
+            _ => self.eval_arena_kind(ast, id),
+        }
+    }
+
+    #[inline(never)]
+    fn eval_arena_kind(&mut self, ast: &Ast, id: NodeId) -> Result<Value, EvalError> {
+        match &ast[id].kind {
+            Kind::Number(_)
+            | Kind::Integer(_)
+            | Kind::Bool(_)
+            | Kind::Null
+            | Kind::UnaryOp(..)
+            | Kind::BinaryOp(..) => unreachable!("evaluated by eval_arena_node"),
+            Kind::Str(s) => Ok(Value::Str(s.clone())),
+            Kind::FStr(parts) => {
+                let mut result = String::new();
+                for part in parts {
+                    match part {
+                        Part::Literal(s) => result.push_str(s),
+                        Part::Expr(expr) => result.push_str(&self.eval_at(ast, *expr)?.to_string()),
+                    }
+                }
+                Ok(Value::Str(result))
+            }
+            Kind::VarRef(name) => self.variable(name),
+            Kind::Let(name, value) => {
+                let value = self.eval_at(ast, *value)?;
+                self.env.check_redeclare(name)?;
+                self.env.define(name, value.clone());
+                Ok(value)
+            }
+            Kind::Const(name, value) => {
+                let value = self.eval_at(ast, *value)?;
+                self.env.check_redeclare(name)?;
+                self.env
+                    .define_const(name, value.clone(), Some(ast[id].span));
+                Ok(value)
+            }
+            Kind::Global(names) => {
+                for name in names {
+                    self.env.declare_global(name, &self.globals)?;
+                }
+                Ok(Value::Null)
+            }
+            Kind::Import(path) => {
+                self.import(path)?;
+                Ok(Value::Null)
+            }
+            Kind::Assign(name, value) => {
+                let value = self.eval_at(ast, *value)?;
+                self.env.assign(name, value.clone(), &self.globals)?;
+                Ok(value)
+            }
+            Kind::DestructureLet(names, value) => {
+                let value = self.eval_at(ast, *value)?;
+                self.destructure(names, value)
+            }
+            Kind::Sequence(statements) => {
+                let mut result = None;
+                for statement in statements {
+                    result = Some(self.eval_at(ast, *statement)?);
+                }
+                Ok(result.expect("sequence has at least two statements"))
+            }
+            Kind::Block(statements) => {
+                let env = self.env.child();
+                let saved = std::mem::replace(&mut self.env, env);
+                let result = statements
+                    .iter()
+                    .try_fold(Value::Null, |_, statement| self.eval_at(ast, *statement));
+                self.env = saved;
+                result
+            }
+            Kind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                if self.arena_condition(ast, *cond)? {
+                    self.eval_at(ast, *then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.eval_at(ast, *else_branch)
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            Kind::While { cond, body } => {
+                while self.arena_condition(ast, *cond)? {
+                    self.eval_at(ast, *body)?;
+                }
+                Ok(Value::Null)
+            }
+            Kind::For {
+                var,
+                iterable,
+                body,
+            } => {
+                let items = items_of(self.eval_at(ast, *iterable)?)?;
+                for item in items {
+                    let env = self.env.child();
+                    env.define(var, item);
+                    let saved = std::mem::replace(&mut self.env, env);
+                    let result = self.eval_at(ast, *body);
+                    self.env = saved;
+                    result?;
+                }
+                Ok(Value::Null)
+            }
+            Kind::Match { subject, arms } => {
+                let subject = self.eval_at(ast, *subject)?;
+                for (arm, body) in arms {
+                    let binding = match arm {
+                        Arm::Wildcard => None,
+                        Arm::Ident(name) => Some(name),
+                        Arm::Literal(literal) => {
+                            if !values_equal(&self.eval_at(ast, *literal)?, &subject) {
+                                continue;
+                            }
+                            None
+                        }
+                    };
+                    let env = self.env.child();
+                    if let Some(name) = binding {
+                        env.define(name, subject.clone());
+                    }
+                    let saved = std::mem::replace(&mut self.env, env);
+                    let result = self.eval_at(ast, *body);
+                    self.env = saved;
+                    return result;
+                }
+                Err(EvalError::NonExhaustiveMatch(subject.to_string()))
+            }
+            Kind::Try {
+                body,
+                binding,
+                handler,
+                finally,
+            } => {
+                let mut result = self.eval_at(ast, *body);
+                if let (Err(err), Some(handler)) = (&result, handler) {
+                    self.error_span = None;
+                    let env = self.env.child();
+                    if let Some(name) = binding {
+                        env.define(name, err.to_value());
+                    }
+                    let saved = std::mem::replace(&mut self.env, env);
+                    result = self.eval_at(ast, *handler);
+                    self.env = saved;
+                }
+                if let Some(finally) = finally {
+                    let error_span = self.error_span.take();
+                    self.eval_at(ast, *finally)?;
+                    self.error_span = error_span;
+                }
+                result
+            }
+            Kind::Throw(value) => Err(EvalError::thrown(self.eval_at(ast, *value)?)),
+            Kind::Membership {
+                item,
+                container,
+                negated,
+            } => {
+                let item = self.eval_at(ast, *item)?;
+                let container = self.eval_at(ast, *container)?;
+                Ok(Value::Bool(contains(&container, &item)? != *negated))
+            }
+            Kind::StructDef { name, fields } => {
+                self.env.define_struct(name, fields.clone());
+                Ok(Value::Null)
+            }
+            Kind::StructLit { name, fields } => {
+                let declared = self
+                    .env
+                    .get_struct(name)
+                    .ok_or_else(|| EvalError::UnknownStruct(name.clone()))?;
+                let mut values = HashMap::new();
+                for (field, value) in fields {
+                    if !declared.contains(field) {
+                        return Err(EvalError::NoSuchField(name.clone(), field.clone()));
+                    }
+                    let value = self.eval_at(ast, *value)?;
+                    values.insert(field.clone(), value);
+                }
+                if let Some(missing) = declared.iter().find(|field| !values.contains_key(*field)) {
+                    return Err(EvalError::MissingField(name.clone(), missing.clone()));
+                }
+                Ok(Value::Struct {
+                    type_name: name.clone(),
+                    fields: values,
+                })
+            }
+            Kind::FieldAccess(target, field) => {
+                let target = self.eval_at(ast, *target)?;
+                field_of(target, field)
+            }
+            Kind::SafeFieldAccess(target, field) => match self.eval_at(ast, *target)? {
+                Value::Null => Ok(Value::Null),
+                target => field_of(target, field),
+            },
+            Kind::Logical(left, Token::NullCoalesce, right) => match self.eval_at(ast, *left)? {
+                Value::Null => self.eval_at(ast, *right),
+                left => Ok(left),
+            },
+            Kind::Logical(left, op, right) => {
+                let left = logical_value(op, self.eval_at(ast, *left)?)?;
+                if left == (*op == Token::Or) {
+                    return Ok(Value::Bool(left));
+                }
+                Ok(Value::Bool(logical_value(op, self.eval_at(ast, *right)?)?))
+            }
+            Kind::Map(entries) => {
+                let mut map = HashMap::new();
+                for (key, value) in entries {
+                    let key = match self.eval_at(ast, *key)? {
+                        Value::Str(s) => s,
+                        other => {
+                            return Err(EvalError::TypeMismatch(format!(
+                                "map keys must be strings, found {}",
+                                other
+                            )))
+                        }
+                    };
+                    let value = self.eval_at(ast, *value)?;
+                    map.insert(key, value);
+                }
+                Ok(Value::Map(map))
+            }
+            Kind::Array(items) => Ok(Value::Array(self.eval_all(ast, items)?)),
+            Kind::Tuple(items) => Ok(Value::Tuple(self.eval_all(ast, items)?)),
+            Kind::Index(target, index) => {
+                let target = self.eval_at(ast, *target)?;
+                let index = self.eval_at(ast, *index)?;
+                index_into(target, index)
+            }
+            Kind::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => {
+                let start = self.eval_at(ast, *start)?;
+                let end = self.eval_at(ast, *end)?;
+                let step = match step {
+                    Some(step) => self.eval_at(ast, *step)?,
+                    None => Value::Integer(1),
+                };
+                make_range(start, end, step, *inclusive)
+            }
+            Kind::Call(callee, args) => {
+                let callee = self.eval_at(ast, *callee)?;
+                let args = self.eval_all(ast, args)?;
+                self.call_function(&callee, args)
+            }
+            Kind::Pipe(value, function) => {
+                let value = self.eval_at(ast, *value)?;
+                let function = self.eval_at(ast, *function)?;
+                self.call_function(&function, vec![value])
+            }
+            Kind::FnDef {
+                name,
+                params,
+                rest_param,
+                body,
+            } => Ok(self.define_function(name, params, rest_param, body)),
+        }
+    }
+
+    fn arena_condition(&mut self, ast: &Ast, cond: NodeId) -> Result<bool, EvalError> {
+        condition_value(self.eval_at(ast, cond)?)
+    }
+
+    fn eval_all(&mut self, ast: &Ast, ids: &[NodeId]) -> Result<Vec<Value>, EvalError> {
+        ids.iter().map(|id| self.eval_at(ast, *id)).collect()
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+
+use super::{Function, Interpreter, Value};
+use crate::ast::{ASTNode, NodeKind};
+use crate::error::EvalError;
+use crate::visitor::{walk, AstVisitor};
+
+// Remembered values of the pure subtrees a program repeats, so that a formula using
+// `sqrt(a * a + b * b)` five times computes it once. A subtree is pure when it is made of
+// literals, variables, operators, indexing, list and tuple literals and calls to built-ins
+// that `is_pure_builtin` names, and it is worth remembering when it is at least
+// `MIN_NODES` nodes and appears more than once; only the outermost of nested candidates is
+// remembered. A value is reused while every variable in the subtree, the called built-ins
+// included, holds the value it had when the subtree was computed, so reassigning one, or
+// shadowing `sqrt` with a function, computes the subtree again.
+//
+// Nodes are known by their address, which stays put while `run` is evaluating the tree
+// the memo was built for, and the memo lasts only that long.
+#[derive(Default)]
+pub(super) struct Memo {
+    ids: HashMap<usize, usize>,
+    entries: Vec<Entry>,
+    hits: usize,
+}
+
+struct Entry {
+    // Every variable name in the subtree, once each.
+    names: Vec<String>,
+    // What the names were bound to when the subtree was last computed, and what it gave.
+    // A name bound to nothing is the built-in of that name.
+    cached: Option<(Vec<Option<Value>>, Value)>,
+}
+
+const MIN_NODES: usize = 4;
+
+impl Memo {
+    pub(super) fn build(ast: &ASTNode) -> Memo {
+        let mut scan = Scan::default();
+        scan.visit(ast);
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for &(hash, _) in scan.pure.values() {
+            *counts.entry(hash).or_default() += 1;
+        }
+        let mut select = Select {
+            pure: scan.pure,
+            counts,
+            keys: HashMap::new(),
+            memo: Memo::default(),
+        };
+        select.visit(ast);
+        select.memo
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub(super) fn id(&self, node: &ASTNode) -> Option<usize> {
+        self.ids.get(&address(node)).copied()
+    }
+}
+
+fn address(node: &ASTNode) -> usize {
+    node as *const ASTNode as usize
+}
+
+// Whether a built-in gives the same result for the same arguments and does nothing else.
+// Each one is listed, so a new built-in is impure until it is added here.
+pub(super) fn is_pure_builtin(name: &str) -> bool {
+    match name {
+        "map_get" | "map_set" | "map_delete" | "map_has" | "map_keys" => true,
+        "dedent" | "trim" | "to_upper" | "to_lower" | "len" | "format" => true,
+        "find" | "replace" | "replace_all" | "starts_with" | "ends_with" => true,
+        "to_hex" | "to_bin" | "to_oct" | "from_base" => true,
+        "int" | "float" | "str" | "type" | "hash" => true,
+        "is_number" | "is_string" | "is_list" | "is_fn" => true,
+        "sum" | "product" | "avg" | "count" | "min" | "max" | "zip" | "range" => true,
+        "abs" | "sqrt" | "floor" | "ceil" | "round" => true,
+        "sin" | "cos" | "tan" | "ln" | "exp" => true,
+        "json_parse" | "json_stringify" => true,
+        "regex_match" | "regex_find" | "regex_find_all" | "regex_replace" => true,
+        "args" | "assert" | "assert_eq" => true,
+        // Reads input, files or the clock, or draws random numbers.
+        "input" | "read_file" | "file_exists" | "now" | "elapsed_ms" | "rand" | "rand_int" => false,
+        // Writes output, changes files, the random sequence or the time, or runs code.
+        "print" | "println" | "write_file" | "append_file" | "seed" | "sleep" | "parse" => false,
+        // Call functions they are given, which may do anything.
+        "map" | "filter" | "reduce" | "sort" => false,
+        _ => false,
+    }
+}
+
+// Hashes the structure of every pure subtree, leaving out spans, bottom up.
+#[derive(Default)]
+struct Scan {
+    // Each pure node of `MIN_NODES` or more by address: its hash and size.
+    pure: HashMap<usize, (u64, usize)>,
+    // The hash and size of each child visited so far, or None for an impure one.
+    stack: Vec<Option<(u64, usize)>>,
+}
+
+impl AstVisitor for Scan {
+    fn visit(&mut self, node: &ASTNode) {
+        let mark = self.stack.len();
+        walk(self, node);
+        let children = self.stack.split_off(mark);
+        let result = children
+            .into_iter()
+            .try_fold((structure(node), 1), |(mut hasher, size), child| {
+                let (hash, child_size) = child?;
+                hash.hash(&mut hasher);
+                Some((hasher, size + child_size))
+            })
+            .filter(|_| is_pure(node))
+            .map(|(hasher, size)| (hasher.finish(), size));
+        if let Some((hash, size)) = result {
+            if size >= MIN_NODES {
+                self.pure.insert(address(node), (hash, size));
+            }
+        }
+        self.stack.push(result);
+    }
+}
+
+// Whether `node` itself could be part of a pure subtree, its children aside.
+fn is_pure(node: &ASTNode) -> bool {
+    match &node.kind {
+        NodeKind::Number(_)
+        | NodeKind::Integer(_)
+        | NodeKind::Bool(_)
+        | NodeKind::Null
+        | NodeKind::Str(_)
+        | NodeKind::VarRef(_)
+        | NodeKind::UnaryOp(..)
+        | NodeKind::BinaryOp(..)
+        | NodeKind::Logical(..)
+        | NodeKind::Membership { .. }
+        | NodeKind::Index(..)
+        | NodeKind::Array(_)
+        | NodeKind::Tuple(_) => true,
+        NodeKind::Call(callee, _) => {
+            matches!(&callee.kind, NodeKind::VarRef(name) if is_pure_builtin(name))
+        }
+        _ => false,
+    }
+}
+
+// A hasher holding what distinguishes `node` from another of its kind, its children aside.
+fn structure(node: &ASTNode) -> DefaultHasher {
+    let mut hasher = DefaultHasher::new();
+    mem::discriminant(&node.kind).hash(&mut hasher);
+    match &node.kind {
+        NodeKind::Number(n) => n.to_bits().hash(&mut hasher),
+        NodeKind::Integer(n) => n.hash(&mut hasher),
+        NodeKind::Bool(b) => b.hash(&mut hasher),
+        NodeKind::Str(s) | NodeKind::VarRef(s) => s.hash(&mut hasher),
+        NodeKind::UnaryOp(op, _) | NodeKind::BinaryOp(_, op, _) | NodeKind::Logical(_, op, _) => {
+            mem::discriminant(op).hash(&mut hasher)
+        }
+        NodeKind::Membership { negated, .. } => negated.hash(&mut hasher),
+        NodeKind::Array(items) | NodeKind::Tuple(items) => items.len().hash(&mut hasher),
+        NodeKind::Call(_, args) => args.len().hash(&mut hasher),
+        _ => {}
+    }
+    hasher
+}
+
+// Gives an id to the outermost pure subtrees whose hash appears more than once. Subtrees
+// with the same hash share an id only when their debug forms, which also leave out
+// spans, are the same.
+struct Select {
+    pure: HashMap<usize, (u64, usize)>,
+    counts: HashMap<u64, usize>,
+    keys: HashMap<String, usize>,
+    memo: Memo,
+}
+
+impl AstVisitor for Select {
+    fn visit(&mut self, node: &ASTNode) {
+        let repeated = self
+            .pure
+            .get(&address(node))
+            .is_some_and(|(hash, _)| self.counts[hash] > 1);
+        if !repeated {
+            return walk(self, node);
+        }
+        let entries = &mut self.memo.entries;
+        let id = *self.keys.entry(format!("{:?}", node)).or_insert_with(|| {
+            let mut names = Names::default();
+            names.visit(node);
+            entries.push(Entry {
+                names: names.0,
+                cached: None,
+            });
+            entries.len() - 1
+        });
+        self.memo.ids.insert(address(node), id);
+    }
+}
+
+#[derive(Default)]
+struct Names(Vec<String>);
+
+impl AstVisitor for Names {
+    fn visit_var_ref(&mut self, name: &str) {
+        if !self.0.iter().any(|known| known == name) {
+            self.0.push(name.to_string());
+        }
+    }
+}
+
+impl Interpreter {
+    // The number of times the last `run` reused a remembered value; see `with_memoization`.
+    pub fn memo_hits(&self) -> usize {
+        self.memo_hits
+    }
+
+    // Evaluates `node`, which has memo entry `id`, or reuses the value it last gave.
+    pub(super) fn memoized(&mut self, id: usize, node: &ASTNode) -> Result<Value, EvalError> {
+        let values: Vec<Option<Value>> = self.memo.entries[id]
+            .names
+            .iter()
+            .map(|name| self.env.get(name))
+            .collect();
+        if let Some((known, value)) = &self.memo.entries[id].cached {
+            if known.iter().zip(&values).all(|pair| match pair {
+                (Some(a), Some(b)) => identical((a, b)),
+                (a, b) => a.is_none() && b.is_none(),
+            }) {
+                self.memo.hits += 1;
+                return Ok(value.clone());
+            }
+        }
+        let result = self.evaluate(node)?;
+        // A function among the values may have shadowed a built-in with one of its own.
+        let pure = values.iter().flatten().all(|value| match value {
+            Value::Function(Function::Builtin(name)) => is_pure_builtin(name),
+            Value::Function(_) => false,
+            _ => true,
+        });
+        if pure {
+            self.memo.entries[id].cached = Some((values, result.clone()));
+        }
+        Ok(result)
+    }
+
+    // Runs `evaluate` on `ast` with a memo built for it in place of the current one.
+    pub(super) fn with_memo(
+        &mut self,
+        ast: &ASTNode,
+        evaluate: impl FnOnce(&mut Self) -> Result<Value, EvalError>,
+    ) -> Result<Value, EvalError> {
+        let outer = mem::replace(&mut self.memo, Memo::build(ast));
+        let result = evaluate(self);
+        self.memo_hits = mem::replace(&mut self.memo, outer).hits;
+        result
+    }
+}
+
+// Whether two values are the same down to the sign of a zero, which `==` ignores.
+fn identical((a, b): (&Value, &Value)) -> bool {
+    match (a, b) {
+        (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+        (Value::Array(a), Value::Array(b)) | (Value::Tuple(a), Value::Tuple(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(identical)
+        }
+        (Value::Map(a), Value::Map(b)) => identical_fields(a, b),
+        (
+            Value::Struct {
+                type_name: a_type,
+                fields: a,
+            },
+            Value::Struct {
+                type_name: b_type,
+                fields: b,
+            },
+        ) => a_type == b_type && identical_fields(a, b),
+        (a, b) => a == b,
+    }
+}
+
+fn identical_fields(a: &HashMap<String, Value>, b: &HashMap<String, Value>) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .all(|(key, a)| b.get(key).is_some_and(|b| identical((a, b))))
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use serde_json::{Map, Number};
+
+use crate::error::EvalError;
+use crate::interpreter::Value;
+
+// Every JSON number is a float, as it is in JavaScript.
+pub fn parse(source: &str) -> Result<Value, EvalError> {
+    let json =
+        serde_json::from_str(source).map_err(|err| EvalError::JsonParseError(err.to_string()))?;
+    Ok(from_json(json))
+}
+
+// Objects come out with their keys sorted, so the same value always gives the same text.
+pub fn stringify(value: &Value) -> Result<String, EvalError> {
+    Ok(to_json(value)?.to_string())
+}
+
+fn from_json(json: serde_json::Value) -> Value {
+    match json {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Bool(b),
+        serde_json::Value::Number(n) => {
+            Value::Float(n.as_f64().expect("every JSON number fits in an f64"))
+        }
+        serde_json::Value::String(s) => Value::Str(s),
+        serde_json::Value::Array(items) => Value::Array(items.into_iter().map(from_json).collect()),
+        serde_json::Value::Object(entries) => Value::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, from_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+// A struct becomes an object of its fields, and a caught error one of its message and
+// kind. Tuples are refused rather than turned into arrays that would not read back as
+// tuples.
+fn to_json(value: &Value) -> Result<serde_json::Value, EvalError> {
+    Ok(match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Bool(b) => serde_json::Value::Bool(*b),
+        Value::Integer(n) => serde_json::Value::Number(Number::from(*n)),
+        Value::Float(n) => match Number::from_f64(*n) {
+            Some(n) => serde_json::Value::Number(n),
+            None => return Err(unserializable(value)),
+        },
+        Value::Str(s) => serde_json::Value::String(s.clone()),
+        Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(to_json).collect::<Result<_, _>>()?)
+        }
+        Value::Map(entries)
+        | Value::Struct {
+            fields: entries, ..
+        } => object(entries)?,
+        Value::Error { message, kind } => {
+            let mut entries = Map::new();
+            entries.insert("message".to_string(), message.clone().into());
+            entries.insert("kind".to_string(), kind.clone().into());
+            serde_json::Value::Object(entries)
+        }
+        Value::Tuple(_) | Value::Function(_) => return Err(unserializable(value)),
+    })
+}
+
+fn object(entries: &HashMap<String, Value>) -> Result<serde_json::Value, EvalError> {
+    let entries = entries
+        .iter()
+        .map(|(key, value)| Ok((key.clone(), to_json(value)?)))
+        .collect::<Result<Map<_, _>, EvalError>>()?;
+    Ok(serde_json::Value::Object(entries))
+}
+
+fn unserializable(value: &Value) -> EvalError {
+    EvalError::JsonSerializeError(format!("{} has no JSON form", value))
+}
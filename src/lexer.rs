@@ -0,0 +1,904 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::BufRead;
+
+use crate::error::{LexError, LexErrorKind};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum Token {
+    Number(f64),
+    Integer(i64),
+    Str(String),
+    RawStr(String),
+    FStr(Vec<FStrPart>),
+    Ident(String),
+    Let,
+    Const,
+    Global,
+    Import,
+    By,
+    True,
+    False,
+    Null,
+    If,
+    Else,
+    While,
+    For,
+    Fn,
+    Match,
+    Struct,
+    Try,
+    Catch,
+    Finally,
+    Throw,
+    In,
+    Not,
+    And,
+    Or,
+    NullCoalesce,
+    FatArrow,
+    Underscore,
+    Plus,
+    Minus,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    // `√x`, parsed as a call to `sqrt`.
+    Sqrt,
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Bar,
+    // `x |> f`, which calls `f(x)`.
+    Pipe,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    DotDot,
+    DotDotEq,
+    // `...name`, the rest parameter of a function.
+    Ellipsis,
+    Dot,
+    // `?.`, which gives null instead of reading a field of null.
+    SafeDot,
+    Colon,
+    Comma,
+    Semicolon,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    MulAssign,
+    DivAssign,
+    ModAssign,
+    EOF,
+}
+
+impl Token {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Mul => "*",
+            Token::Div => "/",
+            Token::Mod => "%",
+            Token::Pow => "^",
+            Token::Eq => "==",
+            Token::NotEq => "!=",
+            Token::Lt => "<",
+            Token::Le => "<=",
+            Token::Gt => ">",
+            Token::Ge => ">=",
+            Token::Not => "not",
+            Token::And => "and",
+            Token::Or => "or",
+            Token::NullCoalesce => "??",
+            Token::Pipe => "|>",
+            _ => "?",
+        }
+    }
+}
+
+// Source text as written, for diagnostics.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            Token::Number(n) => return write!(f, "{}", n),
+            Token::Integer(n) => return write!(f, "{}", n),
+            Token::Str(s) => return write!(f, "{:?}", s),
+            Token::RawStr(s) => return write!(f, "\"\"\"{}\"\"\"", s),
+            Token::FStr(_) => "f-string",
+            Token::Ident(name) => return write!(f, "{}", name),
+            Token::Let => "let",
+            Token::Const => "const",
+            Token::Global => "global",
+            Token::Import => "import",
+            Token::By => "by",
+            Token::True => "true",
+            Token::False => "false",
+            Token::Null => "null",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::While => "while",
+            Token::For => "for",
+            Token::Fn => "fn",
+            Token::Match => "match",
+            Token::Struct => "struct",
+            Token::Try => "try",
+            Token::Catch => "catch",
+            Token::Finally => "finally",
+            Token::Throw => "throw",
+            Token::In => "in",
+            Token::FatArrow => "=>",
+            Token::Underscore => "_",
+            Token::Sqrt => "√",
+            Token::Bar => "|",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::DotDot => "..",
+            Token::DotDotEq => "..=",
+            Token::Ellipsis => "...",
+            Token::Dot => ".",
+            Token::SafeDot => "?.",
+            Token::Colon => ":",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::Assign => "=",
+            Token::PlusAssign => "+=",
+            Token::MinusAssign => "-=",
+            Token::MulAssign => "*=",
+            Token::DivAssign => "/=",
+            Token::ModAssign => "%=",
+            Token::EOF => "end of input",
+            _ => self.symbol(),
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// Byte offsets of the first character of a token or node and one past its last.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+// Lines and columns are 1-based, with columns counted in characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+// A piece of an `f"..."` literal; `Expr` holds the raw source between braces and the
+// byte offset, line and column where it starts.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
+pub enum FStrPart {
+    Literal(String),
+    Expr {
+        source: String,
+        offset: usize,
+        line: usize,
+        col: usize,
+    },
+}
+
+// The default limits on input size; see `LexerOptions`.
+pub(crate) const DEFAULT_MAX_INPUT_LEN: usize = 16 << 20;
+pub(crate) const DEFAULT_MAX_TOKENS: usize = 1_000_000;
+
+// Enough bytes for the three characters `peek_nth` looks past the next one, however they
+// are encoded.
+const LOOKAHEAD: usize = 16;
+// How much input a lexer reading from a reader lets pile up before dropping it.
+const COMPACT_AFTER: usize = 8 << 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LexerOptions {
+    // Match keywords such as `IF` or `Mod` regardless of case.
+    pub case_insensitive: bool,
+    // Read `3,14` as a number, and `;` inside parentheses or brackets as the
+    // separator that `,` normally is, so `max(1,5; 2,5)` has two arguments. A fraction
+    // needs its leading zero, `0,5`, since `,5` is a comma and then 5, and a number with
+    // a decimal point, such as `1.5` or `.5`, is an error saying so.
+    pub decimal_comma: bool,
+    // Input longer than this many bytes, or with more tokens than `max_tokens`, is an
+    // error. `usize::MAX` turns a limit off.
+    pub max_input_len: usize,
+    pub max_tokens: usize,
+}
+
+impl Default for LexerOptions {
+    fn default() -> Self {
+        LexerOptions {
+            case_insensitive: false,
+            decimal_comma: false,
+            max_input_len: DEFAULT_MAX_INPUT_LEN,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+}
+
+pub struct Lexer {
+    // The input, or for a lexer reading from a reader the part of it read and not yet
+    // dropped; see `from_reader`.
+    input: String,
+    // The byte offset in the whole input of the first byte of `input`. Offsets below are
+    // into `input`, and spans and errors add this to them.
+    base: usize,
+    // Where the rest of the input comes from, until it ends or fails to be read.
+    reader: Option<Box<dyn BufRead>>,
+    // Why the reader failed; reported where the input read before it ends.
+    read_error: Option<String>,
+    // Byte offset of the next character to be read; always on a char boundary.
+    position: usize,
+    // Where the token being read began.
+    token_start: usize,
+    // The 1-based line and column of the next character, and where the token began.
+    line: usize,
+    col: usize,
+    token_start_line: usize,
+    token_start_col: usize,
+    // Set once `EOF` or an error has been produced; the iterator ends after that.
+    finished: bool,
+    options: LexerOptions,
+    // Brackets opened so far and not yet closed, innermost last.
+    open: Vec<char>,
+    // How many tokens have been read, not counting `EOF`.
+    count: usize,
+}
+
+// Where the lexer has got to; the input and any reader are left out.
+impl fmt::Debug for Lexer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Lexer")
+            .field("offset", &(self.base + self.position))
+            .field("line", &self.line)
+            .field("col", &self.col)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Lexer {
+    /// Lexes the whole of `input`, which it holds until it is dropped.
+    pub fn new(input: String) -> Self {
+        Self::with_options(input, LexerOptions::default())
+    }
+
+    /// [`Lexer::new`] with the keywords, limits and number format `options` gives.
+    pub fn with_options(input: String, options: LexerOptions) -> Self {
+        Lexer {
+            input,
+            base: 0,
+            reader: None,
+            read_error: None,
+            position: 0,
+            token_start: 0,
+            line: 1,
+            col: 1,
+            token_start_line: 1,
+            token_start_col: 1,
+            finished: false,
+            options,
+            open: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Lexes the input `reader` gives, a line at a time, holding only the lines the current
+    /// token is on and a few characters after it. Spans and error positions are the same
+    /// as for the whole input read into a string, though an input over `max_input_len` is
+    /// only rejected once that much of it has been read.
+    pub fn from_reader(reader: impl BufRead + 'static) -> Self {
+        Self::from_reader_with_options(reader, LexerOptions::default())
+    }
+
+    pub fn from_reader_with_options(reader: impl BufRead + 'static, options: LexerOptions) -> Self {
+        let mut lexer = Self::with_options(String::new(), options);
+        lexer.reader = Some(Box::new(reader));
+        lexer.fill();
+        lexer
+    }
+
+    // Reads lines until `LOOKAHEAD` bytes after `position` are in `input`, or the input
+    // ends.
+    fn fill(&mut self) {
+        while self.input.len() - self.position < LOOKAHEAD {
+            let Some(reader) = &mut self.reader else {
+                return;
+            };
+            match reader.read_line(&mut self.input) {
+                Ok(0) => self.reader = None,
+                Ok(_) => {}
+                Err(err) => {
+                    self.read_error = Some(err.to_string());
+                    self.reader = None;
+                }
+            }
+        }
+    }
+
+    // Drops the input before `position` once there is enough of it to be worth moving
+    // what follows. Only done between tokens, since a token's text is sliced from `input`.
+    fn compact(&mut self) {
+        if self.position >= COMPACT_AFTER {
+            self.input.drain(..self.position);
+            self.base += self.position;
+            self.position = 0;
+        }
+    }
+
+    // The line and column of the next character to be read.
+    pub fn current_position(&self) -> (usize, usize) {
+        (self.line, self.col)
+    }
+
+    // These helpers walk a byte offset into `input` rather than a `Peekable<CharIndices>`
+    // over it: the iterator would borrow `input` from the lexer that owns it, `fill` and
+    // `compact` change `input` between tokens, and `peek_nth` looks further ahead than
+    // `Peekable` can.
+
+    // The next character, without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.peek_nth(0)
+    }
+
+    // The character after `peek()`.
+    fn peek_next(&self) -> Option<char> {
+        self.peek_nth(1)
+    }
+
+    // The char `n` places after the next one. The input is sliced at a byte offset, so
+    // this only walks the `n` chars it skips.
+    fn peek_nth(&self, n: usize) -> Option<char> {
+        self.input[self.position..].chars().nth(n)
+    }
+
+    // Consumes the next character, keeping the line and column up to date.
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        if self.reader.is_some() {
+            self.fill();
+        }
+        Some(c)
+    }
+
+    // Consumes the next character if it is `c`.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() != Some(c) {
+            return false;
+        }
+        self.bump();
+        true
+    }
+
+    // Consumes characters for as long as `predicate` holds and returns them.
+    fn eat_while(&mut self, predicate: impl Fn(char) -> bool) -> &str {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.bump();
+        }
+        &self.input[start..self.position]
+    }
+
+    // Consumes the next character, which finishes `token`.
+    fn single(&mut self, token: Token) -> Token {
+        self.bump();
+        token
+    }
+
+    // The source of the token read so far.
+    fn text(&self) -> &str {
+        &self.input[self.token_start..self.position]
+    }
+
+    // Every remaining token with its span, ending with `EOF`.
+    pub fn get_tokens(&mut self) -> Result<Vec<SpannedToken>, LexError> {
+        let mut tokens = Vec::new();
+        self.tokenize_into(&mut tokens)?;
+        Ok(tokens)
+    }
+
+    // Like `get_tokens`, into a buffer that is cleared first, so that a caller lexing one
+    // input after another can keep reusing its allocation. On an error it holds the
+    // tokens before it.
+    pub fn tokenize_into(&mut self, tokens: &mut Vec<SpannedToken>) -> Result<(), LexError> {
+        tokens.clear();
+        for token in self {
+            tokens.push(token?);
+        }
+        Ok(())
+    }
+
+    // Skips whitespace and reads one token; `EOF` once the input is used up.
+    fn next_token(&mut self) -> Result<Token, LexError> {
+        // Including non-breaking and other Unicode spaces, as pasted from web pages.
+        self.eat_while(char::is_whitespace);
+        if self.reader.is_some() {
+            self.compact();
+        }
+        self.token_start = self.position;
+        self.token_start_line = self.line;
+        self.token_start_col = self.col;
+        let Some(c) = self.bump() else {
+            return Ok(Token::EOF);
+        };
+        let digit_follows = self.peek().is_some_and(|c| c.is_ascii_digit());
+        let token = match c {
+            '.' if self.eat('.') => match self.peek() {
+                Some('=') => self.single(Token::DotDotEq),
+                Some('.') => self.single(Token::Ellipsis),
+                _ => Token::DotDot,
+            },
+            '.' if !digit_follows => Token::Dot,
+            '.' => self.number()?,
+            '0'..='9' => self.number()?,
+            '(' | '{' | '[' => {
+                self.open.push(c);
+                match c {
+                    '(' => Token::LParen,
+                    '{' => Token::LBrace,
+                    _ => Token::LBracket,
+                }
+            }
+            ')' | '}' | ']' => {
+                self.open.pop();
+                match c {
+                    ')' => Token::RParen,
+                    '}' => Token::RBrace,
+                    _ => Token::RBracket,
+                }
+            }
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            ';' if self.options.decimal_comma
+                && matches!(self.open.last(), Some('(') | Some('[')) =>
+            {
+                Token::Comma
+            }
+            ';' => Token::Semicolon,
+            '=' if self.eat('=') => Token::Eq,
+            '=' if self.eat('>') => Token::FatArrow,
+            '=' => Token::Assign,
+            '!' if self.eat('=') => Token::NotEq,
+            '<' if self.eat('=') => Token::Le,
+            '<' => Token::Lt,
+            '>' if self.eat('=') => Token::Ge,
+            '>' => Token::Gt,
+            '|' if self.eat('>') => Token::Pipe,
+            '|' => Token::Bar,
+            '?' if self.eat('?') => Token::NullCoalesce,
+            '?' if self.eat('.') => Token::SafeDot,
+            '%' if self.eat('=') => Token::ModAssign,
+            '%' => Token::Mod,
+            '_' if !self.peek().is_some_and(is_ident_char) => Token::Underscore,
+            '+' if self.eat('=') => Token::PlusAssign,
+            '+' => Token::Plus,
+            '-' if self.eat('=') => Token::MinusAssign,
+            '-' | '−' => Token::Minus,
+            '*' if self.eat('=') => Token::MulAssign,
+            '*' if self.eat('*') => Token::Pow,
+            '*' | '×' => Token::Mul,
+            '/' if self.eat('=') => Token::DivAssign,
+            '/' | '÷' => Token::Div,
+            '√' => Token::Sqrt,
+            '^' => Token::Pow,
+            '"' if self.peek() == Some('"') && self.peek_next() == Some('"') => {
+                self.raw_string()?
+            }
+            '"' => self.string()?,
+            'f' if self.eat('"') => self.fstring()?,
+            _ if c.is_alphabetic() || c == '_' => self.identifier(),
+            _ => return Err(self.error(LexErrorKind::UnexpectedChar(c))),
+        };
+        Ok(token)
+    }
+
+    // `next_token`, failing instead once the input or the tokens read exceed the limits.
+    fn limited_token(&mut self) -> Result<Token, LexError> {
+        let LexerOptions {
+            max_input_len,
+            max_tokens,
+            ..
+        } = self.options;
+        if self.base + self.input.len() > max_input_len {
+            return Err(LexError {
+                kind: LexErrorKind::InputTooLong(max_input_len),
+                offset: 0,
+                line: 1,
+                col: 1,
+            });
+        }
+        let token = self.next_token();
+        if self.read_error.is_some() && self.position == self.input.len() {
+            let reason = self.read_error.take().unwrap_or_default();
+            let at = (self.position, self.line, self.col);
+            return Err(self.error_at(LexErrorKind::ReadFailed(reason), at));
+        }
+        let token = token?;
+        if token != Token::EOF {
+            self.count += 1;
+            if self.count > max_tokens {
+                return Err(self.error(LexErrorKind::TooManyTokens(max_tokens)));
+            }
+        }
+        Ok(token)
+    }
+
+    // An error at the start of the token being read.
+    fn error(&self, kind: LexErrorKind) -> LexError {
+        let at = (
+            self.token_start,
+            self.token_start_line,
+            self.token_start_col,
+        );
+        self.error_at(kind, at)
+    }
+
+    // An error at an offset into `input` with its line and column, which the lexer keeps
+    // track of rather than counting lines in the input before it, which it may not have.
+    fn error_at(&self, kind: LexErrorKind, (offset, line, col): (usize, usize, usize)) -> LexError {
+        LexError {
+            kind,
+            offset: self.base + offset,
+            line,
+            col,
+        }
+    }
+
+    // Where the character just read, which is not a newline, began.
+    fn last_char(&self) -> (usize, usize, usize) {
+        (self.position - 1, self.line, self.col - 1)
+    }
+
+    // Strings report where they were opened rather than where the input ran out.
+    fn unterminated(&self) -> LexError {
+        self.error(LexErrorKind::UnterminatedString)
+    }
+
+    // The rest of a number whose first digit, or leading `.`, has been read.
+    fn number(&mut self) -> Result<Token, LexError> {
+        let separator = if self.options.decimal_comma { ',' } else { '.' };
+        if separator == ',' && self.text() == "." {
+            return Err(self.decimal_point());
+        }
+        let mut seen_separator = false;
+        while let Some(c) = self.peek() {
+            // Stop before `..` so that `1..2` lexes as a range, not `1.` and `.2`.
+            if c == '.' && self.peek_next() == Some('.') {
+                break;
+            }
+            // `1.5` typed with decimal commas on is a slip, not `1` followed by `.5`.
+            if c == '.'
+                && separator == ','
+                && !seen_separator
+                && self.peek_next().is_some_and(|c| c.is_ascii_digit())
+            {
+                self.bump();
+                return Err(self.decimal_point());
+            }
+            // In decimal comma mode only `,` directly followed by a digit continues the
+            // number; `f(1, 2)` still has two arguments.
+            if c == ',' && (seen_separator || !self.peek_next().is_some_and(|c| c.is_ascii_digit()))
+            {
+                break;
+            }
+            if c == separator {
+                seen_separator = true;
+            } else if !c.is_ascii_digit() && c != '_' {
+                break;
+            }
+            self.bump();
+        }
+        // `1x` is one bad literal rather than a number followed by a name.
+        if !self.eat_while(is_ident_char).is_empty() {
+            let kind = LexErrorKind::InvalidLiteral(self.text().to_string());
+            return Err(self.error(kind));
+        }
+        let text = self.text();
+        let invalid = |kind: fn(String) -> LexErrorKind| Err(self.error(kind(text.to_string())));
+        // `1_000` groups digits, but an underscore must sit between two of them, and a
+        // literal has at most one decimal separator: `1_`, `1._5` and `1.2.3` are rejected.
+        let misplaced_underscore = text.ends_with('_')
+            || text
+                .chars()
+                .zip(text.chars().skip(1))
+                .any(|pair| pair == ('_', separator) || pair == (separator, '_'));
+        if misplaced_underscore || text.matches(separator).count() > 1 {
+            return invalid(LexErrorKind::InvalidNumber);
+        }
+        // Most literals are parsed straight from the input; only digit groups and decimal
+        // commas need a copy without them.
+        let digits = if text.contains(['_', ',']) {
+            Cow::Owned(text.replace('_', "").replace(',', "."))
+        } else {
+            Cow::Borrowed(text)
+        };
+        if digits.contains('.') {
+            match digits.parse::<f64>() {
+                Ok(n) => Ok(Token::Number(n)),
+                Err(_) => invalid(LexErrorKind::InvalidNumber),
+            }
+        } else {
+            match digits.parse::<i64>() {
+                Ok(n) => Ok(Token::Integer(n)),
+                Err(_) => invalid(LexErrorKind::IntegerOutOfRange),
+            }
+        }
+    }
+
+    // Reads the rest of a number whose decimal point has just been read, to report it.
+    fn decimal_point(&mut self) -> LexError {
+        self.eat_while(|c| c.is_ascii_digit() || c == '_');
+        self.error(LexErrorKind::DecimalPoint(self.text().to_string()))
+    }
+
+    // The rest of a string whose opening quote has been read.
+    fn string(&mut self) -> Result<Token, LexError> {
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(Token::Str(value)),
+                Some('\\') => value.push(self.escape()?),
+                Some(c) => value.push(c),
+                None => return Err(self.unterminated()),
+            }
+        }
+    }
+
+    // Everything up to the closing `"""` is taken verbatim, newlines and backslashes included.
+    fn raw_string(&mut self) -> Result<Token, LexError> {
+        self.bump();
+        self.bump();
+        let start = self.position;
+        loop {
+            if self.peek() == Some('"')
+                && self.peek_next() == Some('"')
+                && self.peek_nth(2) == Some('"')
+            {
+                let value = self.input[start..self.position].to_string();
+                for _ in 0..3 {
+                    self.bump();
+                }
+                return Ok(Token::RawStr(value));
+            }
+            if self.bump().is_none() {
+                return Err(self.unterminated());
+            }
+        }
+    }
+
+    // The character a backslash, already read, escapes.
+    fn escape(&mut self) -> Result<char, LexError> {
+        let backslash = self.last_char();
+        let c = match self.bump() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('{') => '{',
+            Some('}') => '}',
+            Some(c) => return Err(self.error_at(LexErrorKind::UnknownEscape(c), backslash)),
+            None => return Err(self.unterminated()),
+        };
+        Ok(c)
+    }
+
+    // `{{` and `}}` are literal braces; anything else inside braces is an expression.
+    fn fstring(&mut self) -> Result<Token, LexError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => break,
+                Some('\\') => literal.push(self.escape()?),
+                Some('{') if self.eat('{') => literal.push('{'),
+                Some('}') if self.eat('}') => literal.push('}'),
+                Some('{') => {
+                    if !literal.is_empty() {
+                        parts.push(FStrPart::Literal(std::mem::take(&mut literal)));
+                    }
+                    let (offset, line, col) = (self.base + self.position, self.line, self.col);
+                    let source = self.fstring_expr()?;
+                    parts.push(FStrPart::Expr {
+                        source,
+                        offset,
+                        line,
+                        col,
+                    });
+                }
+                Some('}') => {
+                    return Err(self.error_at(LexErrorKind::UnmatchedBrace, self.last_char()))
+                }
+                Some(c) => literal.push(c),
+                None => return Err(self.unterminated()),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(FStrPart::Literal(literal));
+        }
+        Ok(Token::FStr(parts))
+    }
+
+    // The source of an f-string expression up to its closing brace, whose opening brace
+    // has been read.
+    fn fstring_expr(&mut self) -> Result<String, LexError> {
+        let start = self.position;
+        let mut depth = 0;
+        let mut in_string = false;
+        loop {
+            match self.bump() {
+                Some('}') if depth == 0 && !in_string => {
+                    return Ok(self.input[start..self.position - 1].to_string());
+                }
+                Some('"') => in_string = !in_string,
+                Some('{') if !in_string => depth += 1,
+                Some('}') if !in_string => depth -= 1,
+                Some(_) => {}
+                None => return Err(self.unterminated()),
+            }
+        }
+    }
+
+    // The rest of a name or keyword whose first character has been read.
+    fn identifier(&mut self) -> Token {
+        self.eat_while(is_ident_char);
+        let ident = self.text();
+        if let Some(token) = keyword(ident) {
+            return token;
+        }
+        // Only a name with capitals in it can be a keyword written differently.
+        if self.options.case_insensitive && ident.bytes().any(|b| b.is_ascii_uppercase()) {
+            if let Some(token) = keyword(&ident.to_ascii_lowercase()) {
+                return token;
+            }
+        }
+        Token::Ident(ident.to_string())
+    }
+}
+
+fn keyword(ident: &str) -> Option<Token> {
+    Some(match ident {
+        "plus" => Token::Plus,
+        "minus" => Token::Minus,
+        "mul" => Token::Mul,
+        "div" => Token::Div,
+        "mod" => Token::Mod,
+        "true" => Token::True,
+        "false" => Token::False,
+        "null" => Token::Null,
+        "if" => Token::If,
+        "else" => Token::Else,
+        "while" => Token::While,
+        "for" => Token::For,
+        "fn" => Token::Fn,
+        "match" => Token::Match,
+        "struct" => Token::Struct,
+        "try" => Token::Try,
+        "catch" => Token::Catch,
+        "finally" => Token::Finally,
+        "throw" => Token::Throw,
+        "in" => Token::In,
+        "not" => Token::Not,
+        "and" => Token::And,
+        "or" => Token::Or,
+        "let" => Token::Let,
+        "const" => Token::Const,
+        "global" => Token::Global,
+        "import" => Token::Import,
+        "by" => Token::By,
+        _ => return None,
+    })
+}
+
+// Identifiers start with a letter (in any script) or `_`, and continue with letters,
+// ASCII digits and `_`.
+fn is_ident_char(c: char) -> bool {
+    c.is_alphabetic() || c.is_ascii_digit() || c == '_'
+}
+
+impl Iterator for Lexer {
+    type Item = Result<SpannedToken, LexError>;
+
+    // Yields `EOF` as the last token. After an error the lexer cannot resynchronize, so
+    // the error is the last item.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let token = self.limited_token();
+        self.finished = !matches!(token, Ok(ref token) if *token != Token::EOF);
+        Some(token.map(|token| SpannedToken {
+            token,
+            span: Span {
+                start: self.base + self.token_start,
+                end: self.base + self.position,
+            },
+            start_line: self.token_start_line,
+            start_col: self.token_start_col,
+            end_line: self.line,
+            end_col: self.col,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn peeking_counts_characters_not_bytes() {
+        let mut lexer = Lexer::new("√π1".to_string());
+        assert_eq!(lexer.peek(), Some('√'));
+        assert_eq!(lexer.peek_next(), Some('π'));
+        assert_eq!(lexer.peek_nth(2), Some('1'));
+        assert_eq!(lexer.peek_nth(3), None);
+        assert_eq!(lexer.bump(), Some('√'));
+        assert_eq!((lexer.position, lexer.line, lexer.col), (3, 1, 2));
+    }
+
+    #[test]
+    fn eating_consumes_only_what_matches() {
+        let mut lexer = Lexer::new("ab_1 c\nd".to_string());
+        assert_eq!(lexer.eat_while(is_ident_char), "ab_1");
+        assert!(!lexer.eat('c'));
+        assert!(lexer.eat(' ') && lexer.eat('c') && lexer.eat('\n'));
+        assert_eq!((lexer.line, lexer.col), (2, 1));
+        assert_eq!(lexer.eat_while(char::is_whitespace), "");
+    }
+
+    #[test]
+    fn keywords_are_whole_lowercase_words() {
+        assert_eq!(keyword("let"), Some(Token::Let));
+        assert_eq!(keyword("mod"), Some(Token::Mod));
+        assert_eq!(keyword("Let"), None);
+        assert_eq!(keyword("lets"), None);
+        assert!(is_ident_char('é') && is_ident_char('_') && !is_ident_char('-'));
+    }
+
+    #[test]
+    fn a_reader_is_held_a_window_at_a_time() {
+        let source = "let x = 1\n".repeat(5_000);
+        let mut lexer = Lexer::from_reader(Cursor::new(source.clone().into_bytes()));
+        let mut held = 0;
+        while let Some(token) = lexer.next() {
+            token.unwrap();
+            held = held.max(lexer.input.len());
+        }
+        // What has been lexed is dropped once there is `COMPACT_AFTER` of it, and no more
+        // than `LOOKAHEAD` and a line is read ahead.
+        assert!(held <= COMPACT_AFTER + LOOKAHEAD + 10, "{}", held);
+        assert_eq!(lexer.base + lexer.position, source.len());
+    }
+}
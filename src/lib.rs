@@ -0,0 +1,139 @@
+//! An interpreter for a small expression language, to embed or to run as a program. Most
+//! embedders want a number from some text, which takes one call:
+//!
+//! ```
+//! let total = interpreter::evaluate("3 plus 4 mul 2")?;
+//! assert_eq!(total, 11.0);
+//!
+//! // `evaluate_with` keeps variables between calls, as a calculator does.
+//! let mut ctx = interpreter::EvalContext::new();
+//! interpreter::evaluate_with(&mut ctx, "let x = 5")?;
+//! assert_eq!(interpreter::evaluate_with(&mut ctx, "x + 1")?, 6.0);
+//! # Ok::<(), interpreter::Error>(())
+//! ```
+//!
+//! Any failure, whether in lexing, parsing or evaluation, is an [`Error`] saying which. The
+//! stages are public for anything more: [`lexer`], [`parser`], [`interpreter`] and the
+//! modules around them.
+pub mod arena;
+pub mod ast;
+pub mod builtins;
+pub mod compiler;
+pub mod diagnostic;
+pub mod disasm;
+pub mod error;
+pub mod expr;
+pub mod interpreter;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod lexer;
+pub mod lint;
+pub mod optimizer;
+pub mod parser;
+pub mod pretty;
+#[cfg(feature = "rand")]
+pub mod random;
+pub mod repl;
+pub mod visitor;
+pub mod vm;
+
+pub use error::Error;
+pub use expr::CompiledExpr;
+pub use interpreter::Value;
+pub use repl::Repl;
+
+use std::{panic, thread};
+
+use interpreter::Interpreter;
+use lexer::Lexer;
+use parser::Parser;
+
+/// Evaluates `input` in a fresh interpreter and returns the value of its last statement.
+pub fn eval(input: &str) -> Result<Value, Error> {
+    EvalContext::new().eval(input)
+}
+
+/// Like [`eval`], for input that computes a number, as a calculator or plotter wants it.
+pub fn evaluate(input: &str) -> Result<f64, Error> {
+    evaluate_with(&mut EvalContext::new(), input)
+}
+
+/// [`evaluate`] in the session `ctx` holds: what earlier calls bound is visible to
+/// `input`, and what `input` binds is kept for later ones, even if it goes on to fail.
+///
+/// The crate's own binary does not go through this. Its REPL and script runner report
+/// every syntax error in an input, point at where a runtime error happened and print
+/// values of any type, so they use [`Interpreter`] and [`Repl`] directly.
+pub fn evaluate_with(ctx: &mut EvalContext, input: &str) -> Result<f64, Error> {
+    Ok(interpreter::number_value(ctx.eval(input)?)?)
+}
+
+/// [`evaluate`] for each of `inputs`, spread over as many threads as the machine has.
+/// Each input is evaluated in an interpreter of its own, so one cannot see another's
+/// bindings, and an error in one leaves the others be. The results are in the order of
+/// the inputs.
+pub fn evaluate_batch(inputs: &[&str]) -> Vec<Result<f64, Error>> {
+    let threads = thread::available_parallelism().map_or(1, usize::from);
+    evaluate_batch_on(inputs, threads)
+}
+
+// Batches smaller than this are evaluated on the calling thread, since starting threads
+// would cost more than it saves.
+const MIN_BATCH_PER_THREAD: usize = 256;
+
+/// [`evaluate_batch`] on at most `threads` threads.
+pub fn evaluate_batch_on(inputs: &[&str], threads: usize) -> Vec<Result<f64, Error>> {
+    let threads = threads.min(inputs.len() / MIN_BATCH_PER_THREAD);
+    if threads <= 1 {
+        return inputs.iter().map(|input| evaluate(input)).collect();
+    }
+    let chunk = inputs.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let workers: Vec<_> = inputs
+            .chunks(chunk)
+            .map(|inputs| {
+                scope.spawn(move || {
+                    inputs
+                        .iter()
+                        .map(|input| evaluate(input))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// Keeps bindings alive across `eval` calls, so `ctx.eval("let x = 5")` followed by
+/// `ctx.eval("x + 1")` gives 6.
+pub struct EvalContext {
+    interpreter: Interpreter,
+}
+
+impl EvalContext {
+    /// A session with nothing bound but the built-ins.
+    pub fn new() -> Self {
+        EvalContext {
+            interpreter: Interpreter::new(),
+        }
+    }
+
+    /// Evaluates `input` in this session and returns the value of its last statement.
+    pub fn eval(&mut self, input: &str) -> Result<Value, Error> {
+        let ast = Parser::new(Lexer::new(input.to_string())).parse()?;
+        Ok(self.interpreter.run(ast)?)
+    }
+}
+
+impl Default for EvalContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
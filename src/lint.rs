@@ -0,0 +1,104 @@
+use std::fmt;
+
+use crate::ast::{ASTNode, NodeKind};
+use crate::lexer::Span;
+use crate::visitor::{walk, AstVisitor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeadBranchKind {
+    // `if true { .. } else { .. }`
+    ElseNeverTaken,
+    // `if false { .. }`, with or without an else branch.
+    ThenNeverTaken,
+    // `while false { .. }`
+    LoopNeverRuns,
+}
+
+// Something legal but almost certainly unintended, with the span of the code it is about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    DeadBranch { span: Span, kind: DeadBranchKind },
+}
+
+impl Warning {
+    // How the program could be rewritten to say what it does.
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            Warning::DeadBranch { kind, .. } => match kind {
+                DeadBranchKind::ElseNeverTaken => "replace the `if` with its first branch",
+                DeadBranchKind::ThenNeverTaken => {
+                    "replace the `if` with its else branch, or remove it"
+                }
+                DeadBranchKind::LoopNeverRuns => "remove the loop",
+            },
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::DeadBranch { kind, .. } => {
+                let message = match kind {
+                    DeadBranchKind::ElseNeverTaken => {
+                        "else branch is unreachable: the condition is always true"
+                    }
+                    DeadBranchKind::ThenNeverTaken => {
+                        "if branch is unreachable: the condition is always false"
+                    }
+                    DeadBranchKind::LoopNeverRuns => {
+                        "loop body is unreachable: the condition is always false"
+                    }
+                };
+                write!(f, "{} (help: {})", message, self.suggestion())
+            }
+        }
+    }
+}
+
+// Looks for branches that can never run. Conditions are only recognised as literals,
+// so this is meant to run after `optimizer::fold_constants`.
+pub fn lint(node: &ASTNode) -> Vec<Warning> {
+    let mut linter = Linter {
+        warnings: Vec::new(),
+    };
+    linter.visit(node);
+    linter.warnings
+}
+
+struct Linter {
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    fn dead_branch(&mut self, span: Span, kind: DeadBranchKind) {
+        self.warnings.push(Warning::DeadBranch { span, kind });
+    }
+}
+
+// Warnings point at the whole `if` or `while`, so the checks are made where the node
+// itself is in view.
+impl AstVisitor for Linter {
+    fn visit(&mut self, node: &ASTNode) {
+        match &node.kind {
+            NodeKind::If {
+                cond, else_branch, ..
+            } => match cond.kind {
+                NodeKind::Bool(true) if else_branch.is_some() => {
+                    self.dead_branch(node.span, DeadBranchKind::ElseNeverTaken)
+                }
+                NodeKind::Bool(false) => {
+                    self.dead_branch(node.span, DeadBranchKind::ThenNeverTaken)
+                }
+                _ => {}
+            },
+            NodeKind::While { cond, .. } => {
+                if let NodeKind::Bool(false) = cond.kind {
+                    self.dead_branch(node.span, DeadBranchKind::LoopNeverRuns);
+                }
+            }
+            _ => {}
+        }
+        walk(self, node);
+    }
+}
@@ -1,229 +1,183 @@
-use std::io::{self, Write};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::thread;
+
+use interpreter::diagnostic::render_diagnostic;
+use interpreter::interpreter::{Interpreter, Value};
+use interpreter::lexer::Span;
+use interpreter::repl::show_result;
+use interpreter::{Error, Repl};
+
+const USAGE: &str = "Usage: Interpreter [--check] [--trace] [--no-warn] [--no-limits] \
+                     [script | -e <expr>...] [-- args...]";
+
+// The parser and interpreter recurse once per level of nesting, and a debug build uses
+// several times the stack per level that a release build does. The program runs on a
+// thread with room for both to reach their nesting limits in either.
+const STACK_SIZE: usize = 256 << 20;
 
-#[derive(Debug, PartialEq, Clone)]
-enum Token {
-    Number(f64),
-    Plus,
-    Minus,
-    Mul,
-    Div,
-    LParen,
-    RParen,
-    EOF,
-}
-
-struct Lexer {
-    input: String,
-    position: usize,
-    current_char: Option<char>,
-}
-
-impl Lexer {
-    fn new(input: String) -> Self {
-        let mut lexer = Lexer {
-            input,
-            position: 0,
-            current_char: None,
-        };
-        lexer.current_char = lexer.input.chars().next();
-        lexer
-    }
-
-    fn advance(&mut self) {
-        self.position += 1;
-        self.current_char = if self.position < self.input.len() {
-            Some(self.input.chars().nth(self.position).unwrap())
-        } else {
-            None
-        };
+fn main() {
+    let cli = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(cli)
+        .expect("cannot start the interpreter thread");
+    // The panic message has already been printed.
+    if cli.join().is_err() {
+        process::exit(101);
     }
+}
 
-    fn get_tokens(&mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        while let Some(c) = self.current_char {
-            match c {
-                '0'..='9' | '.' => tokens.push(self.number()),
-                ' ' | '\t' | '\n' | '\r' => self.advance(),
-                '(' => {
-                    tokens.push(Token::LParen);
-                    self.advance();
-                }
-                ')' => {
-                    tokens.push(Token::RParen);
-                    self.advance();
-                }
-                _ if c.is_alphabetic() => tokens.push(self.identifier()),
-                _ => panic!("Unexpected character: {}", c),
+// With no script or `-e` the interactive prompt starts; otherwise the program runs and
+// the process exits with 0 on success, 1 on an error and 2 on bad usage.
+fn cli() {
+    let mut args = env::args().skip(1);
+    let mut check = false;
+    let mut trace = false;
+    let mut warn = true;
+    let mut limits = true;
+    let mut script = None;
+    let mut evals = Vec::new();
+    let mut script_args = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" => {
+                script_args = args.by_ref().collect();
             }
+            "--check" => check = true,
+            "--trace" => trace = true,
+            "--no-warn" => warn = false,
+            "--no-limits" => limits = false,
+            "-e" | "--eval" => match args.next() {
+                Some(source) => evals.push(source),
+                None => usage_error(&format!("{} needs an expression", arg)),
+            },
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option {}", arg)),
+            _ if script.is_none() => script = Some(arg),
+            _ => usage_error(&format!("unexpected argument {}", arg)),
         }
-        tokens.push(Token::EOF);
-        tokens
     }
 
-    fn number(&mut self) -> Token {
-        let start_pos = self.position;
-        while let Some(c) = self.current_char {
-            if c.is_numeric() || c == '.' {
-                self.advance();
-            } else {
-                break;
+    // The trace goes to stderr so that it stays apart from the program's output.
+    let mut interpreter = if trace {
+        Interpreter::with_trace(Box::new(io::stderr()))
+    } else {
+        Interpreter::new()
+    };
+    interpreter.warnings = warn;
+    // Lifts the limits on input size, for trusted input too big for them.
+    if !limits {
+        interpreter.max_input_len = usize::MAX;
+        interpreter.max_tokens = usize::MAX;
+        interpreter.max_nodes = usize::MAX;
+    }
+    interpreter.args = script_args;
+
+    match script {
+        Some(_) if !evals.is_empty() => usage_error("--eval cannot be combined with a script"),
+        Some(path) => match fs::read_to_string(&path) {
+            Ok(source) => {
+                interpreter.set_script(Path::new(&path));
+                process::exit(run(interpreter, &[source], check))
             }
-        }
-        let number_str: String = self.input[start_pos..self.position].to_string();
-        Token::Number(number_str.parse::<f64>().unwrap())
-    }
-
-    fn identifier(&mut self) -> Token {
-        let start_pos = self.position;
-        while let Some(c) = self.current_char {
-            if c.is_alphanumeric() {
-                self.advance();
-            } else {
-                break;
+            Err(err) => {
+                eprintln!("Error: cannot read {}: {}", path, err);
+                process::exit(1);
             }
-        }
-        let ident: String = self.input[start_pos..self.position].to_string();
-        match ident.as_str() {
-            "plus" => Token::Plus,
-            "minus" => Token::Minus,
-            "mul" => Token::Mul,
-            "div" => Token::Div,
-            _ => panic!("Unexpected identifier: {}", ident),
-        }
+        },
+        None if !evals.is_empty() => process::exit(run(interpreter, &evals, check)),
+        None if check => usage_error("--check needs a script or -e"),
+        None => Repl::with_interpreter(interpreter).run(),
     }
 }
 
-#[derive(Debug)]
-enum ASTNode {
-    Number(f64),
-    BinaryOp(Box<ASTNode>, Token, Box<ASTNode>),
-}
-
-struct Parser {
-    tokens: Vec<Token>,
-    position: usize,
+fn usage_error(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    eprintln!("{}", USAGE);
+    process::exit(2);
 }
 
-impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
-        Parser { tokens, position: 0 }
-    }
-
-    fn parse(&mut self) -> ASTNode {
-        self.expression()
-    }
-
-    fn advance(&mut self) {
-        self.position += 1;
-    }
-
-    fn current_token(&self) -> &Token {
-        &self.tokens[self.position]
-    }
-
-    fn expression(&mut self) -> ASTNode {
-        self.term()
-    }
-
-    fn term(&mut self) -> ASTNode {
-        let mut node = self.factor();
-        while let Token::Plus | Token::Minus = self.current_token() {
-            let op = self.current_token().clone();
-            self.advance();
-            node = ASTNode::BinaryOp(Box::new(node), op, Box::new(self.factor()));
+// Parses every source, then evaluates them in order in one interpreter and prints the
+// value of the last. Every syntax error in every source is reported before giving up.
+fn run(mut interpreter: Interpreter, sources: &[String], check: bool) -> i32 {
+    let mut programs = Vec::new();
+    let mut failed = false;
+    for source in sources {
+        let (ast, errors) = interpreter.parser(source.clone()).parse_recovering();
+        // An empty script runs, doing nothing.
+        if matches!(errors.as_slice(), [Error::Parse(err)] if err.is_empty_input()) {
+            continue;
         }
-        node
-    }
-
-    fn factor(&mut self) -> ASTNode {
-        let mut node = self.primary();
-        while let Token::Mul | Token::Div = self.current_token() {
-            let op = self.current_token().clone();
-            self.advance();
-            node = ASTNode::BinaryOp(Box::new(node), op, Box::new(self.primary()));
+        for err in &errors {
+            report(source, err, None);
         }
-        node
-    }
-
-    fn primary(&mut self) -> ASTNode {
-        match self.current_token() {
-            Token::Number(n) => {
-                let value = *n;
-                self.advance();
-                ASTNode::Number(value)
-            }
-            Token::LParen => {
-                self.advance();
-                let node = self.expression();
-                self.expect(Token::RParen);
-                node
-            }
-            _ => panic!("Unexpected token: {:?}", self.current_token()),
+        match ast {
+            Some(ast) if errors.is_empty() => programs.push((source, ast)),
+            _ => failed = true,
         }
     }
-
-    fn expect(&mut self, expected: Token) {
-        if *self.current_token() == expected {
-            self.advance();
-        } else {
-            panic!("Expected {:?}, found {:?}", expected, self.current_token());
-        }
+    if failed {
+        return 1;
     }
-}
-
-struct Interpreter;
-
-impl Interpreter {
-    fn new() -> Self {
-        Interpreter
+    if check {
+        return 0;
     }
-
-    fn interpret(&mut self, node: &ASTNode) -> f64 {
-        match node {
-            ASTNode::Number(n) => *n,
-            ASTNode::BinaryOp(left, op, right) => {
-                let left_val = self.interpret(left);
-                let right_val = self.interpret(right);
-                match op {
-                    Token::Plus => left_val + right_val,
-                    Token::Minus => left_val - right_val,
-                    Token::Mul => left_val * right_val,
-                    Token::Div => left_val / right_val,
-                    _ => unreachable!(),
-                }
+    let mut result = Value::Null;
+    for (source, ast) in programs {
+        match interpreter.run(ast) {
+            Ok(value) => result = value,
+            Err(err) => {
+                report(source, &Error::Runtime(err), interpreter.error_span());
+                return 1;
             }
         }
     }
+    if result != Value::Null {
+        println!("{}", show_result(&result, false));
+    }
+    0
 }
 
-fn main() {
-    loop {
-        let mut input = String::new(); 
-        print!("Enter expression or type 'exit' to quit: ");
-        io::stdout().flush().unwrap();
-        io::stdin().read_line(&mut input).unwrap();
-
-        let input = input.trim().to_string();
-        if input.is_empty() {
-            continue;
-        }
-
-        if input.eq_ignore_ascii_case("exit") {
-            println!("Exiting...");
-            break;
+// Prints an error under the line it was found on, with the source it points at
+// underlined. A runtime error's span comes from the interpreter and is only used if it
+// lies within `source`.
+fn report(source: &str, err: &Error, runtime_span: Option<Span>) {
+    let span = match err {
+        Error::Lex(err) => Span {
+            start: err.offset,
+            end: err.offset,
+        },
+        Error::Parse(err) => match &err.unclosed {
+            Some(bracket) => bracket.span,
+            None => err.span,
+        },
+        Error::Runtime(_) => {
+            match runtime_span.filter(|span| source.get(span.start..span.end).is_some()) {
+                Some(span) => span,
+                None => {
+                    eprintln!("Error: {}", err);
+                    return;
+                }
+            }
         }
-
-        let mut lexer = Lexer::new(input);
-        let tokens = lexer.get_tokens();
-        println!("{:?}", tokens);
-
-        let mut parser = Parser::new(tokens);
-        let ast = parser.parse();
-        println!("{:?}", ast);
-
-        let mut interpreter = Interpreter::new();
-        let result = interpreter.interpret(&ast);
-        println!("Result: {}", result);
-    }
+    };
+    let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let column = source[line_start..span.start].chars().count() + 1;
+    let position = if source.contains('\n') {
+        let line = source[..line_start].matches('\n').count() + 1;
+        format!("line {}, column {}", line, column)
+    } else {
+        format!("column {}", column)
+    };
+    let message = match err {
+        Error::Lex(err) => format!("{} at {}", err.kind, position),
+        Error::Parse(err) => err.describe(&position),
+        Error::Runtime(err) => format!("{} at {}", err, position),
+    };
+    eprintln!(
+        "{}",
+        render_diagnostic(source, span, &format!("Error: {}", message))
+    );
 }
-
@@ -0,0 +1,363 @@
+use std::rc::Rc;
+
+use crate::ast::{ASTNode, FStrNode, NodeKind};
+use crate::interpreter::{Interpreter, Value};
+use crate::lexer::{Span, Token};
+
+// Replaces operators whose operands are all number or bool literals with their result.
+// Folding goes through the interpreter's own operators, so a folded tree evaluates
+// exactly as the original did. Anything that fails or gives a non-finite float is left
+// in place to be evaluated, and reported, at run time.
+pub fn fold_constants(node: ASTNode) -> ASTNode {
+    Folder {
+        interpreter: Interpreter::new(),
+        identities: None,
+    }
+    .fold(node)
+}
+
+// Folds constants as `fold_constants` does and rewrites arithmetic identities as it goes.
+// With `FloatMath::Strict` the rewritten tree gives exactly what the original does under
+// the interpreter's default settings, NaN, infinities, -0.0 and whether a number is an
+// integer or a float included, so only these apply:
+//
+//   x * 1, 1 * x, x - 0    x, where an integer 1 or 0 is given or x is a float
+//   x / 1                  x, where x is a float, since dividing integers gives a float
+//   -(-x)                  x, where x is a float, since the smallest integer cannot be negated
+//   not not x              x, where x is a boolean
+//
+// `FloatMath::Fast` gives those distinctions up, lifting the conditions above and adding:
+//
+//   x + 0, 0 + x           x, though -0.0 + 0 is 0.0
+//   x * 0, 0 * x           0, though NaN * 0 is NaN
+//   x - x                  0, the same
+//
+// The last two drop x, so they only apply when it is a literal or a variable, which
+// cannot fail or have an effect. The rules assume an operand whose type is not known is a
+// number, so one that is not, which would have made the operator fail, may get through.
+pub fn simplify(node: ASTNode, math: FloatMath) -> ASTNode {
+    Folder {
+        interpreter: Interpreter::new(),
+        identities: Some(math),
+    }
+    .fold(node)
+}
+
+// How freely `simplify` may treat floats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatMath {
+    Strict,
+    Fast,
+}
+
+struct Folder {
+    interpreter: Interpreter,
+    // Set when identities are rewritten as well as constants folded.
+    identities: Option<FloatMath>,
+}
+
+impl Folder {
+    // A folded node keeps the span of the expression it replaces.
+    fn fold(&self, node: ASTNode) -> ASTNode {
+        let span = node.span;
+        let kind = match node.into_kind() {
+            NodeKind::UnaryOp(op, operand) => {
+                let operand = self.fold(*operand);
+                match literal_value(&operand)
+                    .and_then(|value| self.interpreter.unary_op(&op, value).ok())
+                    .and_then(literal)
+                {
+                    Some(folded) => folded,
+                    None => match self.identities {
+                        Some(math) => unary_identity(op, operand, math),
+                        None => NodeKind::UnaryOp(op, Box::new(operand)),
+                    },
+                }
+            }
+            NodeKind::BinaryOp(left, op, right) => {
+                let left = self.fold(*left);
+                let right = self.fold(*right);
+                match literal_value(&left)
+                    .zip(literal_value(&right))
+                    .and_then(|(l, r)| self.interpreter.binary_op(&op, l, r).ok())
+                    .and_then(literal)
+                {
+                    Some(folded) => folded,
+                    None => match self.identities {
+                        Some(math) => binary_identity(left, op, right, math),
+                        None => NodeKind::BinaryOp(Box::new(left), op, Box::new(right)),
+                    },
+                }
+            }
+            NodeKind::FStr(parts) => NodeKind::FStr(
+                parts
+                    .into_iter()
+                    .map(|part| match part {
+                        FStrNode::Expr(expr) => FStrNode::Expr(self.fold(expr)),
+                        literal => literal,
+                    })
+                    .collect(),
+            ),
+            NodeKind::Let(name, value) => NodeKind::Let(name, self.fold_box(value)),
+            NodeKind::DestructureLet(names, value) => {
+                NodeKind::DestructureLet(names, self.fold_box(value))
+            }
+            NodeKind::Const(name, value) => NodeKind::Const(name, self.fold_box(value)),
+            NodeKind::Assign(name, value) => NodeKind::Assign(name, self.fold_box(value)),
+            NodeKind::Sequence(statements) => NodeKind::Sequence(self.fold_all(statements)),
+            NodeKind::Block(statements) => NodeKind::Block(self.fold_all(statements)),
+            NodeKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => NodeKind::If {
+                cond: self.fold_box(cond),
+                then_branch: self.fold_box(then_branch),
+                else_branch: else_branch.map(|branch| self.fold_box(branch)),
+            },
+            NodeKind::While { cond, body } => NodeKind::While {
+                cond: self.fold_box(cond),
+                body: self.fold_box(body),
+            },
+            NodeKind::For {
+                var,
+                iterable,
+                body,
+            } => NodeKind::For {
+                var,
+                iterable: self.fold_box(iterable),
+                body: self.fold_box(body),
+            },
+            NodeKind::Match { subject, arms } => NodeKind::Match {
+                subject: self.fold_box(subject),
+                arms: arms
+                    .into_iter()
+                    .map(|(pattern, body)| (pattern, self.fold(body)))
+                    .collect(),
+            },
+            NodeKind::Try {
+                body,
+                binding,
+                handler,
+                finally,
+            } => NodeKind::Try {
+                body: self.fold_box(body),
+                binding,
+                handler: handler.map(|handler| self.fold_box(handler)),
+                finally: finally.map(|finally| self.fold_box(finally)),
+            },
+            NodeKind::Throw(value) => NodeKind::Throw(self.fold_box(value)),
+            NodeKind::Membership {
+                item,
+                container,
+                negated,
+            } => NodeKind::Membership {
+                item: self.fold_box(item),
+                container: self.fold_box(container),
+                negated,
+            },
+            NodeKind::Logical(left, op, right) => {
+                NodeKind::Logical(self.fold_box(left), op, self.fold_box(right))
+            }
+            NodeKind::Call(callee, args) => {
+                NodeKind::Call(self.fold_box(callee), self.fold_all(args))
+            }
+            NodeKind::Pipe(value, function) => {
+                NodeKind::Pipe(self.fold_box(value), self.fold_box(function))
+            }
+            // Parameters or a body already shared with a closure are left alone.
+            NodeKind::FnDef {
+                name,
+                params,
+                rest_param,
+                body,
+            } => NodeKind::FnDef {
+                name,
+                params: match Rc::try_unwrap(params) {
+                    Ok(params) => Rc::new(
+                        params
+                            .into_iter()
+                            .map(|(param, default)| (param, default.map(|d| self.fold(d))))
+                            .collect(),
+                    ),
+                    Err(params) => params,
+                },
+                rest_param,
+                body: match Rc::try_unwrap(body) {
+                    Ok(body) => Rc::new(self.fold(body)),
+                    Err(body) => body,
+                },
+            },
+            NodeKind::StructLit { name, fields } => NodeKind::StructLit {
+                name,
+                fields: fields
+                    .into_iter()
+                    .map(|(field, value)| (field, self.fold(value)))
+                    .collect(),
+            },
+            NodeKind::FieldAccess(target, field) => {
+                NodeKind::FieldAccess(self.fold_box(target), field)
+            }
+            NodeKind::SafeFieldAccess(target, field) => {
+                NodeKind::SafeFieldAccess(self.fold_box(target), field)
+            }
+            NodeKind::Map(entries) => NodeKind::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (self.fold(key), self.fold(value)))
+                    .collect(),
+            ),
+            NodeKind::Array(items) => NodeKind::Array(self.fold_all(items)),
+            NodeKind::Tuple(items) => NodeKind::Tuple(self.fold_all(items)),
+            NodeKind::Index(target, index) => {
+                NodeKind::Index(self.fold_box(target), self.fold_box(index))
+            }
+            NodeKind::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => NodeKind::Range {
+                start: self.fold_box(start),
+                end: self.fold_box(end),
+                step: step.map(|step| self.fold_box(step)),
+                inclusive,
+            },
+            leaf @ (NodeKind::Number(_)
+            | NodeKind::Integer(_)
+            | NodeKind::Bool(_)
+            | NodeKind::Null
+            | NodeKind::Str(_)
+            | NodeKind::VarRef(_)
+            | NodeKind::Global(_)
+            | NodeKind::Import(_)
+            | NodeKind::StructDef { .. }) => leaf,
+        };
+        ASTNode::new(kind, span)
+    }
+
+    // Folds in place, reusing the allocation.
+    fn fold_box(&self, mut node: Box<ASTNode>) -> Box<ASTNode> {
+        let inner = std::mem::replace(
+            &mut *node,
+            ASTNode::new(NodeKind::Bool(false), Span::default()),
+        );
+        *node = self.fold(inner);
+        node
+    }
+
+    fn fold_all(&self, nodes: Vec<ASTNode>) -> Vec<ASTNode> {
+        nodes.into_iter().map(|node| self.fold(node)).collect()
+    }
+}
+
+fn literal_value(node: &ASTNode) -> Option<Value> {
+    match node.kind {
+        NodeKind::Number(n) => Some(Value::Float(n)),
+        NodeKind::Integer(n) => Some(Value::Integer(n)),
+        NodeKind::Bool(b) => Some(Value::Bool(b)),
+        _ => None,
+    }
+}
+
+fn literal(value: Value) -> Option<NodeKind> {
+    match value {
+        Value::Float(n) if n.is_finite() => Some(NodeKind::Number(n)),
+        Value::Integer(n) => Some(NodeKind::Integer(n)),
+        Value::Bool(b) => Some(NodeKind::Bool(b)),
+        _ => None,
+    }
+}
+
+// What an expression is known to give when it does not fail.
+#[derive(Clone, Copy, PartialEq)]
+enum Ty {
+    Float,
+    Bool,
+    Unknown,
+}
+
+fn ty(node: &ASTNode) -> Ty {
+    match &node.kind {
+        NodeKind::Number(_) => Ty::Float,
+        NodeKind::Bool(_) | NodeKind::Membership { .. } => Ty::Bool,
+        NodeKind::Logical(_, Token::And | Token::Or, _) => Ty::Bool,
+        NodeKind::UnaryOp(Token::Not, _) => Ty::Bool,
+        NodeKind::UnaryOp(Token::Minus, operand) if ty(operand) == Ty::Float => Ty::Float,
+        NodeKind::BinaryOp(_, Token::Eq | Token::NotEq, _)
+        | NodeKind::BinaryOp(_, Token::Lt | Token::Le | Token::Gt | Token::Ge, _) => Ty::Bool,
+        // Dividing gives a float whatever the operands, and the other arithmetic operators
+        // do when either operand is one.
+        NodeKind::BinaryOp(_, Token::Div, _) => Ty::Float,
+        NodeKind::BinaryOp(left, Token::Plus | Token::Minus | Token::Mul, right)
+        | NodeKind::BinaryOp(left, Token::Mod | Token::Pow, right)
+            if ty(left) == Ty::Float || ty(right) == Ty::Float =>
+        {
+            Ty::Float
+        }
+        _ => Ty::Unknown,
+    }
+}
+
+// Whether evaluating `node` can neither fail nor have an effect. A variable counts, on the
+// understanding that it is defined.
+fn pure(node: &ASTNode) -> bool {
+    matches!(
+        node.kind,
+        NodeKind::Integer(_)
+            | NodeKind::Number(_)
+            | NodeKind::Bool(_)
+            | NodeKind::Null
+            | NodeKind::Str(_)
+            | NodeKind::VarRef(_)
+    )
+}
+
+fn is_number(node: &ASTNode, n: f64) -> bool {
+    match node.kind {
+        NodeKind::Integer(m) => m as f64 == n,
+        NodeKind::Number(m) => m == n,
+        _ => false,
+    }
+}
+
+// Whether `x op n`, for `n` the literal 1 or 0, may become `x` under `math`: it keeps the
+// kind of number `x` is when `n` is an integer or `x` a float.
+fn drops_to(x: &ASTNode, n: &ASTNode, math: FloatMath) -> bool {
+    math == FloatMath::Fast || matches!(n.kind, NodeKind::Integer(_)) || ty(x) == Ty::Float
+}
+
+fn same_variable(left: &ASTNode, right: &ASTNode) -> bool {
+    matches!((&left.kind, &right.kind), (NodeKind::VarRef(a), NodeKind::VarRef(b)) if a == b)
+}
+
+fn unary_identity(op: Token, operand: ASTNode, math: FloatMath) -> NodeKind {
+    let span = operand.span;
+    match (&op, operand.into_kind()) {
+        (Token::Minus, NodeKind::UnaryOp(Token::Minus, x))
+            if math == FloatMath::Fast || ty(&x) == Ty::Float =>
+        {
+            x.into_kind()
+        }
+        (Token::Not, NodeKind::UnaryOp(Token::Not, x)) if ty(&x) == Ty::Bool => x.into_kind(),
+        (_, kind) => NodeKind::UnaryOp(op, Box::new(ASTNode::new(kind, span))),
+    }
+}
+
+fn binary_identity(left: ASTNode, op: Token, right: ASTNode, math: FloatMath) -> NodeKind {
+    let fast = math == FloatMath::Fast;
+    let one = |n: &ASTNode| is_number(n, 1.0);
+    let zero = |n: &ASTNode| is_number(n, 0.0);
+    match op {
+        Token::Mul if one(&right) && drops_to(&left, &right, math) => left.into_kind(),
+        Token::Mul if one(&left) && drops_to(&right, &left, math) => right.into_kind(),
+        Token::Minus if zero(&right) && drops_to(&left, &right, math) => left.into_kind(),
+        Token::Div if one(&right) && (fast || ty(&left) == Ty::Float) => left.into_kind(),
+        Token::Plus if fast && zero(&right) => left.into_kind(),
+        Token::Plus if fast && zero(&left) => right.into_kind(),
+        Token::Mul if fast && zero(&right) && pure(&left) => right.into_kind(),
+        Token::Mul if fast && zero(&left) && pure(&right) => left.into_kind(),
+        Token::Minus if fast && same_variable(&left, &right) => NodeKind::Integer(0),
+        op => NodeKind::BinaryOp(Box::new(left), op, Box::new(right)),
+    }
+}
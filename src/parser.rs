@@ -0,0 +1,1441 @@
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::arena::Ast;
+use crate::ast::{ASTNode, FStrNode, NodeKind, Param, Pattern};
+use crate::error::{Error, LexError, ParseError, ParseErrorKind, ParseLimit, PROGRAM};
+use crate::lexer::{FStrPart, Lexer, LexerOptions, Span, SpannedToken, Token};
+
+#[cfg(test)]
+mod legacy;
+
+type TokenStream = Box<dyn Iterator<Item = Result<SpannedToken, LexError>>>;
+
+// Binding powers, loosest first. An operator's operands are built from operators that bind
+// more tightly, or as tightly on the side it associates to.
+const PIPE: u8 = 1;
+const COALESCE: u8 = 2;
+const OR: u8 = 3;
+const AND: u8 = 4;
+const NOT: u8 = 5;
+const COMPARISON: u8 = 6;
+const RANGE: u8 = 7;
+const TERM: u8 = 8;
+const FACTOR: u8 = 9;
+const POWER: u8 = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Assoc {
+    Left,
+    Right,
+    // `a..b..c` is an error rather than a range of ranges.
+    Neither,
+}
+
+// The node an infix operator builds.
+#[derive(Clone, Copy)]
+enum Infix {
+    Binary,
+    Logical,
+    Pipe,
+    // After an operand, `not` can only begin `not in`.
+    Membership { negated: bool },
+    // `a..b by c`
+    Range { inclusive: bool },
+}
+
+// `a ?? b` binds more loosely than `or`, so `x ?? a or b` defaults to `a or b`, and `^`
+// binds tighter than unary minus on its left, so `-2 ^ 2` is `-(2 ^ 2)`.
+const INFIX: &[(Token, u8, Assoc, Infix)] = &[
+    (Token::Pipe, PIPE, Assoc::Left, Infix::Pipe),
+    (Token::NullCoalesce, COALESCE, Assoc::Left, Infix::Logical),
+    (Token::Or, OR, Assoc::Left, Infix::Logical),
+    (Token::And, AND, Assoc::Left, Infix::Logical),
+    (Token::Eq, COMPARISON, Assoc::Left, Infix::Binary),
+    (Token::NotEq, COMPARISON, Assoc::Left, Infix::Binary),
+    (Token::Lt, COMPARISON, Assoc::Left, Infix::Binary),
+    (Token::Le, COMPARISON, Assoc::Left, Infix::Binary),
+    (Token::Gt, COMPARISON, Assoc::Left, Infix::Binary),
+    (Token::Ge, COMPARISON, Assoc::Left, Infix::Binary),
+    (
+        Token::In,
+        COMPARISON,
+        Assoc::Left,
+        Infix::Membership { negated: false },
+    ),
+    (
+        Token::Not,
+        COMPARISON,
+        Assoc::Left,
+        Infix::Membership { negated: true },
+    ),
+    (
+        Token::DotDot,
+        RANGE,
+        Assoc::Neither,
+        Infix::Range { inclusive: false },
+    ),
+    (
+        Token::DotDotEq,
+        RANGE,
+        Assoc::Neither,
+        Infix::Range { inclusive: true },
+    ),
+    (Token::Plus, TERM, Assoc::Left, Infix::Binary),
+    (Token::Minus, TERM, Assoc::Left, Infix::Binary),
+    (Token::Mul, FACTOR, Assoc::Left, Infix::Binary),
+    (Token::Div, FACTOR, Assoc::Left, Infix::Binary),
+    (Token::Mod, FACTOR, Assoc::Left, Infix::Binary),
+    (Token::Pow, POWER, Assoc::Right, Infix::Binary),
+];
+
+// Prefix operators and the binding power of their operand. `not` only begins an operand
+// that may bind that loosely, so `1 + not x` is an error, while `-` may begin any.
+const PREFIX: &[(Token, u8)] = &[
+    (Token::Not, NOT),
+    (Token::Minus, POWER),
+    (Token::Sqrt, POWER),
+];
+
+// How deeply expressions may nest by default; see `with_max_nesting`.
+const DEFAULT_MAX_NESTING: usize = 500;
+
+// How many nodes a syntax tree may have by default; see `with_max_nodes`.
+pub(crate) const DEFAULT_MAX_NODES: usize = 1_000_000;
+
+// Pulls tokens from the lexer as it goes, so a lex error is reported without
+// tokenizing the rest of the input. One token of lookahead is buffered; both
+// buffers hold `EOF` once the stream runs out.
+pub struct Parser {
+    tokens: TokenStream,
+    current: SpannedToken,
+    lookahead: SpannedToken,
+    // Set while parsing an `if`/`while` condition or `match` subject, where `name {`
+    // must open the body rather than a struct literal.
+    no_struct_literal: bool,
+    // Brackets consumed and not yet closed, innermost last.
+    open: Vec<SpannedToken>,
+    // Where the last consumed token ended, which is where a node being built ends.
+    last_end: usize,
+    // Set by `parse_recovering`, which collects syntax errors here instead of stopping.
+    recovering: bool,
+    errors: Vec<Error>,
+    // How many nested expressions are being parsed, and how many may be.
+    nesting: usize,
+    max_nesting: usize,
+    // How many nodes have been built, and how many may be.
+    nodes: Cell<usize>,
+    max_nodes: usize,
+    // How the expressions in f-strings are lexed, which should be how the rest was.
+    lexer_options: LexerOptions,
+    // Parses operators with the chain of functions that `operators` replaced, so tests can
+    // check that both build the same trees; see `legacy`.
+    #[cfg(test)]
+    legacy_expressions: bool,
+}
+
+// The token the parser is at and its limits; the token stream is left out.
+impl fmt::Debug for Parser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Parser")
+            .field("current", &self.current)
+            .field("max_nesting", &self.max_nesting)
+            .field("max_nodes", &self.max_nodes)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Parser {
+    /// Parses the tokens `tokens` gives, usually a [`Lexer`], pulling each as it is needed.
+    pub fn new(tokens: impl Iterator<Item = Result<SpannedToken, LexError>> + 'static) -> Self {
+        let eof = SpannedToken {
+            token: Token::EOF,
+            span: Span::default(),
+            start_line: 1,
+            start_col: 1,
+            end_line: 1,
+            end_col: 1,
+        };
+        Parser {
+            tokens: Box::new(tokens),
+            current: eof.clone(),
+            lookahead: eof,
+            no_struct_literal: false,
+            open: Vec::new(),
+            last_end: 0,
+            recovering: false,
+            errors: Vec::new(),
+            nesting: 0,
+            max_nesting: DEFAULT_MAX_NESTING,
+            nodes: Cell::new(0),
+            max_nodes: DEFAULT_MAX_NODES,
+            lexer_options: LexerOptions::default(),
+            #[cfg(test)]
+            legacy_expressions: false,
+        }
+    }
+
+    /// Parsing recurses once per nested expression, so input nested deeper than `limit`
+    /// is rejected rather than allowed to overflow the stack.
+    pub fn with_max_nesting(mut self, limit: usize) -> Self {
+        self.max_nesting = limit;
+        self
+    }
+
+    /// Input that builds a tree of more than `limit` nodes is rejected as soon as it does.
+    /// `usize::MAX` turns the limit off.
+    pub fn with_max_nodes(mut self, limit: usize) -> Self {
+        self.max_nodes = limit;
+        self
+    }
+
+    // The options the tokens were lexed with, for lexing the expressions inside f-strings
+    // the same way.
+    pub fn with_lexer_options(mut self, options: LexerOptions) -> Self {
+        self.lexer_options = options;
+        self
+    }
+
+    // Input with nothing in it is an error for which `ParseError::is_empty_input` holds.
+    pub fn parse(&mut self) -> Result<ASTNode, Error> {
+        self.lookahead = self.next_token()?;
+        self.advance()?;
+        if *self.current_token() == Token::EOF {
+            return Err(self.error(PROGRAM));
+        }
+        let mut statements = self.statements(Token::EOF)?;
+        self.expect(Token::EOF)?;
+        self.check_nodes()?;
+        if statements.len() == 1 {
+            return Ok(statements.pop().unwrap());
+        }
+        let span = match (statements.first(), statements.last()) {
+            (Some(first), Some(last)) => joined(first, last),
+            _ => Span::default(),
+        };
+        Ok(self.spanned(NodeKind::Sequence(statements), span))
+    }
+
+    // Like `parse`, with the tree moved into an arena; see `arena::Ast`. The parser still
+    // builds the boxed tree first.
+    pub fn parse_ast(&mut self) -> Result<Ast, Error> {
+        self.parse().map(Ast::from_tree)
+    }
+
+    // Like `parse`, but a syntax error only skips the statement it is in, so every error
+    // in the input is reported in one pass. The tree holds the statements that parsed. A
+    // lex error still ends parsing, since the tokens after it cannot be trusted, and
+    // leaves no tree.
+    pub fn parse_recovering(&mut self) -> (Option<ASTNode>, Vec<Error>) {
+        self.recovering = true;
+        let result = self.parse();
+        let mut errors = std::mem::take(&mut self.errors);
+        match result {
+            Ok(ast) => (Some(ast), errors),
+            Err(err) => {
+                errors.push(err);
+                (None, errors)
+            }
+        }
+    }
+
+    // Statements are separated by `;`, which may be omitted after one that ends in a block.
+    fn statements(&mut self, end: Token) -> Result<Vec<ASTNode>, Error> {
+        let mut statements = Vec::new();
+        while *self.current_token() != end {
+            let depth = self.open.len();
+            let statement = match self.statement() {
+                Ok(statement) => statement,
+                Err(err) => {
+                    self.recover(err, &end, depth)?;
+                    // A block left open at the end of input has nothing more to parse.
+                    if *self.current_token() == Token::EOF {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let ends_in_block = matches!(
+                statement.kind,
+                NodeKind::Block(_)
+                    | NodeKind::If { .. }
+                    | NodeKind::While { .. }
+                    | NodeKind::For { .. }
+                    | NodeKind::Match { .. }
+                    | NodeKind::Try { .. }
+                    | NodeKind::FnDef { name: Some(_), .. }
+                    | NodeKind::StructDef { .. }
+            );
+            statements.push(statement);
+            if *self.current_token() == Token::Semicolon {
+                self.advance()?;
+            } else if !ends_in_block {
+                if !self.recovering || *self.current_token() == end {
+                    break;
+                }
+                let err = self.unexpected(&end);
+                self.recover(err, &end, depth)?;
+            }
+        }
+        Ok(statements)
+    }
+
+    // Records `err` and skips to just past the next `;`, or up to the `end` of the
+    // statements it was in. Brackets left open by the failed statement are abandoned, and
+    // any opened while skipping must close before a `;` counts. Without recovery, or for
+    // a lex error, `err` is returned instead.
+    fn recover(&mut self, err: Error, end: &Token, depth: usize) -> Result<(), Error> {
+        let fatal = match &err {
+            Error::Parse(err) => matches!(err.limit, Some(ParseLimit::Nodes(_))),
+            _ => true,
+        };
+        if !self.recovering || fatal {
+            return Err(err);
+        }
+        // Once the input has run out, each block left open fails again as it unwinds; only
+        // the first of those errors is worth reporting.
+        let repeated = matches!(
+            (self.errors.last(), &err),
+            (Some(Error::Parse(last)), Error::Parse(err))
+                if last.found == Token::EOF && err.found == Token::EOF
+        );
+        if !repeated {
+            self.errors.push(err);
+        }
+        let abandoned = self.open.len();
+        loop {
+            let outside = self.open.len() <= abandoned;
+            match self.current_token() {
+                Token::EOF => break,
+                Token::Semicolon if outside => {
+                    self.advance()?;
+                    break;
+                }
+                token if token == end && outside => break,
+                _ => self.advance()?,
+            }
+        }
+        self.open.truncate(depth);
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        match self.current_token() {
+            Token::Let => {
+                self.advance()?;
+                if *self.current_token() == Token::LParen {
+                    return self.destructure_let(start);
+                }
+                let (name, value) = self.binding("let")?;
+                Ok(self.node(start, NodeKind::Let(name, value)))
+            }
+            Token::Const => {
+                self.advance()?;
+                let (name, value) = self.binding("const")?;
+                Ok(self.node(start, NodeKind::Const(name, value)))
+            }
+            Token::Global => {
+                self.advance()?;
+                let mut names = vec![self.global_name()?];
+                while *self.current_token() == Token::Comma {
+                    self.advance()?;
+                    names.push(self.global_name()?);
+                }
+                Ok(self.node(start, NodeKind::Global(names)))
+            }
+            Token::Import => {
+                self.advance()?;
+                let path = match self.current_token() {
+                    Token::Str(path) | Token::RawStr(path) => path.clone(),
+                    _ => return Err(self.error("a file path string after `import`")),
+                };
+                self.advance()?;
+                Ok(self.node(start, NodeKind::Import(path)))
+            }
+            _ => self.expression(),
+        }
+    }
+
+    fn global_name(&mut self) -> Result<String, Error> {
+        match self.current_token() {
+            Token::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                Ok(name)
+            }
+            _ => Err(self.error("an identifier after `global`")),
+        }
+    }
+
+    // The `name = value` part of a `let` or `const`.
+    fn binding(&mut self, keyword: &str) -> Result<(String, Box<ASTNode>), Error> {
+        let name = match self.current_token() {
+            Token::Ident(name) => name.clone(),
+            _ => return Err(self.error(&format!("an identifier after `{}`", keyword))),
+        };
+        self.advance()?;
+        self.expect(Token::Assign)?;
+        Ok((name, Box::new(self.expression()?)))
+    }
+
+    fn destructure_let(&mut self, start: usize) -> Result<ASTNode, Error> {
+        self.expect(Token::LParen)?;
+        let mut names = Vec::new();
+        while let Token::Ident(name) = self.current_token() {
+            names.push(name.clone());
+            self.advance()?;
+            if *self.current_token() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::RParen)?;
+        self.expect(Token::Assign)?;
+        let value = Box::new(self.expression()?);
+        Ok(self.node(start, NodeKind::DestructureLet(names, value)))
+    }
+
+    fn advance(&mut self) -> Result<(), Error> {
+        match self.current.token {
+            Token::LParen | Token::LBracket | Token::LBrace => self.open.push(self.current.clone()),
+            Token::RParen | Token::RBracket | Token::RBrace => {
+                self.open.pop();
+            }
+            _ => {}
+        }
+        self.last_end = self.current.span.end;
+        self.check_nodes()?;
+        let next = self.next_token()?;
+        self.current = std::mem::replace(&mut self.lookahead, next);
+        Ok(())
+    }
+
+    fn next_token(&mut self) -> Result<SpannedToken, Error> {
+        let end = self.lookahead.span.end;
+        let (line, col) = (self.lookahead.end_line, self.lookahead.end_col);
+        Ok(self.tokens.next().transpose()?.unwrap_or(SpannedToken {
+            token: Token::EOF,
+            span: Span { start: end, end },
+            start_line: line,
+            start_col: col,
+            end_line: line,
+            end_col: col,
+        }))
+    }
+
+    fn current_token(&self) -> &Token {
+        &self.current.token
+    }
+
+    // The token after the current one, without consuming anything.
+    fn peek(&self) -> &Token {
+        &self.lookahead.token
+    }
+
+    // Where the current token, and so a node beginning with it, starts.
+    fn start(&self) -> usize {
+        self.current.span.start
+    }
+
+    // A node covering the source from `start` to the end of the last consumed token.
+    fn node(&self, start: usize, kind: NodeKind) -> ASTNode {
+        self.spanned(
+            kind,
+            Span {
+                start,
+                end: self.last_end,
+            },
+        )
+    }
+
+    // Every node is built here, so that `check_nodes` can tell how many there are.
+    fn spanned(&self, kind: NodeKind, span: Span) -> ASTNode {
+        self.nodes.set(self.nodes.get() + 1);
+        ASTNode::new(kind, span)
+    }
+
+    fn binary(&self, left: ASTNode, op: Token, right: ASTNode) -> ASTNode {
+        let span = joined(&left, &right);
+        self.spanned(
+            NodeKind::BinaryOp(Box::new(left), op, Box::new(right)),
+            span,
+        )
+    }
+
+    // Nodes are only counted as they are built, so the limit is checked as each token is
+    // consumed, and once more for the nodes built after the last one.
+    fn check_nodes(&self) -> Result<(), Error> {
+        if self.nodes.get() <= self.max_nodes {
+            return Ok(());
+        }
+        Err(Error::Parse(ParseError {
+            limit: Some(ParseLimit::Nodes(self.max_nodes)),
+            ..self.parse_error("a smaller program")
+        }))
+    }
+
+    // Reports that `expected` should have appeared where the current token is.
+    fn error(&self, expected: &str) -> Error {
+        Error::Parse(self.parse_error(expected))
+    }
+
+    fn parse_error(&self, expected: &str) -> ParseError {
+        let unclosed = match self.current.token {
+            Token::EOF => self.open.last().cloned().map(Box::new),
+            _ => None,
+        };
+        let at = unclosed.as_deref().unwrap_or(&self.current);
+        ParseError {
+            expected: expected.to_string(),
+            found: self.current.token.clone(),
+            span: self.current.span,
+            line: at.start_line,
+            col: at.start_col,
+            unclosed,
+            limit: None,
+            kind: None,
+        }
+    }
+
+    fn expression(&mut self) -> Result<ASTNode, Error> {
+        self.nested(Self::assignment)
+    }
+
+    // Every recursive path through the grammar passes through here, directly or through
+    // `expression`.
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<ASTNode, Error>,
+    ) -> Result<ASTNode, Error> {
+        if self.nesting == self.max_nesting {
+            return Err(self.too_deep());
+        }
+        self.nesting += 1;
+        let node = parse(self);
+        self.nesting -= 1;
+        node
+    }
+
+    // Operator and postfix chains like `1 + 2 + 3` or `a.b.c` are parsed in a loop, but
+    // each link nests the tree a level deeper, so links count against the limit too.
+    fn link(&self, links: &mut usize) -> Result<(), Error> {
+        *links += 1;
+        if self.nesting + *links > self.max_nesting {
+            return Err(self.too_deep());
+        }
+        Ok(())
+    }
+
+    fn too_deep(&self) -> Error {
+        Error::Parse(ParseError {
+            limit: Some(ParseLimit::Nesting(self.max_nesting)),
+            ..self.parse_error("a less deeply nested expression")
+        })
+    }
+
+    fn restricted_expression(&mut self, no_struct_literal: bool) -> Result<ASTNode, Error> {
+        let saved = std::mem::replace(&mut self.no_struct_literal, no_struct_literal);
+        let node = self.expression();
+        self.no_struct_literal = saved;
+        node
+    }
+
+    // Right-associative, so `a = b = 1` assigns to both; `x += e` becomes `x = x + e`.
+    fn assignment(&mut self) -> Result<ASTNode, Error> {
+        let name = match self.current_token() {
+            Token::Ident(name) => name.clone(),
+            _ => return self.operator_expression(),
+        };
+        let op = match self.peek() {
+            Token::Assign => None,
+            Token::PlusAssign => Some(Token::Plus),
+            Token::MinusAssign => Some(Token::Minus),
+            Token::MulAssign => Some(Token::Mul),
+            Token::DivAssign => Some(Token::Div),
+            Token::ModAssign => Some(Token::Mod),
+            _ => return self.operator_expression(),
+        };
+        let target = self.current.span;
+        self.advance()?;
+        self.advance()?;
+        let mut value = self.expression()?;
+        if let Some(op) = op {
+            let target = self.spanned(NodeKind::VarRef(name.clone()), target);
+            value = self.binary(target, op, value);
+        }
+        Ok(self.node(target.start, NodeKind::Assign(name, Box::new(value))))
+    }
+
+    fn operator_expression(&mut self) -> Result<ASTNode, Error> {
+        #[cfg(test)]
+        if self.legacy_expressions {
+            return self.pipe();
+        }
+        self.operators(PIPE)
+    }
+
+    // Parses operators binding at least as tightly as `min_bp`, looking each one up in
+    // `INFIX`. A loop builds left-associative chains; an operand recurses only for
+    // operators that bind tighter.
+    fn operators(&mut self, min_bp: u8) -> Result<ASTNode, Error> {
+        let (mut node, mut ceiling) = self.prefix(min_bp)?;
+        // Links are counted per binding power, as if each had a loop of its own.
+        let (mut links, mut level) = (0, u8::MAX);
+        loop {
+            let token = self.current_token();
+            let Some(&(_, bp, assoc, infix)) = INFIX.iter().find(|(op, ..)| op == token) else {
+                return Ok(node);
+            };
+            // An operator binding tighter than `ceiling` would have been taken by the last
+            // operand unless it refused it, as `b..c` refuses the second `..` of `a..b..c`.
+            if bp < min_bp || bp > ceiling {
+                return Ok(node);
+            }
+            let op = token.clone();
+            let right_bp = match assoc {
+                Assoc::Left => {
+                    if bp < level {
+                        (links, level) = (0, bp);
+                    }
+                    self.link(&mut links)?;
+                    ceiling = bp;
+                    bp + 1
+                }
+                Assoc::Right => {
+                    ceiling = bp - 1;
+                    bp
+                }
+                Assoc::Neither => {
+                    ceiling = bp - 1;
+                    bp + 1
+                }
+            };
+            self.advance()?;
+            if let Infix::Membership { negated: true } = infix {
+                self.expect(Token::In)?;
+            }
+            let right = match assoc {
+                Assoc::Right => self.nested(|parser| parser.operators(right_bp))?,
+                _ => self.operators(right_bp)?,
+            };
+            node = self.infix(node, op, infix, right, right_bp)?;
+        }
+    }
+
+    // `-x`, `√x` and `not x`, where an operand binding at least as tightly as `min_bp` may
+    // appear. Also returns the loosest operator that may follow.
+    fn prefix(&mut self, min_bp: u8) -> Result<(ASTNode, u8), Error> {
+        let token = self.current_token();
+        let row = PREFIX.iter().find(|(op, _)| op == token);
+        let Some(&(_, bp)) = row.filter(|&&(_, bp)| min_bp <= bp) else {
+            return Ok((self.postfix()?, u8::MAX));
+        };
+        let start = self.start();
+        let op = token.clone();
+        let sqrt = (op == Token::Sqrt)
+            .then(|| self.spanned(NodeKind::VarRef("sqrt".to_string()), self.current.span));
+        self.advance()?;
+        let operand = self.nested(|parser| parser.operators(bp))?;
+        let kind = match sqrt {
+            Some(sqrt) => NodeKind::Call(Box::new(sqrt), vec![operand]),
+            None => NodeKind::UnaryOp(op, Box::new(operand)),
+        };
+        Ok((self.node(start, kind), bp - 1))
+    }
+
+    fn infix(
+        &mut self,
+        left: ASTNode,
+        op: Token,
+        infix: Infix,
+        right: ASTNode,
+        right_bp: u8,
+    ) -> Result<ASTNode, Error> {
+        let span = joined(&left, &right);
+        let (left, right) = (Box::new(left), Box::new(right));
+        Ok(match infix {
+            Infix::Binary => self.spanned(NodeKind::BinaryOp(left, op, right), span),
+            Infix::Logical => self.spanned(NodeKind::Logical(left, op, right), span),
+            Infix::Pipe => self.spanned(NodeKind::Pipe(left, right), span),
+            Infix::Membership { negated } => {
+                let start = left.span.start;
+                let kind = NodeKind::Membership {
+                    item: left,
+                    container: right,
+                    negated,
+                };
+                self.node(start, kind)
+            }
+            Infix::Range { inclusive } => {
+                let step = if *self.current_token() == Token::By {
+                    self.advance()?;
+                    Some(Box::new(self.operators(right_bp)?))
+                } else {
+                    None
+                };
+                let start = left.span.start;
+                let kind = NodeKind::Range {
+                    start: left,
+                    end: right,
+                    step,
+                    inclusive,
+                };
+                self.node(start, kind)
+            }
+        })
+    }
+
+    fn postfix(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.primary()?;
+        loop {
+            let start = node.span.start;
+            match self.current_token() {
+                Token::LBracket => {
+                    self.link(&mut links)?;
+                    self.advance()?;
+                    let index = self.restricted_expression(false)?;
+                    self.expect(Token::RBracket)?;
+                    node = self.node(start, NodeKind::Index(Box::new(node), Box::new(index)));
+                }
+                Token::Dot => {
+                    self.link(&mut links)?;
+                    self.advance()?;
+                    let field = self.field_name()?;
+                    node = self.node(start, NodeKind::FieldAccess(Box::new(node), field));
+                }
+                Token::SafeDot => {
+                    self.link(&mut links)?;
+                    self.advance()?;
+                    let field = self.field_name()?;
+                    node = self.node(start, NodeKind::SafeFieldAccess(Box::new(node), field));
+                }
+                // No literal can be called, so `2 (3)` is input left after `2`, not a call.
+                Token::LParen if is_literal(&node) => return Ok(node),
+                Token::LParen => {
+                    self.link(&mut links)?;
+                    self.advance()?;
+                    let args = self.arguments()?;
+                    node = self.node(start, NodeKind::Call(Box::new(node), args));
+                }
+                _ => return Ok(node),
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        let kind = match self.current_token() {
+            Token::Number(n) => NodeKind::Number(*n),
+            Token::Integer(n) => NodeKind::Integer(*n),
+            Token::Str(s) | Token::RawStr(s) => NodeKind::Str(s.clone()),
+            Token::True | Token::False => NodeKind::Bool(*self.current_token() == Token::True),
+            Token::Null => NodeKind::Null,
+            Token::Ident(name) if *self.peek() == Token::LBrace && !self.no_struct_literal => {
+                let name = name.clone();
+                self.advance()?;
+                return self.struct_literal(start, name);
+            }
+            Token::Ident(name) => NodeKind::VarRef(name.clone()),
+            Token::Bar => return self.lambda(),
+            Token::FStr(parts) => NodeKind::FStr(
+                parts
+                    .clone()
+                    .into_iter()
+                    .map(|part| self.fstring_part(part))
+                    .collect::<Result<Vec<FStrNode>, Error>>()?,
+            ),
+            Token::LParen => {
+                self.advance()?;
+                let mut node = self.restricted_expression(false)?;
+                // The parentheses are part of the source the node covers, so that a
+                // node built around it does not cut one of them off.
+                if *self.current_token() != Token::Comma {
+                    self.expect(Token::RParen)?;
+                    node.span = Span {
+                        start,
+                        end: self.last_end,
+                    };
+                    return Ok(node);
+                }
+                let mut items = vec![node];
+                while *self.current_token() == Token::Comma {
+                    self.advance()?;
+                    if *self.current_token() == Token::RParen {
+                        break;
+                    }
+                    items.push(self.restricted_expression(false)?);
+                }
+                self.expect(Token::RParen)?;
+                if items.len() == 1 {
+                    let mut node = items.pop().unwrap();
+                    node.span = Span {
+                        start,
+                        end: self.last_end,
+                    };
+                    return Ok(node);
+                }
+                return Ok(self.node(start, NodeKind::Tuple(items)));
+            }
+            Token::LBrace => return self.brace(),
+            Token::If => return self.if_expr(),
+            Token::While => {
+                self.advance()?;
+                let cond = self.restricted_expression(true)?;
+                let body = self.block()?;
+                return Ok(self.node(
+                    start,
+                    NodeKind::While {
+                        cond: Box::new(cond),
+                        body: Box::new(body),
+                    },
+                ));
+            }
+            Token::For => return self.for_loop(),
+            Token::Fn => return self.fn_def(),
+            Token::Match => return self.match_expr(),
+            Token::Struct => return self.struct_def(),
+            Token::Try => return self.try_expr(),
+            Token::Throw => return self.throw_expr(),
+            Token::LBracket => {
+                self.advance()?;
+                let mut items = Vec::new();
+                while *self.current_token() != Token::RBracket {
+                    items.push(self.restricted_expression(false)?);
+                    if *self.current_token() != Token::Comma {
+                        break;
+                    }
+                    self.advance()?;
+                }
+                self.expect(Token::RBracket)?;
+                return Ok(self.node(start, NodeKind::Array(items)));
+            }
+            _ => return Err(self.error("an expression")),
+        };
+        // Literal tokens fall through here to be consumed.
+        self.advance()?;
+        Ok(self.node(start, kind))
+    }
+
+    fn for_loop(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::For)?;
+        let Token::Ident(var) = self.current_token().clone() else {
+            return Err(self.error("a loop variable name"));
+        };
+        self.advance()?;
+        self.expect(Token::In)?;
+        let iterable = self.restricted_expression(true)?;
+        let body = self.block()?;
+        Ok(self.node(
+            start,
+            NodeKind::For {
+                var,
+                iterable: Box::new(iterable),
+                body: Box::new(body),
+            },
+        ))
+    }
+
+    // `|x, y| body`; the body extends as far as an expression can.
+    fn lambda(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::Bar)?;
+        let (params, rest_param) = self.params(Token::Bar)?;
+        let body = Rc::new(self.expression()?);
+        Ok(self.node(
+            start,
+            NodeKind::FnDef {
+                name: None,
+                params: Rc::new(params),
+                rest_param,
+                body,
+            },
+        ))
+    }
+
+    // Parameters up to and including `end`. Once one has a default, `name = value`, the
+    // rest must too; a rest parameter `...name` may only come last.
+    fn params(&mut self, end: Token) -> Result<(Vec<Param>, Option<String>), Error> {
+        let mut params: Vec<Param> = Vec::new();
+        let mut rest_param = None;
+        loop {
+            match self.current_token().clone() {
+                Token::Ident(name) => {
+                    let defaulted = params.iter().any(|(_, default)| default.is_some());
+                    if defaulted && *self.peek() != Token::Assign {
+                        return Err(Error::Parse(ParseError {
+                            kind: Some(ParseErrorKind::RequiredParamAfterDefault),
+                            ..self.parse_error(&format!("a default value for `{}`", name))
+                        }));
+                    }
+                    self.advance()?;
+                    let default = if *self.current_token() == Token::Assign {
+                        self.advance()?;
+                        Some(self.expression()?)
+                    } else {
+                        None
+                    };
+                    params.push((name, default));
+                }
+                Token::Ellipsis => {
+                    self.advance()?;
+                    let Token::Ident(name) = self.current_token().clone() else {
+                        return Err(self.error("a rest parameter name"));
+                    };
+                    rest_param = Some(name);
+                    self.advance()?;
+                    break;
+                }
+                _ => break,
+            }
+            if *self.current_token() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(end)?;
+        Ok((params, rest_param))
+    }
+
+    // The expression is parsed on its own, with its spans moved to where it sits in the
+    // enclosing source. It counts against this parser's limits, as if parsed in place.
+    fn fstring_part(&self, part: FStrPart) -> Result<FStrNode, Error> {
+        match part {
+            FStrPart::Literal(s) => Ok(FStrNode::Literal(s)),
+            FStrPart::Expr {
+                source,
+                offset,
+                line,
+                col,
+            } => {
+                let relocate = move |at: (usize, usize)| match at {
+                    (1, c) => (line, col + c - 1),
+                    (l, c) => (line + l - 1, c),
+                };
+                let tokens =
+                    Lexer::with_options(source, self.lexer_options).map(move |token| match token {
+                        Ok(mut token) => {
+                            token.span.start += offset;
+                            token.span.end += offset;
+                            (token.start_line, token.start_col) =
+                                relocate((token.start_line, token.start_col));
+                            (token.end_line, token.end_col) =
+                                relocate((token.end_line, token.end_col));
+                            Ok(token)
+                        }
+                        Err(mut err) => {
+                            err.offset += offset;
+                            (err.line, err.col) = relocate((err.line, err.col));
+                            Err(err)
+                        }
+                    });
+                let mut parser = Parser::new(tokens)
+                    .with_max_nesting(self.max_nesting)
+                    .with_max_nodes(self.max_nodes)
+                    .with_lexer_options(self.lexer_options);
+                parser.nesting = self.nesting;
+                parser.nodes.set(self.nodes.get());
+                let node = parser.parse();
+                self.nodes.set(parser.nodes.get());
+                Ok(FStrNode::Expr(node?))
+            }
+        }
+    }
+
+    // Parses a comma-separated argument list; the opening paren is already consumed.
+    fn arguments(&mut self) -> Result<Vec<ASTNode>, Error> {
+        let mut args = Vec::new();
+        while *self.current_token() != Token::RParen {
+            args.push(self.restricted_expression(false)?);
+            if *self.current_token() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::RParen)?;
+        Ok(args)
+    }
+
+    // In expression position `{` starts a map literal when it is empty or its first
+    // entry is a string/identifier key followed by `:`, and a block otherwise.
+    fn brace(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::LBrace)?;
+        let is_map = match self.current_token() {
+            Token::RBrace => true,
+            Token::Str(_) | Token::Ident(_) => *self.peek() == Token::Colon,
+            _ => false,
+        };
+        if is_map {
+            self.map_entries(start)
+        } else {
+            self.block_body(start)
+        }
+    }
+
+    fn block(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::LBrace)?;
+        self.block_body(start)
+    }
+
+    // The statements and closing brace of a block whose `{` is already consumed.
+    fn block_body(&mut self, start: usize) -> Result<ASTNode, Error> {
+        let saved = std::mem::replace(&mut self.no_struct_literal, false);
+        let statements = self.statements(Token::RBrace);
+        self.no_struct_literal = saved;
+        let statements = statements?;
+        self.expect(Token::RBrace)?;
+        Ok(self.node(start, NodeKind::Block(statements)))
+    }
+
+    fn if_expr(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::If)?;
+        let cond = self.restricted_expression(true)?;
+        let then_branch = self.block()?;
+        let else_branch = if *self.current_token() == Token::Else {
+            self.advance()?;
+            if *self.current_token() == Token::If {
+                Some(Box::new(self.if_expr()?))
+            } else {
+                Some(Box::new(self.block()?))
+            }
+        } else {
+            None
+        };
+        Ok(self.node(
+            start,
+            NodeKind::If {
+                cond: Box::new(cond),
+                then_branch: Box::new(then_branch),
+                else_branch,
+            },
+        ))
+    }
+
+    // `fn name(a, b) { ... }` binds `name`; `fn(a, b) { ... }` is an anonymous function.
+    fn fn_def(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::Fn)?;
+        let name = match self.current_token() {
+            Token::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                Some(name)
+            }
+            _ => None,
+        };
+        self.expect(Token::LParen)?;
+        let (params, rest_param) = self.params(Token::RParen)?;
+        let body = Rc::new(self.block()?);
+        Ok(self.node(
+            start,
+            NodeKind::FnDef {
+                name,
+                params: Rc::new(params),
+                rest_param,
+                body,
+            },
+        ))
+    }
+
+    fn match_expr(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::Match)?;
+        let subject = self.restricted_expression(true)?;
+        self.expect(Token::LBrace)?;
+        let mut arms = Vec::new();
+        while *self.current_token() != Token::RBrace {
+            let pattern = self.pattern()?;
+            self.expect(Token::FatArrow)?;
+            arms.push((pattern, self.restricted_expression(false)?));
+            if *self.current_token() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::RBrace)?;
+        Ok(self.node(
+            start,
+            NodeKind::Match {
+                subject: Box::new(subject),
+                arms,
+            },
+        ))
+    }
+
+    fn try_expr(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::Try)?;
+        let body = self.block()?;
+        let mut binding = None;
+        let mut handler = None;
+        if *self.current_token() == Token::Catch {
+            self.advance()?;
+            binding = self.catch_binding()?;
+            handler = Some(Box::new(self.block()?));
+        }
+        let mut finally = None;
+        if *self.current_token() == Token::Finally {
+            self.advance()?;
+            finally = Some(Box::new(self.block()?));
+        }
+        if handler.is_none() && finally.is_none() {
+            return Err(self.error("`catch` or `finally`"));
+        }
+        Ok(self.node(
+            start,
+            NodeKind::Try {
+                body: Box::new(body),
+                binding,
+                handler,
+                finally,
+            },
+        ))
+    }
+
+    // The name after `catch`, written bare or in parentheses, if there is one.
+    fn catch_binding(&mut self) -> Result<Option<String>, Error> {
+        let parenthesized = *self.current_token() == Token::LParen;
+        if parenthesized {
+            self.advance()?;
+        }
+        let Token::Ident(name) = self.current_token().clone() else {
+            if parenthesized {
+                return Err(self.error("an error name"));
+            }
+            return Ok(None);
+        };
+        self.advance()?;
+        if parenthesized {
+            self.expect(Token::RParen)?;
+        }
+        Ok(Some(name))
+    }
+
+    // `throw value`; the value extends as far as an expression can.
+    fn throw_expr(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::Throw)?;
+        let value = self.expression()?;
+        Ok(self.node(start, NodeKind::Throw(Box::new(value))))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Error> {
+        let start = self.start();
+        match self.current_token() {
+            Token::Underscore => {
+                self.advance()?;
+                Ok(Pattern::PatWildcard)
+            }
+            Token::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                Ok(Pattern::PatIdent(name))
+            }
+            Token::Minus => {
+                self.advance()?;
+                let operand = Box::new(self.primary()?);
+                Ok(Pattern::PatLiteral(
+                    self.node(start, NodeKind::UnaryOp(Token::Minus, operand)),
+                ))
+            }
+            Token::Number(_)
+            | Token::Integer(_)
+            | Token::Str(_)
+            | Token::RawStr(_)
+            | Token::True
+            | Token::False
+            | Token::Null => Ok(Pattern::PatLiteral(self.primary()?)),
+            _ => Err(self.error("a pattern")),
+        }
+    }
+
+    // The entries and closing brace of a map literal whose `{` is already consumed.
+    fn map_entries(&mut self, start: usize) -> Result<ASTNode, Error> {
+        let mut entries = Vec::new();
+        while *self.current_token() != Token::RBrace {
+            let key = match self.current_token() {
+                Token::Str(s) | Token::Ident(s) => {
+                    self.spanned(NodeKind::Str(s.clone()), self.current.span)
+                }
+                _ => return Err(self.error("a map key")),
+            };
+            self.advance()?;
+            self.expect(Token::Colon)?;
+            entries.push((key, self.restricted_expression(false)?));
+            if *self.current_token() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::RBrace)?;
+        Ok(self.node(start, NodeKind::Map(entries)))
+    }
+
+    // `struct Point { x, y }`
+    fn struct_def(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        self.expect(Token::Struct)?;
+        let name = self.field_name()?;
+        self.expect(Token::LBrace)?;
+        let mut fields = Vec::new();
+        while *self.current_token() != Token::RBrace {
+            fields.push(self.field_name()?);
+            if *self.current_token() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::RBrace)?;
+        Ok(self.node(start, NodeKind::StructDef { name, fields }))
+    }
+
+    // `Point { x: 1, y: 2 }`; the type name, which began at `start`, is already consumed.
+    fn struct_literal(&mut self, start: usize, name: String) -> Result<ASTNode, Error> {
+        self.expect(Token::LBrace)?;
+        let mut fields = Vec::new();
+        while *self.current_token() != Token::RBrace {
+            let field = self.field_name()?;
+            self.expect(Token::Colon)?;
+            fields.push((field, self.restricted_expression(false)?));
+            if *self.current_token() != Token::Comma {
+                break;
+            }
+            self.advance()?;
+        }
+        self.expect(Token::RBrace)?;
+        Ok(self.node(start, NodeKind::StructLit { name, fields }))
+    }
+
+    fn field_name(&mut self) -> Result<String, Error> {
+        match self.current_token() {
+            Token::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                Ok(name)
+            }
+            _ => Err(self.error("an identifier")),
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Error> {
+        if *self.current_token() == expected {
+            self.advance()
+        } else {
+            Err(self.unexpected(&expected))
+        }
+    }
+
+    fn unexpected(&self, expected: &Token) -> Error {
+        self.error(&match expected {
+            Token::EOF => expected.to_string(),
+            _ => format!("`{}`", expected),
+        })
+    }
+}
+
+// The span from the start of `first` to the end of `last`.
+fn joined(first: &ASTNode, last: &ASTNode) -> Span {
+    Span {
+        start: first.span.start,
+        end: last.span.end,
+    }
+}
+
+fn is_literal(node: &ASTNode) -> bool {
+    matches!(
+        node.kind,
+        NodeKind::Number(_)
+            | NodeKind::Integer(_)
+            | NodeKind::Bool(_)
+            | NodeKind::Null
+            | NodeKind::Str(_)
+            | NodeKind::FStr(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn parser(source: &str) -> Parser {
+        Parser::new(Lexer::new(source.to_string()))
+    }
+
+    #[test]
+    fn parsing_counts_every_node_and_unwinds_its_nesting() {
+        let mut parser = parser("(1 + 2) * [3][0]");
+        parser.parse().unwrap();
+        // The sum, the product, the list, the index and four numbers.
+        assert_eq!(parser.nodes.get(), 8);
+        assert_eq!(parser.nesting, 0);
+        assert!(parser.open.is_empty());
+    }
+
+    #[test]
+    fn unclosed_brackets_are_remembered_innermost_last() {
+        let mut parser = parser("(1 + [2");
+        let Err(Error::Parse(err)) = parser.parse() else {
+            panic!("parsed");
+        };
+        let open: Vec<&Token> = parser.open.iter().map(|open| &open.token).collect();
+        assert_eq!(open, [&Token::LParen, &Token::LBracket]);
+        assert_eq!(err.unclosed.map(|open| open.token), Some(Token::LBracket));
+    }
+
+    #[test]
+    fn binary_nodes_span_both_operands() {
+        let parser = parser("");
+        let left = ASTNode::new(NodeKind::Integer(1), Span { start: 2, end: 3 });
+        let right = ASTNode::new(NodeKind::Integer(2), Span { start: 6, end: 9 });
+        let node = parser.binary(left, Token::Plus, right);
+        assert_eq!(node.span, Span { start: 2, end: 9 });
+        assert_eq!(parser.nodes.get(), 1);
+    }
+
+    // Reads the first two tokens, as `parse` does before parsing anything.
+    fn primed(source: &str) -> Parser {
+        let mut parser = parser(source);
+        parser.lookahead = parser.next_token().unwrap();
+        parser.advance().unwrap();
+        parser
+    }
+
+    #[test]
+    fn peek_does_not_advance() {
+        let mut parser = primed("let x = 1");
+        assert_eq!(*parser.current_token(), Token::Let);
+        assert_eq!(*parser.peek(), Token::Ident("x".to_string()));
+        assert_eq!(*parser.current_token(), Token::Let);
+        assert_eq!(parser.start(), 0);
+        parser.advance().unwrap();
+        assert_eq!(*parser.current_token(), Token::Ident("x".to_string()));
+        assert_eq!(*parser.peek(), Token::Assign);
+    }
+
+    #[test]
+    fn repeated_peeks_agree() {
+        let mut parser = primed("a { 1 }");
+        let first = parser.peek().clone();
+        assert_eq!(*parser.peek(), first);
+        assert_eq!(*parser.peek(), Token::LBrace);
+        for _ in 0..4 {
+            parser.advance().unwrap();
+        }
+        assert_eq!(*parser.current_token(), Token::EOF);
+        assert_eq!(*parser.peek(), Token::EOF);
+        assert_eq!(*parser.peek(), Token::EOF);
+    }
+
+    fn parse_error(source: &str) -> ParseError {
+        match parser(source).parse() {
+            Err(Error::Parse(err)) => err,
+            other => panic!("{:?} gave {:?}", source, other),
+        }
+    }
+
+    #[test]
+    fn errors_say_what_was_expected_what_was_found_and_where() {
+        let cases = [
+            (
+                "1 + ",
+                Token::EOF,
+                (1, 5),
+                "expected an expression, found end of input at line 1, col 5",
+            ),
+            (
+                ")",
+                Token::RParen,
+                (1, 1),
+                "expected an expression, found `)` at line 1, col 1",
+            ),
+            (
+                "+ 3",
+                Token::Plus,
+                (1, 1),
+                "expected an expression, found `+` at line 1, col 1",
+            ),
+            (
+                "let = 2",
+                Token::Assign,
+                (1, 5),
+                "expected an identifier after `let`, found `=` at line 1, col 5",
+            ),
+            (
+                "1\n  2 3",
+                Token::Integer(2),
+                (2, 3),
+                "unexpected trailing input starting at line 2, col 3: `2`",
+            ),
+        ];
+        for (source, found, position, message) in cases {
+            let err = parse_error(source);
+            assert_eq!(err.found, found, "{:?}", source);
+            assert_eq!((err.line, err.col), position, "{:?}", source);
+            assert_eq!(err.to_string(), message, "{:?}", source);
+        }
+    }
+
+    // At the end of input the error points at the bracket left open, not at the end.
+    #[test]
+    fn unclosed_brackets_are_reported_where_they_open() {
+        let err = parse_error("(1 + 2");
+        assert_eq!(err.found, Token::EOF);
+        assert_eq!(err.span, Span { start: 6, end: 6 });
+        assert_eq!((err.line, err.col), (1, 1));
+        assert_eq!(err.to_string(), "unclosed `(` opened at line 1, col 1");
+        let err = parse_error("f(1,\n  [2");
+        assert_eq!((err.line, err.col), (2, 3));
+        assert_eq!(err.unclosed.map(|open| open.token), Some(Token::LBracket));
+    }
+
+    #[test]
+    fn input_left_after_a_complete_program_is_rejected() {
+        let cases = [
+            (
+                "1 2",
+                Token::Integer(2),
+                "unexpected trailing input starting at line 1, col 3: `2`",
+            ),
+            (
+                "3 + 4 5",
+                Token::Integer(5),
+                "unexpected trailing input starting at line 1, col 7: `5`",
+            ),
+            (
+                "(2) 3",
+                Token::Integer(3),
+                "unexpected trailing input starting at line 1, col 5: `3`",
+            ),
+            ("(1+2))", Token::RParen, "unmatched `)` at line 1, col 6"),
+            ("[1]]", Token::RBracket, "unmatched `]` at line 1, col 4"),
+            (
+                "2 (3)",
+                Token::LParen,
+                "unexpected trailing input starting at line 1, col 3: `(`",
+            ),
+            (
+                "1 + (2.5) (3)",
+                Token::LParen,
+                "unexpected trailing input starting at line 1, col 11: `(`",
+            ),
+            (
+                "1 + 2 foo bar",
+                Token::Ident("foo".to_string()),
+                "unexpected trailing input starting at line 1, col 7: `foo`",
+            ),
+        ];
+        for (source, found, message) in cases {
+            let err = parse_error(source);
+            assert!(err.is_trailing_input(), "{:?}", source);
+            assert_eq!(err.found, found, "{:?}", source);
+            assert_eq!(err.to_string(), message, "{:?}", source);
+        }
+    }
+}
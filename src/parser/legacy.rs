@@ -0,0 +1,330 @@
+// The chain of functions, one per level of `INFIX`, that `Parser::operators` replaced.
+// It is kept so these tests can check that both build the same trees with the same spans,
+// and fail with the same errors, including at the nesting limit.
+use proptest::prelude::*;
+
+use super::{joined, Parser};
+use crate::ast::{ASTNode, NodeKind};
+use crate::error::Error;
+use crate::lexer::{Lexer, Span, Token};
+use crate::visitor::{walk, AstVisitor};
+
+impl Parser {
+    // `x |> f |> g` is `g(f(x))`.
+    pub(super) fn pipe(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.null_coalesce()?;
+        while *self.current_token() == Token::Pipe {
+            self.link(&mut links)?;
+            self.advance()?;
+            let function = self.null_coalesce()?;
+            let span = joined(&node, &function);
+            node = self.spanned(NodeKind::Pipe(Box::new(node), Box::new(function)), span);
+        }
+        Ok(node)
+    }
+
+    // `a ?? b` binds more loosely than `or`, so `x ?? a or b` defaults to `a or b`.
+    fn null_coalesce(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.logical_or()?;
+        while *self.current_token() == Token::NullCoalesce {
+            self.link(&mut links)?;
+            self.advance()?;
+            let right = self.logical_or()?;
+            let span = joined(&node, &right);
+            node = self.spanned(
+                NodeKind::Logical(Box::new(node), Token::NullCoalesce, Box::new(right)),
+                span,
+            );
+        }
+        Ok(node)
+    }
+
+    fn logical_or(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.logical_and()?;
+        while *self.current_token() == Token::Or {
+            self.link(&mut links)?;
+            self.advance()?;
+            let right = self.logical_and()?;
+            let span = joined(&node, &right);
+            node = self.spanned(
+                NodeKind::Logical(Box::new(node), Token::Or, Box::new(right)),
+                span,
+            );
+        }
+        Ok(node)
+    }
+
+    fn logical_and(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.logical_not()?;
+        while *self.current_token() == Token::And {
+            self.link(&mut links)?;
+            self.advance()?;
+            let right = self.logical_not()?;
+            let span = joined(&node, &right);
+            node = self.spanned(
+                NodeKind::Logical(Box::new(node), Token::And, Box::new(right)),
+                span,
+            );
+        }
+        Ok(node)
+    }
+
+    fn logical_not(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        if *self.current_token() == Token::Not {
+            self.advance()?;
+            let operand = Box::new(self.nested(Self::logical_not)?);
+            return Ok(self.node(start, NodeKind::UnaryOp(Token::Not, operand)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.range()?;
+        loop {
+            match self.current_token() {
+                Token::Eq | Token::NotEq | Token::Lt | Token::Le | Token::Gt | Token::Ge => {
+                    self.link(&mut links)?;
+                    let op = self.current_token().clone();
+                    self.advance()?;
+                    let right = self.range()?;
+                    node = self.binary(node, op, right);
+                }
+                Token::In => {
+                    self.link(&mut links)?;
+                    self.advance()?;
+                    node = self.membership(node, false)?;
+                }
+                // After an operand, `not` can only begin `not in`.
+                Token::Not => {
+                    self.link(&mut links)?;
+                    self.advance()?;
+                    self.expect(Token::In)?;
+                    node = self.membership(node, true)?;
+                }
+                _ => return Ok(node),
+            }
+        }
+    }
+
+    fn membership(&mut self, item: ASTNode, negated: bool) -> Result<ASTNode, Error> {
+        let start = item.span.start;
+        let container = Box::new(self.range()?);
+        Ok(self.node(
+            start,
+            NodeKind::Membership {
+                item: Box::new(item),
+                container,
+                negated,
+            },
+        ))
+    }
+
+    fn range(&mut self) -> Result<ASTNode, Error> {
+        let start = self.term()?;
+        let inclusive = match self.current_token() {
+            Token::DotDot => false,
+            Token::DotDotEq => true,
+            _ => return Ok(start),
+        };
+        self.advance()?;
+        let end = self.term()?;
+        let step = if *self.current_token() == Token::By {
+            self.advance()?;
+            Some(Box::new(self.term()?))
+        } else {
+            None
+        };
+        let span_start = start.span.start;
+        Ok(self.node(
+            span_start,
+            NodeKind::Range {
+                start: Box::new(start),
+                end: Box::new(end),
+                step,
+                inclusive,
+            },
+        ))
+    }
+
+    fn term(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.factor()?;
+        while let Token::Plus | Token::Minus = self.current_token() {
+            self.link(&mut links)?;
+            let op = self.current_token().clone();
+            self.advance()?;
+            let right = self.factor()?;
+            node = self.binary(node, op, right);
+        }
+        Ok(node)
+    }
+
+    fn factor(&mut self) -> Result<ASTNode, Error> {
+        let mut links = 0;
+        let mut node = self.unary()?;
+        while let Token::Mul | Token::Div | Token::Mod = self.current_token() {
+            self.link(&mut links)?;
+            let op = self.current_token().clone();
+            self.advance()?;
+            let right = self.unary()?;
+            node = self.binary(node, op, right);
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<ASTNode, Error> {
+        let start = self.start();
+        if *self.current_token() == Token::Minus {
+            self.advance()?;
+            let operand = Box::new(self.nested(Self::unary)?);
+            return Ok(self.node(start, NodeKind::UnaryOp(Token::Minus, operand)));
+        }
+        if *self.current_token() == Token::Sqrt {
+            let sqrt = self.spanned(NodeKind::VarRef("sqrt".to_string()), self.current.span);
+            self.advance()?;
+            let operand = self.nested(Self::unary)?;
+            return Ok(self.node(start, NodeKind::Call(Box::new(sqrt), vec![operand])));
+        }
+        self.power()
+    }
+
+    // `^` binds tighter than unary minus on its left and is right-associative.
+    fn power(&mut self) -> Result<ASTNode, Error> {
+        let node = self.postfix()?;
+        if *self.current_token() == Token::Pow {
+            self.advance()?;
+            let exponent = self.nested(Self::unary)?;
+            return Ok(self.binary(node, Token::Pow, exponent));
+        }
+        Ok(node)
+    }
+}
+
+#[derive(Default)]
+struct Spans(Vec<Span>);
+
+impl AstVisitor for Spans {
+    fn visit(&mut self, node: &ASTNode) {
+        self.0.push(node.span);
+        walk(self, node);
+    }
+}
+
+// The tree's debug form and every span in it, or the error.
+fn outcome(result: Result<ASTNode, Error>) -> Result<(String, Vec<Span>), Error> {
+    result.map(|ast| {
+        let mut spans = Spans::default();
+        spans.visit(&ast);
+        (format!("{:?}", ast), spans.0)
+    })
+}
+
+fn assert_same(source: &str, max_nesting: usize) {
+    let parse = |legacy: bool| {
+        let mut parser = Parser::new(Lexer::new(source.to_string())).with_max_nesting(max_nesting);
+        parser.legacy_expressions = legacy;
+        outcome(parser.parse())
+    };
+    assert_eq!(parse(false), parse(true), "{}", source);
+}
+
+const CORPUS: &[&str] = &[
+    "1 + 2 * 3 - 4 / 5 % 6",
+    "2 ^ 3 ^ 2",
+    "-2 ^ 2",
+    "2 ^ -1",
+    "2 ^ -1 ^ 2",
+    "- - 1",
+    "√x + 1",
+    "√-x ^ 2",
+    "-a.b[0](1) ^ c",
+    "a * b ^ c * d",
+    "a ?? b or c and d",
+    "x ?? a or b",
+    "not a == b and not not c",
+    "a or not b in c",
+    "a not in b == c",
+    "a in b in c",
+    "a < b <= c > d >= e == f != g",
+    "1 + 2 .. 3 * 4",
+    "0..10 by 2",
+    "0..=n by -1 < m",
+    "-a..b",
+    "x |> f |> g",
+    "x |> f ?? g",
+    "a = b = c + 1",
+    "x += 1 |> f",
+    "(a + b) * (c - d)",
+    "(1..2)..3",
+    "[a + b, c * d]",
+    "f(a or b, c and d)",
+    "if a < b { a - b } else { b ^ a }",
+    "a..b..c",
+    "a .. b by c by d",
+    "1 + not x",
+    "a == not b",
+    "a not b",
+    "a not",
+    "1 +",
+    "2 ^",
+    "- ",
+    "a ?? ",
+    "a * * b",
+    "x in",
+    "1..",
+];
+
+#[test]
+fn both_parsers_agree_on_the_corpus() {
+    for source in CORPUS {
+        assert_same(source, 500);
+    }
+}
+
+#[test]
+fn both_parsers_agree_at_the_nesting_limit() {
+    for source in CORPUS {
+        for limit in 0..6 {
+            assert_same(source, limit);
+        }
+    }
+    for source in [
+        "1 + 1 + 1 + 1 * 1 * 1 * 1 + 1",
+        "a or b or c and d and e and f or g",
+        "a == b == c == d in e not in f",
+        "- - - - 1 + - - 2",
+        "2 ^ 2 ^ 2 ^ 2 ^ 2",
+        "not not not not a",
+        "a * b + c * d + e * f",
+    ] {
+        for limit in 0..10 {
+            assert_same(source, limit);
+        }
+    }
+}
+
+fn token() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec![
+        "a", "1", "2.5", "f(x)", "b.c", "(", ")", "+", "-", "*", "/", "%", "^", "√", "not", "in",
+        "and", "or", "??", "|>", "==", "!=", "<", "<=", ">", ">=", "..", "..=", "by", "=", "+=",
+    ])
+}
+
+proptest! {
+    // Jumbles of operators and operands, mostly malformed, so errors are compared too.
+    #[test]
+    fn both_parsers_agree_on_arbitrary_operators(
+        tokens in prop::collection::vec(token(), 1..16),
+        limit in 3usize..12,
+    ) {
+        let source = tokens.join(" ");
+        assert_same(&source, limit);
+        assert_same(&source, 500);
+    }
+}
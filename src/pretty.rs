@@ -0,0 +1,520 @@
+use std::fmt;
+
+use crate::ast::{ASTNode, FStrNode, NodeKind, Param, Pattern};
+use crate::lexer::Token;
+
+// Binding strength of each grammar level, loosest first. An operand printed where a
+// tighter level is expected is wrapped in parentheses.
+const LOWEST: u8 = 0;
+const PIPE: u8 = 1;
+const COALESCE: u8 = 2;
+const OR: u8 = 3;
+const AND: u8 = 4;
+const NOT: u8 = 5;
+const COMPARISON: u8 = 6;
+const RANGE: u8 = 7;
+const TERM: u8 = 8;
+const FACTOR: u8 = 9;
+const UNARY: u8 = 10;
+const POWER: u8 = 11;
+const POSTFIX: u8 = 12;
+const PRIMARY: u8 = 13;
+
+// Renders `node` as source text that parses back to the same tree.
+pub fn pretty_print(node: &ASTNode) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        depth: 0,
+        no_struct_literal: false,
+    };
+    match &node.kind {
+        NodeKind::Sequence(statements) => printer.statements(statements),
+        _ => printer.expr(node, LOWEST),
+    }
+    printer.out
+}
+
+impl fmt::Display for ASTNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", pretty_print(self))
+    }
+}
+
+struct Printer {
+    out: String,
+    // Current block nesting; each level indents by four spaces.
+    depth: usize,
+    // Mirrors the parser: inside a condition `Name { ... }` must be parenthesized.
+    no_struct_literal: bool,
+}
+
+fn precedence(node: &ASTNode) -> u8 {
+    match &node.kind {
+        NodeKind::Let(..)
+        | NodeKind::DestructureLet(..)
+        | NodeKind::Const(..)
+        | NodeKind::Assign(..)
+        | NodeKind::Global(_)
+        | NodeKind::Import(_)
+        | NodeKind::Sequence(_) => LOWEST,
+        // A lambda body extends as far as it can, so `|x| x` must end its expression.
+        NodeKind::FnDef {
+            name: None, body, ..
+        } if !matches!(body.kind, NodeKind::Block(_)) => LOWEST,
+        NodeKind::Throw(_) => LOWEST,
+        NodeKind::UnaryOp(Token::Not, _) => NOT,
+        NodeKind::UnaryOp(..) => UNARY,
+        NodeKind::Membership { .. } => COMPARISON,
+        NodeKind::BinaryOp(_, op, _) => operator_precedence(op),
+        NodeKind::Pipe(..) => PIPE,
+        NodeKind::Logical(_, Token::NullCoalesce, _) => COALESCE,
+        NodeKind::Logical(_, Token::Or, _) => OR,
+        NodeKind::Logical(..) => AND,
+        NodeKind::Range { .. } => RANGE,
+        NodeKind::Call(..)
+        | NodeKind::Index(..)
+        | NodeKind::FieldAccess(..)
+        | NodeKind::SafeFieldAccess(..) => POSTFIX,
+        _ => PRIMARY,
+    }
+}
+
+fn operator_precedence(op: &Token) -> u8 {
+    match op {
+        Token::Plus | Token::Minus => TERM,
+        Token::Mul | Token::Div | Token::Mod => FACTOR,
+        Token::Pow => POWER,
+        _ => COMPARISON,
+    }
+}
+
+impl Printer {
+    // Writes `node`, parenthesized if it binds more loosely than `min`.
+    fn expr(&mut self, node: &ASTNode, min: u8) {
+        let parens = precedence(node) < min
+            || (self.no_struct_literal && matches!(node.kind, NodeKind::StructLit { .. }));
+        if parens {
+            self.out.push('(');
+            self.nested(node, LOWEST);
+            self.out.push(')');
+        } else {
+            self.node(node);
+        }
+    }
+
+    // Writes an operand enclosed by delimiters, where struct literals are allowed again.
+    fn nested(&mut self, node: &ASTNode, min: u8) {
+        let saved = std::mem::replace(&mut self.no_struct_literal, false);
+        self.expr(node, min);
+        self.no_struct_literal = saved;
+    }
+
+    fn condition(&mut self, node: &ASTNode) {
+        let saved = std::mem::replace(&mut self.no_struct_literal, true);
+        self.expr(node, LOWEST);
+        self.no_struct_literal = saved;
+    }
+
+    fn node(&mut self, node: &ASTNode) {
+        match &node.kind {
+            NodeKind::Number(n) => {
+                let text = n.to_string();
+                self.out.push_str(&text);
+                if !text.contains('.') {
+                    self.out.push_str(".0");
+                }
+            }
+            NodeKind::Integer(n) => self.out.push_str(&n.to_string()),
+            NodeKind::Bool(b) => self.out.push_str(&b.to_string()),
+            NodeKind::Null => self.out.push_str("null"),
+            NodeKind::Str(s) => self.string(s),
+            NodeKind::FStr(parts) => {
+                self.out.push_str("f\"");
+                for part in parts {
+                    match part {
+                        FStrNode::Literal(s) => {
+                            for c in s.chars() {
+                                match c {
+                                    '{' => self.out.push_str("{{"),
+                                    '}' => self.out.push_str("}}"),
+                                    _ => self.escaped(c),
+                                }
+                            }
+                        }
+                        FStrNode::Expr(expr) => {
+                            self.out.push('{');
+                            self.nested(expr, LOWEST);
+                            self.out.push('}');
+                        }
+                    }
+                }
+                self.out.push('"');
+            }
+            NodeKind::VarRef(name) => self.out.push_str(name),
+            NodeKind::Let(name, value) => {
+                self.out.push_str("let ");
+                self.out.push_str(name);
+                self.out.push_str(" = ");
+                self.expr(value, LOWEST);
+            }
+            NodeKind::DestructureLet(names, value) => {
+                self.out.push_str("let (");
+                self.out.push_str(&names.join(", "));
+                self.out.push_str(") = ");
+                self.expr(value, LOWEST);
+            }
+            NodeKind::Const(name, value) => {
+                self.out.push_str("const ");
+                self.out.push_str(name);
+                self.out.push_str(" = ");
+                self.expr(value, LOWEST);
+            }
+            NodeKind::Assign(name, value) => {
+                self.out.push_str(name);
+                self.out.push_str(" = ");
+                self.expr(value, LOWEST);
+            }
+            NodeKind::Global(names) => {
+                self.out.push_str("global ");
+                self.out.push_str(&names.join(", "));
+            }
+            NodeKind::Import(path) => {
+                self.out.push_str("import ");
+                self.string(path);
+            }
+            NodeKind::Sequence(statements) => {
+                for (i, statement) in statements.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str("; ");
+                    }
+                    self.expr(statement, LOWEST);
+                }
+            }
+            NodeKind::Block(statements) => self.block(statements),
+            NodeKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                self.out.push_str("if ");
+                self.condition(cond);
+                self.out.push(' ');
+                self.node(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.out.push_str(" else ");
+                    self.node(else_branch);
+                }
+            }
+            NodeKind::While { cond, body } => {
+                self.out.push_str("while ");
+                self.condition(cond);
+                self.out.push(' ');
+                self.node(body);
+            }
+            NodeKind::For {
+                var,
+                iterable,
+                body,
+            } => {
+                self.out.push_str("for ");
+                self.out.push_str(var);
+                self.out.push_str(" in ");
+                self.condition(iterable);
+                self.out.push(' ');
+                self.node(body);
+            }
+            NodeKind::Match { subject, arms } => {
+                self.out.push_str("match ");
+                self.condition(subject);
+                self.out.push_str(" {\n");
+                self.depth += 1;
+                for (pattern, body) in arms {
+                    self.indent();
+                    match pattern {
+                        Pattern::PatWildcard => self.out.push('_'),
+                        Pattern::PatIdent(name) => self.out.push_str(name),
+                        Pattern::PatLiteral(literal) => self.node(literal),
+                    }
+                    self.out.push_str(" => ");
+                    self.nested(body, LOWEST);
+                    self.out.push_str(",\n");
+                }
+                self.depth -= 1;
+                self.indent();
+                self.out.push('}');
+            }
+            NodeKind::Try {
+                body,
+                binding,
+                handler,
+                finally,
+            } => {
+                self.out.push_str("try ");
+                self.node(body);
+                if let Some(handler) = handler {
+                    self.out.push_str(" catch ");
+                    if let Some(name) = binding {
+                        self.out.push_str(name);
+                        self.out.push(' ');
+                    }
+                    self.node(handler);
+                }
+                if let Some(finally) = finally {
+                    self.out.push_str(" finally ");
+                    self.node(finally);
+                }
+            }
+            NodeKind::Throw(value) => {
+                self.out.push_str("throw ");
+                self.expr(value, LOWEST);
+            }
+            NodeKind::UnaryOp(Token::Not, operand) => {
+                self.out.push_str("not ");
+                self.expr(operand, NOT);
+            }
+            NodeKind::UnaryOp(op, operand) => {
+                self.out.push_str(op.symbol());
+                self.expr(operand, UNARY);
+            }
+            NodeKind::Membership {
+                item,
+                container,
+                negated,
+            } => {
+                self.expr(item, COMPARISON);
+                self.out
+                    .push_str(if *negated { " not in " } else { " in " });
+                self.expr(container, RANGE);
+            }
+            NodeKind::BinaryOp(left, op, right) => {
+                let (left_min, right_min) = match operator_precedence(op) {
+                    TERM => (TERM, FACTOR),
+                    FACTOR => (FACTOR, UNARY),
+                    // Right-associative, and the right operand may carry a unary minus.
+                    POWER => (POSTFIX, UNARY),
+                    _ => (COMPARISON, RANGE),
+                };
+                self.expr(left, left_min);
+                self.out.push(' ');
+                self.out.push_str(op.symbol());
+                self.out.push(' ');
+                self.expr(right, right_min);
+            }
+            NodeKind::Logical(left, op, right) => {
+                let level = precedence(node);
+                self.expr(left, level);
+                self.out.push(' ');
+                self.out.push_str(op.symbol());
+                self.out.push(' ');
+                self.expr(right, level + 1);
+            }
+            NodeKind::Pipe(value, function) => {
+                self.expr(value, PIPE);
+                self.out.push_str(" |> ");
+                self.expr(function, PIPE + 1);
+            }
+            NodeKind::Call(callee, args) => {
+                self.expr(callee, POSTFIX);
+                self.out.push('(');
+                self.list(args);
+                self.out.push(')');
+            }
+            NodeKind::FnDef {
+                name,
+                params,
+                rest_param,
+                body,
+            } => match name {
+                Some(name) => {
+                    self.out.push_str("fn ");
+                    self.out.push_str(name);
+                    self.params(params, rest_param.as_deref());
+                    self.node(body);
+                }
+                None if matches!(body.kind, NodeKind::Block(_)) => {
+                    self.out.push_str("fn");
+                    self.params(params, rest_param.as_deref());
+                    self.node(body);
+                }
+                None => {
+                    self.out.push('|');
+                    self.param_list(params, rest_param.as_deref());
+                    self.out.push_str("| ");
+                    self.expr(body, LOWEST);
+                }
+            },
+            NodeKind::StructDef { name, fields } => {
+                self.out.push_str("struct ");
+                self.out.push_str(name);
+                if fields.is_empty() {
+                    self.out.push_str(" {}");
+                } else {
+                    self.out.push_str(" { ");
+                    self.out.push_str(&fields.join(", "));
+                    self.out.push_str(" }");
+                }
+            }
+            NodeKind::StructLit { name, fields } => {
+                self.out.push_str(name);
+                if fields.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+                self.out.push_str(" { ");
+                for (i, (field, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(field);
+                    self.out.push_str(": ");
+                    self.nested(value, LOWEST);
+                }
+                self.out.push_str(" }");
+            }
+            NodeKind::FieldAccess(target, field) => {
+                // `1.x` would lex as the number `1.`, so numeric targets always get parentheses.
+                let min = match target.kind {
+                    NodeKind::Number(_) | NodeKind::Integer(_) => PRIMARY + 1,
+                    _ => POSTFIX,
+                };
+                self.expr(target, min);
+                self.out.push('.');
+                self.out.push_str(field);
+            }
+            NodeKind::SafeFieldAccess(target, field) => {
+                self.expr(target, POSTFIX);
+                self.out.push_str("?.");
+                self.out.push_str(field);
+            }
+            NodeKind::Map(entries) => {
+                self.out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.node(key);
+                    self.out.push_str(": ");
+                    self.nested(value, LOWEST);
+                }
+                self.out.push('}');
+            }
+            NodeKind::Array(items) => {
+                self.out.push('[');
+                self.list(items);
+                self.out.push(']');
+            }
+            NodeKind::Tuple(items) => {
+                self.out.push('(');
+                self.list(items);
+                self.out.push(')');
+            }
+            NodeKind::Index(target, index) => {
+                self.expr(target, POSTFIX);
+                self.out.push('[');
+                self.nested(index, LOWEST);
+                self.out.push(']');
+            }
+            NodeKind::Range {
+                start,
+                end,
+                step,
+                inclusive,
+            } => {
+                self.expr(start, TERM);
+                self.out.push_str(if *inclusive { "..=" } else { ".." });
+                self.expr(end, TERM);
+                if let Some(step) = step {
+                    self.out.push_str(" by ");
+                    self.expr(step, TERM);
+                }
+            }
+        }
+    }
+
+    fn block(&mut self, statements: &[ASTNode]) {
+        if statements.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push_str("{\n");
+        self.depth += 1;
+        let saved = std::mem::replace(&mut self.no_struct_literal, false);
+        self.statements(statements);
+        self.no_struct_literal = saved;
+        self.out.push('\n');
+        self.depth -= 1;
+        self.indent();
+        self.out.push('}');
+    }
+
+    // One statement per line at the current depth, separated by `;`.
+    fn statements(&mut self, statements: &[ASTNode]) {
+        for (i, statement) in statements.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(";\n");
+            }
+            self.indent();
+            self.expr(statement, LOWEST);
+        }
+    }
+
+    fn params(&mut self, params: &[Param], rest_param: Option<&str>) {
+        self.out.push('(');
+        self.param_list(params, rest_param);
+        self.out.push_str(") ");
+    }
+
+    fn param_list(&mut self, params: &[Param], rest_param: Option<&str>) {
+        for (i, (name, default)) in params.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.out.push_str(name);
+            if let Some(default) = default {
+                self.out.push_str(" = ");
+                self.nested(default, LOWEST);
+            }
+        }
+        if let Some(rest_param) = rest_param {
+            if !params.is_empty() {
+                self.out.push_str(", ");
+            }
+            self.out.push_str("...");
+            self.out.push_str(rest_param);
+        }
+    }
+
+    fn list(&mut self, items: &[ASTNode]) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.nested(item, LOWEST);
+        }
+    }
+
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            self.escaped(c);
+        }
+        self.out.push('"');
+    }
+
+    fn escaped(&mut self, c: char) {
+        match c {
+            '"' => self.out.push_str("\\\""),
+            '\\' => self.out.push_str("\\\\"),
+            '\n' => self.out.push_str("\\n"),
+            '\t' => self.out.push_str("\\t"),
+            '\r' => self.out.push_str("\\r"),
+            _ => self.out.push(c),
+        }
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.out.push_str("    ");
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+// SplitMix64: small, fast and good enough for simulations and games, though not for
+// anything that has to be unpredictable.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    // Seeded differently each time, from the randomness the standard library keeps for
+    // hash maps.
+    pub fn new() -> Self {
+        Rng::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    // The same seed always gives the same sequence.
+    pub fn seeded(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // In `[0, 1)`, from the top 53 bits, as many as an `f64` holds.
+    pub fn float(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // In `[min, max]`, which must not be empty.
+    pub fn int_in(&mut self, min: i64, max: i64) -> i64 {
+        let span = (max as i128 - min as i128 + 1) as u128;
+        let offset = (self.next_u64() as u128 * span) >> 64;
+        (min as i128 + offset as i128) as i64
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
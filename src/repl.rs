@@ -0,0 +1,525 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::ast::ASTNode;
+use crate::interpreter::{DivByZero, Interpreter, OverflowMode, Value};
+use crate::pretty::pretty_print;
+use crate::{compiler, disasm, optimizer, Error};
+
+// The interactive prompt; bindings persist from one line to the next.
+pub struct Repl {
+    interpreter: Interpreter,
+    // Where results, errors and command output go; stdout unless `with_output` says.
+    output: Box<dyn Write>,
+}
+
+impl Repl {
+    /// A session with a default interpreter, writing to stdout.
+    pub fn new() -> Self {
+        Self::with_interpreter(Interpreter::new())
+    }
+
+    /// Starts the session from an interpreter configured by the caller.
+    pub fn with_interpreter(interpreter: Interpreter) -> Self {
+        Repl {
+            interpreter,
+            output: Box::new(io::stdout()),
+        }
+    }
+
+    // Writes everything but the prompts to `writer` in place of stdout.
+    pub fn with_output(mut self, writer: Box<dyn Write>) -> Self {
+        self.output = writer;
+        self
+    }
+
+    // Reads entries until the input ends or one of them ends the session. The same buffer
+    // holds each entry in turn.
+    pub fn run(&mut self) {
+        let mut reader = LineReader::new();
+        let mut input = String::new();
+        loop {
+            input.clear();
+            match reader.read_entry(&mut input, &mut self.output) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => {
+                    self.say(format_args!("Error: cannot read input: {}", err));
+                    break;
+                }
+            }
+            let entry = input.trim();
+            if entry.is_empty() {
+                continue;
+            }
+            reader.add_history(entry);
+            if !self.entry(entry) {
+                break;
+            }
+        }
+        reader.save_history();
+    }
+
+    // Handles one complete entry as if it had been typed at the prompt: a command or a
+    // program to evaluate. Returns false if it ends the session.
+    pub fn entry(&mut self, input: &str) -> bool {
+        let input = input.trim();
+        if input.is_empty() {
+            return true;
+        }
+
+        if input.eq_ignore_ascii_case("exit") {
+            self.say(format_args!("Exiting..."));
+            return false;
+        }
+
+        if let Some(command) = input.strip_prefix(':') {
+            self.run_command(command);
+            return true;
+        }
+
+        // `.5` is a number, so only a letter after the dot makes a command.
+        if let Some(command) = input
+            .strip_prefix('.')
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            return self.run_dot_command(command);
+        }
+
+        self.guarded(|repl| {
+            if let Some(ast) = repl.parse(input) {
+                repl.say(format_args!("{:?}", ast));
+                repl.evaluate(ast);
+            }
+        });
+        true
+    }
+
+    // A failing output writer should not end the session.
+    fn say(&mut self, line: fmt::Arguments) {
+        let _ = writeln!(self.output, "{}", line);
+    }
+
+    // Reports every syntax error in `input`; only input without any is evaluated. Empty
+    // input is ignored.
+    fn parse(&mut self, input: &str) -> Option<ASTNode> {
+        let mut parser = self.interpreter.parser(input.to_string());
+        let (ast, mut errors) = parser.parse_recovering();
+        errors.retain(|err| !matches!(err, Error::Parse(err) if err.is_empty_input()));
+        for err in &errors {
+            self.say(format_args!("Error: {}", err));
+        }
+        ast.filter(|_| errors.is_empty())
+    }
+
+    fn evaluate(&mut self, ast: ASTNode) {
+        match self.interpreter.run(ast) {
+            Ok(result) => {
+                let result = self.show(&result);
+                self.say(format_args!("Result: {}", result));
+            }
+            Err(err) => self.say(format_args!("Error: {}", err)),
+        }
+    }
+
+    // Runs one entry so that a panic inside the interpreter is reported as an internal
+    // error and the session carries on from the top-level scope.
+    fn guarded(&mut self, entry: impl FnOnce(&mut Self)) {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = panic::catch_unwind(AssertUnwindSafe(|| entry(self)));
+        panic::set_hook(hook);
+        if let Err(payload) = result {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            self.say(format_args!("Error: internal error: {}", message));
+            self.interpreter.recover();
+        }
+    }
+
+    fn show(&self, value: &Value) -> String {
+        show_result(value, self.interpreter.decimal_comma)
+    }
+
+    // Handles `.help`, `.vars` and the other dot commands. Returns false to end the session.
+    fn run_dot_command(&mut self, command: &str) -> bool {
+        let (name, arg) = match command.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (command, ""),
+        };
+        match name {
+            "help" => {
+                let _ = write!(self.output, "{}", HELP);
+            }
+            "vars" => {
+                for (name, value) in self.interpreter.variables() {
+                    let shown = self.show(&value);
+                    self.say(format_args!("{} = {} ({})", name, shown, value.type_name()));
+                }
+            }
+            "reset" => {
+                self.interpreter.reset();
+                self.say(format_args!("Environment cleared"));
+            }
+            "ast" if arg.is_empty() => self.say(format_args!("Usage: .ast <expr>")),
+            "ast" => self.show_ast(arg),
+            "disasm" if arg.is_empty() => self.say(format_args!("Usage: .disasm <expr>")),
+            "disasm" => self.disasm(arg),
+            "load" if arg.is_empty() => self.say(format_args!("Usage: .load <file>")),
+            "load" => self.load(arg),
+            "quit" | "exit" => {
+                self.say(format_args!("Exiting..."));
+                return false;
+            }
+            _ => self.say(format_args!(
+                "Unknown command: .{} (type .help for a list)",
+                name
+            )),
+        }
+        true
+    }
+
+    // Prints the tree `source` parses to and, when constant folding changes it, the tree
+    // `run` would evaluate.
+    fn show_ast(&mut self, source: &str) {
+        let Some(ast) = self.parse(source) else {
+            return;
+        };
+        let parsed = pretty_print(&ast);
+        self.say(format_args!("{}", parsed));
+        let folded = pretty_print(&optimizer::fold_constants(ast));
+        if folded != parsed {
+            let note = if self.interpreter.optimize {
+                ""
+            } else {
+                " (optimize is off)"
+            };
+            self.say(format_args!("folded{}:\n{}", note, folded));
+        }
+    }
+
+    // Prints the bytecode `source` compiles to, without running it.
+    fn disasm(&mut self, source: &str) {
+        let Some(ast) = self.parse(source) else {
+            return;
+        };
+        match compiler::compile(&ast) {
+            Ok(code) => {
+                let _ = write!(self.output, "{}", disasm::disassemble(&code));
+            }
+            Err(err) => self.say(format_args!("Error: {}", err)),
+        }
+    }
+
+    // Evaluates a file one entry at a time, grouping lines the same way the prompt does.
+    fn load(&mut self, path: &str) {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                self.say(format_args!("Error: cannot read {}: {}", path, err));
+                return;
+            }
+        };
+        let mut entry = String::new();
+        for line in source.lines() {
+            entry.push_str(line);
+            entry.push('\n');
+            match open_delimiters(&entry) {
+                Ok(0) => {}
+                Ok(_) => continue,
+                Err(c) => {
+                    self.say(format_args!("Error: unmatched `{}`", c));
+                    entry.clear();
+                    continue;
+                }
+            }
+            let input = std::mem::take(&mut entry).trim().to_string();
+            if input.is_empty() {
+                continue;
+            }
+            self.guarded(|repl| {
+                if let Some(ast) = repl.parse(&input) {
+                    repl.evaluate(ast);
+                }
+            });
+        }
+        if !entry.trim().is_empty() {
+            self.say(format_args!("Error: unexpected end of file in {}", path));
+        }
+    }
+
+    // Handles `:set <option> <value>` lines typed at the prompt.
+    fn run_command(&mut self, command: &str) {
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        match parts.as_slice() {
+            ["set", "overflow", "error"] => self.interpreter.overflow = OverflowMode::Error,
+            ["set", "overflow", "promote"] => self.interpreter.overflow = OverflowMode::Promote,
+            ["set", "divzero", "error"] => self.interpreter.div_by_zero = DivByZero::Error,
+            ["set", "divzero", "ieee"] => self.interpreter.div_by_zero = DivByZero::Ieee,
+            ["set", "divzero", value] => match value.parse() {
+                Ok(value) => self.interpreter.div_by_zero = DivByZero::Value(value),
+                Err(_) => self.say(format_args!(
+                    "Invalid divzero setting: {} (try error, ieee or a number)",
+                    value
+                )),
+            },
+            ["set", "strict-float", "on"] => self.interpreter.strict_float = true,
+            ["set", "strict-float", "off"] => self.interpreter.strict_float = false,
+            ["set", "case-insensitive", "on"] => self.interpreter.case_insensitive = true,
+            ["set", "case-insensitive", "off"] => self.interpreter.case_insensitive = false,
+            ["set", "decimal", "comma"] => self.interpreter.decimal_comma = true,
+            ["set", "decimal", "point"] => self.interpreter.decimal_comma = false,
+            ["set", "optimize", "on"] => self.interpreter.optimize = true,
+            ["set", "optimize", "off"] => self.interpreter.optimize = false,
+            ["set", option, ..] => self.say(format_args!("Unknown setting: {}", option)),
+            _ => self.say(format_args!("Unknown command: :{}", command)),
+        }
+    }
+}
+
+const HELP: &str = "\
+.help                         show this list
+.vars                         list session variables with their values and types
+.reset                        forget every variable, function and struct
+.load <file>                  evaluate a file, printing each result
+.ast <expr>                   show an expression's syntax tree before and after folding
+.disasm <expr>                show the bytecode an expression compiles to
+.quit, .exit                  leave the interpreter
+:set overflow error|promote   choose what integer overflow does
+:set divzero error|ieee|<n>   choose what division by zero gives
+:set strict-float on|off      fail on any NaN or infinity, not just warn about results
+:set case-insensitive on|off  match keywords and built-ins regardless of case
+:set decimal comma|point      read and print 3,14 or 3.14
+:set optimize on|off          fold constant expressions before evaluating
+";
+
+const PROMPT: &str = "Enter expression or type 'exit' to quit: ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+// Where prompt lines come from: rustyline with the `repl` feature, plain stdin otherwise.
+struct LineReader {
+    #[cfg(feature = "repl")]
+    editor: Option<rustyline::DefaultEditor>,
+    #[cfg(feature = "repl")]
+    history: Option<std::path::PathBuf>,
+}
+
+impl LineReader {
+    // Reads lines into `input` until every bracket is closed, prompting with `... ` for
+    // each continuation line, and reports an unmatched closing bracket to `output`.
+    // Returns false once input is exhausted.
+    fn read_entry(&mut self, input: &mut String, output: &mut dyn Write) -> io::Result<bool> {
+        let mut prompt = PROMPT;
+        loop {
+            match self.read_line(prompt)? {
+                Some(line) => input.push_str(&line),
+                None => return Ok(false),
+            }
+            match open_delimiters(input) {
+                Ok(0) => return Ok(true),
+                Ok(_) => prompt = CONTINUATION_PROMPT,
+                Err(c) => {
+                    // A failing output writer should not end the session.
+                    let _ = writeln!(output, "Error: unmatched `{}`", c);
+                    input.clear();
+                    return Ok(true);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "repl"))]
+impl LineReader {
+    fn new() -> Self {
+        LineReader {}
+    }
+
+    fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        read_stdin_line(prompt)
+    }
+
+    fn add_history(&mut self, _entry: &str) {}
+
+    fn save_history(&mut self) {}
+}
+
+// History is read from `INTERPRETER_HISTORY_FILE`, or `~/.interpreter_history`, at
+// startup and written back when the loop ends. If the terminal cannot be driven the
+// reader falls back to plain stdin.
+#[cfg(feature = "repl")]
+impl LineReader {
+    fn new() -> Self {
+        let history = std::env::var_os("INTERPRETER_HISTORY_FILE")
+            .map(std::path::PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| std::path::Path::new(&home).join(".interpreter_history"))
+            });
+        let editor = rustyline::DefaultEditor::new().ok().map(|mut editor| {
+            if let Some(path) = &history {
+                let _ = editor.load_history(path);
+            }
+            editor
+        });
+        LineReader { editor, history }
+    }
+
+    fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        use rustyline::error::ReadlineError;
+
+        let Some(editor) = &mut self.editor else {
+            return read_stdin_line(prompt);
+        };
+        match editor.readline(prompt) {
+            Ok(line) => Ok(Some(line + "\n")),
+            // Ctrl-C and Ctrl-D both end the session, as `exit` does.
+            Err(ReadlineError::Eof | ReadlineError::Interrupted) => Ok(None),
+            Err(ReadlineError::Io(err)) => Err(err),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        if let Some(editor) = &mut self.editor {
+            let _ = editor.add_history_entry(entry);
+        }
+    }
+
+    fn save_history(&mut self) {
+        if let (Some(editor), Some(path)) = (&mut self.editor, &self.history) {
+            if let Err(err) = editor.save_history(path) {
+                eprintln!("Could not save history to {}: {}", path.display(), err);
+            }
+        }
+    }
+}
+
+/// Renders a result for the user: `{:#}` in decimal comma mode, and a bare NaN with a note
+/// on what it means. Plain `Display` keeps printing `NaN` so `float(str(x))` round-trips.
+pub fn show_result(value: &Value, decimal_comma: bool) -> String {
+    match value {
+        Value::Float(n) if n.is_nan() => "NaN (not a number)".to_string(),
+        _ if decimal_comma => format!("{:#}", value),
+        _ => value.to_string(),
+    }
+}
+
+fn read_stdin_line(prompt: &str) -> io::Result<Option<String>> {
+    // A prompt that can't be shown is no reason to stop reading.
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "{}", prompt).and_then(|()| stdout.flush());
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line)? {
+        0 => Ok(None),
+        _ => Ok(Some(line)),
+    }
+}
+
+// Counts the brackets left open in `input`, or returns a closing bracket that does not
+// match. Brackets inside string literals are skipped.
+fn open_delimiters(input: &str) -> Result<usize, char> {
+    let mut open = Vec::new();
+    let mut in_string = false;
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '(' | '[' | '{' => open.push(c),
+            ')' | ']' | '}' => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if open.pop() != Some(expected) {
+                    return Err(c);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(open.len())
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn balanced_input_is_complete() {
+        assert_eq!(open_delimiters("1 + 2\n"), Ok(0));
+        assert_eq!(open_delimiters("f([1], {\"a\": (2)})"), Ok(0));
+        assert_eq!(open_delimiters(""), Ok(0));
+    }
+
+    #[test]
+    fn open_brackets_ask_for_more() {
+        assert_eq!(open_delimiters("(1 +\n"), Ok(1));
+        assert_eq!(open_delimiters("fn f(x) {\n  [x,\n"), Ok(2));
+        assert_eq!(open_delimiters("fn f(x) {\n  [x,\n]\n}\n"), Ok(0));
+    }
+
+    #[test]
+    fn brackets_in_strings_do_not_count() {
+        assert_eq!(open_delimiters("\"(\""), Ok(0));
+        assert_eq!(open_delimiters("\"a\\\"(\" + (1"), Ok(1));
+        assert_eq!(open_delimiters("\")\""), Ok(0));
+    }
+
+    #[test]
+    fn a_stray_closing_bracket_is_an_error_at_once() {
+        assert_eq!(open_delimiters("))"), Err(')'));
+        assert_eq!(open_delimiters("(1]"), Err(']'));
+        assert_eq!(open_delimiters("{ (1 }"), Err('}'));
+    }
+
+    #[derive(Clone, Default)]
+    struct Capture(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_panic_is_reported_and_the_session_goes_on() {
+        let output = Capture::default();
+        let mut repl = Repl::new().with_output(Box::new(output.clone()));
+        repl.entry("let x = 2");
+        output.0.borrow_mut().clear();
+        repl.guarded(|_| panic!("boom"));
+        repl.entry("x * 3");
+        let output = String::from_utf8(output.0.borrow().clone()).unwrap();
+        assert!(
+            output.starts_with("Error: internal error: boom\n"),
+            "{}",
+            output
+        );
+        assert!(output.ends_with("Result: 6\n"), "{}", output);
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use crate::ast::{ASTNode, FStrNode, NodeKind, Param, Pattern};
+use crate::lexer::Token;
+
+// Read-only traversal of the AST. Every `visit_*` method defaults to visiting the node's
+// children, so an implementor only overrides the nodes it cares about. Overriding `visit`
+// itself sees every node; call `walk` from it to keep descending.
+pub trait AstVisitor {
+    fn visit(&mut self, node: &ASTNode) {
+        walk(self, node);
+    }
+
+    fn visit_number(&mut self, _value: f64) {}
+
+    fn visit_integer(&mut self, _value: i64) {}
+
+    fn visit_bool(&mut self, _value: bool) {}
+
+    fn visit_null(&mut self) {}
+
+    fn visit_str(&mut self, _value: &str) {}
+
+    fn visit_fstr(&mut self, parts: &[FStrNode]) {
+        for part in parts {
+            if let FStrNode::Expr(expr) = part {
+                self.visit(expr);
+            }
+        }
+    }
+
+    fn visit_var_ref(&mut self, _name: &str) {}
+
+    fn visit_let(&mut self, _name: &str, value: &ASTNode) {
+        self.visit(value);
+    }
+
+    fn visit_destructure_let(&mut self, _names: &[String], value: &ASTNode) {
+        self.visit(value);
+    }
+
+    fn visit_const(&mut self, _name: &str, value: &ASTNode) {
+        self.visit(value);
+    }
+
+    fn visit_assign(&mut self, _name: &str, value: &ASTNode) {
+        self.visit(value);
+    }
+
+    fn visit_global(&mut self, _names: &[String]) {}
+
+    fn visit_import(&mut self, _path: &str) {}
+
+    fn visit_sequence(&mut self, statements: &[ASTNode]) {
+        for statement in statements {
+            self.visit(statement);
+        }
+    }
+
+    fn visit_block(&mut self, statements: &[ASTNode]) {
+        for statement in statements {
+            self.visit(statement);
+        }
+    }
+
+    fn visit_if(&mut self, cond: &ASTNode, then_branch: &ASTNode, else_branch: Option<&ASTNode>) {
+        self.visit(cond);
+        self.visit(then_branch);
+        if let Some(else_branch) = else_branch {
+            self.visit(else_branch);
+        }
+    }
+
+    fn visit_while(&mut self, cond: &ASTNode, body: &ASTNode) {
+        self.visit(cond);
+        self.visit(body);
+    }
+
+    fn visit_for(&mut self, _var: &str, iterable: &ASTNode, body: &ASTNode) {
+        self.visit(iterable);
+        self.visit(body);
+    }
+
+    fn visit_match(&mut self, subject: &ASTNode, arms: &[(Pattern, ASTNode)]) {
+        self.visit(subject);
+        for (pattern, body) in arms {
+            self.visit_pattern(pattern);
+            self.visit(body);
+        }
+    }
+
+    fn visit_try(
+        &mut self,
+        body: &ASTNode,
+        _binding: Option<&str>,
+        handler: Option<&ASTNode>,
+        finally: Option<&ASTNode>,
+    ) {
+        self.visit(body);
+        if let Some(handler) = handler {
+            self.visit(handler);
+        }
+        if let Some(finally) = finally {
+            self.visit(finally);
+        }
+    }
+
+    fn visit_throw(&mut self, value: &ASTNode) {
+        self.visit(value);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        if let Pattern::PatLiteral(literal) = pattern {
+            self.visit(literal);
+        }
+    }
+
+    fn visit_unary_op(&mut self, _op: &Token, operand: &ASTNode) {
+        self.visit(operand);
+    }
+
+    fn visit_membership(&mut self, item: &ASTNode, container: &ASTNode, _negated: bool) {
+        self.visit(item);
+        self.visit(container);
+    }
+
+    fn visit_binary_op(&mut self, left: &ASTNode, _op: &Token, right: &ASTNode) {
+        self.visit(left);
+        self.visit(right);
+    }
+
+    fn visit_logical(&mut self, left: &ASTNode, _op: &Token, right: &ASTNode) {
+        self.visit(left);
+        self.visit(right);
+    }
+
+    fn visit_pipe(&mut self, value: &ASTNode, function: &ASTNode) {
+        self.visit(value);
+        self.visit(function);
+    }
+
+    fn visit_call(&mut self, callee: &ASTNode, args: &[ASTNode]) {
+        self.visit(callee);
+        for arg in args {
+            self.visit(arg);
+        }
+    }
+
+    fn visit_fn_def(
+        &mut self,
+        _name: Option<&str>,
+        params: &[Param],
+        _rest_param: Option<&str>,
+        body: &ASTNode,
+    ) {
+        for default in params.iter().filter_map(|(_, default)| default.as_ref()) {
+            self.visit(default);
+        }
+        self.visit(body);
+    }
+
+    fn visit_struct_def(&mut self, _name: &str, _fields: &[String]) {}
+
+    fn visit_struct_lit(&mut self, _name: &str, fields: &[(String, ASTNode)]) {
+        for (_, value) in fields {
+            self.visit(value);
+        }
+    }
+
+    fn visit_field_access(&mut self, target: &ASTNode, _field: &str) {
+        self.visit(target);
+    }
+
+    fn visit_safe_field_access(&mut self, target: &ASTNode, _field: &str) {
+        self.visit(target);
+    }
+
+    fn visit_map(&mut self, entries: &[(ASTNode, ASTNode)]) {
+        for (key, value) in entries {
+            self.visit(key);
+            self.visit(value);
+        }
+    }
+
+    fn visit_array(&mut self, items: &[ASTNode]) {
+        for item in items {
+            self.visit(item);
+        }
+    }
+
+    fn visit_tuple(&mut self, items: &[ASTNode]) {
+        for item in items {
+            self.visit(item);
+        }
+    }
+
+    fn visit_index(&mut self, target: &ASTNode, index: &ASTNode) {
+        self.visit(target);
+        self.visit(index);
+    }
+
+    fn visit_range(
+        &mut self,
+        start: &ASTNode,
+        end: &ASTNode,
+        step: Option<&ASTNode>,
+        _inclusive: bool,
+    ) {
+        self.visit(start);
+        self.visit(end);
+        if let Some(step) = step {
+            self.visit(step);
+        }
+    }
+}
+
+// Dispatches `node` to the matching `visit_*` method.
+pub fn walk<V: AstVisitor + ?Sized>(visitor: &mut V, node: &ASTNode) {
+    match &node.kind {
+        NodeKind::Number(n) => visitor.visit_number(*n),
+        NodeKind::Integer(n) => visitor.visit_integer(*n),
+        NodeKind::Bool(b) => visitor.visit_bool(*b),
+        NodeKind::Null => visitor.visit_null(),
+        NodeKind::Str(s) => visitor.visit_str(s),
+        NodeKind::FStr(parts) => visitor.visit_fstr(parts),
+        NodeKind::VarRef(name) => visitor.visit_var_ref(name),
+        NodeKind::Let(name, value) => visitor.visit_let(name, value),
+        NodeKind::DestructureLet(names, value) => visitor.visit_destructure_let(names, value),
+        NodeKind::Const(name, value) => visitor.visit_const(name, value),
+        NodeKind::Assign(name, value) => visitor.visit_assign(name, value),
+        NodeKind::Global(names) => visitor.visit_global(names),
+        NodeKind::Import(path) => visitor.visit_import(path),
+        NodeKind::Sequence(statements) => visitor.visit_sequence(statements),
+        NodeKind::Block(statements) => visitor.visit_block(statements),
+        NodeKind::If {
+            cond,
+            then_branch,
+            else_branch,
+        } => visitor.visit_if(cond, then_branch, else_branch.as_deref()),
+        NodeKind::While { cond, body } => visitor.visit_while(cond, body),
+        NodeKind::For {
+            var,
+            iterable,
+            body,
+        } => visitor.visit_for(var, iterable, body),
+        NodeKind::Match { subject, arms } => visitor.visit_match(subject, arms),
+        NodeKind::Try {
+            body,
+            binding,
+            handler,
+            finally,
+        } => visitor.visit_try(
+            body,
+            binding.as_deref(),
+            handler.as_deref(),
+            finally.as_deref(),
+        ),
+        NodeKind::Throw(value) => visitor.visit_throw(value),
+        NodeKind::UnaryOp(op, operand) => visitor.visit_unary_op(op, operand),
+        NodeKind::Membership {
+            item,
+            container,
+            negated,
+        } => visitor.visit_membership(item, container, *negated),
+        NodeKind::BinaryOp(left, op, right) => visitor.visit_binary_op(left, op, right),
+        NodeKind::Logical(left, op, right) => visitor.visit_logical(left, op, right),
+        NodeKind::Call(callee, args) => visitor.visit_call(callee, args),
+        NodeKind::Pipe(value, function) => visitor.visit_pipe(value, function),
+        NodeKind::FnDef {
+            name,
+            params,
+            rest_param,
+            body,
+        } => visitor.visit_fn_def(name.as_deref(), params, rest_param.as_deref(), body),
+        NodeKind::StructDef { name, fields } => visitor.visit_struct_def(name, fields),
+        NodeKind::StructLit { name, fields } => visitor.visit_struct_lit(name, fields),
+        NodeKind::FieldAccess(target, field) => visitor.visit_field_access(target, field),
+        NodeKind::SafeFieldAccess(target, field) => visitor.visit_safe_field_access(target, field),
+        NodeKind::Map(entries) => visitor.visit_map(entries),
+        NodeKind::Array(items) => visitor.visit_array(items),
+        NodeKind::Tuple(items) => visitor.visit_tuple(items),
+        NodeKind::Index(target, index) => visitor.visit_index(target, index),
+        NodeKind::Range {
+            start,
+            end,
+            step,
+            inclusive,
+        } => visitor.visit_range(start, end, step.as_deref(), *inclusive),
+    }
+}
+
+// Counts the nodes of each kind in a tree, keyed by variant name (`"BinaryOp"`, ...).
+#[derive(Debug, Default)]
+pub struct NodeCounter {
+    pub counts: HashMap<&'static str, usize>,
+}
+
+impl NodeCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn count(&self, kind: &str) -> usize {
+        self.counts.get(kind).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+impl AstVisitor for NodeCounter {
+    fn visit(&mut self, node: &ASTNode) {
+        *self.counts.entry(node.kind.name()).or_default() += 1;
+        walk(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> ASTNode {
+        Parser::new(Lexer::new(source.to_string())).parse().unwrap()
+    }
+
+    #[test]
+    fn node_counter_counts_each_kind() {
+        let ast = parse("let x = 1 + 2 * 3; if x > 2 { f(x, 1.5) } else { -x }");
+        let mut counter = NodeCounter::new();
+        counter.visit(&ast);
+        let expected = [
+            ("Sequence", 1),
+            ("Let", 1),
+            ("BinaryOp", 3),
+            ("Integer", 4),
+            ("If", 1),
+            ("Block", 2),
+            ("Call", 1),
+            ("VarRef", 4),
+            ("Number", 1),
+            ("UnaryOp", 1),
+        ];
+        for (kind, count) in expected {
+            assert_eq!(counter.count(kind), count, "{}", kind);
+        }
+        assert_eq!(counter.count("While"), 0);
+        assert_eq!(
+            counter.total(),
+            expected.iter().map(|(_, n)| n).sum::<usize>()
+        );
+    }
+
+    // Overriding one method leaves the defaults to reach it wherever it is nested.
+    #[test]
+    fn defaults_visit_every_child() {
+        #[derive(Default)]
+        struct Names(Vec<String>);
+        impl AstVisitor for Names {
+            fn visit_var_ref(&mut self, name: &str) {
+                self.0.push(name.to_string());
+            }
+        }
+        let mut names = Names::default();
+        names.visit(&parse(
+            r#"fn g(a) { while a { for i in b { [c, {"k": d}][e] } } }; f"{h}""#,
+        ));
+        assert_eq!(names.0, ["a", "b", "c", "d", "e", "h"]);
+    }
+}
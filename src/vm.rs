@@ -0,0 +1,268 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::ASTNode;
+use crate::compiler::{self, CompiledFunction, Instruction};
+use crate::error::{CompileError, EvalError};
+use crate::interpreter::{self, Function, Interpreter, Value};
+
+// One call of a compiled function.
+pub struct CallFrame {
+    function: Rc<CompiledFunction>,
+    // Where the caller continues once the call returns.
+    return_ip: usize,
+    // The call's scopes, innermost last; the first holds the parameters.
+    locals: Vec<HashMap<String, Value>>,
+}
+
+// Runs the output of `compiler::compile`. Operators and built-in functions are borrowed
+// from the tree-walking interpreter, so both give the same values and errors.
+pub struct Vm {
+    pub stack: Vec<Value>,
+    pub globals: HashMap<String, Value>,
+    pub ip: usize,
+    pub call_stack: Vec<CallFrame>,
+    // Block scopes entered outside any call, innermost last.
+    scopes: Vec<HashMap<String, Value>>,
+    interpreter: Interpreter,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Vm {
+            stack: Vec::new(),
+            globals: HashMap::new(),
+            ip: 0,
+            call_stack: Vec::new(),
+            scopes: Vec::new(),
+            interpreter: Interpreter::new(),
+        }
+    }
+
+    // How many calls may be running at once; the interpreter's `max_depth` by default.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.interpreter.max_depth = max_depth;
+        self
+    }
+
+    // Executes `code` from the start and returns the value left by its `Return`. Globals
+    // persist from one run to the next. Code that runs off its end or takes more off the
+    // stack than it put there fails with `InvalidBytecode`, and calls nested deeper than
+    // the interpreter's `max_depth` with `TooDeep`.
+    pub fn run(&mut self, code: &[Instruction]) -> Result<Value, EvalError> {
+        self.stack.clear();
+        self.scopes.clear();
+        self.call_stack.clear();
+        self.ip = 0;
+        // The function being run, or None for `code` itself.
+        let mut current: Option<Rc<CompiledFunction>> = None;
+        loop {
+            let instruction = match &current {
+                Some(function) => function.code.get(self.ip),
+                None => code.get(self.ip),
+            };
+            let Some(instruction) = instruction else {
+                return Err(EvalError::InvalidBytecode(format!(
+                    "no instruction at {}",
+                    self.ip
+                )));
+            };
+            self.ip += 1;
+            match instruction {
+                Instruction::LoadConst(value) => self.stack.push(value.clone()),
+                Instruction::LoadVar(name) => {
+                    let value = self.load(name)?;
+                    self.stack.push(value);
+                }
+                Instruction::DefineVar(name) => {
+                    let value = self.peek()?.clone();
+                    let scopes = match self.call_stack.last_mut() {
+                        Some(frame) => &mut frame.locals,
+                        None => &mut self.scopes,
+                    };
+                    match scopes.last_mut() {
+                        Some(scope) => scope.insert(name.clone(), value),
+                        None => self.globals.insert(name.clone(), value),
+                    };
+                }
+                Instruction::StoreVar(name) => {
+                    let value = self.peek()?.clone();
+                    self.store(name, value)?;
+                }
+                Instruction::BinaryOp(op) => {
+                    let right = self.pop()?;
+                    let left = self.pop()?;
+                    let value = self.interpreter.binary_op(&op.token(), left, right)?;
+                    self.stack.push(value);
+                }
+                Instruction::UnaryOp(op) => {
+                    let operand = self.pop()?;
+                    let value = self.interpreter.unary_op(&op.token(), operand)?;
+                    self.stack.push(value);
+                }
+                Instruction::CheckBool(op) => {
+                    let value = self.pop()?;
+                    let b = interpreter::logical_value(&op.token(), value)?;
+                    self.stack.push(Value::Bool(b));
+                }
+                Instruction::Jump(target) => self.ip = *target,
+                Instruction::JumpIfFalse(target) => {
+                    if !interpreter::condition_value(self.pop()?)? {
+                        self.ip = *target;
+                    }
+                }
+                Instruction::Call(argc) => {
+                    let Some(start) = self.stack.len().checked_sub(*argc) else {
+                        return Err(self.underflow());
+                    };
+                    let args = self.stack.split_off(start);
+                    match self.pop()? {
+                        Value::Function(Function::Compiled(function)) => {
+                            if args.len() != function.params.len() {
+                                return Err(EvalError::ArityMismatch(
+                                    function.params.len(),
+                                    args.len(),
+                                ));
+                            }
+                            if self.call_stack.len() >= self.interpreter.max_depth {
+                                return Err(EvalError::TooDeep(self.interpreter.max_depth));
+                            }
+                            let params = function.params.iter().cloned().zip(args).collect();
+                            self.call_stack.push(CallFrame {
+                                function: Rc::clone(&function),
+                                return_ip: self.ip,
+                                locals: vec![params],
+                            });
+                            current = Some(function);
+                            self.ip = 0;
+                        }
+                        callee => {
+                            let value = self.interpreter.call_function(&callee, args)?;
+                            self.stack.push(value);
+                        }
+                    }
+                }
+                Instruction::EnterScope => self.scopes_mut().push(HashMap::new()),
+                Instruction::ExitScope => {
+                    self.scopes_mut().pop();
+                }
+                // The return value stays on top of the stack for the caller.
+                Instruction::Return => match self.call_stack.pop() {
+                    Some(frame) => {
+                        self.ip = frame.return_ip;
+                        current = self
+                            .call_stack
+                            .last()
+                            .map(|frame| Rc::clone(&frame.function));
+                    }
+                    None => return self.pop(),
+                },
+                Instruction::Pop => {
+                    self.pop()?;
+                }
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Result<Value, EvalError> {
+        match self.stack.pop() {
+            Some(value) => Ok(value),
+            None => Err(self.underflow()),
+        }
+    }
+
+    fn peek(&self) -> Result<&Value, EvalError> {
+        self.stack.last().ok_or_else(|| self.underflow())
+    }
+
+    // The instruction being executed needs more values than the stack holds.
+    fn underflow(&self) -> EvalError {
+        EvalError::InvalidBytecode(format!("stack underflow at {}", self.ip - 1))
+    }
+
+    fn scopes_mut(&mut self) -> &mut Vec<HashMap<String, Value>> {
+        match self.call_stack.last_mut() {
+            Some(frame) => &mut frame.locals,
+            None => &mut self.scopes,
+        }
+    }
+
+    // Compiled functions only see their own locals and the session variables. Names no
+    // binding covers fall back to the interpreter's built-ins.
+    fn load(&self, name: &str) -> Result<Value, EvalError> {
+        let scopes = match self.call_stack.last() {
+            Some(frame) => &frame.locals,
+            None => &self.scopes,
+        };
+        scopes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.globals))
+            .find_map(|scope| scope.get(name).cloned())
+            .or_else(|| self.interpreter.builtin(name))
+            .ok_or_else(|| EvalError::UndefinedVariable(name.to_string()))
+    }
+
+    // As in the interpreter, assigning to a session variable inside a call makes a new
+    // local of the call instead.
+    fn store(&mut self, name: &str, value: Value) -> Result<(), EvalError> {
+        let in_call = !self.call_stack.is_empty();
+        let scopes = match self.call_stack.last_mut() {
+            Some(frame) => &mut frame.locals,
+            None => &mut self.scopes,
+        };
+        if let Some(binding) = scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name))
+        {
+            *binding = value;
+            return Ok(());
+        }
+        if !in_call {
+            if let Some(binding) = self.globals.get_mut(name) {
+                *binding = value;
+                return Ok(());
+            }
+        }
+        if !self.globals.contains_key(name) && self.interpreter.is_const(name) {
+            return Err(EvalError::ConstReassignment(name.to_string(), None));
+        }
+        if !in_call {
+            return Err(EvalError::UndefinedVariable(name.to_string()));
+        }
+        scopes[0].insert(name.to_string(), value);
+        Ok(())
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A formula compiled once and evaluated for many values of its variables, as a plotter
+// evaluates `y = f(x)`. Each evaluation reuses the same VM.
+pub struct Compiled {
+    code: Vec<Instruction>,
+    vm: RefCell<Vm>,
+}
+
+impl Compiled {
+    pub fn new(ast: &ASTNode) -> Result<Self, CompileError> {
+        Ok(Compiled {
+            code: compiler::compile(ast)?,
+            vm: RefCell::new(Vm::new()),
+        })
+    }
+
+    // Runs the code with `env` as its session variables, giving a number as a float and
+    // anything else as a `TypeMismatch`.
+    pub fn eval(&self, env: &HashMap<String, Value>) -> Result<f64, EvalError> {
+        let mut vm = self.vm.borrow_mut();
+        vm.globals.clone_from(env);
+        interpreter::number_value(vm.run(&self.code)?)
+    }
+}
@@ -0,0 +1,68 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+#[test]
+fn take_a_list_or_the_values_themselves() {
+    assert_eq!(eval("sum(1..=100)"), Ok(Value::Integer(5050)));
+    assert_eq!(eval("sum(1, 2, 3)"), Ok(Value::Integer(6)));
+    assert_eq!(eval("sum([])"), Ok(Value::Integer(0)));
+    assert_eq!(eval("product([1, 2, 3, 4])"), Ok(Value::Integer(24)));
+    assert_eq!(eval("product(2, 0.5)"), Ok(Value::Float(1.0)));
+    assert_eq!(eval("avg(1, 2, 3, 4)"), Ok(Value::Float(2.5)));
+    assert_eq!(eval("avg(1..=9)"), Ok(Value::Float(5.0)));
+}
+
+#[test]
+fn numeric_aggregates_reject_other_values() {
+    assert!(matches!(
+        eval(r#"sum(["a"])"#),
+        Err(EvalError::TypeMismatch(_))
+    ));
+    assert!(matches!(
+        eval(r#"avg(1, "2")"#),
+        Err(EvalError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn avg_of_nothing_is_an_error() {
+    let err = Err(EvalError::InvalidArgument(
+        "avg() requires at least one value".to_string(),
+    ));
+    assert_eq!(eval("avg([])"), err);
+    assert_eq!(eval("avg()"), err);
+}
+
+#[test]
+fn count_counts_any_values() {
+    assert_eq!(eval(r#"count(["a", null, [1]])"#), Ok(Value::Integer(3)));
+    assert_eq!(eval("count(1..11)"), Ok(Value::Integer(10)));
+    assert_eq!(eval(r#"count("a", "b")"#), Ok(Value::Integer(2)));
+    assert_eq!(eval("count([])"), Ok(Value::Integer(0)));
+}
+
+// Adding 0.1 a hundred thousand times one by one is off in the eighth decimal place.
+#[test]
+fn sums_are_compensated() {
+    let source = "
+        let xs = map(|i| 0.1, 0..100000);
+        let naive = 0.0;
+        for x in xs { naive = naive + x };
+        [naive, sum(xs)]
+    ";
+    let Ok(Value::Array(sums)) = eval(source) else {
+        panic!("summing failed");
+    };
+    let [Value::Float(naive), Value::Float(compensated)] = sums[..] else {
+        panic!("expected two floats, got {:?}", sums);
+    };
+    assert!((naive - 10_000.0).abs() > 1e-9, "{}", naive);
+    assert_eq!(compensated, 10_000.0);
+    assert_eq!(
+        eval("sum(10.0 ^ 100, 1.0, -(10.0 ^ 100))"),
+        Ok(Value::Float(1.0))
+    );
+}
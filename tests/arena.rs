@@ -0,0 +1,190 @@
+// `arena::Ast` against the boxed tree it is made from: converting back gives the same
+// tree, and evaluating it gives the same value, error, error span and trace.
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::thread;
+
+use interpreter::arena::Ast;
+use interpreter::ast::ASTNode;
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::{Lexer, Span};
+use interpreter::parser::Parser;
+use interpreter::visitor::{walk, AstVisitor};
+use interpreter::Value;
+
+mod common;
+use common::parse;
+
+#[derive(Default)]
+struct Spans(Vec<Span>);
+
+impl AstVisitor for Spans {
+    fn visit(&mut self, node: &ASTNode) {
+        self.0.push(node.span);
+        walk(self, node);
+    }
+}
+
+// The tree's debug form and every span in it.
+fn shape(ast: &ASTNode) -> (String, Vec<Span>) {
+    let mut spans = Spans::default();
+    spans.visit(ast);
+    (format!("{:?}", ast), spans.0)
+}
+
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// The result of running `source`, where its last error came from, and the trace.
+fn outcome(source: &str, arena: bool) -> (Result<Value, EvalError>, Option<Span>, String) {
+    let capture = Capture::default();
+    let mut interpreter = Interpreter::with_trace(Box::new(capture.clone())).with_arena(arena);
+    let result = interpreter.run(parse(source));
+    let trace = String::from_utf8(capture.0.borrow().clone()).unwrap();
+    (result, interpreter.error_span(), trace)
+}
+
+// The same without a trace, which `interpret_ast` evaluates some expressions faster
+// without.
+fn untraced(source: &str, arena: bool) -> (Result<Value, EvalError>, Option<Span>) {
+    let mut interpreter = Interpreter::new().with_arena(arena);
+    let result = interpreter.run(parse(source));
+    (result, interpreter.error_span())
+}
+
+const CORPUS: &[&str] = &[
+    "1 + 2 * 3 - 4 / 5 % 6",
+    "2 ^ 3 ^ 2",
+    "-2.5 + √16",
+    "let x = 3; x += 1; x",
+    "const c = 1; c = 2",
+    "let (a, b) = (1, 2); a - b",
+    "let (a, b) = (1, 2, 3)",
+    "{ let x = 1; x } + 1",
+    "if 1 < 2 { \"yes\" } else { \"no\" }",
+    "if 1 { 2 }",
+    "let n = 0; while n < 5 { n += 1 }; n",
+    "let total = 0; for x in [1, 2, 3] { total += x }; total",
+    "for k in {\"b\": 1, \"a\": 2} { k }",
+    "for x in 5 { x }",
+    "match 2 { 1 => \"one\", 2 => \"two\", _ => \"many\" }",
+    "match 9 { n => n * 2 }",
+    "match 9 { 1 => 0 }",
+    "try { 1 / 0 } catch e { e.kind } finally { 3 }",
+    "try { throw \"boom\" } catch e { e.message }",
+    "throw 42",
+    "1 in [1, 2] and 3 not in (1, 2)",
+    "null ?? 4",
+    "true or undefined",
+    "false and 1",
+    "1 or true",
+    "fn add(a, b = 10) { a + b }; add(1) + add(1, 2)",
+    "fn all(first, ...rest) { rest }; all(1, 2, 3)",
+    "let f = |x| x * 2; [1, 2, 3] |> len |> f",
+    "map(|x| x + 1, [1, 2, 3])",
+    "struct P { x, y }; let p = P { x: 1, y: 2 }; p.x + p.y",
+    "struct P { x }; P { y: 1 }",
+    "struct P { x, y }; P { x: 1 }",
+    "Q { x: 1 }",
+    "null?.x",
+    "{\"a\": 1}.a",
+    "{\"a\": 1}[0]",
+    "{\"a\": 1, \"b\": 2}.c",
+    "[1, 2, 3][1]",
+    "[1, 2, 3][5]",
+    "(1, \"two\")",
+    "0..10 by 3",
+    "10..=0 by -5",
+    "f\"{1 + 1} and {\"x\"}\"",
+    "undefined + 1",
+    "let x = 2; -x * (x + 1) - 3 ^ x",
+    "let x = 2; 1 + (x * (4 - undefined))",
+    "1 + 2 * (3 - (4 / (0 * 5)))",
+    "\"a\" + \"b\" == \"ab\"",
+    "-(2 ^ 62) * -(2 ^ 62)",
+    "1 + \"a\"",
+    "9223372036854775807 + 1",
+    "1 / 0",
+    "1.0 / 0.0",
+    "global g; g = 1",
+    "abs(-3) + max(1, 2)",
+    "len(1)",
+    "(|x| x)(1, 2)",
+    "let n = 3; n(1)",
+];
+
+#[test]
+fn the_tree_survives_the_round_trip() {
+    for source in CORPUS {
+        let tree = parse(source);
+        let expected = shape(&tree);
+        let ast = Ast::from_tree(tree);
+        assert_eq!(shape(&ast.to_tree()), expected, "{}", source);
+        assert_eq!(shape(&ast.subtree(ast.root())), expected, "{}", source);
+    }
+}
+
+#[test]
+fn parse_ast_builds_the_same_tree() {
+    let ast = Parser::new(Lexer::new("1 + 2 * 3".to_string()))
+        .parse_ast()
+        .unwrap();
+    assert_eq!(ast.len(), 5);
+    assert_eq!(ast[ast.root()].span, Span { start: 0, end: 9 });
+    assert_eq!(
+        format!("{:?}", ast.to_tree()),
+        "BinaryOp(Integer(1), Plus, BinaryOp(Integer(2), Mul, Integer(3)))"
+    );
+}
+
+#[test]
+fn both_representations_evaluate_alike() {
+    for source in CORPUS {
+        assert_eq!(outcome(source, true), outcome(source, false), "{}", source);
+        assert_eq!(
+            untraced(source, true),
+            untraced(source, false),
+            "{}",
+            source
+        );
+    }
+}
+
+// A debug build needs a lot of stack per level of nesting, more than a test thread has
+// for sixty of them.
+#[test]
+fn the_depth_limit_applies_alike() {
+    let test = || {
+        let source = format!("{}1{}", "(1 + ".repeat(60), ")".repeat(60));
+        for max_depth in [0, 1, 20, 59, 61, 62, 200] {
+            let run = |arena: bool| {
+                let mut interpreter = Interpreter::new()
+                    .with_arena(arena)
+                    .with_optimization(false);
+                interpreter.max_depth = max_depth;
+                let result = interpreter.run(parse(&source));
+                (result, interpreter.error_span())
+            };
+            assert_eq!(run(true), run(false), "max_depth {}", max_depth);
+        }
+    };
+    thread::Builder::new()
+        .stack_size(256 << 20)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -0,0 +1,45 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn failed(message: Option<&str>) -> Result<Value, EvalError> {
+    Err(EvalError::AssertionFailed(message.map(str::to_string)))
+}
+
+#[test]
+fn passing_assertions_give_null() {
+    assert_eq!(eval("assert(1 < 2)"), Ok(Value::Null));
+    assert_eq!(eval(r#"assert(true, "never shown")"#), Ok(Value::Null));
+    assert_eq!(eval("assert_eq(1 + 1, 2)"), Ok(Value::Null));
+    assert_eq!(eval("assert_eq(2, 2.0)"), Ok(Value::Null));
+}
+
+#[test]
+fn a_failed_assertion_stops_evaluation() {
+    assert_eq!(eval("assert(1 > 2)"), failed(None));
+    assert_eq!(eval("let x = 1; assert(false); x = 2"), failed(None));
+    assert_eq!(
+        eval(r#"assert(1 > 2, "one is small")"#),
+        failed(Some("one is small"))
+    );
+    assert!(matches!(eval("assert(1)"), Err(EvalError::TypeMismatch(_))));
+}
+
+#[test]
+fn messages_show_both_values_in_full() {
+    let err = eval("assert_eq(0.1 + 0.2, 0.3)").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "assertion failed: left: 0.30000000000000004, right: 0.3"
+    );
+    assert_eq!(
+        eval(r#"assert_eq("1", 1, "types")"#),
+        failed(Some(r#"types (left: "1", right: 1)"#))
+    );
+    assert_eq!(
+        eval("assert(false, \"no\")").unwrap_err().to_string(),
+        "assertion failed: no"
+    );
+}
@@ -0,0 +1,50 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::{Error, Value};
+
+fn run(case_insensitive: bool, source: &str) -> Result<Value, Error> {
+    let mut interpreter = Interpreter::new();
+    interpreter.case_insensitive = case_insensitive;
+    let ast = interpreter.parser(source.to_string()).parse()?;
+    Ok(interpreter.run(ast)?)
+}
+
+#[test]
+fn mixed_case_builtins_and_constants_need_the_option() {
+    for source in ["SQRT(4)", "Sqrt(4)", "Pi > 3", "LEN([1])", "MAX(1, 2)"] {
+        assert!(run(true, source).is_ok(), "{}", source);
+        assert!(
+            matches!(
+                run(false, source),
+                Err(Error::Runtime(EvalError::UndefinedVariable(_)))
+            ),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(run(true, "SQRT(4)"), Ok(Value::Float(2.0)));
+    assert_eq!(run(false, "sqrt(4)"), Ok(Value::Float(2.0)));
+}
+
+#[test]
+fn mixed_case_keywords_need_the_option() {
+    let source = "LET x = 7; IF x MOD 2 == 1 { True } Else { FALSE }";
+    assert_eq!(run(true, source), Ok(Value::Bool(true)));
+    assert!(run(false, source).is_err());
+}
+
+// Only keywords, built-ins and built-in constants ignore case; user names never do.
+#[test]
+fn user_names_stay_case_sensitive() {
+    assert!(matches!(
+        run(true, "let total = 1; TOTAL"),
+        Err(Error::Runtime(EvalError::UndefinedVariable(_)))
+    ));
+    assert_eq!(
+        run(true, "let x = 1; let X = 2; [x, X]")
+            .unwrap()
+            .to_string(),
+        "[1, 2]"
+    );
+    assert_eq!(run(true, "let len = 3; LEN([1, 2])"), Ok(Value::Integer(2)));
+}
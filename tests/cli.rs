@@ -0,0 +1,286 @@
+// The binary run as a user would run it, through its arguments, stdin, stdout, stderr
+// and exit code.
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn interpreter(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_Interpreter"))
+        .args(args)
+        .output()
+        .expect("cannot run the interpreter")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+// A script in the temporary directory, removed when dropped. `name` keeps the tests
+// running at once from sharing one.
+struct Script(PathBuf);
+
+impl Script {
+    fn new(name: &str, source: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("cli_{}_{}.calc", std::process::id(), name));
+        fs::write(&path, source).unwrap();
+        Script(path)
+    }
+
+    fn path(&self) -> &str {
+        self.0.to_str().unwrap()
+    }
+}
+
+impl Drop for Script {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn a_script_prints_its_last_value() {
+    let script = Script::new(
+        "last_value",
+        "let total = 0;\nfn add(n) {\n    total + n\n}\nlet i = 0;\nwhile i < 3 { total = add(i); i = i + 1 };\ntotal\n",
+    );
+    let output = interpreter(&[script.path()]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "3\n");
+}
+
+#[test]
+fn a_script_that_fails_exits_with_1() {
+    let script = Script::new("fails", "let x = 1;\nx / 0\n");
+    let output = interpreter(&[script.path()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains("division by zero in `1 / 0` at line 2, column 1"),
+        "{}",
+        stderr(&output)
+    );
+    let output = interpreter(&["/no/such/script.calc"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("Error: cannot read /no/such/script.calc: "));
+}
+
+#[test]
+fn arguments_after_a_double_dash_go_to_the_script() {
+    let script = Script::new("args", "args()");
+    let output = interpreter(&[script.path(), "--", "one", "--two"]);
+    assert_eq!(stdout(&output), "[\"one\", \"--two\"]\n");
+    let output = interpreter(&[script.path()]);
+    assert_eq!(stdout(&output), "[]\n");
+}
+
+#[test]
+fn check_parses_without_evaluating() {
+    let script = Script::new("check_ok", "1 / 0");
+    let output = interpreter(&["--check", script.path()]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "");
+    let script = Script::new("check_bad", "let x = (1 +\n");
+    let output = interpreter(&["--check", script.path()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Error: "), "{}", stderr(&output));
+}
+
+#[test]
+fn eval_prints_the_value_and_exits_with_0() {
+    let output = interpreter(&["-e", "2 ^ 10"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "1024\n");
+    assert_eq!(stderr(&output), "");
+    let output = interpreter(&["--eval", "\"text\""]);
+    assert_eq!(stdout(&output), "text\n");
+}
+
+#[test]
+fn eval_explains_a_nan_result() {
+    let output = interpreter(&["-e", "sqrt(-1)"]);
+    assert_eq!(stdout(&output), "NaN (not a number)\n");
+    let output = interpreter(&["-e", "str(sqrt(-1))"]);
+    assert_eq!(stdout(&output), "NaN\n");
+}
+
+#[test]
+fn eval_flags_share_one_environment() {
+    let output = interpreter(&["-e", "let x = 5", "-e", "fn f(n) { n * x }", "-e", "f(2)"]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "10\n");
+}
+
+#[test]
+fn eval_errors_exit_with_1() {
+    let output = interpreter(&["-e", "1 / 0"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "");
+    assert!(
+        stderr(&output).contains("division by zero"),
+        "{}",
+        stderr(&output)
+    );
+    let output = interpreter(&["-e", "1 +"]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn bad_usage_exits_with_2() {
+    let script = Script::new("with_eval", "1");
+    for args in [
+        &["-e", "1", script.path()][..],
+        &["-e"],
+        &["--frobnicate"],
+        &["a.calc", "b.calc"],
+        &["--check"],
+    ] {
+        let output = interpreter(args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+        assert!(
+            stderr(&output).contains("Usage: Interpreter"),
+            "{:?}: {}",
+            args,
+            stderr(&output)
+        );
+    }
+    let output = interpreter(&["-e", "1", script.path()]);
+    assert!(stderr(&output).starts_with("Error: --eval cannot be combined with a script\n"));
+}
+
+#[test]
+fn trace_goes_to_stderr_indented_by_depth() {
+    let output = interpreter(&["--trace", "-e", "let x = 2; x * 3"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stdout(&output), "6\n");
+    let expected = [
+        "[depth=0] Evaluating: Sequence([Let(\"x\", Integer(2)), BinaryOp(VarRef(\"x\"), Mul, Integer(3))])",
+        "  [depth=1] Evaluating: Let(\"x\", Integer(2))",
+        "    [depth=2] Evaluating: Integer(2)",
+        "    [depth=2] => 2",
+        "  [depth=1] => 2",
+        "  [depth=1] Evaluating: BinaryOp(VarRef(\"x\"), Mul, Integer(3))",
+        "    [depth=2] Evaluating: VarRef(\"x\")",
+        "    [depth=2] => 2",
+        "    [depth=2] Evaluating: Integer(3)",
+        "    [depth=2] => 3",
+        "  [depth=1] => 6",
+        "[depth=0] => 6",
+    ];
+    let trace = stderr(&output);
+    assert_eq!(trace.lines().collect::<Vec<_>>(), expected, "{}", trace);
+}
+
+#[test]
+fn without_trace_stderr_stays_empty() {
+    let output = interpreter(&["-e", "let x = 2; x * 3"]);
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn dead_branches_are_warned_about_unless_no_warn_is_given() {
+    let script = Script::new("dead_branch", "while false { 1 };\n2\n");
+    let output = interpreter(&[script.path()]);
+    assert_eq!(stdout(&output), "2\n");
+    assert_eq!(
+        stderr(&output),
+        "Warning: loop body is unreachable: the condition is always false (help: remove the loop)\n"
+    );
+    let output = interpreter(&["--no-warn", script.path()]);
+    assert_eq!(stdout(&output), "2\n");
+    assert_eq!(stderr(&output), "");
+}
+
+// The echoed input, a caret under the bracket at fault, and its column.
+#[test]
+fn unbalanced_brackets_point_at_their_column() {
+    let cases = [
+        ("((1 + 2) * 3", 0, "unclosed `(` opened at column 1"),
+        ("(1 + (2", 5, "unclosed `(` opened at column 6"),
+        ("  (((1)", 3, "unclosed `(` opened at column 4"),
+        ("f([1, (2)", 2, "unclosed `[` opened at column 3"),
+        ("1 + 2)", 5, "unmatched `)` at column 6"),
+        ("(1))", 3, "unmatched `)` at column 4"),
+    ];
+    for (source, offset, message) in cases {
+        let output = interpreter(&["-e", source]);
+        assert_eq!(output.status.code(), Some(1), "{:?}", source);
+        assert_eq!(
+            stderr(&output),
+            format!(
+                "  {}\n  {}^\nError: {}\n",
+                source,
+                " ".repeat(offset),
+                message
+            ),
+            "{:?}",
+            source
+        );
+    }
+}
+
+// With no script the binary reads entries from stdin. A line that fails at any stage is
+// reported without its AST, and the next line still runs.
+#[test]
+fn the_repl_reports_a_bad_line_and_runs_the_next() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_Interpreter"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("cannot run the interpreter");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"1 +\n2 * 3\n$\nlet x = 1 / 0\nx\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    // Whether the prompt is printed once input is not a terminal depends on the `repl`
+    // feature.
+    let stdout = stdout(&output).replace("Enter expression or type 'exit' to quit: ", "");
+    assert_eq!(
+        stdout,
+        "Error: expected an expression, found end of input at line 1, col 4\n\
+         BinaryOp(Integer(2), Mul, Integer(3))\n\
+         Result: 6\n\
+         Error: unexpected character `$` at line 1, col 1\n\
+         Let(\"x\", BinaryOp(Integer(1), Div, Integer(0)))\n\
+         Error: division by zero in `1 / 0`\n\
+         VarRef(\"x\")\n\
+         Error: undefined variable: x\n"
+    );
+}
+
+// Input that isn't text ends the session with an error instead of a panic, and a stray
+// closing bracket is reported with the rest of the output.
+#[test]
+fn the_repl_reports_unreadable_input_and_stops() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_Interpreter"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("cannot run the interpreter");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"1 + 1)\n2\n\xff\n3\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let stdout = stdout(&output).replace("Enter expression or type 'exit' to quit: ", "");
+    assert!(
+        stdout
+            .starts_with("Error: unmatched `)`\nInteger(2)\nResult: 2\nError: cannot read input: "),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("Result: 3"), "{}", stdout);
+}
@@ -0,0 +1,29 @@
+// Helpers the integration tests share. Each test binary that uses them declares
+// `mod common;`, and uses only some, so the rest would warn as dead code in it.
+#![allow(dead_code)]
+
+use interpreter::ast::ASTNode;
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::Value;
+
+// Panics if `source` does not parse, which a test of evaluation does not expect.
+pub fn parse(source: &str) -> ASTNode {
+    Parser::new(Lexer::new(source.to_string())).parse().unwrap()
+}
+
+// Runs `source` in a fresh interpreter with the default settings.
+pub fn eval(source: &str) -> Result<Value, EvalError> {
+    eval_in(&mut Interpreter::new(), source)
+}
+
+// Runs `source` in `interpreter`, whose session earlier calls may have set up.
+pub fn eval_in(interpreter: &mut Interpreter, source: &str) -> Result<Value, EvalError> {
+    interpreter.run(parse(source))
+}
+
+pub fn string(s: &str) -> Value {
+    Value::Str(s.to_string())
+}
@@ -0,0 +1,187 @@
+// `CompiledExpr` against the interpreter: for each formula and a grid of arguments, the
+// compiled form gives the value or error the interpreter gives with the same floats bound
+// to the variables.
+use interpreter::error::{Error, EvalError};
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::{CompiledExpr, Value};
+
+fn interpret(source: &str, x: f64, y: f64) -> Result<f64, EvalError> {
+    let source = format!("let x = {}; let y = {}; {}", literal(x), literal(y), source);
+    let ast = Parser::new(Lexer::new(source)).parse().unwrap();
+    match Interpreter::new().run(ast)? {
+        Value::Float(n) => Ok(n),
+        other => panic!("{} is not a float", other),
+    }
+}
+
+fn literal(x: f64) -> String {
+    if x.fract() == 0.0 {
+        format!("({:.1})", x)
+    } else {
+        format!("({})", x)
+    }
+}
+
+fn compile_error(source: &str, params: &[&str]) -> EvalError {
+    match CompiledExpr::compile(source, params) {
+        Err(Error::Runtime(err)) => err,
+        other => panic!("{}: {:?}", source, other),
+    }
+}
+
+const FORMULAS: &[&str] = &[
+    "x ^ 2 + 3 * x - 1",
+    "x * y - y / x",
+    "x % y + 2 ^ x",
+    "-x - -(y * 2)",
+    "sqrt(x * x + y * y)",
+    "√(x + 4)",
+    "abs(x - y) + floor(x) - ceil(y) + round(x * y)",
+    "sin(x) * cos(y) - tan(x / 3)",
+    "ln(y) + exp(x / 4)",
+    "min(x, y, 1) + max(x / 2, y - 1)",
+    "pi * x + e - π",
+    "(1 + 2) * x + 10 / 4",
+    "x / (y - y)",
+    "1 / x",
+    "x % 0",
+    "x ^ y ^ 0.5",
+];
+
+const ARGUMENTS: &[f64] = &[0.0, 1.0, -1.0, 2.5, -0.75, 3.0, 100.0, 0.1];
+
+#[test]
+fn compiled_formulas_evaluate_as_the_interpreter_does() {
+    for source in FORMULAS {
+        let compiled = CompiledExpr::compile(source, &["x", "y"]).unwrap();
+        for &x in ARGUMENTS {
+            for &y in ARGUMENTS {
+                let expected = interpret(source, x, y);
+                let found = compiled.eval(&[x, y]);
+                let same = match (&found, &expected) {
+                    (Ok(a), Ok(b)) => a.to_bits() == b.to_bits() || a.is_nan() && b.is_nan(),
+                    _ => found == expected,
+                };
+                assert!(
+                    same,
+                    "{} at ({}, {}): {:?}, not {:?}",
+                    source, x, y, found, expected
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn the_example_evaluates_repeatedly() {
+    let f = CompiledExpr::compile("x^2 + 3*x - 1", &["x"]).unwrap();
+    assert_eq!(f.eval(&[2.0]), Ok(9.0));
+    assert_eq!(f.eval(&[-3.0]), Ok(-1.0));
+    assert_eq!(f.params(), ["x"]);
+    let total: f64 = (0..1_000).map(|i| f.eval(&[i as f64]).unwrap()).sum();
+    assert_eq!(
+        total,
+        (0..1_000).map(|i| (i * i + 3 * i - 1) as f64).sum::<f64>()
+    );
+}
+
+#[test]
+fn parameters_are_slots_in_the_given_order() {
+    let f = CompiledExpr::compile("a - b * c", &["c", "a", "b"]).unwrap();
+    assert_eq!(f.eval(&[2.0, 10.0, 3.0]), Ok(4.0));
+    // A parameter hides the constant of the same name.
+    let f = CompiledExpr::compile("e * 2", &["e"]).unwrap();
+    assert_eq!(f.eval(&[4.0]), Ok(8.0));
+    let f = CompiledExpr::compile("2 ^ 10", &[]).unwrap();
+    assert_eq!(f.eval(&[]), Ok(1024.0));
+}
+
+#[test]
+fn variables_outside_the_list_are_refused_when_compiling() {
+    assert_eq!(
+        compile_error("x + z", &["x"]),
+        EvalError::UndefinedVariable("z".to_string())
+    );
+    assert_eq!(
+        compile_error("sqrt(x) + y", &[]),
+        EvalError::UndefinedVariable("x".to_string())
+    );
+}
+
+#[test]
+fn only_numeric_expressions_compile() {
+    for (source, message) in [
+        ("x < 1", "`x < 1`"),
+        ("len(x)", "`len(x)`"),
+        ("x and 1", "`x and 1`"),
+        ("x + \"a\"", "`\"a\"`"),
+        ("sqrt(1, x)", "`sqrt(1, x)`"),
+        ("sqrt(sqrt)", "`sqrt(sqrt)`"),
+    ] {
+        assert_eq!(
+            compile_error(source, &["x", "sqrt"]),
+            EvalError::TypeMismatch(format!(
+                "{} is not a numeric expression that can be compiled",
+                message
+            )),
+            "{}",
+            source
+        );
+    }
+    assert!(matches!(
+        CompiledExpr::compile("x +", &["x"]),
+        Err(Error::Parse(_))
+    ));
+    // What does not depend on the parameters fails as it would when run.
+    assert!(matches!(
+        compile_error("x + 2 ^ 70", &["x"]),
+        EvalError::Overflow(_)
+    ));
+}
+
+#[test]
+fn arguments_must_match_the_parameters() {
+    let f = CompiledExpr::compile("x + y", &["x", "y"]).unwrap();
+    assert_eq!(f.eval(&[1.0]), Err(EvalError::ArityMismatch(2, 1)));
+    assert_eq!(
+        f.eval(&[1.0, 2.0, 3.0]),
+        Err(EvalError::ArityMismatch(2, 3))
+    );
+}
+
+// Run on a thread with a larger stack, which parsing such deep nesting needs in a debug
+// build.
+fn deep<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    std::thread::Builder::new()
+        .stack_size(256 << 20)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn deep_expressions_compile_within_the_depth_limit() {
+    deep(|| {
+        let source = format!("{}x{}", "(1 + ".repeat(400), ")".repeat(400));
+        let f = CompiledExpr::compile(&source, &["x"]).unwrap();
+        assert_eq!(f.eval(&[0.5]), Ok(400.5));
+        // Deep enough to need more stack than evaluation keeps inline.
+        let source = format!("{}{}", vec!["x"; 100].join(" * (1 + "), ")".repeat(99));
+        let f = CompiledExpr::compile(&source, &["x"]).unwrap();
+        assert_eq!(f.eval(&[0.25]), interpret(&source, 0.25, 0.0));
+    });
+
+    // Past the parser's own limit, as a tree built another way may be.
+    let result = deep(|| {
+        let source = format!("{}x", "-".repeat(3_000));
+        let ast = Parser::new(Lexer::new(source))
+            .with_max_nesting(10_000)
+            .parse()
+            .unwrap();
+        CompiledExpr::from_ast(ast, &["x"]).map(|_| ())
+    });
+    assert_eq!(result, Err(EvalError::TooDeep(2_000)));
+}
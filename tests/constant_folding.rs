@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use interpreter::ast::NodeKind;
+use interpreter::interpreter::Interpreter;
+use interpreter::optimizer::fold_constants;
+use interpreter::pretty::pretty_print;
+
+mod common;
+use common::parse;
+
+fn folded(source: &str) -> String {
+    pretty_print(&fold_constants(parse(source)))
+}
+
+#[test]
+fn literal_operators_are_replaced_by_their_result() {
+    assert_eq!(folded("2 * 3 + 4"), "10");
+    assert_eq!(folded("-(1 + 2)"), "-3");
+    assert_eq!(folded("2 ^ 10"), "1024");
+    assert_eq!(folded("1.5 * 2"), "3.0");
+    assert_eq!(folded("1 < 2 == true"), "true");
+    assert_eq!(folded("not (3 == 3)"), "false");
+}
+
+#[test]
+fn only_the_constant_parts_of_an_expression_fold() {
+    assert_eq!(folded("2 * 3 + x"), "6 + x");
+    assert_eq!(folded("x * (3 + 4)"), "x * 7");
+    // `2 * pi` is not a literal operand, so nothing above it folds.
+    assert_eq!(folded("2 * pi * (3 + 4)"), "2 * pi * 7");
+    // `and` and `or` short-circuit, so only their operands fold.
+    assert_eq!(folded("1 < 2 and 2 > 3"), "true and false");
+    assert_eq!(folded("f(1 + 1, [2 * 2])"), "f(2, [4])");
+    assert_eq!(
+        folded("let y = 60 * 60; fn g(x) { x + 2 * 3 }"),
+        "let y = 3600;\nfn g(x) {\n    x + 6\n}"
+    );
+}
+
+#[test]
+fn errors_and_non_finite_results_are_left_for_run_time() {
+    for source in [
+        "1 / 0",
+        "1 % 0",
+        "0.0 / 0.0",
+        "9223372036854775807 + 1",
+        "1 + true",
+    ] {
+        assert_eq!(folded(source), pretty_print(&parse(source)), "{}", source);
+    }
+    // The failing part stays while its neighbours fold.
+    assert_eq!(folded("(1 + 1) / 0"), "2 / 0");
+}
+
+#[test]
+fn a_folded_node_keeps_the_span_it_replaces() {
+    let ast = fold_constants(parse("  2 * 3"));
+    assert!(matches!(ast.kind, NodeKind::Integer(6)));
+    assert_eq!((ast.span.start, ast.span.end), (2, 7));
+}
+
+#[test]
+fn folding_does_not_change_what_a_program_evaluates_to() {
+    let corpus = [
+        "1 + 2 * 3 - 4 / 2",
+        "2 ^ 3 ^ 2",
+        "-5 % 3",
+        "7 / 2",
+        "0.1 + 0.2",
+        "(1 + 2) * (3 + 4) == 21",
+        "not (1 > 2) or false",
+        "1 / 0",
+        "0.0 / 0.0",
+        "9223372036854775807 + 1",
+        "let x = 10; x * (2 + 3)",
+        "fn sq(x) { x * x }; sq(2 + 1) + 1 * 1",
+        "if 1 + 1 == 2 { 3 * 3 } else { 0 }",
+        "let total = 0; for i in 1..=3 { total = total + i * (1 + 1) }; total",
+        "f\"{1 + 2} and {2 * 2.5}\"",
+        "[1 + 1, 2 * 2] |> len",
+    ];
+    for source in corpus {
+        let expected = Interpreter::new()
+            .with_optimization(false)
+            .run(parse(source))
+            .map(|value| value.to_string());
+        let actual = Interpreter::new()
+            .run(fold_constants(parse(source)))
+            .map(|value| value.to_string());
+        assert_eq!(actual, expected, "{}", source);
+    }
+}
+
+#[test]
+fn negated_literals_and_booleans_fold() {
+    assert_eq!(folded("-2.5"), "-2.5");
+    assert!(matches!(
+        fold_constants(parse("-2.5")).kind,
+        NodeKind::Number(n) if n == -2.5
+    ));
+    assert_eq!(folded("true == false"), "false");
+    assert_eq!(folded("1 + 2 == 3 == true"), "true");
+}
+
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// The trace shows the first node `run` evaluates: the folded one unless folding is off.
+#[test]
+fn run_folds_unless_optimization_is_off() {
+    let first_step = |optimize: bool| {
+        let trace = Capture::default();
+        Interpreter::with_trace(Box::new(trace.clone()))
+            .with_optimization(optimize)
+            .run(parse("2 * 3 + 1"))
+            .unwrap();
+        let trace = String::from_utf8(trace.0.borrow().clone()).unwrap();
+        trace.lines().next().unwrap().to_string()
+    };
+    assert_eq!(first_step(true), "[depth=0] Evaluating: Integer(7)");
+    assert_eq!(
+        first_step(false),
+        "[depth=0] Evaluating: BinaryOp(BinaryOp(Integer(2), Mul, Integer(3)), Plus, Integer(1))"
+    );
+}
@@ -0,0 +1,113 @@
+use std::ops::Range;
+
+use interpreter::error::EvalError;
+use interpreter::lexer::{Lexer, Span, Token};
+use interpreter::parser::Parser;
+use interpreter::{Error, Value};
+
+mod common;
+use common::eval;
+
+// The constant was declared at `declared` in the source.
+fn reassignment(name: &str, declared: Range<usize>) -> Result<Value, EvalError> {
+    let span = Span {
+        start: declared.start,
+        end: declared.end,
+    };
+    Err(EvalError::ConstReassignment(name.to_string(), Some(span)))
+}
+
+#[test]
+fn constants_cannot_be_reassigned() {
+    assert_eq!(eval("const X = 5; X = 6"), reassignment("X", 0..11));
+    assert_eq!(eval("const X = 5; X += 1"), reassignment("X", 0..11));
+    assert_eq!(eval("const X = 5; let X = 6"), reassignment("X", 0..11));
+    assert_eq!(eval("const X = 5; const X = 6"), reassignment("X", 0..11));
+}
+
+#[test]
+fn constants_cannot_be_reassigned_from_child_scopes() {
+    assert_eq!(eval("const X = 5; { X = 6 }"), reassignment("X", 0..11));
+    assert_eq!(
+        eval("const X = 5; fn f() { X = 6 }; f()"),
+        reassignment("X", 0..11)
+    );
+    assert_eq!(
+        eval("fn f() { global X; X = 6 }; const X = 5; f()"),
+        reassignment("X", 28..39)
+    );
+}
+
+#[test]
+fn functions_see_constants() {
+    assert_eq!(
+        eval("const X = 5; let f = fn() { X }; f() == 5"),
+        Ok(Value::Bool(true))
+    );
+}
+
+// A `let` in an inner scope makes a new binding rather than reassigning the constant.
+#[test]
+fn inner_scopes_may_shadow_constants() {
+    assert_eq!(
+        eval("const X = 5; let y = { let X = 1; X = 2; X }; [X, y]"),
+        Ok(Value::Array(vec![Value::Integer(5), Value::Integer(2)]))
+    );
+}
+
+#[test]
+fn the_initializer_is_evaluated_at_the_declaration() {
+    assert_eq!(
+        eval("let n = 1; const X = n + 1; n = 10; X"),
+        Ok(Value::Integer(2))
+    );
+    assert!(matches!(
+        eval("const X = 1 / 0; 1"),
+        Err(EvalError::DivisionByZero(_))
+    ));
+}
+
+#[test]
+fn constants_need_an_initializer() {
+    let err = Parser::new(Lexer::new("const X;".to_string()))
+        .parse()
+        .unwrap_err();
+    assert!(matches!(err, Error::Parse(err) if err.found == Token::Semicolon));
+}
+
+#[test]
+fn builtin_constants_are_constants() {
+    assert_eq!(
+        eval("pi = 3"),
+        Err(EvalError::ConstReassignment("pi".to_string(), None))
+    );
+}
+
+#[test]
+fn constants_can_be_defined_from_constants() {
+    assert_eq!(
+        eval("const RATE = 0.25; const DOUBLE = RATE * 2; DOUBLE"),
+        Ok(Value::Float(0.5))
+    );
+    assert_eq!(eval("const TAU = 2 * pi; TAU / pi"), Ok(Value::Float(2.0)));
+    assert_eq!(
+        eval("const A = 1; const B = A; B = 2"),
+        reassignment("B", 13..24)
+    );
+}
+
+#[test]
+fn builtin_constants_can_be_shadowed_like_any_other() {
+    assert_eq!(eval("{ let pi = 3; pi }"), Ok(Value::Integer(3)));
+    assert_eq!(eval("fn f() { let e = 1; e }; f()"), Ok(Value::Integer(1)));
+}
+
+#[test]
+fn the_error_names_the_constant() {
+    assert_eq!(
+        eval("const rate = 0.21; rate = 0.3")
+            .unwrap_err()
+            .to_string(),
+        "cannot assign to constant `rate` declared at 0..17"
+    );
+}
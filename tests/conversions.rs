@@ -0,0 +1,87 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+fn invalid(message: &str) -> Result<Value, EvalError> {
+    Err(EvalError::InvalidArgument(message.to_string()))
+}
+
+#[test]
+fn int_truncates_and_parses() {
+    assert_eq!(eval("int(3.9)"), Ok(Value::Integer(3)));
+    assert_eq!(eval("int(-3.9)"), Ok(Value::Integer(-3)));
+    assert_eq!(eval(r#"int("42")"#), Ok(Value::Integer(42)));
+    assert_eq!(eval(r#"int(" -7 ")"#), Ok(Value::Integer(-7)));
+    assert_eq!(eval("int(5)"), Ok(Value::Integer(5)));
+}
+
+#[test]
+fn int_errors_quote_the_value() {
+    assert_eq!(
+        eval(r#"int("abc")"#),
+        invalid(r#"cannot convert "abc" to int"#)
+    );
+    assert_eq!(
+        eval(r#"int("2.5")"#),
+        invalid(r#"cannot convert "2.5" to int"#)
+    );
+    assert_eq!(eval("int(true)"), invalid("cannot convert true to int"));
+    assert_eq!(
+        eval("int(2.0 ^ 63)"),
+        invalid("cannot convert 9223372036854776000 to int")
+    );
+    assert!(eval("int(sqrt(-1))").is_err());
+}
+
+#[test]
+fn float_converts_numbers_and_strings() {
+    assert_eq!(eval(r#"float("2.5")"#), Ok(Value::Float(2.5)));
+    assert_eq!(eval("float(2)"), Ok(Value::Float(2.0)));
+    assert_eq!(
+        eval(r#"float("x")"#),
+        invalid(r#"cannot convert "x" to float"#)
+    );
+    assert_eq!(eval("float([1])"), invalid("cannot convert [1] to float"));
+}
+
+#[test]
+fn str_shows_the_value() {
+    assert_eq!(eval("str(3.14)"), Ok(string("3.14")));
+    assert_eq!(eval("str(2.0)"), Ok(string("2")));
+    assert_eq!(eval(r#"str([1, "a"])"#), Ok(string(r#"[1, "a"]"#)));
+    assert_eq!(eval(r#"str("a")"#), Ok(string("a")));
+}
+
+#[test]
+fn parse_runs_in_the_callers_scope() {
+    assert_eq!(eval(r#"parse("1 + 2")"#), Ok(Value::Integer(3)));
+    assert_eq!(eval(r#"let x = 5; parse("x + 1")"#), Ok(Value::Integer(6)));
+    assert_eq!(eval(r#"parse("let y = 2"); y"#), Ok(Value::Integer(2)));
+}
+
+#[test]
+fn str_then_parse_round_trips() {
+    for source in ["3.14", "-7", "[1, 2.5, [3]]", "(1, 2)", "true", "null"] {
+        assert_eq!(
+            eval(&format!("parse(str({0})) == {0}", source)),
+            Ok(Value::Bool(true)),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn parse_reports_syntax_errors_without_panicking() {
+    let Err(EvalError::InvalidArgument(message)) = eval(r#"parse("1 +")"#) else {
+        panic!("parse accepted `1 +`");
+    };
+    assert!(message.starts_with(r#"parse("1 +"): "#), "{}", message);
+    assert!(matches!(
+        eval(r#"parse("1 / 0")"#),
+        Err(EvalError::DivisionByZero(_))
+    ));
+    assert!(eval(r#"parse("")"#).is_err());
+}
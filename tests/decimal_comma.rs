@@ -0,0 +1,66 @@
+use interpreter::error::LexErrorKind;
+use interpreter::interpreter::Interpreter;
+use interpreter::{Error, Value};
+
+fn run(decimal_comma: bool, source: &str) -> Result<Value, Error> {
+    let mut interpreter = Interpreter::new();
+    interpreter.decimal_comma = decimal_comma;
+    let ast = interpreter.parser(source.to_string()).parse()?;
+    Ok(interpreter.run(ast)?)
+}
+
+fn lex_error(source: &str) -> Option<LexErrorKind> {
+    match run(true, source) {
+        Err(Error::Lex(err)) => Some(err.kind),
+        _ => None,
+    }
+}
+
+#[test]
+fn a_comma_between_digits_depends_on_the_mode() {
+    assert_eq!(run(false, "max(1,2)"), Ok(Value::Integer(2)));
+    assert_eq!(run(true, "max(1,2)"), Ok(Value::Float(1.2)));
+    assert_eq!(run(false, "len([1,5])"), Ok(Value::Integer(2)));
+    assert_eq!(run(true, "len([1,5])"), Ok(Value::Integer(1)));
+}
+
+#[test]
+fn semicolons_separate_arguments_with_decimal_commas() {
+    assert_eq!(run(true, "max(1,5; 2,5)"), Ok(Value::Float(2.5)));
+    assert_eq!(run(true, "[1,5; 2]").unwrap().to_string(), "[1.5, 2]");
+    // A comma with a space after it still separates.
+    assert_eq!(run(true, "max(1, 2)"), Ok(Value::Integer(2)));
+}
+
+#[test]
+fn results_print_with_the_same_separator() {
+    let value = run(true, "[3,14; 2]").unwrap();
+    assert_eq!(format!("{:#}", value), "[3,14; 2]");
+    assert_eq!(value.to_string(), "[3.14, 2]");
+}
+
+#[test]
+fn a_decimal_point_is_an_error_that_says_what_to_write() {
+    assert_eq!(
+        lex_error("1.5"),
+        Some(LexErrorKind::DecimalPoint("1.5".to_string()))
+    );
+    assert_eq!(
+        lex_error("2 * .5"),
+        Some(LexErrorKind::DecimalPoint(".5".to_string()))
+    );
+    assert_eq!(
+        LexErrorKind::DecimalPoint(".5".to_string()).to_string(),
+        "`.5` has a decimal point, but decimal commas are on: write `0,5`"
+    );
+    assert_eq!(run(false, ".5 + 1"), Ok(Value::Float(1.5)));
+    // `..` is still a range.
+    assert_eq!(run(true, "1..3").unwrap().to_string(), "[1, 2]");
+}
+
+// `,5` is a comma followed by 5, so a fraction needs its leading zero.
+#[test]
+fn a_fraction_needs_its_leading_zero() {
+    assert_eq!(run(true, "0,5 + 1"), Ok(Value::Float(1.5)));
+    assert!(matches!(run(true, ",5"), Err(Error::Parse(_))));
+}
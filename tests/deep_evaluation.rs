@@ -0,0 +1,164 @@
+// Operator chains far deeper than the parser accepts, built directly as trees. They
+// are run, and dropped, on the test thread's default stack with the default settings.
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use interpreter::ast::{ASTNode, NodeKind};
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::{Span, Token};
+use interpreter::Value;
+
+// Operators per chain, so a chain of binary operators has a million and one nodes.
+const LENGTH: usize = 500_000;
+
+fn node(kind: NodeKind, start: usize) -> ASTNode {
+    ASTNode::new(
+        kind,
+        Span {
+            start,
+            end: start + 1,
+        },
+    )
+}
+
+fn integer(n: i64) -> ASTNode {
+    node(NodeKind::Integer(n), 0)
+}
+
+// `first op operand op operand ...`, grouped to the left; node `i` is at offset `i`.
+fn chain(first: ASTNode, op: Token, operand: impl Fn() -> ASTNode) -> ASTNode {
+    (1..=LENGTH).fold(first, |left, i| {
+        node(
+            NodeKind::BinaryOp(Box::new(left), op.clone(), Box::new(operand())),
+            i,
+        )
+    })
+}
+
+fn run(ast: ASTNode) -> Result<Value, EvalError> {
+    Interpreter::new().run(ast)
+}
+
+#[test]
+fn a_million_node_chain_of_additions_evaluates() {
+    let ast = chain(integer(1), Token::Plus, || integer(1));
+    assert_eq!(run(ast), Ok(Value::Integer(LENGTH as i64 + 1)));
+
+    let ast = chain(node(NodeKind::Number(0.5), 0), Token::Mul, || integer(1));
+    assert_eq!(run(ast), Ok(Value::Float(0.5)));
+}
+
+#[test]
+fn unary_and_logical_chains_evaluate() {
+    let ast = (1..=LENGTH).fold(node(NodeKind::Bool(true), 0), |operand, i| {
+        node(NodeKind::UnaryOp(Token::Not, Box::new(operand)), i)
+    });
+    assert_eq!(run(ast), Ok(Value::Bool(LENGTH.is_multiple_of(2))));
+
+    let logical = |first: ASTNode, op: Token, operand: fn() -> ASTNode| {
+        (1..=LENGTH).fold(first, |left, i| {
+            node(
+                NodeKind::Logical(Box::new(left), op.clone(), Box::new(operand())),
+                i,
+            )
+        })
+    };
+    let ast = logical(node(NodeKind::Bool(false), 0), Token::Or, || {
+        node(NodeKind::Bool(false), 0)
+    });
+    assert_eq!(run(ast), Ok(Value::Bool(false)));
+
+    let ast = logical(node(NodeKind::Null, 0), Token::NullCoalesce, || {
+        node(NodeKind::Null, 0)
+    });
+    assert_eq!(run(ast), Ok(Value::Null));
+}
+
+#[test]
+fn an_error_at_the_bottom_of_a_chain_propagates() {
+    let division = node(
+        NodeKind::BinaryOp(Box::new(integer(1)), Token::Div, Box::new(integer(0))),
+        0,
+    );
+    let ast = chain(division, Token::Plus, || integer(1));
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.run(ast),
+        Err(EvalError::DivisionByZero("1 / 0".to_string()))
+    );
+    // The span is the innermost node that failed.
+    assert_eq!(interpreter.error_span(), Some(Span { start: 0, end: 1 }));
+
+    let ast = chain(integer(1), Token::Minus, || {
+        node(NodeKind::VarRef("x".to_string()), 7)
+    });
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        interpreter.run(ast),
+        Err(EvalError::UndefinedVariable("x".to_string()))
+    );
+    assert_eq!(interpreter.error_span(), Some(Span { start: 7, end: 8 }));
+}
+
+#[test]
+fn an_error_partway_up_a_chain_stops_it() {
+    // `i64::MAX - 1 + 1 + 1 + ...` overflows at the second addition.
+    let ast = chain(integer(i64::MAX - 1), Token::Plus, || integer(1));
+    let mut interpreter = Interpreter::new();
+    assert!(matches!(interpreter.run(ast), Err(EvalError::Overflow(_))));
+    assert_eq!(interpreter.error_span(), Some(Span { start: 2, end: 3 }));
+}
+
+#[test]
+fn chains_do_not_count_against_the_depth_limit() {
+    let mut interpreter = Interpreter::new();
+    interpreter.max_depth = 1;
+    let ast = chain(integer(1), Token::Plus, || integer(1));
+    assert_eq!(interpreter.run(ast), Ok(Value::Integer(LENGTH as i64 + 1)));
+    // What the operators are applied to still does.
+    interpreter.max_depth = 0;
+    let ast = chain(integer(1), Token::Plus, || integer(1));
+    assert_eq!(interpreter.run(ast), Err(EvalError::TooDeep(0)));
+}
+
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn nested_operators_trace_as_if_evaluated_recursively() {
+    let capture = Capture::default();
+    let mut interpreter = Interpreter::with_trace(Box::new(capture.clone()));
+    let sum = node(
+        NodeKind::BinaryOp(Box::new(integer(1)), Token::Plus, Box::new(integer(2))),
+        0,
+    );
+    let ast = node(NodeKind::UnaryOp(Token::Minus, Box::new(sum)), 0);
+    assert_eq!(interpreter.interpret(&ast), Ok(Value::Integer(-3)));
+    let trace = String::from_utf8(capture.0.borrow().clone()).unwrap();
+    assert_eq!(
+        trace,
+        "\
+[depth=0] Evaluating: UnaryOp(Minus, BinaryOp(Integer(1), Plus, Integer(2)))
+  [depth=1] Evaluating: BinaryOp(Integer(1), Plus, Integer(2))
+    [depth=2] Evaluating: Integer(1)
+    [depth=2] => 1
+    [depth=2] Evaluating: Integer(2)
+    [depth=2] => 2
+  [depth=1] => 3
+[depth=0] => -3
+"
+    );
+}
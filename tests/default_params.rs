@@ -0,0 +1,124 @@
+use interpreter::compiler;
+use interpreter::error::{CompileError, EvalError, ParseErrorKind};
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::pretty::pretty_print;
+use interpreter::{Error, Value};
+
+mod common;
+use common::{eval, parse};
+
+fn integers(values: &[i64]) -> Result<Value, EvalError> {
+    Ok(Value::Array(
+        values.iter().copied().map(Value::Integer).collect(),
+    ))
+}
+
+#[test]
+fn a_left_out_argument_takes_its_default() {
+    assert_eq!(
+        eval("fn add(a, b = 1) { a + b }; [add(5), add(5, 2)]"),
+        integers(&[6, 7])
+    );
+    assert_eq!(
+        eval("fn f(a = 1, b = 2) { [a, b] }; [f(), f(10), f(10, 20)]"),
+        Ok(Value::Array(vec![
+            integers(&[1, 2]).unwrap(),
+            integers(&[10, 2]).unwrap(),
+            integers(&[10, 20]).unwrap(),
+        ]))
+    );
+    assert_eq!(eval("(|x, y = 3| x * y)(2)"), Ok(Value::Integer(6)));
+}
+
+#[test]
+fn defaults_are_evaluated_at_each_call_in_the_functions_scope() {
+    // Earlier parameters are in scope.
+    assert_eq!(
+        eval("fn f(a, b = a * 2) { b }; [f(3), f(3, 1)]"),
+        integers(&[6, 1])
+    );
+    // A default sees the variable as it is when the call happens, not when `f` was defined.
+    assert_eq!(
+        eval("let n = 1; fn f(x = n) { x }; n = 5; f()"),
+        Ok(Value::Integer(5))
+    );
+    // It is only evaluated when the argument is left out.
+    assert!(matches!(
+        eval("fn f(x = 1 / 0) { x }; f()"),
+        Err(EvalError::DivisionByZero(_))
+    ));
+    assert_eq!(eval("fn f(x = 1 / 0) { x }; f(4)"), Ok(Value::Integer(4)));
+}
+
+#[test]
+fn arity_counts_the_required_parameters() {
+    assert_eq!(
+        eval("fn f(a, b = 1) { a }; f()"),
+        Err(EvalError::ArityMismatch(1, 0))
+    );
+    assert_eq!(
+        eval("fn f(a, b = 1) { a }; f(1, 2, 3)"),
+        Err(EvalError::ArityMismatch(2, 3))
+    );
+    assert_eq!(
+        eval("fn f(a, b = 1, ...rest) { [a, b, len(rest)] }; [f(1), f(1, 2, 3, 4)]"),
+        Ok(Value::Array(vec![
+            integers(&[1, 1, 0]).unwrap(),
+            integers(&[1, 2, 2]).unwrap(),
+        ]))
+    );
+}
+
+#[test]
+fn required_parameters_must_come_first() {
+    for (source, col) in [
+        ("fn f(a = 1, b) { b }", 13),
+        ("|a = 1, b| b", 9),
+        ("fn f(a = 1, b, ...c) { b }", 13),
+    ] {
+        let Err(Error::Parse(err)) = Parser::new(Lexer::new(source.to_string())).parse() else {
+            panic!("expected a parse error for {}", source);
+        };
+        assert_eq!(
+            err.kind,
+            Some(ParseErrorKind::RequiredParamAfterDefault),
+            "{}",
+            source
+        );
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "required parameter `b` follows one with a default value at line 1, col {}",
+                col
+            ),
+            "{}",
+            source
+        );
+    }
+    let result = Parser::new(Lexer::new("fn f(a =) { a }".to_string())).parse();
+    assert!(matches!(result, Err(Error::Parse(_))));
+}
+
+#[test]
+fn pretty_printing_round_trips() {
+    for source in [
+        "fn f(a, b = 1, ...rest) {\n    a\n}",
+        "fn(x = [1, 2]) {\n    x\n}",
+        "|x, y = x + 1| y",
+    ] {
+        let ast = parse(source);
+        assert_eq!(pretty_print(&ast), source);
+    }
+}
+
+#[test]
+fn the_compiler_does_not_support_defaults() {
+    let ast = Parser::new(Lexer::new("fn f(x = 1) { x }".to_string()))
+        .parse()
+        .unwrap();
+    assert!(matches!(
+        compiler::compile(&ast),
+        Err(CompileError::Unsupported("FnDef"))
+    ));
+}
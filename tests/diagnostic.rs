@@ -0,0 +1,65 @@
+use interpreter::diagnostic::render_diagnostic;
+use interpreter::lexer::{Lexer, Span};
+use interpreter::parser::Parser;
+use interpreter::Error;
+
+fn span(start: usize, end: usize) -> Span {
+    Span { start, end }
+}
+
+#[test]
+fn underlines_the_span() {
+    assert_eq!(
+        render_diagnostic("let x = 1 + true", span(8, 16), "Error: type mismatch"),
+        "  let x = 1 + true\n          ^^^^^^^^\nError: type mismatch"
+    );
+}
+
+#[test]
+fn points_at_the_line_the_span_starts_on() {
+    let source = "let a = 1;\nlet b = a +\n  c";
+    assert_eq!(
+        render_diagnostic(source, span(19, 28), "Error: undefined variable: c"),
+        "  let b = a +\n          ^^^\nError: undefined variable: c"
+    );
+}
+
+#[test]
+fn expands_tabs_in_both_lines() {
+    // The second tab only has one column to go to the next tab stop.
+    assert_eq!(
+        render_diagnostic("\tx +\ty", span(4, 6), "Error"),
+        "      x + y\n         ^^\nError"
+    );
+}
+
+#[test]
+fn measures_multi_byte_characters_by_display_width() {
+    // `é` is two bytes but one column; each of `日本` is three bytes and two columns.
+    assert_eq!(
+        render_diagnostic("\"é\" + 日本", span(7, 13), "Error"),
+        "  \"é\" + 日本\n        ^^^^\nError"
+    );
+}
+
+#[test]
+fn marks_an_empty_span_at_the_end_of_a_line() {
+    assert_eq!(
+        render_diagnostic("1 +\n2", span(3, 3), "Error: expected an expression"),
+        "  1 +\n     ^\nError: expected an expression"
+    );
+}
+
+#[test]
+fn renders_a_parse_error_at_its_span() {
+    let source = "(1 + 2";
+    let err = match Parser::new(Lexer::new(source.to_string())).parse() {
+        Err(Error::Parse(err)) => err,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+    let span = err.unclosed.as_ref().unwrap().span;
+    assert_eq!(
+        render_diagnostic(source, span, &format!("Error: {}", err)),
+        "  (1 + 2\n  ^\nError: unclosed `(` opened at line 1, col 1"
+    );
+}
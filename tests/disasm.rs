@@ -0,0 +1,53 @@
+use interpreter::compiler;
+use interpreter::disasm::disassemble;
+
+mod common;
+use common::parse;
+
+fn listing(source: &str) -> String {
+    disassemble(&compiler::compile(&parse(source)).unwrap())
+}
+
+#[test]
+fn one_line_per_instruction_with_offset_mnemonic_and_operand() {
+    assert_eq!(
+        listing("3.14 * x"),
+        "0000  LOAD_CONST 3.14\n\
+         0001  LOAD_VAR \"x\"\n\
+         0002  BINARY_OP Mul\n\
+         0003  RETURN\n"
+    );
+}
+
+#[test]
+fn jumps_show_their_target_as_an_index_and_a_label() {
+    assert_eq!(
+        listing("if x { 1 } else { 2 }"),
+        "0000  LOAD_VAR \"x\"\n\
+         0001  JUMP_IF_FALSE 4 (->0004)\n\
+         0002  LOAD_CONST 1\n\
+         0003  JUMP 5 (->0005)\n\
+         0004  LOAD_CONST 2\n\
+         0005  RETURN\n"
+    );
+}
+
+#[test]
+fn function_bodies_follow_the_code_that_loads_them() {
+    assert_eq!(
+        listing("fn sq(n) { n * n }; sq(\"a\")"),
+        "0000  LOAD_CONST <fn sq>\n\
+         0001  DEFINE_VAR \"sq\"\n\
+         0002  POP\n\
+         0003  LOAD_VAR \"sq\"\n\
+         0004  LOAD_CONST \"a\"\n\
+         0005  CALL 1\n\
+         0006  RETURN\n\
+         \n\
+         fn sq(n):\n\
+         0000  LOAD_VAR \"n\"\n\
+         0001  LOAD_VAR \"n\"\n\
+         0002  BINARY_OP Mul\n\
+         0003  RETURN\n"
+    );
+}
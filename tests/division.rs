@@ -0,0 +1,88 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::{DivByZero, Interpreter};
+use interpreter::Value;
+
+mod common;
+use common::eval_in;
+
+fn eval(policy: DivByZero, source: &str) -> Result<Value, EvalError> {
+    let mut interpreter = Interpreter::new();
+    interpreter.div_by_zero = policy;
+    eval_in(&mut interpreter, source)
+}
+
+fn float(result: Result<Value, EvalError>) -> f64 {
+    match result {
+        Ok(Value::Float(n)) => n,
+        other => panic!("expected a float, got {:?}", other),
+    }
+}
+
+#[test]
+fn errors_by_default() {
+    assert_eq!(Interpreter::new().div_by_zero, DivByZero::Error);
+    for source in ["1 / 0", "1.5 / 0.0", "0 / 0", "7 % 0", "7.5 % 0.0"] {
+        assert!(
+            matches!(
+                eval(DivByZero::Error, source),
+                Err(EvalError::DivisionByZero(_))
+            ),
+            "{}",
+            source
+        );
+    }
+}
+
+// Operands that are not numbers are a type error whatever the policy.
+#[test]
+fn non_numbers_are_still_mismatched() {
+    assert!(matches!(
+        eval(DivByZero::Ieee, "\"a\" / 0"),
+        Err(EvalError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn ieee_gives_infinity_and_nan() {
+    assert_eq!(float(eval(DivByZero::Ieee, "1 / 0")), f64::INFINITY);
+    assert_eq!(float(eval(DivByZero::Ieee, "-1 / 0.0")), f64::NEG_INFINITY);
+    assert!(float(eval(DivByZero::Ieee, "0 / 0")).is_nan());
+    assert!(float(eval(DivByZero::Ieee, "7 % 0")).is_nan());
+}
+
+#[test]
+fn value_replaces_every_result() {
+    let policy = DivByZero::Value(0.0);
+    assert_eq!(float(eval(policy, "1 / 0")), 0.0);
+    assert_eq!(float(eval(policy, "0 / 0")), 0.0);
+    assert_eq!(float(eval(policy, "7 % 0")), 0.0);
+    assert_eq!(
+        float(eval(DivByZero::Value(-1.0), "let x = 2.5 % 0.0; x")),
+        -1.0
+    );
+}
+
+#[test]
+fn nonzero_divisors_are_unaffected() {
+    assert_eq!(eval(DivByZero::Value(0.0), "7 % 3"), Ok(Value::Integer(1)));
+    assert_eq!(eval(DivByZero::Ieee, "1 / 4"), Ok(Value::Float(0.25)));
+}
+
+// A division deep inside an expression still fails the whole program, and the
+// message names the operands it was given.
+#[test]
+fn an_error_deep_in_the_tree_surfaces() {
+    for (source, message) in [
+        ("1 + 2 * (3 - 4 / 0)", "division by zero in `4 / 0`"),
+        ("let x = 0; -(10 % x) + 1", "division by zero in `10 % 0`"),
+        (
+            "fn f(n) { n / (n - 3) }; 1 + f(f(3))",
+            "division by zero in `3 / 0`",
+        ),
+        ("[1, 2, [3, 6 / (2 - 2)]]", "division by zero in `6 / 0`"),
+    ] {
+        let error = eval(DivByZero::Error, source).unwrap_err();
+        assert!(matches!(error, EvalError::DivisionByZero(_)), "{}", source);
+        assert_eq!(error.to_string(), message, "{}", source);
+    }
+}
@@ -0,0 +1,48 @@
+use interpreter::error::EvalError;
+use interpreter::{eval, Error, EvalContext, Value};
+
+#[test]
+fn eval_returns_the_last_value() {
+    assert_eq!(eval("1 + 2"), Ok(Value::Integer(3)));
+    assert_eq!(eval("let x = 2; x * 3"), Ok(Value::Integer(6)));
+    assert_eq!(eval(r#"len("abc")"#), Ok(Value::Integer(3)));
+}
+
+#[test]
+fn eval_says_which_stage_failed() {
+    assert!(matches!(eval("\"open"), Err(Error::Lex(_))));
+    assert!(matches!(eval("(1 +"), Err(Error::Parse(_))));
+    assert_eq!(
+        eval("missing"),
+        Err(Error::Runtime(EvalError::UndefinedVariable(
+            "missing".to_string()
+        )))
+    );
+}
+
+#[test]
+fn each_eval_starts_afresh() {
+    assert_eq!(eval("let x = 5"), Ok(Value::Integer(5)));
+    assert!(matches!(
+        eval("x"),
+        Err(Error::Runtime(EvalError::UndefinedVariable(_)))
+    ));
+}
+
+#[test]
+fn a_context_keeps_bindings_between_calls() {
+    let mut ctx = EvalContext::new();
+    ctx.eval("let x = 5").unwrap();
+    assert_eq!(ctx.eval("x + 1"), Ok(Value::Integer(6)));
+    ctx.eval("fn double(n) { n * 2 }").unwrap();
+    assert_eq!(ctx.eval("double(x)"), Ok(Value::Integer(10)));
+}
+
+#[test]
+fn a_context_survives_errors() {
+    let mut ctx = EvalContext::default();
+    ctx.eval("let x = 1").unwrap();
+    assert!(ctx.eval("x +").is_err());
+    assert!(ctx.eval("x / 0").is_err());
+    assert_eq!(ctx.eval("x"), Ok(Value::Integer(1)));
+}
@@ -0,0 +1,52 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::Error;
+
+fn is_empty_input(err: &Error) -> bool {
+    matches!(err, Error::Parse(err) if err.is_empty_input())
+}
+
+#[test]
+fn empty_and_blank_input_is_an_error() {
+    for source in ["", "   ", "\t\n  \r\n"] {
+        let err = Parser::new(Lexer::new(source.to_string()))
+            .parse()
+            .unwrap_err();
+        assert!(is_empty_input(&err), "{:?}", source);
+        assert_eq!(err.to_string(), "empty input");
+    }
+}
+
+#[test]
+fn recovery_reports_empty_input_once() {
+    let (ast, errors) = Parser::new(Lexer::new("  ".to_string())).parse_recovering();
+    assert!(ast.is_none());
+    assert_eq!(errors.len(), 1);
+    assert!(is_empty_input(&errors[0]));
+}
+
+// Only input without a single token is empty; a missing operand is an ordinary error.
+#[test]
+fn incomplete_input_is_not_empty() {
+    for source in ["1 +", "(", ";"] {
+        let err = Parser::new(Lexer::new(source.to_string()))
+            .parse()
+            .unwrap_err();
+        assert!(!is_empty_input(&err), "{:?}", source);
+    }
+}
+
+#[test]
+fn parsing_empty_source_at_runtime_is_an_error() {
+    let ast = Parser::new(Lexer::new("parse(\" \")".to_string()))
+        .parse()
+        .unwrap();
+    assert_eq!(
+        Interpreter::new().run(ast),
+        Err(EvalError::InvalidArgument(
+            "parse(\" \"): empty input".to_string()
+        ))
+    );
+}
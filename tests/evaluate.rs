@@ -0,0 +1,56 @@
+use interpreter::error::EvalError;
+use interpreter::{evaluate, evaluate_with, Error, EvalContext};
+
+#[test]
+fn numbers_come_back_as_floats() {
+    assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+    assert_eq!(evaluate("7 / 2").unwrap(), 3.5);
+    assert_eq!(
+        evaluate("fn f(x) { x ^ 2 }; f(3) + sqrt(16)").unwrap(),
+        13.0
+    );
+}
+
+#[test]
+fn anything_else_is_an_error() {
+    assert_eq!(
+        evaluate("\"hi\"").unwrap_err().to_string(),
+        "type mismatch: expected a number, found string"
+    );
+    assert!(matches!(evaluate("1 +"), Err(Error::Parse(_))));
+    assert!(matches!(
+        evaluate("1 / 0"),
+        Err(Error::Runtime(EvalError::DivisionByZero(_)))
+    ));
+}
+
+#[test]
+fn each_stage_fails_with_its_own_error() {
+    assert!(matches!(evaluate("1 @ 2"), Err(Error::Lex(_))));
+    assert!(matches!(evaluate("(1 + 2"), Err(Error::Parse(_))));
+    assert!(matches!(
+        evaluate("undefined"),
+        Err(Error::Runtime(EvalError::UndefinedVariable(_)))
+    ));
+    assert_eq!(evaluate("3 plus 4 mul 2").unwrap(), 11.0);
+}
+
+#[test]
+fn a_context_keeps_assignments_between_calls() {
+    let mut ctx = EvalContext::new();
+    assert_eq!(
+        evaluate_with(&mut ctx, "let rate = 0.5; 10 * rate").unwrap(),
+        5.0
+    );
+    assert_eq!(
+        evaluate_with(&mut ctx, "rate = rate * 4; rate").unwrap(),
+        2.0
+    );
+    // Bindings made before a failure are kept.
+    assert!(evaluate_with(&mut ctx, "let base = 3; base / 0").is_err());
+    assert_eq!(evaluate_with(&mut ctx, "base * rate").unwrap(), 6.0);
+    assert!(matches!(
+        evaluate("rate"),
+        Err(Error::Runtime(EvalError::UndefinedVariable(_)))
+    ));
+}
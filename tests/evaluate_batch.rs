@@ -0,0 +1,59 @@
+use interpreter::error::EvalError;
+use interpreter::{evaluate, evaluate_batch, evaluate_batch_on, Error};
+
+#[test]
+fn results_keep_the_order_of_the_inputs() {
+    let inputs: Vec<String> = (0..5_000)
+        .map(|i| match i % 4 {
+            0 => format!("{} * 2", i),
+            1 => format!("{} +", i),
+            2 => format!("{} / 0", i),
+            _ => format!("\"{}\"", i),
+        })
+        .collect();
+    let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+    for results in [evaluate_batch(&inputs), evaluate_batch_on(&inputs, 7)] {
+        assert_eq!(results.len(), inputs.len());
+        for (i, (result, input)) in results.iter().zip(&inputs).enumerate() {
+            match i % 4 {
+                0 => assert_eq!(result, &Ok(i as f64 * 2.0)),
+                1 => assert!(matches!(result, Err(Error::Parse(_))), "{}", input),
+                2 => assert!(
+                    matches!(result, Err(Error::Runtime(EvalError::DivisionByZero(_)))),
+                    "{}",
+                    input
+                ),
+                _ => assert!(
+                    matches!(result, Err(Error::Runtime(EvalError::TypeMismatch(_)))),
+                    "{}",
+                    input
+                ),
+            }
+            assert_eq!(result, &evaluate(input), "{}", input);
+        }
+    }
+}
+
+#[test]
+fn inputs_do_not_share_bindings() {
+    let inputs = ["let x = 2; x", "x"].repeat(300);
+    let results = evaluate_batch_on(&inputs, 4);
+    assert_eq!(results[0], Ok(2.0));
+    assert!(matches!(
+        results[1],
+        Err(Error::Runtime(EvalError::UndefinedVariable(_)))
+    ));
+    for pair in results.chunks(2) {
+        assert_eq!(pair, &results[..2]);
+    }
+}
+
+#[test]
+fn small_and_empty_batches() {
+    assert_eq!(evaluate_batch(&[]), Vec::new());
+    assert_eq!(
+        evaluate_batch_on(&["1 + 1", "sqrt(9)"], 8),
+        vec![Ok(2.0), Ok(3.0)]
+    );
+    assert_eq!(evaluate_batch_on(&["1"; 1_000], 0), vec![Ok(1.0); 1_000]);
+}
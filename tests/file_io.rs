@@ -0,0 +1,121 @@
+#![cfg(feature = "io")]
+use std::fs;
+use std::path::PathBuf;
+
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+// A fresh directory for one test, as a path relative to the working directory, since
+// the built-ins take no other kind.
+fn scratch(test: &str) -> String {
+    let tmp = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("file_io")
+        .join(test);
+    let _ = fs::remove_dir_all(&tmp);
+    fs::create_dir_all(&tmp).unwrap();
+    let cwd = std::env::current_dir().unwrap();
+    tmp.strip_prefix(cwd).unwrap().display().to_string()
+}
+
+#[test]
+fn written_files_read_back() {
+    let dir = scratch("round_trip");
+    assert_eq!(
+        eval(&format!(
+            "let path = \"{dir}/out.txt\"; [write_file(path, \"hello\"), read_file(path)]"
+        )),
+        Ok(Value::Array(vec![
+            Value::Null,
+            Value::Str("hello".to_string()),
+        ]))
+    );
+    // Writing again replaces the contents.
+    assert_eq!(
+        eval(&format!(
+            "write_file(\"{dir}/out.txt\", \"bye\"); read_file(\"{dir}/out.txt\")"
+        )),
+        Ok(Value::Str("bye".to_string()))
+    );
+    assert_eq!(fs::read_to_string(format!("{dir}/out.txt")).unwrap(), "bye");
+}
+
+#[test]
+fn appending_adds_to_the_end() {
+    let dir = scratch("append");
+    assert_eq!(
+        eval(&format!(
+            "let log = \"{dir}/log.txt\"; append_file(log, \"a\\n\"); append_file(log, \"b\\n\"); read_file(log)"
+        )),
+        Ok(Value::Str("a\nb\n".to_string()))
+    );
+}
+
+#[test]
+fn file_exists_checks_the_path() {
+    let dir = scratch("exists");
+    fs::write(format!("{dir}/here.txt"), "").unwrap();
+    assert_eq!(
+        eval(&format!(
+            "[file_exists(\"{dir}/here.txt\"), file_exists(\"{dir}/gone.txt\")]"
+        )),
+        Ok(Value::Array(vec![Value::Bool(true), Value::Bool(false)]))
+    );
+}
+
+#[test]
+fn failures_are_io_errors() {
+    let dir = scratch("failures");
+    let Err(EvalError::IoError(message)) = eval(&format!("read_file(\"{dir}/missing.txt\")"))
+    else {
+        panic!("expected an I/O error");
+    };
+    assert!(
+        message.starts_with(&format!("read_file(\"{dir}/missing.txt\"): ")),
+        "{}",
+        message
+    );
+    assert!(matches!(
+        eval(&format!("write_file(\"{dir}/no/such/dir.txt\", \"x\")")),
+        Err(EvalError::IoError(_))
+    ));
+    assert_eq!(
+        eval(&format!(
+            "try {{ read_file(\"{dir}/missing.txt\") }} catch e {{ e.kind }}"
+        )),
+        Ok(Value::Str("IoError".to_string()))
+    );
+}
+
+#[test]
+fn paths_may_not_leave_the_working_directory() {
+    for path in ["../secret.txt", "data/../../secret.txt", "/etc/passwd"] {
+        let denied = Err(EvalError::PermissionDenied(path.to_string()));
+        assert_eq!(eval(&format!("read_file({:?})", path)), denied);
+        assert_eq!(eval(&format!("write_file({:?}, \"x\")", path)), denied);
+        assert_eq!(eval(&format!("append_file({:?}, \"x\")", path)), denied);
+        assert_eq!(eval(&format!("file_exists({:?})", path)), denied);
+    }
+    assert_eq!(
+        EvalError::PermissionDenied("../x".to_string()).to_string(),
+        "permission denied: ../x is outside the working directory"
+    );
+}
+
+#[test]
+fn contents_must_be_strings() {
+    assert_eq!(
+        eval("write_file(\"out.txt\", 1)"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to write_file".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("read_file()"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to read_file".to_string()
+        ))
+    );
+}
@@ -0,0 +1,68 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::Value;
+
+mod common;
+use common::eval_in;
+
+fn eval(strict: bool, source: &str) -> Result<Value, EvalError> {
+    let mut interpreter = Interpreter::new();
+    interpreter.strict_float = strict;
+    eval_in(&mut interpreter, source)
+}
+
+#[test]
+fn overflow_to_infinity_is_only_a_warning_by_default() {
+    assert_eq!(
+        eval(false, "10.0 ^ 308 * 10"),
+        Ok(Value::Float(f64::INFINITY))
+    );
+}
+
+#[test]
+fn strict_mode_rejects_overflow_to_infinity() {
+    assert!(matches!(
+        eval(true, "10.0 ^ 308 * 10"),
+        Err(EvalError::NonFinite(Some(_), n)) if n == f64::INFINITY
+    ));
+}
+
+#[test]
+fn zero_times_infinity_is_nan() {
+    let source = "let big = 10.0 ^ 308 * 10; 0 * big";
+    assert!(matches!(eval(false, source), Ok(Value::Float(n)) if n.is_nan()));
+    // Strict mode stops at the infinity, before it can turn into NaN.
+    assert!(matches!(
+        eval(true, source),
+        Err(EvalError::NonFinite(_, n)) if n == f64::INFINITY
+    ));
+}
+
+#[test]
+fn strict_mode_rejects_intermediate_nans() {
+    // The NaN from `sqrt` is caught at the first operator it reaches.
+    assert!(matches!(
+        eval(true, "sqrt(-1) * 0 + 1"),
+        Err(EvalError::NonFinite(Some(expr), n)) if expr == "NaN * 0" && n.is_nan()
+    ));
+    // A NaN that no operator touches is still caught as the final result.
+    assert!(matches!(
+        eval(true, "sqrt(-1)"),
+        Err(EvalError::NonFinite(None, n)) if n.is_nan()
+    ));
+}
+
+#[test]
+fn finite_results_pass_strict_mode() {
+    assert_eq!(eval(true, "1 / 4 + 2 ^ 10"), Ok(Value::Float(1024.25)));
+}
+
+#[test]
+fn nan_round_trips_through_str() {
+    assert_eq!(Value::Float(f64::NAN).to_string(), "NaN");
+    assert_eq!(eval(false, "str(sqrt(-1))"), Ok(Value::Str("NaN".into())));
+    assert_eq!(
+        eval(false, "let x = float(str(sqrt(-1))); x != x"),
+        Ok(Value::Bool(true))
+    );
+}
@@ -0,0 +1,102 @@
+use interpreter::error::EvalError;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::pretty::pretty_print;
+use interpreter::{Error, Value};
+
+mod common;
+use common::{eval, parse};
+
+fn string(s: &str) -> Result<Value, EvalError> {
+    Ok(Value::Str(s.to_string()))
+}
+
+#[test]
+fn iterates_over_arrays_in_order() {
+    assert_eq!(
+        eval("let seen = \"\"; for x in [1, 2, 3] { seen = f\"{seen}{x},\" }; seen"),
+        string("1,2,3,")
+    );
+    assert_eq!(
+        eval("let total = 0; for i in 1..=4 { total = total + i }; total"),
+        Ok(Value::Integer(10))
+    );
+    assert_eq!(
+        eval("let n = 0; for i in range(10, 0, -2) { n = n * 100 + i }; n"),
+        Ok(Value::Integer(1008060402))
+    );
+}
+
+#[test]
+fn iterates_over_tuples_and_map_keys() {
+    assert_eq!(
+        eval("let total = 0; for x in (4, 5) { total = total + x }; total"),
+        Ok(Value::Integer(9))
+    );
+    // Map keys come in sorted order.
+    assert_eq!(
+        eval("let keys = \"\"; for k in {b: 1, a: 2, c: 3} { keys = f\"{keys}{k}\" }; keys"),
+        string("abc")
+    );
+}
+
+#[test]
+fn the_loop_variable_is_scoped_to_the_body() {
+    assert_eq!(
+        eval("for x in [1] { x }; x"),
+        Err(EvalError::UndefinedVariable("x".to_string()))
+    );
+    assert_eq!(
+        eval("let x = 7; for x in [1, 2] { x }; x"),
+        Ok(Value::Integer(7))
+    );
+    // Each pass binds a fresh variable, so closures keep the element they saw.
+    assert_eq!(
+        eval("let f = null; let g = null; for i in [1, 2] { if i == 1 { f = || i } else { g = || i } }; [f(), g()]"),
+        Ok(Value::Array(vec![Value::Integer(1), Value::Integer(2)]))
+    );
+}
+
+#[test]
+fn an_empty_collection_runs_no_passes() {
+    assert_eq!(
+        eval("let n = 0; for x in [] { n = 1 }; n"),
+        Ok(Value::Integer(0))
+    );
+    assert_eq!(eval("for x in [1] { x }"), Ok(Value::Null));
+}
+
+#[test]
+fn other_values_are_not_iterable() {
+    assert_eq!(
+        eval("for x in 5 { x }"),
+        Err(EvalError::NotIterable("5".to_string()))
+    );
+    assert!(matches!(
+        eval("for c in \"abc\" { c }"),
+        Err(EvalError::NotIterable(_))
+    ));
+}
+
+#[test]
+fn errors_in_the_body_stop_the_loop() {
+    assert!(matches!(
+        eval("for x in [1, 0] { 1 / x }"),
+        Err(EvalError::DivisionByZero(_))
+    ));
+}
+
+#[test]
+fn needs_a_variable_and_in() {
+    for source in ["for in [1] { 1 }", "for x [1] { 1 }", "for x in [1] 1"] {
+        let result = Parser::new(Lexer::new(source.to_string())).parse();
+        assert!(matches!(result, Err(Error::Parse(_))), "{}", source);
+    }
+}
+
+#[test]
+fn pretty_printing_round_trips() {
+    let source = "for x in [1, 2] {\n    x\n}";
+    let ast = parse(source);
+    assert_eq!(pretty_print(&ast), source);
+}
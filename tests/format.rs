@@ -0,0 +1,83 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn formatted(value: &str, spec: &str) -> String {
+    match eval(&format!("format({}, {:?})", value, spec)) {
+        Ok(Value::Str(s)) => s,
+        other => panic!("format({}, {:?}) gave {:?}", value, spec, other),
+    }
+}
+
+#[test]
+fn fixed_point_rounds_to_the_precision() {
+    assert_eq!(formatted("1.0 / 3.0", "0.2f"), "0.33");
+    assert_eq!(formatted("2.0 / 3.0", ".2f"), "0.67");
+    assert_eq!(formatted("2.5", ".0f"), "2");
+    assert_eq!(formatted("-0.001", ".2f"), "-0.00");
+    assert_eq!(formatted("7", ".3f"), "7.000");
+    // Two decimals unless the spec says otherwise.
+    assert_eq!(formatted("3.14159", "f"), "3.14");
+    assert_eq!(formatted("3.14159", ".4"), "3.1416");
+}
+
+#[test]
+fn scientific_notation() {
+    assert_eq!(formatted("1.0 / 3.0", "e"), "3.33e-1");
+    assert_eq!(formatted("123456", ".3e"), "1.235e5");
+    assert_eq!(formatted("-0.00042", ".1e"), "-4.2e-4");
+}
+
+#[test]
+fn commas_separate_thousands() {
+    assert_eq!(formatted("1000000", ","), "1,000,000");
+    assert_eq!(formatted("-1234567", ","), "-1,234,567");
+    assert_eq!(formatted("999", ","), "999");
+    assert_eq!(formatted("1234567.891", ",.2f"), "1,234,567.89");
+}
+
+#[test]
+fn signs_widths_and_percentages() {
+    assert_eq!(formatted("5", "+"), "+5");
+    assert_eq!(formatted("5", " d"), " 5");
+    assert_eq!(formatted("-5", "+d"), "-5");
+    assert_eq!(formatted("42", "6"), "    42");
+    assert_eq!(formatted("-42", "06d"), "-00042");
+    assert_eq!(formatted("0.256", ".1%"), "25.6%");
+    assert_eq!(formatted("1.5", ""), "1.5");
+    assert_eq!(formatted("1", "1000").len(), 1_000);
+}
+
+#[test]
+fn unsupported_specs_are_errors() {
+    for spec in ["x", "0.2q", ".f", "<5", "5.2f!", ".2d", "1001", ".1001f"] {
+        assert_eq!(
+            eval(&format!("format(1, {:?})", spec)),
+            Err(EvalError::InvalidFormatSpec(spec.to_string())),
+            "{}",
+            spec
+        );
+    }
+    assert_eq!(
+        eval("format(1.5, \"d\")"),
+        Err(EvalError::InvalidFormatSpec("d".to_string()))
+    );
+    assert_eq!(
+        EvalError::InvalidFormatSpec("0.2q".to_string()).to_string(),
+        "invalid format spec: \"0.2q\""
+    );
+    assert_eq!(
+        eval("format(\"a\", \"\")"),
+        Err(EvalError::TypeMismatch(
+            "format() expects a number, found string".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("format(1)"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to format".to_string()
+        ))
+    );
+}
@@ -0,0 +1,48 @@
+use interpreter::error::LexErrorKind;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::{Error, Value};
+
+mod common;
+use common::{eval, string};
+
+fn lex_error(source: &str) -> Option<LexErrorKind> {
+    match Parser::new(Lexer::new(source.to_string())).parse() {
+        Err(Error::Lex(err)) => Some(err.kind),
+        _ => None,
+    }
+}
+
+#[test]
+fn interpolates_variables() {
+    let source = r#"let name = "world"; f"Hello, {name}!""#;
+    assert_eq!(eval(source), Ok(string("Hello, world!")));
+    let source = r#"let name = "world"; f"Hello, {name}!" == "Hello, world!""#;
+    assert_eq!(eval(source), Ok(Value::Bool(true)));
+}
+
+#[test]
+fn interpolates_expressions() {
+    assert_eq!(
+        eval(r#"f"{1 + 2} and {[1, 2]}""#),
+        Ok(string("3 and [1, 2]"))
+    );
+    assert_eq!(eval(r#"f"{len("abc")}""#), Ok(string("3")));
+    assert_eq!(eval(r#"f"""#), Ok(string("")));
+    assert_eq!(eval(r#"f"no holes""#), Ok(string("no holes")));
+}
+
+#[test]
+fn doubled_braces_are_literal() {
+    assert_eq!(eval(r#"f"{{x}}""#), Ok(string("{x}")));
+    assert_eq!(eval(r#"let x = 1; f"{{{x}}}""#), Ok(string("{1}")));
+}
+
+#[test]
+fn unclosed_holes_are_errors() {
+    assert_eq!(
+        lex_error(r#"f"Hello, {name""#),
+        Some(LexErrorKind::UnterminatedString)
+    );
+    assert_eq!(lex_error(r#"f"a } b""#), Some(LexErrorKind::UnmatchedBrace));
+}
@@ -0,0 +1,80 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::Value;
+
+mod common;
+use common::{eval, eval_in};
+
+#[test]
+fn equal_values_hash_equal() {
+    for source in [
+        r#"hash("abc") == hash("abc")"#,
+        "hash(42) == hash(42)",
+        "hash(2) == hash(2.0)",
+        "hash(0.0) == hash(-0.0)",
+        "hash(2.5) == hash(5 / 2)",
+        "hash([1, [\"a\", null]]) == hash([1.0, [\"a\", null]])",
+        "hash((true, 1)) == hash((true, 1))",
+        "try { 1 / 0 } catch e { hash(e) == hash(e) }",
+    ] {
+        assert_eq!(eval(source), Ok(Value::Bool(true)), "{}", source);
+    }
+}
+
+#[test]
+fn different_values_hash_differently() {
+    for source in [
+        r#"hash("abc") != hash("abd")"#,
+        r#"hash("1") != hash(1)"#,
+        "hash(1) != hash(1.5)",
+        "hash([1, 2]) != hash([2, 1])",
+        "hash([1, 2]) != hash((1, 2))",
+        "hash([[1], 2]) != hash([1, [2]])",
+        "hash(null) != hash(false)",
+    ] {
+        assert_eq!(eval(source), Ok(Value::Bool(true)), "{}", source);
+    }
+    assert!(matches!(eval("hash(\"abc\")"), Ok(Value::Integer(_))));
+}
+
+#[test]
+fn hashes_are_stable_within_a_session_only() {
+    let mut session = Interpreter::new();
+    let first = eval_in(&mut session, r#"hash("abc")"#).unwrap();
+    assert_eq!(eval_in(&mut session, r#"hash("abc")"#), Ok(first.clone()));
+    assert_eq!(
+        eval_in(&mut session, r#"let h = hash("abc"); hash("abc") == h"#),
+        Ok(Value::Bool(true))
+    );
+    // Another interpreter keys its hashes afresh.
+    let others: Vec<Value> = (0..4).map(|_| eval(r#"hash("abc")"#).unwrap()).collect();
+    assert!(others.iter().any(|other| *other != first), "{:?}", others);
+}
+
+#[test]
+fn maps_structs_and_functions_are_unhashable() {
+    for (source, type_name) in [
+        ("hash({\"a\": 1})", "map"),
+        ("struct P { x }; hash(P { x: 1 })", "struct"),
+        ("hash(|x| x)", "function"),
+        ("hash(sqrt)", "function"),
+        ("hash([1, ({}, 2)])", "map"),
+    ] {
+        assert_eq!(
+            eval(source),
+            Err(EvalError::Unhashable(type_name.to_string())),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        eval("try { hash({}) } catch e { e.message }"),
+        Ok(Value::Str("cannot hash a map".to_string()))
+    );
+    assert_eq!(
+        eval("hash()"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to hash".to_string()
+        ))
+    );
+}
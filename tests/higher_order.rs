@@ -0,0 +1,93 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn ints(values: &[i64]) -> Result<Value, EvalError> {
+    Ok(Value::Array(
+        values.iter().copied().map(Value::Integer).collect(),
+    ))
+}
+
+#[test]
+fn map_and_filter_compose() {
+    assert_eq!(eval("map(|x| x * x, 1..5)"), ints(&[1, 4, 9, 16]));
+    assert_eq!(
+        eval("filter(|x| x % 2 == 0, map(|x| x * x, 1..7))"),
+        ints(&[4, 16, 36])
+    );
+    assert_eq!(eval("map(|x| x, [])"), ints(&[]));
+    assert_eq!(eval("filter(|x| false, 1..5)"), ints(&[]));
+}
+
+#[test]
+fn reduce_folds_from_the_initial_value() {
+    assert_eq!(
+        eval("reduce(|acc, x| acc + x, 0, 1..=4)"),
+        Ok(Value::Integer(10))
+    );
+    assert_eq!(
+        eval("reduce(|acc, x| acc + x, 42, [])"),
+        Ok(Value::Integer(42))
+    );
+}
+
+#[test]
+fn a_dot_product_through_zip() {
+    let source = "
+        let xs = [1, 2, 3];
+        let ys = [4, 5, 6];
+        reduce(|acc, pair| { let (x, y) = pair; acc + x * y }, 0, zip(xs, ys))
+    ";
+    assert_eq!(eval(source), Ok(Value::Integer(32)));
+}
+
+#[test]
+fn the_function_must_be_callable() {
+    for source in ["map(1, [1])", "filter(1, [1])", "reduce(1, 0, [1])"] {
+        assert_eq!(
+            eval(source),
+            Err(EvalError::NotCallable("1".to_string())),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn callback_errors_name_the_element() {
+    let err = eval("map(|x| 10 / x, [2, 1, 0])").unwrap_err();
+    assert!(
+        matches!(
+            err,
+            EvalError::Callback {
+                function: "map",
+                index: 2,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+    assert_eq!(
+        err.to_string(),
+        "map failed at element 2: division by zero in `10 / 0`"
+    );
+    assert!(matches!(
+        eval("filter(|x| 1 / 0, [5])"),
+        Err(EvalError::Callback {
+            function: "filter",
+            index: 0,
+            ..
+        })
+    ));
+    assert!(matches!(
+        eval("reduce(|acc, x| acc / x, 1, [1, 0])"),
+        Err(EvalError::Callback {
+            function: "reduce",
+            index: 1,
+            ..
+        })
+    ));
+}
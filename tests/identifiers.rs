@@ -0,0 +1,52 @@
+use interpreter::error::{LexError, LexErrorKind};
+use interpreter::lexer::{Lexer, Token};
+
+fn tokens(source: &str) -> Result<Vec<Token>, LexError> {
+    Lexer::new(source.to_string())
+        .map(|token| token.map(|token| token.token))
+        .collect()
+}
+
+fn ident(name: &str) -> Token {
+    Token::Ident(name.to_string())
+}
+
+#[test]
+fn underscores_may_go_anywhere() {
+    for name in ["my_var", "_tmp", "trailing_", "__", "a_1_b", "x1", "_9"] {
+        assert_eq!(tokens(name), Ok(vec![ident(name), Token::EOF]), "{}", name);
+    }
+    // On its own it is the wildcard of `match`.
+    assert_eq!(tokens("_"), Ok(vec![Token::Underscore, Token::EOF]));
+}
+
+#[test]
+fn keywords_are_whole_names() {
+    for name in [
+        "plusone", "iffy", "letter", "fnord", "in_", "_if", "true1", "nullable",
+    ] {
+        assert_eq!(tokens(name), Ok(vec![ident(name), Token::EOF]), "{}", name);
+    }
+    assert_eq!(tokens("if in"), Ok(vec![Token::If, Token::In, Token::EOF]));
+}
+
+#[test]
+fn a_name_stuck_to_a_number_is_an_invalid_literal() {
+    for (source, literal) in [("1x", "1x"), ("12_ab", "12_ab"), ("3.5e", "3.5e")] {
+        let err = tokens(source).unwrap_err();
+        assert_eq!(
+            err.kind,
+            LexErrorKind::InvalidLiteral(literal.to_string()),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        tokens("1x").unwrap_err().kind.to_string(),
+        "invalid literal `1x`"
+    );
+    assert_eq!(
+        tokens("1 x"),
+        Ok(vec![Token::Integer(1), ident("x"), Token::EOF])
+    );
+}
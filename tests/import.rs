@@ -0,0 +1,147 @@
+use std::fs;
+use std::path::PathBuf;
+
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::pretty::pretty_print;
+use interpreter::Value;
+
+mod common;
+use common::parse;
+
+// Writes `files` into a fresh directory and runs `main.calc` from it as a script.
+fn run(test: &str, files: &[(&str, &str)]) -> (Result<Value, EvalError>, PathBuf) {
+    let dir = std::env::temp_dir().join(format!("import-{}-{}", std::process::id(), test));
+    let _ = fs::remove_dir_all(&dir);
+    for (name, source) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    let main = dir.join("main.calc");
+    let source = fs::read_to_string(&main).unwrap();
+    let ast = Parser::new(Lexer::new(source)).parse().unwrap();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_script(&main);
+    let result = interpreter.run(ast);
+    (result, dir.canonicalize().unwrap())
+}
+
+#[test]
+fn imports_bring_bindings_into_scope() {
+    let (result, _) = run(
+        "bindings",
+        &[
+            (
+                "main.calc",
+                "import \"lib/math.calc\"; [square(4), TAU > 6, Point { x: 1 }.x]",
+            ),
+            (
+                "lib/math.calc",
+                "const TAU = 2 * pi; struct Point { x }; fn square(x) { x * x }",
+            ),
+        ],
+    );
+    assert_eq!(
+        result,
+        Ok(Value::Array(vec![
+            Value::Integer(16),
+            Value::Bool(true),
+            Value::Integer(1)
+        ]))
+    );
+}
+
+// Paths are relative to the importing file, not the working directory.
+#[test]
+fn nested_imports_resolve_against_their_own_file() {
+    let (result, dir) = run(
+        "nested",
+        &[
+            ("main.calc", "import \"lib/a.calc\"; [from_b, where_a]"),
+            ("lib/a.calc", "import \"b.calc\"; let where_a = __file__;"),
+            ("lib/b.calc", "let from_b = 2;"),
+        ],
+    );
+    let a = dir.join("lib/a.calc").display().to_string();
+    assert_eq!(
+        result,
+        Ok(Value::Array(vec![Value::Integer(2), Value::Str(a)]))
+    );
+}
+
+#[test]
+fn a_second_import_is_not_run_again() {
+    let (result, _) = run(
+        "twice",
+        &[
+            (
+                "main.calc",
+                "import \"m.calc\"; counter = 10; import \"m.calc\"; counter",
+            ),
+            ("m.calc", "let counter = 1;"),
+        ],
+    );
+    assert_eq!(result, Ok(Value::Integer(10)));
+}
+
+#[test]
+fn circular_imports_are_an_error() {
+    let (result, dir) = run(
+        "circular",
+        &[
+            ("main.calc", "import \"a.calc\""),
+            ("a.calc", "import \"b.calc\""),
+            ("b.calc", "import \"a.calc\""),
+        ],
+    );
+    let a = dir.join("a.calc").display().to_string();
+    assert_eq!(result, Err(EvalError::CircularImport(a)));
+
+    let (result, dir) = run("self", &[("main.calc", "import \"main.calc\"")]);
+    let main = dir.join("main.calc").display().to_string();
+    assert_eq!(result, Err(EvalError::CircularImport(main)));
+}
+
+#[test]
+fn missing_and_malformed_files_fail_to_import() {
+    let (result, _) = run("failed", &[("main.calc", "import \"missing.calc\"")]);
+    assert!(matches!(result, Err(EvalError::ImportFailed(path, _)) if path == "missing.calc"));
+
+    let (result, dir) = run(
+        "malformed",
+        &[("main.calc", "import \"bad.calc\""), ("bad.calc", "1 +")],
+    );
+    let bad = dir.join("bad.calc").display().to_string();
+    assert!(matches!(result, Err(EvalError::ImportFailed(path, _)) if path == bad));
+}
+
+#[test]
+fn errors_inside_a_module_are_reported() {
+    let (result, _) = run(
+        "runtime",
+        &[("main.calc", "import \"m.calc\""), ("m.calc", "1 / 0")],
+    );
+    assert!(matches!(result, Err(EvalError::DivisionByZero(_))));
+}
+
+#[test]
+fn an_empty_module_imports_nothing() {
+    let (result, _) = run(
+        "empty",
+        &[
+            ("main.calc", "import \"empty.calc\"; 1"),
+            ("empty.calc", "\n"),
+        ],
+    );
+    assert_eq!(result, Ok(Value::Integer(1)));
+}
+
+#[test]
+fn pretty_printing_round_trips() {
+    let source = "import \"lib/math.calc\"";
+    let ast = parse(source);
+    assert_eq!(pretty_print(&ast), source);
+}
@@ -0,0 +1,88 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::Value;
+
+mod common;
+use common::{eval_in, parse};
+
+// Runs `source` with `stdin` as its input, returning the result and what was written.
+fn run(source: &str, stdin: &str) -> (Result<Value, EvalError>, String) {
+    let (mut interpreter, output) = Interpreter::make_testable(stdin);
+    let result = eval_in(&mut interpreter, source);
+    let written = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    (result, written)
+}
+
+fn string(s: &str) -> Result<Value, EvalError> {
+    Ok(Value::Str(s.to_string()))
+}
+
+#[test]
+fn reads_a_line_after_writing_the_prompt() {
+    let (result, written) = run("input(\"Enter your name: \")", "Ada\nBob\n");
+    assert_eq!(result, string("Ada"));
+    assert_eq!(written, "Enter your name: ");
+}
+
+#[test]
+fn each_call_reads_the_next_line() {
+    let (result, written) = run(
+        "let a = input(\"a? \"); let b = input(\"b? \"); f\"{a}-{b}\"",
+        "1\n2\n3\n",
+    );
+    assert_eq!(result, string("1-2"));
+    assert_eq!(written, "a? b? ");
+    let (result, _) = run("int(input()) + int(input())", "20\n22\n");
+    assert_eq!(result, Ok(Value::Integer(42)));
+}
+
+#[test]
+fn the_prompt_is_optional() {
+    let (result, written) = run("input()", "line\n");
+    assert_eq!(result, string("line"));
+    assert_eq!(written, "");
+    // Other values are written as they print.
+    let (_, written) = run("input(3)", "");
+    assert_eq!(written, "3");
+}
+
+#[test]
+fn line_endings_are_stripped() {
+    assert_eq!(run("input()", "windows\r\n").0, string("windows"));
+    assert_eq!(run("input()", "no newline").0, string("no newline"));
+    assert_eq!(run("input()", "\n").0, string(""));
+    assert_eq!(run("input()", "  spaces  \n").0, string("  spaces  "));
+}
+
+#[test]
+fn the_end_of_input_is_null() {
+    assert_eq!(run("input(\"? \")", "").0, Ok(Value::Null));
+    assert_eq!(
+        run("[input(), input(), input() ?? \"done\"]", "only\n").0,
+        Ok(Value::Array(vec![
+            Value::Str("only".to_string()),
+            Value::Null,
+            Value::Str("done".to_string()),
+        ]))
+    );
+}
+
+#[test]
+fn takes_at_most_one_argument() {
+    assert_eq!(
+        run("input(\"a\", \"b\")", "x\n").0,
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to input".to_string()
+        ))
+    );
+}
+
+#[test]
+fn a_testable_interpreter_keeps_its_input_and_output_across_runs() {
+    let (mut interpreter, output) = Interpreter::make_testable("3\n4\n");
+    for (source, expected) in [("int(input(\"x? \"))", 3), ("int(input(\"y? \")) * 2", 8)] {
+        let ast = parse(source);
+        assert_eq!(interpreter.run(ast), Ok(Value::Integer(expected)));
+    }
+    assert_eq!(output.lock().unwrap().as_slice(), b"x? y? ");
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "json")]
+use std::collections::HashMap;
+
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+#[test]
+fn json_parse_builds_values() {
+    assert_eq!(
+        eval(r#"json_parse("{\"name\": \"Ada\", \"tags\": [1, 2.5, true, null]}")"#),
+        Ok(Value::Map(HashMap::from([
+            ("name".to_string(), string("Ada")),
+            (
+                "tags".to_string(),
+                Value::Array(vec![
+                    Value::Float(1.0),
+                    Value::Float(2.5),
+                    Value::Bool(true),
+                    Value::Null,
+                ])
+            ),
+        ])))
+    );
+    assert_eq!(eval(r#"json_parse("\"h\\u00e9\"")"#), Ok(string("hé")));
+    assert_eq!(
+        eval(r#"map_get(map_get(json_parse("{\"a\": {\"b\": [3]}}"), "a"), "b")[0]"#),
+        Ok(Value::Float(3.0))
+    );
+}
+
+#[test]
+fn malformed_json_is_a_parse_error() {
+    for source in [r#""{\"a\": }""#, r#""[1, 2""#, r#""""#, r#""nul""#] {
+        let result = eval(&format!("json_parse({})", source));
+        assert!(
+            matches!(result, Err(EvalError::JsonParseError(_))),
+            "{}: {:?}",
+            source,
+            result
+        );
+    }
+    assert_eq!(
+        eval(r#"try { json_parse("{") } catch e { e.kind }"#),
+        Ok(string("JsonParseError"))
+    );
+    assert_eq!(
+        eval(r#"json_parse("[1,]")"#).unwrap_err().to_string(),
+        "invalid JSON: trailing comma at line 1 column 4"
+    );
+}
+
+#[test]
+fn json_stringify_writes_compact_json_with_sorted_keys() {
+    assert_eq!(
+        eval(r#"json_stringify({"b": [1, 2.5, "x\n"], "a": null, "c": true})"#),
+        Ok(string(r#"{"a":null,"b":[1,2.5,"x\n"],"c":true}"#))
+    );
+    assert_eq!(
+        eval(r#"struct P { x, y }; json_stringify(P { x: 1, y: -2 })"#),
+        Ok(string(r#"{"x":1,"y":-2}"#))
+    );
+    assert_eq!(
+        eval(r#"try { 1 / 0 } catch e { json_stringify(e) }"#),
+        Ok(string(
+            r#"{"kind":"DivisionByZero","message":"division by zero in `1 / 0`"}"#
+        ))
+    );
+}
+
+#[test]
+fn values_round_trip() {
+    assert_eq!(
+        eval(
+            r#"let v = {"list": [1.5, "two", {"three": false}]}; json_parse(json_stringify(v)) == v"#
+        ),
+        Ok(Value::Bool(true))
+    );
+}
+
+#[test]
+fn some_values_have_no_json_form() {
+    for (source, message) in [
+        ("json_stringify((1, 2))", "(1, 2) has no JSON form"),
+        (
+            "fn f() { 1 }; json_stringify([f])",
+            "<fn f> has no JSON form",
+        ),
+        (
+            "json_stringify({\"f\": sqrt})",
+            "<builtin sqrt> has no JSON form",
+        ),
+        ("json_stringify(sqrt(-1))", "NaN has no JSON form"),
+    ] {
+        assert_eq!(
+            eval(source),
+            Err(EvalError::JsonSerializeError(message.to_string())),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        EvalError::JsonSerializeError("(1, 2) has no JSON form".to_string()).to_string(),
+        "cannot convert to JSON: (1, 2) has no JSON form"
+    );
+}
+
+#[test]
+fn arguments_are_checked() {
+    assert_eq!(
+        eval("json_parse(1)"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to json_parse".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("json_stringify()"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to json_stringify".to_string()
+        ))
+    );
+}
@@ -0,0 +1,122 @@
+use interpreter::error::LexErrorKind;
+use interpreter::lexer::{Lexer, SpannedToken, Token};
+use interpreter::parser::Parser;
+
+fn tokens(source: &str) -> Vec<SpannedToken> {
+    Lexer::new(source.to_string()).get_tokens().unwrap()
+}
+
+#[test]
+fn tokens_after_a_newline_are_on_the_next_line() {
+    let tokens = tokens("let x = 1;\n  x + 2");
+    let x = &tokens[5];
+    assert_eq!(x.token, Token::Ident("x".to_string()));
+    assert_eq!((x.start_line, x.start_col), (2, 3));
+    assert_eq!((x.end_line, x.end_col), (2, 4));
+}
+
+#[test]
+fn columns_count_characters() {
+    let tokens = tokens("\"héllo\" + 1");
+    assert_eq!((tokens[1].start_line, tokens[1].start_col), (1, 9));
+}
+
+#[test]
+fn tracks_the_lexer_position() {
+    let mut lexer = Lexer::new("1\n22".to_string());
+    assert_eq!(lexer.current_position(), (1, 1));
+    lexer.by_ref().count();
+    assert_eq!(lexer.current_position(), (2, 3));
+}
+
+#[test]
+fn lex_errors_name_their_line_and_column() {
+    let err = Lexer::new("1 +\n\n  2 $ 3".to_string())
+        .get_tokens()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "unexpected character `$` at line 3, col 5");
+}
+
+// Each bad input with the error it gives and where: the byte offset, line and column.
+#[test]
+fn lex_errors_are_at_the_offending_character() {
+    let cases = [
+        ("1 $ 2", LexErrorKind::UnexpectedChar('$'), (2, 1, 3)),
+        ("$", LexErrorKind::UnexpectedChar('$'), (0, 1, 1)),
+        ("x = 1 @", LexErrorKind::UnexpectedChar('@'), (6, 1, 7)),
+        ("\"é\" # 1", LexErrorKind::UnexpectedChar('#'), (5, 1, 5)),
+        (
+            "1.2.3",
+            LexErrorKind::InvalidNumber("1.2.3".to_string()),
+            (0, 1, 1),
+        ),
+        (
+            "2 + 1.2.3",
+            LexErrorKind::InvalidNumber("1.2.3".to_string()),
+            (4, 1, 5),
+        ),
+        (
+            "a\n  12x",
+            LexErrorKind::InvalidLiteral("12x".to_string()),
+            (4, 2, 3),
+        ),
+        ("1 + \"open", LexErrorKind::UnterminatedString, (4, 1, 5)),
+        ("\"a\\q\"", LexErrorKind::UnknownEscape('q'), (2, 1, 3)),
+        (
+            "99999999999999999999",
+            LexErrorKind::IntegerOutOfRange("99999999999999999999".to_string()),
+            (0, 1, 1),
+        ),
+    ];
+    for (source, kind, (offset, line, col)) in cases {
+        let err = Lexer::new(source.to_string()).get_tokens().unwrap_err();
+        assert_eq!(err.kind, kind, "{:?}", source);
+        assert_eq!(
+            (err.offset, err.line, err.col),
+            (offset, line, col),
+            "{:?}",
+            source
+        );
+    }
+}
+
+#[test]
+fn tokenize_into_reuses_the_buffer() {
+    let mut buffer = Vec::with_capacity(16);
+    Lexer::new("a + 1".to_string())
+        .tokenize_into(&mut buffer)
+        .unwrap();
+    assert_eq!(buffer, tokens("a + 1"));
+    let capacity = buffer.capacity();
+    Lexer::new("2".to_string())
+        .tokenize_into(&mut buffer)
+        .unwrap();
+    assert_eq!(buffer, tokens("2"));
+    assert_eq!(buffer.capacity(), capacity);
+    // On an error the buffer holds the tokens before it.
+    assert!(Lexer::new("x $".to_string())
+        .tokenize_into(&mut buffer)
+        .is_err());
+    assert_eq!(buffer, tokens("x")[..1]);
+}
+
+#[test]
+fn parse_errors_name_their_line_and_column() {
+    let source = "let a = 1;\nlet b = 2;\nlet c )";
+    let err = Parser::new(Lexer::new(source.to_string()))
+        .parse()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "expected `=`, found `)` at line 3, col 7");
+}
+
+#[test]
+fn f_string_expressions_keep_their_place() {
+    let source = "1;\nf\"a {1 +}\"";
+    let err = Parser::new(Lexer::new(source.to_string()))
+        .parse()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "expected an expression, found end of input at line 2, col 9"
+    );
+}
@@ -0,0 +1,167 @@
+use std::env;
+use std::fs;
+
+use interpreter::lexer::{Lexer, LexerOptions};
+
+// Token streams recorded from the lexer, one block per input, in
+// `tests/snapshots/lexer_tokens.txt`. After a deliberate change to the token stream, run
+// with `UPDATE_SNAPSHOTS=1` to rewrite the file and review the diff.
+const SNAPSHOT: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/snapshots/lexer_tokens.txt"
+);
+
+const INPUTS: &[&str] = &[
+    "",
+    "   \n\t ",
+    "42",
+    "3.14",
+    ".5",
+    "1_000_000",
+    "1.5e3",
+    "1..10",
+    "1..=10",
+    "0..n by 2",
+    "1.5.2",
+    "1_",
+    "1._5",
+    "1x",
+    "99999999999999999999",
+    "1.",
+    "x.y?.z",
+    "a..b",
+    "f(1, 2)",
+    "== != <= >= < > |> | ?? ?. % %= + += - -= * *= ** / /= ^ √ × ÷ − = => _ _x __file__",
+    "let x = 5; const Y = 2; global z",
+    "if a { b } else { c }",
+    "while x {} for i in xs {}",
+    "fn f(a, b) { a + b }",
+    "match x { 1 => 2, _ => 3 }",
+    "try { x } catch e { y }",
+    "struct P { x, y }",
+    "import \"m.calc\"",
+    "true false null not and or in plus minus mul div mod",
+    "café 数 ñ _a1",
+    "\"hello\"",
+    "\"a\\nb\\t\\\"c\\\\\"",
+    "\"\\q\"",
+    "\"open",
+    "\"\"\"raw\n  \\n text\"\"\"",
+    "\"\"\"unterminated",
+    "f\"x = {x}, y = {y + 1}\"",
+    "f\"{{literal}} {a}\"",
+    "f\"{m[\"k\"]} {g({1})}\"",
+    "f\"bad }\"",
+    "f\"{open\"",
+    "f\"esc \\n {x}\"",
+    "a\nb\n  c",
+    "x = 1 +\n  2",
+    "# comment",
+    "1 @ 2",
+    "3 – 1",
+    "1\u{a0}+\u{3000}2",
+    "[1, 2][0]",
+    "{a: 1, \"b\": 2}",
+    "(1, 2)",
+    "|x| x * 2",
+    "√16 ^ 2",
+    "x?.y ?? z",
+    "a |> f |> g",
+    "\"π\" 1.5 π",
+    "１２",
+    "!x",
+    "a ? b",
+    "−= ×= ÷=",
+    "\"\"",
+    "\"\"\"\"\"\"",
+    "f\"\"",
+    "f x",
+    "_ __ _1",
+    "f\"{x\"}\"}\"",
+    "\"unknown \\q\"",
+    "x\r\ny",
+    "..",
+    "...",
+    "1...2",
+];
+
+const DECIMAL_COMMA_INPUTS: &[&str] = &[
+    "3,14 + f(1,5; 2,5)",
+    "[1;2]",
+    "1,2,3",
+    "x,5",
+    "1.5",
+    ".5",
+    ",5",
+    "x.5",
+    "{1;2}",
+    "1,5..2,5",
+];
+
+const CASE_INSENSITIVE_INPUTS: &[&str] = &["IF Mod While x", "For iN LET"];
+
+// Each token with its span and its start and end positions, or the error that ended
+// the stream.
+fn render(lexer: Lexer) -> String {
+    let mut lines = Vec::new();
+    for item in lexer {
+        lines.push(match item {
+            Ok(token) => format!(
+                "{:?} {}..{} {}:{}-{}:{}",
+                token.token,
+                token.span.start,
+                token.span.end,
+                token.start_line,
+                token.start_col,
+                token.end_line,
+                token.end_col
+            ),
+            Err(err) => format!("error {:?} {}", err.kind, err),
+        });
+    }
+    lines.join("\n")
+}
+
+fn snapshot() -> String {
+    let mut out = String::new();
+    let modes = [
+        ("", INPUTS, LexerOptions::default()),
+        (
+            "decimal comma ",
+            DECIMAL_COMMA_INPUTS,
+            LexerOptions {
+                decimal_comma: true,
+                ..LexerOptions::default()
+            },
+        ),
+        (
+            "case insensitive ",
+            CASE_INSENSITIVE_INPUTS,
+            LexerOptions {
+                case_insensitive: true,
+                ..LexerOptions::default()
+            },
+        ),
+    ];
+    for (mode, inputs, options) in modes {
+        for input in inputs {
+            let tokens = render(Lexer::with_options(input.to_string(), options));
+            out.push_str(&format!("{}{:?}\n{}\n\n", mode, input, tokens));
+        }
+    }
+    out
+}
+
+#[test]
+fn token_streams_match_the_snapshot() {
+    let actual = snapshot();
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(SNAPSHOT, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(SNAPSHOT).unwrap();
+    for (expected, actual) in expected.split("\n\n").zip(actual.split("\n\n")) {
+        assert_eq!(expected, actual);
+    }
+    assert_eq!(expected, actual);
+}
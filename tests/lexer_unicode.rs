@@ -0,0 +1,117 @@
+use interpreter::error::LexErrorKind;
+use interpreter::lexer::{Lexer, Span, SpannedToken, Token};
+use interpreter::Value;
+
+mod common;
+
+fn tokens(source: &str) -> Vec<SpannedToken> {
+    Lexer::new(source.to_string()).get_tokens().unwrap()
+}
+
+fn eval(source: &str) -> Value {
+    common::eval(source).unwrap()
+}
+
+#[test]
+fn unicode_spaces_separate_tokens() {
+    // A non-breaking space, an em space and an ideographic space.
+    let tokens = tokens("1\u{a0}+\u{2003}2\u{3000}");
+    let kinds: Vec<&Token> = tokens.iter().map(|t| &t.token).collect();
+    assert_eq!(
+        kinds,
+        [
+            &Token::Integer(1),
+            &Token::Plus,
+            &Token::Integer(2),
+            &Token::EOF
+        ]
+    );
+    // Spans are byte offsets; columns count characters.
+    assert_eq!(tokens[1].span, Span { start: 3, end: 4 });
+    assert_eq!(tokens[2].span, Span { start: 7, end: 8 });
+    assert_eq!(tokens[2].start_col, 5);
+    assert_eq!(eval("1\u{a0}+\u{a0}2"), Value::Integer(3));
+}
+
+#[test]
+fn full_width_digits_are_rejected() {
+    let err = Lexer::new("１２".to_string()).get_tokens().unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::UnexpectedChar('１'));
+    assert_eq!(err.offset, 0);
+    let err = Lexer::new("12３".to_string()).get_tokens().unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::UnexpectedChar('３'));
+    assert_eq!((err.offset, err.col), (2, 3));
+}
+
+#[test]
+fn multi_byte_identifiers() {
+    let tokens = tokens("café + ñ");
+    assert_eq!(tokens[0].token, Token::Ident("café".to_string()));
+    assert_eq!(tokens[0].span, Span { start: 0, end: 5 });
+    assert_eq!(tokens[1].span, Span { start: 6, end: 7 });
+    assert_eq!(tokens[2].token, Token::Ident("ñ".to_string()));
+    assert_eq!(
+        eval("let café = 2; let 数 = 3; café * 数"),
+        Value::Integer(6)
+    );
+}
+
+#[test]
+fn numbers_after_multi_byte_text() {
+    let tokens = tokens("\"π\" 1.5 π");
+    assert_eq!(tokens[1].token, Token::Number(1.5));
+    assert_eq!(tokens[1].span, Span { start: 5, end: 8 });
+    assert_eq!(eval("\"é\"; 2.5 * 2"), Value::Float(5.0));
+}
+
+#[test]
+fn unknown_multi_byte_characters_are_reported_cleanly() {
+    // An en dash is not the minus sign `−`.
+    let err = Lexer::new("3 – 1".to_string()).get_tokens().unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::UnexpectedChar('–'));
+    assert_eq!((err.offset, err.col), (2, 3));
+    assert_eq!(eval("3 − 1"), Value::Integer(2));
+}
+
+#[test]
+fn math_symbols_are_operators() {
+    let kinds: Vec<Token> = tokens("3 × 4 ÷ 2 − 1")
+        .into_iter()
+        .map(|t| t.token)
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            Token::Integer(3),
+            Token::Mul,
+            Token::Integer(4),
+            Token::Div,
+            Token::Integer(2),
+            Token::Minus,
+            Token::Integer(1),
+            Token::EOF
+        ]
+    );
+    assert_eq!(eval("3 × 4 ÷ 2 − 1"), Value::Float(5.0));
+    assert_eq!(eval("2×3÷3"), Value::Float(2.0));
+    assert_eq!(eval("−2 × −2"), Value::Integer(4));
+}
+
+#[test]
+fn the_root_sign_takes_a_square_root() {
+    assert_eq!(eval("√16"), Value::Float(4.0));
+    assert_eq!(eval("1 + √(8 × 2) × 2"), Value::Float(9.0));
+    assert_eq!(eval("π == pi"), Value::Bool(true));
+    assert_eq!(eval("2 × π ÷ π"), Value::Float(2.0));
+}
+
+#[test]
+fn identifiers_may_mix_scripts() {
+    assert_eq!(eval("let δ = 0.01; δ × 100"), Value::Float(1.0));
+    assert_eq!(
+        eval("let xδ_2 = 3; let λμ = 4; xδ_2 × λμ"),
+        Value::Integer(12)
+    );
+    assert_eq!(tokens("αβγ×2")[0].token, Token::Ident("αβγ".to_string()));
+    assert_eq!(tokens("αβγ×2")[1].span, Span { start: 6, end: 8 });
+}
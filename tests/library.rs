@@ -0,0 +1,84 @@
+// The crate as a program embedding it sees it: each stage through its public types, and
+// the whole pipeline at once.
+use interpreter::ast::{ASTNode, NodeKind};
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::{Lexer, Span, Token};
+use interpreter::parser::Parser;
+use interpreter::{eval, Error, Value};
+
+#[test]
+fn each_stage_can_be_driven_on_its_own() {
+    let tokens: Vec<Token> = Lexer::new("let x = 2; x * 21".to_string())
+        .map(|token| token.unwrap().token)
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            Token::Let,
+            Token::Ident("x".to_string()),
+            Token::Assign,
+            Token::Integer(2),
+            Token::Semicolon,
+            Token::Ident("x".to_string()),
+            Token::Mul,
+            Token::Integer(21),
+            Token::EOF,
+        ]
+    );
+
+    let ast = Parser::new(Lexer::new("let x = 2; x * 21".to_string()))
+        .parse()
+        .unwrap();
+    let NodeKind::Sequence(statements) = &ast.kind else {
+        panic!("{:?}", ast);
+    };
+    assert_eq!(statements.len(), 2);
+    assert_eq!(ast.span, Span { start: 0, end: 17 });
+
+    let mut interpreter = Interpreter::new();
+    assert_eq!(interpreter.run(ast.clone()), Ok(Value::Integer(42)));
+    // The clone ran in the same session, so `x` is bound.
+    assert_eq!(
+        interpreter.interpret(&statements[1]),
+        Ok(Value::Integer(42))
+    );
+}
+
+#[test]
+fn a_tree_can_be_built_without_parsing() {
+    let span = Span::default();
+    let ast = ASTNode::new(
+        NodeKind::BinaryOp(
+            Box::new(ASTNode::new(NodeKind::Integer(6), span)),
+            Token::Mul,
+            Box::new(ASTNode::new(NodeKind::Number(0.5), span)),
+        ),
+        span,
+    );
+    assert_eq!(Interpreter::new().run(ast), Ok(Value::Float(3.0)));
+}
+
+#[test]
+fn the_pipeline_reports_each_kind_of_error() {
+    assert!(matches!(eval("\"open"), Err(Error::Lex(_))));
+    assert!(matches!(eval("1 +"), Err(Error::Parse(_))));
+    assert_eq!(
+        eval("1 / 0"),
+        Err(Error::Runtime(EvalError::DivisionByZero(
+            "1 / 0".to_string()
+        )))
+    );
+    assert_eq!(eval("[1, 2, 3] |> len"), Ok(Value::Integer(3)));
+}
+
+#[test]
+fn public_types_can_be_debugged() {
+    let mut lexer = Lexer::new("a b".to_string());
+    lexer.next();
+    assert!(format!("{:?}", lexer).starts_with("Lexer { offset: 1, line: 1, col: 2"));
+    let parser = Parser::new(Lexer::new("1".to_string())).with_max_nesting(10);
+    assert!(format!("{:?}", parser).contains("max_nesting: 10"));
+    let interpreter = Interpreter::new().with_memoization(true);
+    assert!(format!("{:?}", interpreter).contains("memoize: true"));
+}
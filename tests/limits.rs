@@ -0,0 +1,126 @@
+use interpreter::error::{EvalError, LexErrorKind, ParseLimit};
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::{Lexer, LexerOptions};
+use interpreter::parser::Parser;
+use interpreter::{Error, Value};
+
+fn lex(source: &str, options: LexerOptions) -> Result<(), LexErrorKind> {
+    Lexer::with_options(source.to_string(), options)
+        .get_tokens()
+        .map(|_| ())
+        .map_err(|err| err.kind)
+}
+
+fn parse_with_max_nodes(source: &str, limit: usize) -> Result<(), Error> {
+    Parser::new(Lexer::new(source.to_string()))
+        .with_max_nodes(limit)
+        .parse()
+        .map(|_| ())
+}
+
+#[test]
+fn input_longer_than_the_limit_is_an_error() {
+    let options = LexerOptions {
+        max_input_len: 5,
+        ..LexerOptions::default()
+    };
+    assert_eq!(lex("1 + 2", options), Ok(()));
+    assert_eq!(lex("1 + 23", options), Err(LexErrorKind::InputTooLong(5)));
+    // The limit is in bytes, not characters.
+    assert_eq!(lex("\"π√\"", options), Err(LexErrorKind::InputTooLong(5)));
+}
+
+#[test]
+fn the_default_input_limit_is_checked_before_lexing() {
+    let source = format!("1{}", " ".repeat(16 << 20));
+    let err = Lexer::new(source).get_tokens().unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::InputTooLong(16 << 20));
+    assert_eq!(
+        err.to_string(),
+        "input exceeds limit of 16777216 bytes at line 1, col 1"
+    );
+}
+
+#[test]
+fn more_tokens_than_the_limit_is_an_error() {
+    let options = LexerOptions {
+        max_tokens: 3,
+        ..LexerOptions::default()
+    };
+    // `EOF` does not count.
+    assert_eq!(lex("1 + 2", options), Ok(()));
+    let err = Lexer::with_options("1 + 2 +".to_string(), options)
+        .get_tokens()
+        .unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::TooManyTokens(3));
+    assert_eq!((err.offset, err.col), (6, 7));
+    assert_eq!(
+        err.to_string(),
+        "input exceeds limit of 3 tokens at line 1, col 7"
+    );
+}
+
+#[test]
+fn more_nodes_than_the_limit_is_an_error() {
+    assert!(parse_with_max_nodes("1 + 2", 3).is_ok());
+    let Err(Error::Parse(err)) = parse_with_max_nodes("1 + 2 + 3", 3) else {
+        panic!("expected a parse error");
+    };
+    assert_eq!(err.limit, Some(ParseLimit::Nodes(3)));
+    assert!(err
+        .to_string()
+        .starts_with("input exceeds limit of 3 syntax tree nodes"));
+    // Nodes built after the last token count too.
+    assert!(parse_with_max_nodes("[1, [2]]", 4).is_ok());
+    assert!(parse_with_max_nodes("[1, [2]]", 3).is_err());
+}
+
+#[test]
+fn recovery_stops_at_the_node_limit() {
+    let (ast, errors) = Parser::new(Lexer::new("1 + 2 + 3; 4; 5; 6".to_string()))
+        .with_max_nodes(3)
+        .parse_recovering();
+    assert!(ast.is_none());
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn interpreter_settings_set_the_limits() {
+    let mut interpreter = Interpreter::new();
+    interpreter.max_tokens = 2;
+    let err = interpreter.parser("1 + 2".to_string()).parse().unwrap_err();
+    assert!(matches!(err, Error::Lex(err) if err.kind == LexErrorKind::TooManyTokens(2)));
+
+    interpreter.max_tokens = usize::MAX;
+    interpreter.max_nodes = 1;
+    let err = interpreter.parser("1 + 2".to_string()).parse().unwrap_err();
+    assert!(matches!(err, Error::Parse(err) if err.limit == Some(ParseLimit::Nodes(1))));
+
+    interpreter.max_nodes = usize::MAX;
+    let ast = interpreter.parser("1 + 2".to_string()).parse().unwrap();
+    assert_eq!(interpreter.run(ast), Ok(Value::Integer(3)));
+}
+
+#[test]
+fn parse_at_runtime_respects_the_limits() {
+    let mut interpreter = Interpreter::new();
+    interpreter.max_input_len = 8;
+    let ast = interpreter
+        .parser("parse(\"1 + 2 + 3\")".to_string())
+        .parse();
+    // The call itself is longer than the limit.
+    assert!(ast.is_err());
+
+    interpreter.max_input_len = usize::MAX;
+    let ast = interpreter
+        .parser("parse(\"1 + 2 + 3\")".to_string())
+        .parse()
+        .unwrap();
+    interpreter.max_input_len = 8;
+    assert_eq!(
+        interpreter.run(ast),
+        Err(EvalError::InvalidArgument(
+            "parse(\"1 + 2 + 3\"): input exceeds limit of 8 bytes at line 1, col 1".to_string()
+        ))
+    );
+}
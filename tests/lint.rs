@@ -0,0 +1,82 @@
+use interpreter::lexer::Span;
+use interpreter::lint::{lint, DeadBranchKind, Warning};
+use interpreter::optimizer::fold_constants;
+
+mod common;
+use common::parse;
+
+fn kinds(source: &str) -> Vec<DeadBranchKind> {
+    lint(&fold_constants(parse(source)))
+        .into_iter()
+        .map(|Warning::DeadBranch { kind, .. }| kind)
+        .collect()
+}
+
+#[test]
+fn literal_conditions_have_dead_branches() {
+    assert_eq!(
+        kinds("if true { 1 } else { 2 }"),
+        [DeadBranchKind::ElseNeverTaken]
+    );
+    assert_eq!(
+        kinds("if false { 1 } else { 2 }"),
+        [DeadBranchKind::ThenNeverTaken]
+    );
+    assert_eq!(kinds("if false { 1 }"), [DeadBranchKind::ThenNeverTaken]);
+    assert_eq!(kinds("while false { 1 }"), [DeadBranchKind::LoopNeverRuns]);
+}
+
+#[test]
+fn conditions_that_fold_are_caught_too() {
+    assert_eq!(
+        kinds("if 1 + 1 == 2 { 1 } else { 2 }"),
+        [DeadBranchKind::ElseNeverTaken]
+    );
+    assert_eq!(kinds("while 2 < 1 { 1 }"), [DeadBranchKind::LoopNeverRuns]);
+}
+
+#[test]
+fn live_branches_are_not_warned_about() {
+    for source in [
+        "if true { 1 }",
+        "let x = 1; if x == 1 { 1 } else { 2 }",
+        "let i = 0; while i < 3 { i = i + 1 }",
+        "1 + 2",
+    ] {
+        assert_eq!(kinds(source), [], "{}", source);
+    }
+}
+
+#[test]
+fn nested_dead_branches_are_all_found() {
+    assert_eq!(
+        kinds("fn f() { if false { while false { 1 } } }; [if true { 1 } else { 2 }]"),
+        [
+            DeadBranchKind::ThenNeverTaken,
+            DeadBranchKind::LoopNeverRuns,
+            DeadBranchKind::ElseNeverTaken,
+        ]
+    );
+}
+
+#[test]
+fn a_warning_covers_the_whole_statement_and_suggests_a_fix() {
+    let source = "1; if false { 2 } else { 3 }";
+    let warnings = lint(&fold_constants(parse(source)));
+    assert_eq!(
+        warnings,
+        [Warning::DeadBranch {
+            span: Span { start: 3, end: 28 },
+            kind: DeadBranchKind::ThenNeverTaken,
+        }]
+    );
+    assert_eq!(
+        warnings[0].suggestion(),
+        "replace the `if` with its else branch, or remove it"
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "if branch is unreachable: the condition is always false \
+         (help: replace the `if` with its else branch, or remove it)"
+    );
+}
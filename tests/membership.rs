@@ -0,0 +1,65 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn is(source: &str, expected: bool) {
+    assert_eq!(eval(source), Ok(Value::Bool(expected)), "{}", source);
+}
+
+#[test]
+fn lists_and_tuples_test_their_elements() {
+    is("2 in [1, 2, 3]", true);
+    is("4 in [1, 2, 3]", false);
+    is("2.0 in [1, 2, 3]", true);
+    is("[1] in [[1], [2]]", true);
+    is("1 in []", false);
+    is("\"b\" in (\"a\", \"b\")", true);
+}
+
+#[test]
+fn strings_test_for_a_substring() {
+    is(r#""ell" in "hello""#, true);
+    is(r#""elk" in "hello""#, false);
+    is(r#""" in "hello""#, true);
+}
+
+#[test]
+fn maps_test_their_keys() {
+    is(r#"let m = {"key": 1}; "key" in m"#, true);
+    is(r#"let m = {"key": 1}; "other" in m"#, false);
+}
+
+#[test]
+fn not_in_negates() {
+    is("4 not in [1, 2, 3]", true);
+    is(r#""ell" not in "hello""#, false);
+    is(r#""key" not in {"key": 1}"#, false);
+}
+
+#[test]
+fn membership_chains_with_logical_operators() {
+    is("1 in [1] and 2 in [2]", true);
+    is("1 in [2] or 2 not in [1]", true);
+    is("not 1 in [1]", false);
+    is("1 + 1 in [2]", true);
+}
+
+#[test]
+fn unsupported_containers_are_errors() {
+    assert_eq!(
+        eval("1 in 2"),
+        Err(EvalError::TypeMismatch(
+            "cannot test whether 1 is in 2".to_string()
+        ))
+    );
+    assert!(matches!(
+        eval(r#"1 in "123""#),
+        Err(EvalError::TypeMismatch(_))
+    ));
+    assert!(matches!(
+        eval(r#"1 in {"1": 1}"#),
+        Err(EvalError::TypeMismatch(_))
+    ));
+}
@@ -0,0 +1,69 @@
+// `with_memoization` against evaluation without it: the same value, error and error span
+// for every program, with repeated pure subtrees computed once and impure ones every time.
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Span;
+use interpreter::Value;
+
+mod common;
+use common::parse;
+
+// The outcome of running `source`, and how many remembered values it reused.
+fn run(source: &str, memoize: bool) -> ((Result<Value, EvalError>, Option<Span>), usize) {
+    let mut interpreter = Interpreter::new().with_memoization(memoize);
+    let result = interpreter.run(parse(source));
+    ((result, interpreter.error_span()), interpreter.memo_hits())
+}
+
+const CORPUS: &[&str] = &[
+    "let a = 3; let b = 4; sqrt(a * a + b * b) + sqrt(a * a + b * b) * 2",
+    "let a = 3; let x = sqrt(a * a + 1); a = 5; x + sqrt(a * a + 1)",
+    "let t = 0; for i in 0..50 { t += sqrt(i * i + 1) + sqrt(i * i + 1) }; t",
+    "let a = 1; let x = { let a = 2; abs(a * a - 7) }; x + abs(a * a - 7)",
+    "fn h(a, b) { sqrt(a * a + b * b) }; let a = 3; h(3, 4) + h(5, 12) + sqrt(a * a + 16)",
+    "let f = |x| sqrt(x * x + 1); f(1) + f(2) + f(1)",
+    "let s = sqrt(2 * x + 5); s",
+    "let x = 2; let s = sqrt(x * x + 5); let sqrt = |v| v; s + sqrt(x * x + 5)",
+    "let z = -0.0; let first = str(z * 1 * 2); z = 0.0; [first, str(z * 1 * 2)]",
+    "let a = 0; (1 / a + 1) + (1 / a + 1)",
+    "(q * q + 1) + (q * q + 1)",
+    "let m = {\"k\": 2}; map_get(m, \"k\") * 3 + map_get(m, \"k\") * 3",
+    "let xs = [1, 2]; let n = len(xs) * 2 + 1; xs = [1]; [n, len(xs) * 2 + 1]",
+    "let a = 1.5; [a * a - a, a * a - a, (a * a - a) * 2]",
+    "let a = 4; not (a > 3 and a < 9) or not (a > 3 and a < 9)",
+];
+
+#[test]
+fn memoization_does_not_change_any_outcome() {
+    for source in CORPUS {
+        assert_eq!(run(source, true).0, run(source, false).0, "{}", source);
+        assert_eq!(run(source, false).1, 0);
+    }
+}
+
+#[test]
+fn repeated_pure_subtrees_are_computed_once() {
+    let source = "let a = 3; let b = 4; \
+                  sqrt(a * a + b * b) + sqrt(a * a + b * b) + sqrt(a * a + b * b)";
+    assert_eq!(run(source, true), ((Ok(Value::Float(15.0)), None), 2));
+    // Each iteration binds `i` again, so only the second use in it is reused.
+    let (_, hits) = run(CORPUS[2], true);
+    assert_eq!(hits, 50);
+    // Reassigning `a` computes the subtree again.
+    assert_eq!(run(CORPUS[1], true).1, 0);
+}
+
+#[test]
+fn impure_built_ins_are_called_every_time() {
+    for source in [
+        "seed(7); rand() + rand()",
+        "seed(7); (rand() * 10 + 1) - (rand() * 10 + 1)",
+        "seed(7); [rand_int(1, 1000) * 2 + 1, rand_int(1, 1000) * 2 + 1]",
+    ] {
+        let (with, hits) = run(source, true);
+        assert_eq!(with, run(source, false).0, "{}", source);
+        assert_eq!(hits, 0, "{}", source);
+    }
+    let ((difference, _), _) = run("seed(7); (rand() * 10 + 1) - (rand() * 10 + 1)", true);
+    assert_ne!(difference, Ok(Value::Float(0.0)));
+}
@@ -0,0 +1,140 @@
+use std::thread;
+
+use interpreter::error::{EvalError, ParseLimit};
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::{Error, Value};
+
+mod common;
+use common::eval_in;
+
+// A debug build needs far more stack per level than a release build, so the tests give
+// the default limits the same room the binary's thread does.
+fn with_large_stack<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    thread::Builder::new()
+        .stack_size(256 << 20)
+        .spawn(f)
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+fn parse(source: String) -> Result<(), Error> {
+    Parser::new(Lexer::new(source)).parse().map(|_| ())
+}
+
+fn nesting_limit(result: Result<(), Error>) -> Option<usize> {
+    match result {
+        Err(Error::Parse(err)) => match err.limit {
+            Some(ParseLimit::Nesting(limit)) => Some(limit),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parens(depth: usize) -> String {
+    format!("{}1{}", "(".repeat(depth), ")".repeat(depth))
+}
+
+#[test]
+fn deeply_nested_parentheses_are_an_error() {
+    let result = with_large_stack(|| parse(parens(10_000)));
+    assert_eq!(nesting_limit(result.clone()), Some(500));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "expression too deeply nested at line 1, col 501 (the limit is 500 levels)"
+    );
+}
+
+#[test]
+fn the_limit_is_configurable() {
+    let parse_with_limit = |source: String| {
+        Parser::new(Lexer::new(source))
+            .with_max_nesting(10)
+            .parse()
+            .map(|_| ())
+    };
+    assert!(parse_with_limit(parens(5)).is_ok());
+    assert_eq!(nesting_limit(parse_with_limit(parens(20))), Some(10));
+    assert_eq!(
+        nesting_limit(parse_with_limit("- - - - - - - - - - 1".to_string())),
+        Some(10)
+    );
+}
+
+// Chains are parsed in a loop but still build a tree as deep as they are long.
+#[test]
+fn long_operator_chains_count_as_nesting() {
+    let result = with_large_stack(|| parse(format!("1{}", " + 1".repeat(100_000))));
+    assert_eq!(nesting_limit(result), Some(500));
+    let result = with_large_stack(|| parse(format!("f{}", "()".repeat(100_000))));
+    assert_eq!(nesting_limit(result), Some(500));
+    assert!(parse(format!("1{}", " + 1".repeat(100))).is_ok());
+}
+
+// Each f-string expression is parsed by a parser of its own, which starts where the
+// enclosing one has got to rather than at the top.
+#[test]
+fn deeply_nested_f_strings_are_an_error() {
+    let depth = 20_000;
+    let source = format!("{}1{}", "f\"{".repeat(depth), "}\"".repeat(depth));
+    let result = with_large_stack(|| parse(source));
+    assert_eq!(nesting_limit(result), Some(500));
+    assert!(parse(format!("{}1{}", "f\"{".repeat(10), "}\"".repeat(10))).is_ok());
+}
+
+#[test]
+fn f_string_expressions_are_lexed_with_the_interpreters_options() {
+    let mut interpreter = Interpreter::new();
+    interpreter.decimal_comma = true;
+    interpreter.case_insensitive = true;
+    let ast = interpreter
+        .parser("f\"{IF true { 1,5 } ELSE { 2 }}\"".to_string())
+        .parse()
+        .unwrap();
+    assert_eq!(interpreter.run(ast), Ok(Value::Str("1.5".into())));
+}
+
+#[test]
+fn recovery_reports_deep_nesting_once() {
+    let (ast, errors) = with_large_stack(|| {
+        let source = format!("{}; 2", parens(10_000));
+        let (ast, errors) = Parser::new(Lexer::new(source)).parse_recovering();
+        (ast.is_some(), errors)
+    });
+    assert!(ast);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(nesting_limit(Err(errors[0].clone())), Some(500));
+}
+
+#[test]
+fn runaway_recursion_is_an_error() {
+    with_large_stack(|| {
+        let mut interpreter = Interpreter::new();
+        let mut run = |source: &str| eval_in(&mut interpreter, source);
+        assert_eq!(
+            run("fn f(n) { 1 + f(n + 1) }; f(0)"),
+            Err(EvalError::TooDeep(2_000))
+        );
+        // The interpreter is still usable afterwards.
+        assert_eq!(
+            run("fn g(n) { if n == 0 { 0 } else { 1 + g(n - 1) } }; g(100)"),
+            Ok(Value::Integer(100))
+        );
+    });
+}
+
+#[test]
+fn the_evaluation_limit_is_configurable() {
+    let mut interpreter = Interpreter::new().with_optimization(false);
+    interpreter.max_depth = 5;
+    let mut run = |source: &str| {
+        let ast = Parser::new(Lexer::new(source.to_string())).parse().unwrap();
+        interpreter.run(ast)
+    };
+    assert_eq!(run("[[[[[[1]]]]]]"), Err(EvalError::TooDeep(5)));
+    // A chain of operators is evaluated without recursing, so it isn't limited.
+    assert_eq!(run("-(-(-(-(-(-1)))))"), Ok(Value::Integer(1)));
+}
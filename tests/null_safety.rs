@@ -0,0 +1,53 @@
+use interpreter::error::EvalError;
+use interpreter::pretty::pretty_print;
+use interpreter::Value;
+
+mod common;
+use common::{eval, parse};
+
+#[test]
+fn coalesce_replaces_null() {
+    assert_eq!(eval("null ?? 5 == 5"), Ok(Value::Bool(true)));
+    assert_eq!(eval("null ?? null ?? 3"), Ok(Value::Integer(3)));
+    assert_eq!(eval("0 ?? 5"), Ok(Value::Integer(0)));
+}
+
+// The right side is only evaluated when it is needed.
+#[test]
+fn coalesce_short_circuits() {
+    assert_eq!(eval("1 ?? undefined_name"), Ok(Value::Integer(1)));
+    assert!(matches!(
+        eval("null ?? undefined_name"),
+        Err(EvalError::UndefinedVariable(_))
+    ));
+}
+
+#[test]
+fn coalesce_binds_more_loosely_than_or() {
+    assert_eq!(eval("null ?? false or true"), Ok(Value::Bool(true)));
+    assert_eq!(eval("let x = null ?? 2; x"), Ok(Value::Integer(2)));
+}
+
+#[test]
+fn optional_chaining_stops_at_null() {
+    assert_eq!(eval("null?.x == null"), Ok(Value::Bool(true)));
+    assert_eq!(eval("let p = null; p?.x"), Ok(Value::Null));
+}
+
+#[test]
+fn optional_chaining_reads_fields() {
+    let source = "struct Point { x }; Point { x: 1 }?.x == 1";
+    assert_eq!(eval(source), Ok(Value::Bool(true)));
+    assert!(matches!(
+        eval("struct Point { x }; Point { x: 1 }?.y"),
+        Err(EvalError::NoSuchField(..))
+    ));
+}
+
+#[test]
+fn pretty_printing_round_trips() {
+    for source in ["(a ?? b) or c", "a ?? b or c", "p?.x ?? null"] {
+        let ast = parse(source);
+        assert_eq!(pretty_print(&ast), source);
+    }
+}
@@ -0,0 +1,129 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+#[test]
+fn integers_are_written_in_each_base() {
+    for (source, expected) in [
+        ("to_hex(255)", "ff"),
+        ("to_hex(255, true)", "FF"),
+        ("to_hex(255, false)", "ff"),
+        ("to_hex(0)", "0"),
+        ("to_bin(10)", "1010"),
+        ("to_bin(0)", "0"),
+        ("to_oct(8)", "10"),
+        ("to_hex(9223372036854775807)", "7fffffffffffffff"),
+    ] {
+        assert_eq!(eval(source), Ok(string(expected)), "{}", source);
+    }
+}
+
+#[test]
+fn negative_integers_keep_their_sign() {
+    assert_eq!(eval("to_hex(-255)"), Ok(string("-ff")));
+    assert_eq!(eval("to_bin(-2)"), Ok(string("-10")));
+    assert_eq!(
+        eval("to_hex(-9223372036854775807 - 1, true)"),
+        Ok(string("-8000000000000000"))
+    );
+    assert_eq!(eval("from_base(to_oct(-61), 8)"), Ok(Value::Integer(-61)));
+}
+
+#[test]
+fn from_base_reads_digits_in_any_base() {
+    for (source, expected) in [
+        (r#"from_base("ff", 16)"#, 255),
+        (r#"from_base("FF", 16)"#, 255),
+        (r#"from_base("1010", 2)"#, 10),
+        (r#"from_base("-zz", 36)"#, -1295),
+        (r#"from_base("+17", 8)"#, 15),
+        (r#"from_base("-8000000000000000", 16)"#, i64::MIN),
+    ] {
+        assert_eq!(eval(source), Ok(Value::Integer(expected)), "{}", source);
+    }
+}
+
+#[test]
+fn invalid_digits_are_a_parse_error() {
+    for (source, message) in [
+        (r#"from_base("12", 2)"#, r#""12" is not a number in base 2"#),
+        (
+            r#"from_base("0xff", 16)"#,
+            r#""0xff" is not a number in base 16"#,
+        ),
+        (
+            r#"from_base(" 1", 10)"#,
+            r#"" 1" is not a number in base 10"#,
+        ),
+        (r#"from_base("", 10)"#, r#""" is not a number in base 10"#),
+    ] {
+        assert_eq!(
+            eval(source),
+            Err(EvalError::ParseError(message.to_string())),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        eval(r#"try { from_base("g", 16) } catch e { e.kind }"#),
+        Ok(string("ParseError"))
+    );
+    assert_eq!(
+        eval(r#"from_base("2", 2)"#).unwrap_err().to_string(),
+        r#"cannot parse: "2" is not a number in base 2"#
+    );
+}
+
+#[test]
+fn arguments_are_checked() {
+    assert_eq!(
+        eval("to_hex(2.5)"),
+        Err(EvalError::TypeMismatch(
+            "to_hex() expects an integer, found the float 2.5".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("to_oct(8.0)"),
+        Err(EvalError::TypeMismatch(
+            "to_oct() expects an integer, found the float 8.0".to_string()
+        ))
+    );
+    assert_eq!(
+        eval(r#"to_bin("1")"#),
+        Err(EvalError::TypeMismatch(
+            "to_bin() expects an integer, found string".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("from_base(255, 16)"),
+        Err(EvalError::TypeMismatch(
+            "from_base() expects a string and an integer, found number and number".to_string()
+        ))
+    );
+    assert_eq!(
+        eval(r#"from_base("1", 37)"#),
+        Err(EvalError::InvalidArgument(
+            "from_base() base must be from 2 to 36, found 37".to_string()
+        ))
+    );
+    assert_eq!(
+        eval(r#"from_base("ffffffffffffffffff", 16)"#),
+        Err(EvalError::Overflow(
+            r#"from_base("ffffffffffffffffff", 16)"#.to_string()
+        ))
+    );
+    assert_eq!(
+        eval("to_hex(1, 1)"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to to_hex".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("to_oct()"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to to_oct".to_string()
+        ))
+    );
+}
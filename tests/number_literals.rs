@@ -0,0 +1,103 @@
+use interpreter::error::{Error, LexErrorKind};
+use interpreter::lexer::{Lexer, Token};
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn tokens(source: &str) -> Vec<Token> {
+    Lexer::new(source.to_string())
+        .get_tokens()
+        .unwrap()
+        .into_iter()
+        .map(|token| token.token)
+        .collect()
+}
+
+fn lex_error(source: &str) -> LexErrorKind {
+    Lexer::new(source.to_string())
+        .get_tokens()
+        .unwrap_err()
+        .kind
+}
+
+#[test]
+fn a_point_may_start_or_end_a_number() {
+    assert_eq!(tokens(".5"), [Token::Number(0.5), Token::EOF]);
+    assert_eq!(tokens("5."), [Token::Number(5.0), Token::EOF]);
+    assert_eq!(
+        tokens("5.+.25"),
+        [
+            Token::Number(5.0),
+            Token::Plus,
+            Token::Number(0.25),
+            Token::EOF
+        ]
+    );
+    assert_eq!(eval(".5 + 5."), Ok(Value::Float(5.5)));
+}
+
+// Two points in a row are a range, not a malformed number.
+#[test]
+fn a_range_is_not_a_second_point() {
+    assert_eq!(
+        tokens("1..2"),
+        [
+            Token::Integer(1),
+            Token::DotDot,
+            Token::Integer(2),
+            Token::EOF
+        ]
+    );
+}
+
+// The error holds the whole malformed literal, so nothing after it is mistaken for the
+// start of the next token.
+#[test]
+fn malformed_numbers_are_errors() {
+    let cases = [
+        ("1.2.3", LexErrorKind::InvalidNumber("1.2.3".to_string())),
+        (
+            "1.2.3.4 + 1",
+            LexErrorKind::InvalidNumber("1.2.3.4".to_string()),
+        ),
+        ("0.5.", LexErrorKind::InvalidNumber("0.5.".to_string())),
+        ("1._5", LexErrorKind::InvalidNumber("1._5".to_string())),
+        ("1_", LexErrorKind::InvalidNumber("1_".to_string())),
+        ("1e", LexErrorKind::InvalidLiteral("1e".to_string())),
+        ("1.e3", LexErrorKind::InvalidLiteral("1.e3".to_string())),
+        ("5.x", LexErrorKind::InvalidLiteral("5.x".to_string())),
+    ];
+    for (source, kind) in cases {
+        assert_eq!(lex_error(source), kind, "{:?}", source);
+    }
+}
+
+#[test]
+fn errors_name_the_literal_and_its_column() {
+    let err = Lexer::new("2 * 1.2.3".to_string())
+        .get_tokens()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid number literal `1.2.3` at line 1, col 5"
+    );
+}
+
+// A point with no digits is a field access with nothing to access, not a number.
+#[test]
+fn a_lone_point_is_a_parse_error() {
+    assert_eq!(tokens("."), [Token::Dot, Token::EOF]);
+    assert!(matches!(interpreter::evaluate("."), Err(Error::Parse(_))));
+    assert!(matches!(
+        interpreter::evaluate("1 + ."),
+        Err(Error::Parse(_))
+    ));
+}
+
+#[test]
+fn lexing_stops_at_the_first_error() {
+    let mut lexer = Lexer::new("1.2.3 + 4".to_string());
+    assert!(matches!(lexer.next(), Some(Err(_))));
+    assert!(lexer.next().is_none());
+}
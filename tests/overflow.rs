@@ -0,0 +1,111 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::{Interpreter, OverflowMode};
+use interpreter::Value;
+
+mod common;
+use common::{eval, eval_in};
+
+const MAX: &str = "9223372036854775807";
+const MIN: &str = "(-9223372036854775807 - 1)";
+
+fn overflow(expr: &str) -> Result<Value, EvalError> {
+    Err(EvalError::Overflow(expr.to_string()))
+}
+
+fn promoted(source: &str) -> Result<Value, EvalError> {
+    let mut interpreter = Interpreter::new();
+    interpreter.overflow = OverflowMode::Promote;
+    eval_in(&mut interpreter, source)
+}
+
+#[test]
+fn addition_and_subtraction_overflow() {
+    assert_eq!(
+        eval(&format!("{} + 1", MAX)),
+        overflow("9223372036854775807 + 1")
+    );
+    assert_eq!(
+        eval(&format!("{} - 1", MIN)),
+        overflow("-9223372036854775808 - 1")
+    );
+    assert_eq!(eval(&format!("{} + 0", MAX)), Ok(Value::Integer(i64::MAX)));
+}
+
+#[test]
+fn multiplication_overflows() {
+    assert_eq!(
+        eval("9000000000000000000 * 10"),
+        overflow("9000000000000000000 * 10")
+    );
+    assert_eq!(
+        eval("4294967296 * 4294967296"),
+        overflow("4294967296 * 4294967296")
+    );
+    assert_eq!(
+        eval("3037000499 * 3037000499"),
+        Ok(Value::Integer(9_223_372_030_926_249_001))
+    );
+}
+
+#[test]
+fn negating_the_smallest_integer_overflows() {
+    assert_eq!(
+        eval(&format!("-{}", MIN)),
+        overflow("-(-9223372036854775808)")
+    );
+    assert_eq!(eval(&format!("-{}", MAX)), Ok(Value::Integer(-i64::MAX)));
+}
+
+#[test]
+fn exponentiation_overflows() {
+    assert_eq!(eval("2 ^ 100"), overflow("2 ^ 100"));
+    assert_eq!(eval("2 ^ 62"), Ok(Value::Integer(1 << 62)));
+    assert_eq!(eval("2 ^ 63"), overflow("2 ^ 63"));
+}
+
+// An exponent too large for `checked_pow` still has a result for these bases.
+#[test]
+fn zero_one_and_minus_one_take_any_exponent() {
+    for (source, expected) in [
+        ("0 ^ 5000000000", 0),
+        ("1 ^ 5000000000", 1),
+        ("(-1) ^ 5000000000", 1),
+        ("(-1) ^ 5000000001", -1),
+        ("0 ^ 0", 1),
+        ("1 ^ 0", 1),
+    ] {
+        assert_eq!(eval(source), Ok(Value::Integer(expected)), "{}", source);
+    }
+    assert_eq!(eval("2 ^ 5000000000"), overflow("2 ^ 5000000000"));
+}
+
+#[test]
+fn the_remainder_by_minus_one_is_zero() {
+    assert_eq!(eval(&format!("{} % -1", MIN)), Ok(Value::Integer(0)));
+    assert_eq!(eval(&format!("{} % -1", MAX)), Ok(Value::Integer(0)));
+    assert_eq!(eval("-7 % -1"), Ok(Value::Integer(0)));
+}
+
+#[test]
+fn the_message_names_the_operation() {
+    assert_eq!(
+        eval("2 ^ 100").unwrap_err().to_string(),
+        "integer overflow in `2 ^ 100`"
+    );
+}
+
+#[test]
+fn promote_mode_gives_floats_with_the_right_values() {
+    assert_eq!(promoted("2 ^ 100"), Ok(Value::Float(2f64.powi(100))));
+    assert_eq!(promoted("9000000000000000000 * 10"), Ok(Value::Float(9e19)));
+    assert_eq!(
+        promoted(&format!("{} + 1", MAX)),
+        Ok(Value::Float(9_223_372_036_854_775_808.0))
+    );
+    assert_eq!(
+        promoted(&format!("-{}", MIN)),
+        Ok(Value::Float(9_223_372_036_854_775_808.0))
+    );
+    // Operations that fit stay integers.
+    assert_eq!(promoted("2 ^ 10"), Ok(Value::Integer(1024)));
+}
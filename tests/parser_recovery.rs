@@ -0,0 +1,57 @@
+use interpreter::ast::NodeKind;
+use interpreter::lexer::{Lexer, Token};
+use interpreter::parser::Parser;
+use interpreter::Error;
+
+fn parse_recovering(source: &str) -> (Option<NodeKind>, Vec<(String, Token, usize)>) {
+    let (ast, errors) = Parser::new(Lexer::new(source.to_string())).parse_recovering();
+    let errors = errors
+        .into_iter()
+        .map(|err| match err {
+            Error::Parse(err) => (err.expected.clone(), err.found.clone(), err.offset()),
+            other => panic!("expected a parse error, got {:?}", other),
+        })
+        .collect();
+    (ast.map(|ast| ast.into_kind()), errors)
+}
+
+#[test]
+fn reports_every_syntax_error() {
+    let (ast, errors) = parse_recovering("let = 1; 2 * ; print(1 2); 4");
+    assert_eq!(
+        errors,
+        vec![
+            ("an identifier after `let`".to_string(), Token::Assign, 4),
+            ("an expression".to_string(), Token::Semicolon, 13),
+            ("`)`".to_string(), Token::Integer(2), 23),
+        ]
+    );
+    // Only the statement after the last error survives.
+    assert!(matches!(ast, Some(NodeKind::Integer(4))));
+}
+
+#[test]
+fn recovers_inside_blocks() {
+    let (_, errors) = parse_recovering("fn f() { 1 + ; 2 * }; f(");
+    assert_eq!(
+        errors,
+        vec![
+            ("an expression".to_string(), Token::Semicolon, 13),
+            ("an expression".to_string(), Token::RBrace, 19),
+            ("an expression".to_string(), Token::EOF, 23),
+        ]
+    );
+}
+
+#[test]
+fn reports_a_block_left_open_once() {
+    let (_, errors) = parse_recovering("{ 1 +");
+    assert_eq!(errors, vec![("an expression".to_string(), Token::EOF, 0)]);
+}
+
+#[test]
+fn valid_input_has_no_errors() {
+    let (ast, errors) = parse_recovering("let x = 1; x + 2");
+    assert!(errors.is_empty());
+    assert!(matches!(ast, Some(NodeKind::Sequence(_))));
+}
@@ -0,0 +1,55 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+fn describe(n: i64) -> Result<Value, EvalError> {
+    eval(&format!(
+        r#"match {} {{ 1 => "one", 2 => "two", _ => "other" }}"#,
+        n
+    ))
+}
+
+#[test]
+fn the_first_matching_arm_is_taken() {
+    assert_eq!(describe(1), Ok(string("one")));
+    assert_eq!(describe(2), Ok(string("two")));
+    assert_eq!(describe(3), Ok(string("other")));
+    assert_eq!(
+        eval(r#"match "b" { "a" => 1, "b" => 2, "b" => 3 }"#),
+        Ok(Value::Integer(2))
+    );
+}
+
+#[test]
+fn a_name_binds_the_subject_in_its_arm() {
+    assert_eq!(
+        eval("match 5 { 0 => 0, n => n * 2 }"),
+        Ok(Value::Integer(10))
+    );
+    assert_eq!(
+        eval("let n = 1; match 5 { m => m }; n"),
+        Ok(Value::Integer(1))
+    );
+}
+
+#[test]
+fn only_the_taken_arm_is_evaluated() {
+    assert_eq!(
+        eval("match 1 { 1 => 1, _ => 1 / 0 }"),
+        Ok(Value::Integer(1))
+    );
+}
+
+#[test]
+fn no_matching_arm_is_an_error() {
+    assert_eq!(
+        eval("match 3 { 1 => 1, 2 => 2 }"),
+        Err(EvalError::NonExhaustiveMatch("3".to_string()))
+    );
+    assert_eq!(
+        eval("match 3 { 1 => 1 }").unwrap_err().to_string(),
+        "no match arm matches 3"
+    );
+}
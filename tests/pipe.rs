@@ -0,0 +1,55 @@
+use interpreter::error::EvalError;
+use interpreter::pretty::pretty_print;
+use interpreter::Value;
+
+mod common;
+use common::{eval, parse};
+
+#[test]
+fn pipes_into_builtins() {
+    assert_eq!(eval("[1, 2, 3] |> len"), Ok(Value::Integer(3)));
+    assert_eq!(eval("([1, 2, 3] |> len) == 3"), Ok(Value::Bool(true)));
+    assert_eq!(
+        eval("\"  hello  \" |> trim |> to_upper"),
+        Ok(Value::Str("HELLO".to_string()))
+    );
+}
+
+#[test]
+fn pipes_into_user_functions_and_lambdas() {
+    assert_eq!(
+        eval("fn double(x) { x * 2 }; 3 |> double |> double"),
+        Ok(Value::Integer(12))
+    );
+    assert_eq!(eval("3 |> (|x| x + 1)"), Ok(Value::Integer(4)));
+}
+
+// Everything but assignment binds more tightly, so a pipe's operands need no parentheses.
+#[test]
+fn binds_more_loosely_than_other_operators() {
+    assert_eq!(eval("1 + 2 |> str"), Ok(Value::Str("3".to_string())));
+    assert_eq!(eval("null ?? -4 |> abs"), Ok(Value::Integer(4)));
+    assert_eq!(eval("let n = [1, 2] |> len; n"), Ok(Value::Integer(2)));
+}
+
+#[test]
+fn the_right_side_must_be_a_function() {
+    assert!(matches!(eval("1 |> 2"), Err(EvalError::NotCallable(_))));
+    assert!(matches!(
+        eval("[1, 2, 3] |> len == 3"),
+        Err(EvalError::NotCallable(_))
+    ));
+}
+
+#[test]
+fn pretty_printing_round_trips() {
+    for source in [
+        "x |> f |> g",
+        "x |> (f |> g)",
+        "a ?? b |> f",
+        "x |> (|y| y)",
+    ] {
+        let ast = parse(source);
+        assert_eq!(pretty_print(&ast), source);
+    }
+}
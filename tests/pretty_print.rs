@@ -0,0 +1,87 @@
+use interpreter::ast::ASTNode;
+use interpreter::pretty::pretty_print;
+
+mod common;
+use common::parse;
+
+// Trees are compared by their debug form, which leaves out spans.
+fn shape(node: &ASTNode) -> String {
+    format!("{:?}", node)
+}
+
+// A program for each kind of node, some in awkward spots for the parentheses.
+const CORPUS: &[&str] = &[
+    "1 + 2 * 3",
+    "(1 + 2) * 3",
+    "1 - (2 - 3)",
+    "2 ^ 3 ^ 2",
+    "(2 ^ 3) ^ 2",
+    "-2 ^ 2",
+    "(-2) ^ 2",
+    "not a and b or c",
+    "not (a and b)",
+    "a ?? b ?? c",
+    "x in [1, 2] and y not in (3, 4)",
+    "1..10 by 2",
+    "0..=n",
+    "[1, 2.5, \"s\", true, null]",
+    "{\"a\": 1, \"b\": [2]}",
+    "let (a, b) = (1, 2); a",
+    "const k = 3; k",
+    "let x = 1; x = x + 1; global y",
+    "f(1, g(2))[0].name?.inner",
+    "xs |> len",
+    "|x, y| x + y",
+    "fn add(a, b = 2, ...rest) { a + b }",
+    "if a { 1 } else if b { 2 } else { 3 }",
+    "while i < 3 { i = i + 1 }",
+    "for x in xs { total = total + x }",
+    "match v { 1 => \"one\", n => n, _ => null }",
+    "try { risky() } catch e { e } finally { done() }",
+    "throw \"bad\"",
+    "struct P { x, y }; P { x: 1, y: 2 }.x",
+    "if (P { x: 1 }).x { 1 }",
+    "f\"a {b + 1} c\"",
+    "{ let x = 1; { x } }",
+];
+
+#[test]
+fn printed_programs_parse_back_to_the_same_tree() {
+    for source in CORPUS {
+        let ast = parse(source);
+        let printed = pretty_print(&ast);
+        assert_eq!(
+            shape(&parse(&printed)),
+            shape(&ast),
+            "{} printed as {}",
+            source,
+            printed
+        );
+    }
+}
+
+#[test]
+fn printing_is_idempotent() {
+    for source in CORPUS {
+        let printed = pretty_print(&parse(source));
+        assert_eq!(pretty_print(&parse(&printed)), printed, "{}", source);
+    }
+}
+
+#[test]
+fn only_needed_parentheses_are_kept() {
+    assert_eq!(pretty_print(&parse("((1 + 2)) * 3")), "(1 + 2) * 3");
+    assert_eq!(pretty_print(&parse("1 + (2 * 3)")), "1 + 2 * 3");
+    assert_eq!(pretty_print(&parse("(1 - 2) - 3")), "1 - 2 - 3");
+}
+
+#[test]
+fn blocks_indent_four_spaces_a_level() {
+    let printed = pretty_print(&parse(
+        "fn f(x) { if x { 1 } else { while x { x = x - 1 } } }",
+    ));
+    assert_eq!(
+        printed,
+        "fn f(x) {\n    if x {\n        1\n    } else {\n        while x {\n            x = x - 1\n        }\n    }\n}"
+    );
+}
@@ -0,0 +1,53 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::Value;
+
+mod common;
+use common::eval_in;
+
+// Runs `source` with `configure` applied to a testable interpreter, returning the result
+// and what was printed.
+fn run_with(
+    configure: impl FnOnce(Interpreter) -> Interpreter,
+    source: &str,
+) -> (Result<Value, EvalError>, String) {
+    let (interpreter, output) = Interpreter::make_testable("");
+    let mut interpreter = configure(interpreter);
+    let result = eval_in(&mut interpreter, source);
+    let written = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    (result, written)
+}
+
+fn run(source: &str) -> (Result<Value, EvalError>, String) {
+    run_with(|interpreter| interpreter, source)
+}
+
+#[test]
+fn println_ends_the_line_and_print_does_not() {
+    assert_eq!(run("println(1)"), (Ok(Value::Null), "1\n".to_string()));
+    assert_eq!(
+        run("print(\"a\"); print(\"b\"); println()"),
+        (Ok(Value::Null), "ab\n".to_string())
+    );
+}
+
+#[test]
+fn arguments_are_separated_by_spaces_and_strings_are_not_quoted() {
+    let (_, written) = run("println(\"x =\", 2.5, [1, \"two\"], true, null)");
+    assert_eq!(written, "x = 2.5 [1, \"two\"] true null\n");
+}
+
+#[test]
+fn a_loop_prints_each_element() {
+    let (result, written) = run("for x in [1, 2, 3] { println(x) }");
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(written, "1\n2\n3\n");
+}
+
+// Printing is a side effect, so a memoized call still prints every time.
+#[test]
+fn memoized_calls_still_print() {
+    let source = "fn show(n) { println(n) }; show(1); show(1); show(1)";
+    let (_, written) = run_with(|interpreter| interpreter.with_memoization(true), source);
+    assert_eq!(written, "1\n1\n1\n");
+}
@@ -0,0 +1,118 @@
+// Properties of the front end and the bytecode VM over randomly generated arithmetic. Run with
+// `cargo test --test property_tests`.
+use interpreter::ast::ASTNode;
+use interpreter::compiler;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::Lexer;
+use interpreter::optimizer::fold_constants;
+use interpreter::parser::Parser;
+use interpreter::pretty::pretty_print;
+use interpreter::vm::Vm;
+use interpreter::Error;
+use proptest::prelude::*;
+
+fn parse(source: &str) -> Result<ASTNode, Error> {
+    Parser::new(Lexer::new(source.to_string())).parse()
+}
+
+// Trees are compared by their debug form, which leaves out spans: reprinting moves
+// every node, but it should not change what any of them is.
+fn shape(node: &ASTNode) -> String {
+    format!("{:?}", node)
+}
+
+fn operator() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec!["+", "-", "*", "/", "%", "^"])
+}
+
+prop_compose! {
+    fn number()(whole in 0u32..1000, fraction in prop::option::of(0u32..100)) -> String {
+        match fraction {
+            Some(fraction) => format!("{}.{}", whole, fraction),
+            None => whole.to_string(),
+        }
+    }
+}
+
+prop_compose! {
+    fn binary(operand: BoxedStrategy<String>)(
+        left in operand.clone(),
+        op in operator(),
+        right in operand,
+    ) -> String {
+        format!("{} {} {}", left, op, right)
+    }
+}
+
+// A well-formed expression: numbers joined by operators, optionally negated or
+// parenthesised.
+fn expression() -> impl Strategy<Value = String> {
+    number().prop_recursive(4, 32, 2, |inner| {
+        prop_oneof![
+            binary(inner.clone().boxed()),
+            inner.clone().prop_map(|expr| format!("({})", expr)),
+            inner.prop_map(|expr| format!("-{}", expr)),
+        ]
+    })
+}
+
+// The same, with a variable `x` among the operands.
+fn formula() -> impl Strategy<Value = String> {
+    prop_oneof![number(), Just("x".to_string())].prop_recursive(4, 32, 2, |inner| {
+        prop_oneof![
+            binary(inner.clone().boxed()),
+            inner.clone().prop_map(|expr| format!("({})", expr)),
+            inner.prop_map(|expr| format!("-{}", expr)),
+        ]
+    })
+}
+
+proptest! {
+    #[test]
+    fn well_formed_expressions_parse(source in expression()) {
+        prop_assert!(parse(&source).is_ok(), "failed to parse {:?}", source);
+    }
+
+    // Any jumble of the same characters gives a tree or an error, never a panic.
+    #[test]
+    fn arbitrary_input_is_parsed_or_rejected(source in "[0-9+*/%^().\\- ]{0,40}") {
+        if let Err(err) = parse(&source) {
+            prop_assert!(matches!(err, Error::Lex(_) | Error::Parse(_)), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn pretty_printing_round_trips(source in expression()) {
+        let ast = parse(&source).unwrap();
+        let printed = pretty_print(&ast);
+        let reparsed = parse(&printed);
+        prop_assert!(reparsed.is_ok(), "failed to parse {:?} printed from {:?}", printed, source);
+        prop_assert_eq!(shape(&reparsed.unwrap()), shape(&ast));
+    }
+
+    // Values are compared as text so that a NaN matches itself.
+    #[test]
+    fn folding_preserves_results(source in expression()) {
+        let ast = parse(&source).unwrap();
+        let folded = fold_constants(parse(&source).unwrap());
+        let expected = Interpreter::new().interpret(&ast).map(|value| value.to_string());
+        let actual = Interpreter::new().interpret(&folded).map(|value| value.to_string());
+        prop_assert_eq!(
+            actual.map_err(|err| err.to_string()),
+            expected.map_err(|err| err.to_string()),
+        );
+    }
+
+    // The VM borrows the interpreter's operators, so values and errors should agree exactly.
+    #[test]
+    fn compiled_code_matches_the_interpreter(source in formula(), x in number()) {
+        let ast = parse(&format!("let x = {}; {}", x, source)).unwrap();
+        let expected = Interpreter::new().interpret(&ast).map(|value| value.to_string());
+        let code = compiler::compile(&ast).unwrap();
+        let actual = Vm::new().run(&code).map(|value| value.to_string());
+        prop_assert_eq!(
+            actual.map_err(|err| err.to_string()),
+            expected.map_err(|err| err.to_string()),
+        );
+    }
+}
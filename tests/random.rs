@@ -0,0 +1,74 @@
+#![cfg(feature = "rand")]
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn draws(source: &str) -> Vec<Value> {
+    match eval(&format!("map(|i| {}, 0..20)", source)) {
+        Ok(Value::Array(values)) => values,
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn a_seed_gives_the_same_sequence_each_time() {
+    let sequence = "seed(42); [rand_int(0, 9), rand_int(0, 9), rand_int(0, 9), rand()]";
+    assert_eq!(eval(sequence), eval(sequence));
+    // Seeding again in the same program starts the sequence over.
+    assert_eq!(
+        eval("seed(42); let a = [rand_int(0, 9), rand()]; seed(42); a == [rand_int(0, 9), rand()]"),
+        Ok(Value::Bool(true))
+    );
+    assert_ne!(
+        eval("seed(1); [rand(), rand()]"),
+        eval("seed(2); [rand(), rand()]")
+    );
+}
+
+#[test]
+fn rand_is_a_float_in_the_unit_interval() {
+    for value in draws("rand()") {
+        let Value::Float(x) = value else {
+            panic!("{:?}", value);
+        };
+        assert!((0.0..1.0).contains(&x), "{}", x);
+    }
+}
+
+#[test]
+fn rand_int_includes_both_ends() {
+    for value in draws("rand_int(-2, 2)") {
+        let Value::Integer(n) = value else {
+            panic!("{:?}", value);
+        };
+        assert!((-2..=2).contains(&n), "{}", n);
+    }
+    assert_eq!(eval("rand_int(7, 7)"), Ok(Value::Integer(7)));
+    // The whole range of integers is one range too.
+    assert!(matches!(
+        eval("rand_int(int(\"-9223372036854775807\") - 1, 9223372036854775807)"),
+        Ok(Value::Integer(_))
+    ));
+}
+
+#[test]
+fn an_empty_range_is_an_error() {
+    assert!(matches!(
+        eval("rand_int(3, 1)"),
+        Err(EvalError::InvalidRange(_))
+    ));
+    assert_eq!(
+        eval("rand_int(1.5, 2)"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to rand_int".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("seed(\"x\")"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to seed".to_string()
+        ))
+    );
+}
@@ -0,0 +1,124 @@
+use interpreter::error::EvalError;
+use interpreter::lexer::{Lexer, Token};
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn integers(values: &[i64]) -> Result<Value, EvalError> {
+    Ok(Value::Array(
+        values.iter().copied().map(Value::Integer).collect(),
+    ))
+}
+
+#[test]
+fn counts_up_to_the_stop_value() {
+    assert_eq!(eval("range(0, 5)"), integers(&[0, 1, 2, 3, 4]));
+    assert_eq!(
+        eval("range(0, 5) == [0, 1, 2, 3, 4]"),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(eval("range(0, 10, 2)"), integers(&[0, 2, 4, 6, 8]));
+    assert_eq!(eval("range(0, 9, 3)"), integers(&[0, 3, 6]));
+}
+
+#[test]
+fn counts_down_with_a_negative_step() {
+    assert_eq!(eval("range(5, 0, -1)"), integers(&[5, 4, 3, 2, 1]));
+    assert_eq!(eval("len(range(10, 0, -1))"), Ok(Value::Integer(10)));
+}
+
+// As with `..`, a step pointing away from the stop value gives nothing.
+#[test]
+fn empty_ranges() {
+    assert_eq!(eval("range(0, 0)"), integers(&[]));
+    assert_eq!(eval("range(5, 0)"), integers(&[]));
+    assert_eq!(eval("range(0, 5, -1)"), integers(&[]));
+}
+
+#[test]
+fn arguments_must_be_integers() {
+    for source in [
+        "range(0, 1.5)",
+        "range(0.0, 2)",
+        "range(0, 5, 0.5)",
+        "range(\"a\", 2)",
+    ] {
+        assert!(
+            matches!(eval(source), Err(EvalError::TypeMismatch(_))),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        eval("range(1)"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to range".to_string()
+        ))
+    );
+}
+
+#[test]
+fn a_zero_step_is_division_by_zero() {
+    assert_eq!(
+        eval("range(0, 10, 0)"),
+        Err(EvalError::DivisionByZero("range(0, 10, 0)".to_string()))
+    );
+}
+
+#[test]
+fn works_with_the_list_builtins() {
+    assert_eq!(eval("sum(range(1, 101))"), Ok(Value::Integer(5050)));
+    assert_eq!(eval("map(|x| x * x, range(1, 4))"), integers(&[1, 4, 9]));
+    assert_eq!(eval("range(0, 3) |> len"), Ok(Value::Integer(3)));
+}
+
+#[test]
+fn range_expressions_stop_before_the_end() {
+    assert_eq!(eval("1..5"), integers(&[1, 2, 3, 4]));
+    assert_eq!(eval("1..=5"), integers(&[1, 2, 3, 4, 5]));
+    assert_eq!(eval("0..10 by 3"), integers(&[0, 3, 6, 9]));
+    assert_eq!(eval("0..=9 by 3"), integers(&[0, 3, 6, 9]));
+    assert_eq!(eval("(1..4)[1]"), Ok(Value::Integer(2)));
+    assert_eq!(eval("sum(1..=100)"), Ok(Value::Integer(5050)));
+}
+
+// `10..1` counts up from 10 and so has nothing in it; counting down takes `by -1`.
+#[test]
+fn reversed_ranges_need_a_negative_step() {
+    assert_eq!(eval("10..1"), integers(&[]));
+    assert_eq!(eval("10..1 by -1"), integers(&[10, 9, 8, 7, 6, 5, 4, 3, 2]));
+    assert_eq!(eval("10..=0 by -5"), integers(&[10, 5, 0]));
+    assert_eq!(eval("0..5 by -1"), integers(&[]));
+    assert_eq!(
+        eval("0..5 by 0"),
+        Err(EvalError::InvalidRange("step must not be zero".to_string()))
+    );
+}
+
+#[test]
+fn a_range_is_not_a_float() {
+    let tokens: Vec<Token> = Lexer::new("1..2".to_string())
+        .map(|token| token.unwrap().token)
+        .collect();
+    assert_eq!(
+        tokens,
+        [
+            Token::Integer(1),
+            Token::DotDot,
+            Token::Integer(2),
+            Token::EOF
+        ]
+    );
+    assert_eq!(eval("1..2"), integers(&[1]));
+}
+
+#[test]
+fn enormous_ranges_are_errors() {
+    let too_long = Err(EvalError::InvalidRange(
+        "range has more than 10000000 elements".to_string(),
+    ));
+    assert_eq!(eval("0..1000000000"), too_long);
+    assert_eq!(eval("0.0..1000000000.0"), too_long);
+    assert_eq!(eval("len(0..10000000)"), Ok(Value::Integer(10_000_000)));
+}
@@ -0,0 +1,60 @@
+use interpreter::error::LexErrorKind;
+use interpreter::lexer::{Lexer, Token};
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+fn first_token(source: &str) -> Token {
+    Lexer::new(source.to_string())
+        .next()
+        .unwrap()
+        .unwrap()
+        .token
+}
+
+#[test]
+fn newlines_are_kept() {
+    assert_eq!(
+        first_token("\"\"\"hello\nworld\"\"\""),
+        Token::RawStr("hello\nworld".to_string())
+    );
+    assert_eq!(
+        eval("len(\"\"\"hello\nworld\"\"\")"),
+        Ok(Value::Integer(11))
+    );
+}
+
+#[test]
+fn escapes_are_not_processed() {
+    assert_eq!(
+        first_token(r#""""hello\nworld""""#),
+        Token::RawStr(r"hello\nworld".to_string())
+    );
+    assert_eq!(eval(r#"len("""a\nb""")"#), Ok(Value::Integer(4)));
+    assert_eq!(eval(r#""""say "hi" now""""#), Ok(string(r#"say "hi" now"#)));
+}
+
+#[test]
+fn unterminated_raw_strings_are_errors() {
+    for source in [r#""""abc"#, r#""""abc"""#, r#""""abc"""#, "\"\"\"\n"] {
+        let err = Lexer::new(source.to_string())
+            .find_map(Result::err)
+            .unwrap_or_else(|| panic!("{:?} lexed", source));
+        assert_eq!(err.kind, LexErrorKind::UnterminatedString, "{:?}", source);
+    }
+}
+
+#[test]
+fn dedent_strips_the_shared_indentation() {
+    let source = "dedent(\"\"\"\n    if x {\n        y\n    }\n\"\"\")";
+    assert_eq!(eval(source), Ok(string("\nif x {\n    y\n}\n")));
+    assert_eq!(eval(r#"dedent("a\n  b")"#), Ok(string("a\n  b")));
+}
+
+#[test]
+fn dedent_keeps_the_trailing_newline() {
+    assert_eq!(eval(r#"dedent("  a\n    b\n")"#), Ok(string("a\n  b\n")));
+    assert_eq!(eval(r#"dedent("  a\n")"#), Ok(string("a\n")));
+    assert_eq!(eval(r#"dedent("\n")"#), Ok(string("\n")));
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "regex")]
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+fn strings(values: &[&str]) -> Value {
+    Value::Array(values.iter().map(|s| string(s)).collect())
+}
+
+#[test]
+fn regex_match_tests_for_a_match_anywhere() {
+    assert_eq!(
+        eval(r#"regex_match("\\d+", "abc 123")"#),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        eval(r#"regex_match("\\d+", "abc")"#),
+        Ok(Value::Bool(false))
+    );
+    assert_eq!(
+        eval(r#"regex_match("^[a-z]+$", "abc")"#),
+        Ok(Value::Bool(true))
+    );
+    assert_eq!(
+        eval(r#"regex_match("^[a-z]+$", "abc1")"#),
+        Ok(Value::Bool(false))
+    );
+}
+
+#[test]
+fn regex_find_gives_the_first_match_or_null() {
+    assert_eq!(
+        eval(r#"regex_find("\\d+", "a 12 b 345")"#),
+        Ok(string("12"))
+    );
+    assert_eq!(
+        eval(r#"regex_find("[a-z]+", "12 ab cd")"#),
+        Ok(string("ab"))
+    );
+    assert_eq!(eval(r#"regex_find("\\d+", "none")"#), Ok(Value::Null));
+}
+
+#[test]
+fn regex_find_all_gives_every_match() {
+    assert_eq!(
+        eval(r#"regex_find_all("\\d+", "a 12 b 345 c 6")"#),
+        Ok(strings(&["12", "345", "6"]))
+    );
+    assert_eq!(
+        eval(r#"regex_find_all("[a-z]+", "Hello, wörld and more")"#),
+        Ok(strings(&["ello", "w", "rld", "and", "more"]))
+    );
+    assert_eq!(eval(r#"regex_find_all("\\d+", "none")"#), Ok(strings(&[])));
+}
+
+#[test]
+fn regex_replace_changes_the_first_match() {
+    assert_eq!(
+        eval(r#"regex_replace("\\d+", "a 12 b 345", "N")"#),
+        Ok(string("a N b 345"))
+    );
+    assert_eq!(
+        eval(r#"regex_replace("([a-z]+)@([a-z]+)", "to: ada@home", "$2 at $1")"#),
+        Ok(string("to: home at ada"))
+    );
+    assert_eq!(
+        eval(r#"regex_replace("\\d+", "none", "N")"#),
+        Ok(string("none"))
+    );
+}
+
+#[test]
+fn patterns_compile_once_per_interpreter() {
+    assert_eq!(
+        eval(r#"let n = 0; for i in 0..500 { if regex_match("^\\d+$", str(i)) { n += 1 } }; n"#),
+        Ok(Value::Integer(500))
+    );
+    // More patterns than the cache holds still give the right answers.
+    assert_eq!(
+        eval(r#"let n = 0; for i in 0..200 { n += len(regex_find_all(f"{i}x", "1x 10x")) }; n"#),
+        Ok(Value::Integer(3))
+    );
+}
+
+#[test]
+fn invalid_patterns_are_regex_errors() {
+    let Err(EvalError::RegexError(message)) = eval(r#"regex_match("[a-z", "abc")"#) else {
+        panic!("an unclosed class compiled");
+    };
+    assert!(message.contains("unclosed character class"), "{}", message);
+    assert_eq!(
+        eval(r#"try { regex_find("(", "x") } catch e { e.kind }"#),
+        Ok(string("RegexError"))
+    );
+    assert_eq!(
+        eval(r#"regex_match(1, "abc")"#),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to regex_match".to_string()
+        ))
+    );
+}
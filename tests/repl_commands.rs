@@ -0,0 +1,143 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use interpreter::Repl;
+
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// A session whose output each call to `entry` hands back.
+struct Session {
+    repl: Repl,
+    output: Capture,
+}
+
+impl Session {
+    fn new() -> Self {
+        let output = Capture::default();
+        let repl = Repl::new().with_output(Box::new(output.clone()));
+        Session { repl, output }
+    }
+
+    // The output of `input` and whether the session goes on.
+    fn entry(&mut self, input: &str) -> (String, bool) {
+        let more = self.repl.entry(input);
+        let bytes = std::mem::take(&mut *self.output.0.borrow_mut());
+        (String::from_utf8(bytes).unwrap(), more)
+    }
+
+    fn output(&mut self, input: &str) -> String {
+        self.entry(input).0
+    }
+}
+
+#[test]
+fn help_lists_the_commands() {
+    let help = Session::new().output(".help");
+    for command in [".help", ".vars", ".reset", ".load <file>", ".quit, .exit"] {
+        assert!(help.contains(command), "{} missing from\n{}", command, help);
+    }
+}
+
+#[test]
+fn vars_shows_values_and_types() {
+    let mut session = Session::new();
+    session.output("let b = \"two\"");
+    session.output("let a = 1");
+    assert_eq!(
+        session.output(".vars"),
+        "a = 1 (number)\nb = two (string)\n"
+    );
+}
+
+#[test]
+fn reset_forgets_what_the_session_defined() {
+    let mut session = Session::new();
+    session.output("let x = 1");
+    session.output("fn f() { 2 }");
+    assert_eq!(session.output(".reset"), "Environment cleared\n");
+    assert_eq!(session.output(".vars"), "");
+    assert!(session
+        .output("x")
+        .ends_with("Error: undefined variable: x\n"));
+    assert!(session.output("sqrt(4)").ends_with("Result: 2\n"));
+}
+
+#[test]
+fn nan_results_are_explained() {
+    let mut session = Session::new();
+    assert!(session
+        .output("sqrt(-1)")
+        .ends_with("Result: NaN (not a number)\n"));
+    assert!(session.output("str(sqrt(-1))").ends_with("Result: NaN\n"));
+}
+
+#[test]
+fn load_evaluates_a_file_and_prints_each_result() {
+    let path = std::env::temp_dir().join(format!("repl_load_{}.calc", std::process::id()));
+    fs::write(
+        &path,
+        "let x = 2\nfn double(n) {\n    n * 2\n}\ndouble(x)\n",
+    )
+    .unwrap();
+    let mut session = Session::new();
+    let shown = session.output(&format!(".load {}", path.display()));
+    fs::remove_file(&path).unwrap();
+    assert_eq!(shown.lines().last(), Some("Result: 4"), "{}", shown);
+    assert!(session.output("x").ends_with("Result: 2\n"));
+    assert_eq!(session.output(".load"), "Usage: .load <file>\n");
+    assert!(session
+        .output(".load /no/such/file")
+        .starts_with("Error: cannot read /no/such/file: "));
+}
+
+#[test]
+fn quit_and_exit_end_the_session() {
+    for command in [".quit", ".exit", "exit"] {
+        assert_eq!(
+            Session::new().entry(command),
+            ("Exiting...\n".to_string(), false)
+        );
+    }
+    assert!(Session::new().entry(".vars").1);
+}
+
+#[test]
+fn unknown_commands_say_where_to_look() {
+    assert_eq!(
+        Session::new().entry(".frobnicate"),
+        (
+            "Unknown command: .frobnicate (type .help for a list)\n".to_string(),
+            true
+        )
+    );
+    // A leading dot before a digit is a number, not a command.
+    assert!(Session::new().output(".5 + 1").ends_with("Result: 1.5\n"));
+}
+
+#[test]
+fn disasm_lists_the_bytecode_without_running_it() {
+    let mut session = Session::new();
+    assert_eq!(
+        session.output(".disasm let y = 2"),
+        "0000  LOAD_CONST 2\n0001  DEFINE_VAR \"y\"\n0002  RETURN\n"
+    );
+    assert!(session.output("y").contains("undefined variable"));
+    assert_eq!(session.output(".disasm"), "Usage: .disasm <expr>\n");
+    assert!(session
+        .output(".disasm [1]")
+        .starts_with("Error: the bytecode compiler does not support Array nodes"));
+}
@@ -0,0 +1,99 @@
+// Drives the prompt programmatically for ten thousand entries, counting allocations. The
+// session must keep its variables throughout, and neither the allocations an entry makes
+// nor the memory the session holds may grow with the number of entries before it. This
+// binary has a single test, so nothing else runs while it counts.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use interpreter::Repl;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// The session's output, cleared by the test after each entry so that it does not grow.
+#[derive(Clone, Default)]
+struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+const ENTRIES: usize = 10_000;
+
+#[test]
+fn ten_thousand_entries_keep_state_in_bounded_memory() {
+    let output = Capture::default();
+    let mut repl = Repl::new().with_output(Box::new(output.clone()));
+    assert!(repl.entry("let total = 0"));
+
+    // Allocated up front, so that only the session's own memory changes.
+    let mut live = Vec::with_capacity(ENTRIES / 1_000);
+    let mut allocations = Vec::with_capacity(ENTRIES);
+    for i in 1..=ENTRIES {
+        output.0.borrow_mut().clear();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        assert!(repl.entry("total += 1"));
+        allocations.push(ALLOCATIONS.load(Ordering::Relaxed) - before);
+        let shown = String::from_utf8(output.0.borrow().clone()).unwrap();
+        assert!(shown.ends_with(&format!("Result: {}\n", i)), "{}", shown);
+        if i % 1_000 == 0 {
+            live.push(LIVE_BYTES.load(Ordering::Relaxed));
+        }
+    }
+
+    // Every entry after the first few allocates the same number of times.
+    let steady = allocations[10];
+    assert!(
+        allocations[10..].iter().all(|&n| n == steady),
+        "allocations per entry vary: {:?}",
+        &allocations[10..20]
+    );
+    // The memory held after a thousand entries is what the session holds after ten
+    // thousand, give or take the digit `total` gains: nothing accumulates.
+    assert!(
+        live.iter().all(|&bytes| bytes.abs_diff(live[0]) <= 16),
+        "live bytes grew: {:?}",
+        live
+    );
+
+    output.0.borrow_mut().clear();
+    assert!(repl.entry(".vars"));
+    let vars = String::from_utf8(output.0.borrow().clone()).unwrap();
+    assert_eq!(vars, format!("total = {} (number)\n", ENTRIES));
+    assert!(!repl.entry("exit"));
+}
@@ -0,0 +1,91 @@
+use interpreter::compiler;
+use interpreter::error::{CompileError, EvalError};
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::pretty::pretty_print;
+use interpreter::{Error, Value};
+
+mod common;
+use common::{eval, parse};
+
+fn integers(values: &[i64]) -> Value {
+    Value::Array(values.iter().copied().map(Value::Integer).collect())
+}
+
+#[test]
+fn extra_arguments_are_collected() {
+    assert_eq!(
+        eval("fn sum(...nums) { reduce(|a, b| a + b, 0, nums) }; sum(1, 2, 3, 4)"),
+        Ok(Value::Integer(10))
+    );
+    assert_eq!(
+        eval("fn f(a, b, ...rest) { rest }; f(1, 2, 3, 4)"),
+        Ok(integers(&[3, 4]))
+    );
+    assert_eq!(
+        eval("fn f(a, ...rest) { [a, len(rest)] }; f(1)"),
+        Ok(integers(&[1, 0]))
+    );
+}
+
+#[test]
+fn positional_parameters_are_still_required() {
+    assert_eq!(
+        eval("fn f(a, b, ...rest) { a }; f(1)"),
+        Err(EvalError::ArityMismatch(2, 1))
+    );
+    // Without a rest parameter extra arguments are still an error.
+    assert_eq!(
+        eval("fn f(a) { a }; f(1, 2)"),
+        Err(EvalError::ArityMismatch(1, 2))
+    );
+}
+
+#[test]
+fn lambdas_and_anonymous_functions_take_rest_parameters() {
+    assert_eq!(eval("(|...xs| len(xs))(1, 2, 3)"), Ok(Value::Integer(3)));
+    assert_eq!(
+        eval("let f = fn(first, ...others) { others }; f(1, 2)"),
+        Ok(integers(&[2]))
+    );
+    assert_eq!(
+        eval("[1, 2] |> (|...xs| xs)"),
+        Ok(Value::Array(vec![integers(&[1, 2])]))
+    );
+}
+
+#[test]
+fn the_rest_parameter_must_come_last() {
+    for source in [
+        "fn f(...rest, a) { a }",
+        "fn f(...) { 1 }",
+        "fn f(... rest, ...more) { 1 }",
+        "|...xs, y| y",
+    ] {
+        let result = Parser::new(Lexer::new(source.to_string())).parse();
+        assert!(matches!(result, Err(Error::Parse(_))), "{}", source);
+    }
+}
+
+#[test]
+fn pretty_printing_round_trips() {
+    for source in [
+        "fn f(a, ...rest) {\n    rest\n}",
+        "fn(...rest) {\n    rest\n}",
+        "|x, ...xs| xs",
+    ] {
+        let ast = parse(source);
+        assert_eq!(pretty_print(&ast), source);
+    }
+}
+
+#[test]
+fn the_compiler_does_not_support_rest_parameters() {
+    let ast = Parser::new(Lexer::new("fn f(...xs) { xs }".to_string()))
+        .parse()
+        .unwrap();
+    assert!(matches!(
+        compiler::compile(&ast),
+        Err(CompileError::Unsupported("FnDef"))
+    ));
+}
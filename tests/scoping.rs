@@ -0,0 +1,70 @@
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::Value;
+
+mod common;
+use common::{eval, eval_in};
+
+fn ints(source: &str) -> String {
+    eval(source).unwrap().to_string()
+}
+
+#[test]
+fn assignment_in_a_function_is_local() {
+    assert_eq!(ints("let x = 1; fn f() { x = 2; x }; [f(), x]"), "[2, 1]");
+    assert_eq!(
+        ints("let x = 1; fn f() { let x = 5; x }; [f(), x]"),
+        "[5, 1]"
+    );
+}
+
+#[test]
+fn global_opts_into_the_session_variable() {
+    assert_eq!(
+        ints("let count = 0; fn bump() { global count; count = count + 1 }; bump(); bump(); count"),
+        "2"
+    );
+    assert_eq!(
+        eval("fn f() { global missing; missing = 1 }; f()"),
+        Err(EvalError::UndefinedGlobal("missing".to_string()))
+    );
+    assert_eq!(
+        eval("fn f() { global missing }; f()")
+            .unwrap_err()
+            .to_string(),
+        "`global missing`: no session variable named missing"
+    );
+}
+
+#[test]
+fn helpers_do_not_clobber_session_variables() {
+    let mut interpreter = Interpreter::new();
+    eval_in(&mut interpreter, "let total = 10").unwrap();
+    eval_in(&mut interpreter, "fn helper() { total = 0; total }").unwrap();
+    assert_eq!(eval_in(&mut interpreter, "helper()"), Ok(Value::Integer(0)));
+    assert_eq!(eval_in(&mut interpreter, "total"), Ok(Value::Integer(10)));
+}
+
+#[test]
+fn blocks_see_and_update_the_enclosing_scope() {
+    assert_eq!(ints("let x = 1; { x = 2 }; x"), "2");
+    assert_eq!(ints("let x = 1; { let x = 3; x = 4 }; x"), "1");
+}
+
+#[test]
+fn closures_capture_the_scope_they_are_made_in() {
+    let source = "
+        fn make_adder(n) { |x| x + n };
+        let n = 100;
+        let add2 = make_adder(2);
+        add2(1)
+    ";
+    assert_eq!(ints(source), "3");
+    let source = "
+        let x = 1;
+        let f = fn() { x };
+        x = 2;
+        f()
+    ";
+    assert_eq!(ints(source), "2");
+}
@@ -0,0 +1,56 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+// Runs `condition` with `hit()` counting its calls, and gives the condition's value and
+// how many calls there were.
+fn calls(condition: &str) -> String {
+    let source = format!(
+        "let calls = 0; fn hit(result) {{ global calls; calls = calls + 1; result }}; let x = 0; [{}, calls]",
+        condition
+    );
+    eval(&source).unwrap().to_string()
+}
+
+#[test]
+fn and_skips_the_right_side_when_the_left_is_false() {
+    assert_eq!(calls("false and hit(true)"), "[false, 0]");
+    assert_eq!(calls("true and hit(false)"), "[false, 1]");
+    assert_eq!(calls("hit(false) and hit(true)"), "[false, 1]");
+}
+
+#[test]
+fn or_skips_the_right_side_when_the_left_is_true() {
+    assert_eq!(calls("true or hit(false)"), "[true, 0]");
+    assert_eq!(calls("false or hit(true)"), "[true, 1]");
+    assert_eq!(calls("hit(true) or hit(true) or hit(true)"), "[true, 1]");
+}
+
+#[test]
+fn a_guard_keeps_the_division_from_running() {
+    assert_eq!(calls("x != 0 and hit(1 / x > 2)"), "[false, 0]");
+    assert_eq!(
+        eval("let x = 0; x != 0 and 1 / x > 2"),
+        Ok(Value::Bool(false))
+    );
+    assert_eq!(calls("null ?? hit(1)"), "[1, 1]");
+    assert_eq!(calls("2 ?? hit(1)"), "[2, 0]");
+}
+
+// The operands must be booleans and so is the result; there is no truthiness.
+#[test]
+fn operands_must_be_booleans() {
+    assert_eq!(
+        eval("1 and true"),
+        Err(EvalError::TypeMismatch(
+            "`and` expects booleans, found 1".to_string()
+        ))
+    );
+    assert!(matches!(eval(r#"true or "yes""#), Ok(Value::Bool(true))));
+    assert!(matches!(
+        eval(r#"false or "yes""#),
+        Err(EvalError::TypeMismatch(_))
+    ));
+}
@@ -0,0 +1,169 @@
+// `simplify` pairs of input and pretty-printed output, and evaluation of simplified
+// expressions against the originals with random numbers bound to their variables.
+use interpreter::error::EvalError;
+use interpreter::interpreter::{Interpreter, Value};
+use interpreter::optimizer::{simplify, FloatMath};
+use interpreter::pretty::pretty_print;
+use proptest::prelude::*;
+
+mod common;
+use common::parse;
+
+fn strict(source: &str) -> String {
+    pretty_print(&simplify(parse(source), FloatMath::Strict))
+}
+
+fn fast(source: &str) -> String {
+    pretty_print(&simplify(parse(source), FloatMath::Fast))
+}
+
+#[test]
+fn exact_identities_always_apply() {
+    for (source, expected) in [
+        ("x * 1", "x"),
+        ("1 * x", "x"),
+        ("x - 0", "x"),
+        ("(x - y) * 1 * 1", "x - y"),
+        ("f(x) * 1", "f(x)"),
+        ("x * 1.0", "x * 1.0"),
+        ("(x / y) * 1.0", "x / y"),
+        ("(x / 2) / 1", "x / 2"),
+        ("x / 1", "x / 1"),
+        ("-(-(x * 0.5))", "x * 0.5"),
+        ("-(-x)", "--x"),
+        ("not not (x < y)", "x < y"),
+        ("not not x", "not not x"),
+        ("2 * 3 * x * (4 - 3)", "6 * x"),
+    ] {
+        assert_eq!(strict(source), expected, "{}", source);
+    }
+}
+
+#[test]
+fn the_rest_need_fast_math() {
+    for (source, expected) in [
+        ("x + 0", "x"),
+        ("0.0 + x", "x"),
+        ("x * 0", "0"),
+        ("0 * x", "0"),
+        ("x - x", "0"),
+        ("x - y", "x - y"),
+        ("x / 1", "x"),
+        ("-(-x)", "x"),
+        ("(x + 0) * (y - y) + z * 1", "z"),
+    ] {
+        assert_eq!(fast(source), expected, "{}", source);
+        if source != "x - y" {
+            assert_ne!(strict(source), expected, "{}", source);
+        }
+    }
+}
+
+#[test]
+fn what_might_fail_or_have_an_effect_is_not_dropped() {
+    for source in [
+        "f(x) * 0",
+        "0 * (x / y)",
+        "(x + y) * 0",
+        "f() - f()",
+        "x[0] - x[0]",
+    ] {
+        assert_eq!(fast(source), pretty_print(&parse(source)), "{}", source);
+    }
+}
+
+#[test]
+fn identities_apply_throughout_the_program() {
+    assert_eq!(
+        fast("fn f(x) { let y = x * 1; if y + 0 > 0 { [y - y] } else { -(-y) } }"),
+        "fn f(x) {\n    let y = x;\n    if y > 0 {\n        [0]\n    } else {\n        y\n    }\n}"
+    );
+}
+
+// Source for `value`, which `let` can bind.
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Integer(i64::MIN) => "(-9223372036854775807 - 1)".to_string(),
+        Value::Integer(n) => format!("({})", n),
+        Value::Float(n) if n.is_nan() => "sqrt(-1)".to_string(),
+        Value::Float(n) if n.is_infinite() => {
+            format!("({}(10.0 ^ 400))", if *n < 0.0 { "-" } else { "" })
+        }
+        Value::Float(n) if n.fract() == 0.0 => format!("({:.1})", n),
+        Value::Float(n) => format!("({})", n),
+        other => panic!("no literal for {:?}", other),
+    }
+}
+
+// Runs `expression` with `x` and `y` bound to the given values.
+fn run(expression: &str, x: &Value, y: &Value) -> Result<Value, EvalError> {
+    let source = format!(
+        "let x = {}; let y = {}; {}",
+        literal(x),
+        literal(y),
+        expression
+    );
+    Interpreter::new().run(parse(&source))
+}
+
+fn simplified(source: &str, math: FloatMath) -> String {
+    pretty_print(&simplify(parse(source), math))
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        any::<i64>().prop_map(Value::Integer),
+        (-100i64..100).prop_map(Value::Integer),
+        any::<f64>().prop_map(Value::Float),
+        prop::sample::select(vec![0.0, -0.0, f64::NAN, f64::INFINITY, -f64::INFINITY])
+            .prop_map(Value::Float),
+    ]
+}
+
+const EXPRESSIONS: &[&str] = &[
+    "x * 1 + 1 * y",
+    "(x - 0) * (y * 1)",
+    "(x / y) / 1 - (x * 2.5) * 1.0",
+    "-(-(x / 3)) + -(-(y * 0.5))",
+    "not not (x < y) or not not (x == y)",
+    "x + 0",
+    "x * 0 + 0 * y",
+    "x - x + (y - y)",
+    "-(-x) + y / 1",
+];
+
+proptest! {
+    // Strict simplification changes nothing about the result, down to a NaN or a -0.0.
+    #[test]
+    fn strict_simplification_gives_the_same_value(x in value(), y in value()) {
+        for source in EXPRESSIONS {
+            let simpler = simplified(source, FloatMath::Strict);
+            prop_assert_eq!(
+                format!("{:?}", run(&simpler, &x, &y)),
+                format!("{:?}", run(source, &x, &y)),
+                "{} as {}",
+                source,
+                simpler
+            );
+        }
+    }
+
+    // Fast simplification agrees on every result that is a finite, nonzero number.
+    #[test]
+    fn fast_simplification_agrees_on_ordinary_numbers(
+        x in prop_oneof![(-1000i64..1000).prop_map(Value::Integer), (-1e6..1e6).prop_map(Value::Float)],
+        y in prop_oneof![(1i64..1000).prop_map(Value::Integer), (1e-3..1e6).prop_map(Value::Float)],
+    ) {
+        for source in EXPRESSIONS {
+            let simpler = simplified(source, FloatMath::Fast);
+            let number = |source: &str| match run(source, &x, &y) {
+                Ok(Value::Integer(n)) => Some(n as f64),
+                Ok(Value::Float(n)) => Some(n),
+                _ => None,
+            };
+            if let (Some(a), Some(b)) = (number(source), number(&simpler)) {
+                prop_assert!((a - b).abs() <= 1e-9 * a.abs().max(1.0), "{} as {}: {} and {}", source, simpler, a, b);
+            }
+        }
+    }
+}
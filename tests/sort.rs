@@ -0,0 +1,102 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn ints(values: &[i64]) -> Value {
+    Value::Array(values.iter().copied().map(Value::Integer).collect())
+}
+
+#[test]
+fn sorts_in_natural_order() {
+    assert_eq!(
+        eval("sort([3, 1.5, -2, 10])").unwrap().to_string(),
+        "[-2, 1.5, 3, 10]"
+    );
+    assert_eq!(
+        eval(r#"sort(("b", "B", "a"))"#).unwrap().to_string(),
+        r#"["B", "a", "b"]"#
+    );
+    assert_eq!(eval("sort([])"), Ok(ints(&[])));
+    assert!(matches!(
+        eval(r#"sort([1, "a"])"#),
+        Err(EvalError::TypeMismatch(_))
+    ));
+}
+
+#[test]
+fn sorts_maps_by_a_field_with_a_comparator() {
+    let source = r#"
+        let people = [
+            {"name": "Cy", "age": 41},
+            {"name": "Ada", "age": 36},
+            {"name": "Bo", "age": 41},
+            {"name": "Di", "age": 29},
+        ];
+        let by_age = sort(people, fn(a, b) { map_get(a, "age") - map_get(b, "age") });
+        map(|p| map_get(p, "name"), by_age)
+    "#;
+    // Cy stays before Bo: the sort is stable.
+    assert_eq!(
+        eval(source).unwrap().to_string(),
+        r#"["Di", "Ada", "Cy", "Bo"]"#
+    );
+    assert_eq!(eval("sort([1, 3, 2], |a, b| b - a)"), Ok(ints(&[3, 2, 1])));
+    assert_eq!(
+        eval("sort([1, 3, 2], |a, b| 0.5 * (a - b))"),
+        Ok(ints(&[1, 2, 3]))
+    );
+}
+
+#[test]
+fn the_comparator_must_return_a_number() {
+    assert_eq!(
+        eval("sort([2, 1], |a, b| a < b)"),
+        Err(EvalError::TypeMismatch(
+            "sort() comparator returned true, expected a number".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("sort([2, 1], |a, b| 1 / 0)"),
+        Err(EvalError::DivisionByZero("1 / 0".to_string()))
+    );
+    assert_eq!(
+        eval("sort([2, 1], 3)"),
+        Err(EvalError::NotCallable("3".to_string()))
+    );
+    assert_eq!(
+        eval("sort(1)"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to sort".to_string()
+        ))
+    );
+}
+
+#[test]
+fn an_inconsistent_comparator_does_not_panic() {
+    let source = "let n = 0; sort(range(0, 200), fn(a, b) { n += 1; n % 3 - 1 })";
+    let Ok(Value::Array(items)) = eval(source) else {
+        panic!("sort failed");
+    };
+    assert_eq!(items.len(), 200);
+}
+
+#[test]
+fn a_random_comparator_does_not_panic() {
+    let source = "sort(range(0, 3000), fn(a, b) { rand() - 0.5 })";
+    let Ok(Value::Array(items)) = eval(source) else {
+        panic!("sort failed");
+    };
+    assert_eq!(items.len(), 3000);
+}
+
+#[test]
+fn nan_does_not_panic_the_default_order() {
+    let source = "sort(map(|i| if i % 7 == 0 { sqrt(-1) } else { rand() }, range(0, 3000)))";
+    let Ok(Value::Array(items)) = eval(source) else {
+        panic!("sort failed");
+    };
+    assert_eq!(items.len(), 3000);
+    assert_eq!(eval("sort([3, 1, 2])"), Ok(ints(&[1, 2, 3])));
+}
@@ -0,0 +1,117 @@
+use interpreter::ast::ASTNode;
+use interpreter::error::EvalError;
+use interpreter::interpreter::Interpreter;
+use interpreter::lexer::{Lexer, Span, Token};
+use interpreter::visitor::{walk, AstVisitor};
+
+mod common;
+use common::{eval_in, parse};
+
+// Each node's kind with the source text its span covers, outermost first.
+struct Covered<'a> {
+    source: &'a str,
+    nodes: Vec<(&'static str, &'a str)>,
+}
+
+impl AstVisitor for Covered<'_> {
+    fn visit(&mut self, node: &ASTNode) {
+        let text = &self.source[node.span.start..node.span.end];
+        self.nodes.push((node.kind.name(), text));
+        walk(self, node);
+    }
+}
+
+fn covered(source: &str) -> Vec<(&'static str, &str)> {
+    let mut covered = Covered {
+        source,
+        nodes: Vec::new(),
+    };
+    covered.visit(&parse(source));
+    covered.nodes
+}
+
+#[test]
+fn tokens_span_their_text() {
+    let tokens: Vec<_> = Lexer::new("let x = 10 * y".to_string())
+        .get_tokens()
+        .unwrap()
+        .into_iter()
+        .map(|token| (token.token, token.span))
+        .collect();
+    let span = |start, end| Span { start, end };
+    assert_eq!(
+        tokens,
+        [
+            (Token::Let, span(0, 3)),
+            (Token::Ident("x".to_string()), span(4, 5)),
+            (Token::Assign, span(6, 7)),
+            (Token::Integer(10), span(8, 10)),
+            (Token::Mul, span(11, 12)),
+            (Token::Ident("y".to_string()), span(13, 14)),
+            (Token::EOF, span(14, 14)),
+        ]
+    );
+}
+
+#[test]
+fn nested_nodes_span_their_children() {
+    assert_eq!(
+        covered("(1 + 2) * foo(3, -x)"),
+        [
+            ("BinaryOp", "(1 + 2) * foo(3, -x)"),
+            ("BinaryOp", "(1 + 2)"),
+            ("Integer", "1"),
+            ("Integer", "2"),
+            ("Call", "foo(3, -x)"),
+            ("VarRef", "foo"),
+            ("Integer", "3"),
+            ("UnaryOp", "-x"),
+            ("VarRef", "x"),
+        ]
+    );
+    assert_eq!(
+        covered("let y = [1, \"é\"][0]; if y > 0 { y } else { -y }"),
+        [
+            (
+                "Sequence",
+                "let y = [1, \"é\"][0]; if y > 0 { y } else { -y }"
+            ),
+            ("Let", "let y = [1, \"é\"][0]"),
+            ("Index", "[1, \"é\"][0]"),
+            ("Array", "[1, \"é\"]"),
+            ("Integer", "1"),
+            ("Str", "\"é\""),
+            ("Integer", "0"),
+            ("If", "if y > 0 { y } else { -y }"),
+            ("BinaryOp", "y > 0"),
+            ("VarRef", "y"),
+            ("Integer", "0"),
+            ("Block", "{ y }"),
+            ("VarRef", "y"),
+            ("Block", "{ -y }"),
+            ("UnaryOp", "-y"),
+            ("VarRef", "y"),
+        ]
+    );
+}
+
+#[test]
+fn a_runtime_error_points_at_the_node_that_failed() {
+    let source = "let x = 1;\nx + 1 / (x - x)";
+    let mut interpreter = Interpreter::new();
+    assert!(eval_in(&mut interpreter, source).is_err());
+    let span = interpreter.error_span().unwrap();
+    assert_eq!(&source[span.start..span.end], "1 / (x - x)");
+}
+
+#[test]
+fn an_error_in_parsed_source_points_at_the_parse_call() {
+    let source = r#"let x = 1; x + parse("x / 0")"#;
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        eval_in(&mut interpreter, source),
+        Err(EvalError::DivisionByZero("1 / 0".to_string()))
+    );
+    let span = interpreter.error_span().unwrap();
+    assert_eq!(&source[span.start..span.end], r#"parse("x / 0")"#);
+}
@@ -0,0 +1,119 @@
+// `Lexer::from_reader` against the lexer over the same input held in a string: the same
+// tokens with the same spans and positions, and errors at the same line and column.
+use std::io::{BufReader, Cursor, Read};
+
+use interpreter::error::LexErrorKind;
+use interpreter::lexer::{Lexer, LexerOptions};
+use interpreter::parser::Parser;
+
+mod common;
+use common::parse;
+
+// A few megabytes of script with strings over several lines, raw strings, f-strings and
+// characters of every UTF-8 length.
+fn script() -> String {
+    let mut source = String::new();
+    let mut i = 0;
+    while source.len() < 3 << 20 {
+        source.push_str(&format!(
+            "let x{i} = {i} + 2.5 * √{i}; // note\n\
+             let s{i} = \"héllo\n  wörld 🦀 \\n\";\n\
+             let r{i} = \"\"\"raw \\ text\n  over lines\"\"\";\n\
+             let f{i} = f\"{{x{i} + 1}} and {{\"π\"}}\";\n\
+             if x{i} >= 3 and s{i} != \"\" {{ x{i} ?? null }} else {{ [1, 2][0] }}\n\n",
+        ));
+        i += 1;
+    }
+    source
+}
+
+#[test]
+fn a_reader_gives_the_tokens_a_string_does() {
+    let source = script();
+    let expected = Lexer::new(source.clone()).get_tokens().unwrap();
+    let streamed = Lexer::from_reader(Cursor::new(source.into_bytes()))
+        .get_tokens()
+        .unwrap();
+    assert_eq!(streamed.len(), expected.len());
+    for (i, (streamed, expected)) in streamed.iter().zip(&expected).enumerate() {
+        assert_eq!(streamed, expected, "token {}", i);
+    }
+}
+
+// A reader that hands over a byte at a time, so that the lookahead has to be read in
+// the middle of tokens.
+struct Trickle<R>(R);
+
+impl<R: Read> Read for Trickle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(1);
+        self.0.read(&mut buf[..len])
+    }
+}
+
+#[test]
+fn tokens_do_not_depend_on_how_the_input_is_read() {
+    let source = "let a = 1.5 >= 2 ?? \"x\ny\" |> f\"{a}é\" // end\n  ...rest";
+    let expected = Lexer::new(source.to_string()).get_tokens().unwrap();
+    let reader = BufReader::with_capacity(1, Trickle(source.as_bytes()));
+    assert_eq!(Lexer::from_reader(reader).get_tokens().unwrap(), expected);
+}
+
+#[test]
+fn errors_name_the_line_and_column_they_are_at() {
+    let mut source = script();
+    let line = source.matches('\n').count() + 3;
+    source.push_str("let ok = 1;\n\n  let bad = 2 $ 3\n");
+    let err = Lexer::from_reader(Cursor::new(source.clone().into_bytes()))
+        .get_tokens()
+        .unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::UnexpectedChar('$'));
+    assert_eq!((err.line, err.col), (line, 15));
+    assert_eq!(
+        err.to_string(),
+        format!("unexpected character `$` at line {}, col 15", line)
+    );
+    assert_eq!(Lexer::new(source).get_tokens().unwrap_err(), err);
+
+    let err = Lexer::from_reader(Cursor::new("1\n  \"a\\q\"".as_bytes()))
+        .get_tokens()
+        .unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::UnknownEscape('q'));
+    assert_eq!((err.offset, err.line, err.col), (6, 2, 5));
+}
+
+#[test]
+fn input_that_is_not_utf8_cannot_be_read() {
+    let err = Lexer::from_reader(Cursor::new(b"let x = 1;\nlet y = \"\xff\"".to_vec()))
+        .get_tokens()
+        .unwrap_err();
+    assert!(matches!(err.kind, LexErrorKind::ReadFailed(_)), "{:?}", err);
+    assert_eq!((err.line, err.col), (2, 1));
+}
+
+#[test]
+fn limits_apply_to_what_has_been_read() {
+    let options = LexerOptions {
+        max_input_len: 1 << 10,
+        ..LexerOptions::default()
+    };
+    let source = "x + 1\n".repeat(1 << 10);
+    let err = Lexer::from_reader_with_options(Cursor::new(source.into_bytes()), options)
+        .get_tokens()
+        .unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::InputTooLong(1 << 10));
+}
+
+#[test]
+fn the_parser_reads_from_a_streaming_lexer() {
+    let source = "let total = 0;\nfor x in [1, 2, 3] {\n  total += x\n};\ntotal";
+    let streamed = Parser::new(Lexer::from_reader(Cursor::new(source.as_bytes())))
+        .parse()
+        .unwrap();
+    let expected = parse(source);
+    assert_eq!(format!("{:?}", streamed), format!("{:?}", expected));
+    let empty = Lexer::from_reader(Cursor::new("".as_bytes()))
+        .get_tokens()
+        .unwrap();
+    assert_eq!(empty, Lexer::new(String::new()).get_tokens().unwrap());
+}
@@ -0,0 +1,64 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn is(source: &str, expected: bool) {
+    assert_eq!(eval(source), Ok(Value::Bool(expected)), "{}", source);
+}
+
+#[test]
+fn equality_is_exact() {
+    is(r#""abc" == "abc""#, true);
+    is(r#""abc" == "ABC""#, false);
+    is(r#""abc" != "ABC""#, true);
+    is(r#""abc" != "abc""#, false);
+    is(r#""" == """#, true);
+    is(r#""" == " ""#, false);
+}
+
+// Strings compare by Unicode code point, so every upper-case ASCII letter comes before
+// every lower-case one.
+#[test]
+fn ordering_is_by_code_point() {
+    is(r#""a" < "b""#, true);
+    is(r#""b" > "a""#, true);
+    is(r#""B" < "a""#, true);
+    is(r#""abc" < "abd""#, true);
+    is(r#""ab" < "abc""#, true);
+    is(r#""abc" <= "abc""#, true);
+    is(r#""abc" >= "abd""#, false);
+    is(r#""z" < "é""#, true);
+}
+
+#[test]
+fn the_empty_string_comes_first() {
+    is(r#""" < "a""#, true);
+    is(r#""" <= """#, true);
+    is(r#""" > "a""#, false);
+}
+
+#[test]
+fn strings_and_numbers_do_not_compare() {
+    for source in [r#""a" < 1"#, r#"1 >= "a""#, r#""1" <= 1"#] {
+        assert!(
+            matches!(eval(source), Err(EvalError::TypeMismatch(_))),
+            "{}",
+            source
+        );
+    }
+    // Equality across types is not an error, just false.
+    is(r#""1" == 1"#, false);
+    is(r#""1" != 1"#, true);
+}
+
+#[test]
+fn sort_uses_the_same_order() {
+    assert_eq!(
+        eval(r#"sort(["b", "", "B", "a", "ab"])"#)
+            .unwrap()
+            .to_string(),
+        r#"["", "B", "a", "ab", "b"]"#
+    );
+}
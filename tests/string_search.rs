@@ -0,0 +1,78 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+#[test]
+fn find_gives_a_byte_offset_or_null() {
+    assert_eq!(
+        eval(r#"find("hello world", "world")"#),
+        Ok(Value::Integer(6))
+    );
+    assert_eq!(eval(r#"find("héllo", "l")"#), Ok(Value::Integer(3)));
+    assert_eq!(eval(r#"find("hello", "z")"#), Ok(Value::Null));
+    assert_eq!(eval(r#"find("hello", "")"#), Ok(Value::Integer(0)));
+    assert_eq!(eval(r#"find("", "")"#), Ok(Value::Integer(0)));
+}
+
+#[test]
+fn replace_changes_the_first_occurrence_and_replace_all_every_one() {
+    assert_eq!(eval(r#"replace("aabbcc", "b", "x")"#), Ok(string("aaxbcc")));
+    assert_eq!(
+        eval(r#"replace_all("aabbcc", "b", "x")"#),
+        Ok(string("aaxxcc"))
+    );
+    assert_eq!(eval(r#"replace_all("aaaa", "aa", "b")"#), Ok(string("bb")));
+    assert_eq!(eval(r#"replace("abc", "z", "x")"#), Ok(string("abc")));
+    assert_eq!(eval(r#"replace("abc", "", "x")"#), Ok(string("abc")));
+    assert_eq!(eval(r#"replace_all("abc", "", "x")"#), Ok(string("abc")));
+}
+
+#[test]
+fn starts_with_and_ends_with() {
+    assert_eq!(eval(r#"starts_with("hello", "he")"#), Ok(Value::Bool(true)));
+    assert_eq!(
+        eval(r#"starts_with("hello", "lo")"#),
+        Ok(Value::Bool(false))
+    );
+    assert_eq!(eval(r#"ends_with("hello", "lo")"#), Ok(Value::Bool(true)));
+    assert_eq!(eval(r#"ends_with("hello", "")"#), Ok(Value::Bool(true)));
+    assert_eq!(eval(r#"ends_with("lo", "hello")"#), Ok(Value::Bool(false)));
+}
+
+#[test]
+fn only_strings_are_accepted() {
+    for (source, message) in [
+        (r#"find("abc", 1)"#, "find() expects strings, found number"),
+        (
+            r#"replace("abc", "a", null)"#,
+            "replace() expects strings, found null",
+        ),
+        (
+            r#"replace_all(["a"], "a", "b")"#,
+            "replace_all() expects strings, found list",
+        ),
+        (
+            "starts_with(1, 2)",
+            "starts_with() expects strings, found number",
+        ),
+        (
+            r#"ends_with("a", true)"#,
+            "ends_with() expects strings, found bool",
+        ),
+    ] {
+        assert_eq!(
+            eval(source),
+            Err(EvalError::TypeMismatch(message.to_string())),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        eval(r#"find("abc")"#),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to find".to_string()
+        ))
+    );
+}
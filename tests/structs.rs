@@ -0,0 +1,86 @@
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+const POINT: &str = "struct Point { x, y }; ";
+
+fn with_point(source: &str) -> Result<Value, EvalError> {
+    eval(&format!("{}{}", POINT, source))
+}
+
+#[test]
+fn fields_are_read_with_a_dot() {
+    assert_eq!(
+        with_point("let p = Point { x: 1, y: 2 }; p.x"),
+        Ok(Value::Integer(1))
+    );
+    assert_eq!(
+        with_point("let p = Point { y: 2, x: 1 }; p.x * 10 + p.y"),
+        Ok(Value::Integer(12))
+    );
+    assert_eq!(
+        with_point("Point { x: Point { x: 1, y: 2 }, y: 3 }.x.y"),
+        Ok(Value::Integer(2))
+    );
+}
+
+#[test]
+fn a_struct_value_has_its_type_name() {
+    let Ok(Value::Struct { type_name, fields }) = with_point("Point { x: 1, y: 2 }") else {
+        panic!("not a struct");
+    };
+    assert_eq!(type_name, "Point");
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields.get("y"), Some(&Value::Integer(2)));
+}
+
+#[test]
+fn unknown_fields_are_errors() {
+    assert_eq!(
+        with_point("Point { x: 1, y: 2 }.z"),
+        Err(EvalError::NoSuchField("Point".to_string(), "z".to_string()))
+    );
+    assert_eq!(
+        with_point("Point { x: 1, y: 2 }.z")
+            .unwrap_err()
+            .to_string(),
+        "struct Point has no field z"
+    );
+    assert!(matches!(
+        with_point("Point { x: 1, y: 2, z: 3 }"),
+        Err(EvalError::NoSuchField(..))
+    ));
+}
+
+#[test]
+fn literals_must_give_every_field_of_a_known_struct() {
+    assert_eq!(
+        with_point("Point { x: 1 }"),
+        Err(EvalError::MissingField(
+            "Point".to_string(),
+            "y".to_string()
+        ))
+    );
+    assert_eq!(
+        eval("Line { a: 1 }"),
+        Err(EvalError::UnknownStruct("Line".to_string()))
+    );
+}
+
+// A struct redefined with more fields makes values of the same name that differ.
+#[test]
+fn structs_with_different_fields_are_not_equal() {
+    assert_eq!(
+        eval(
+            "struct P { x }; let a = P { x: 1 }; struct P { x, y }; \
+             let b = P { x: 1, y: 2 }; [a == b, b == a, b == P { x: 1, y: 2 }]"
+        ),
+        Ok(Value::Array(vec![
+            Value::Bool(false),
+            Value::Bool(false),
+            Value::Bool(true),
+        ]))
+    );
+}
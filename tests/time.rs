@@ -0,0 +1,60 @@
+#![cfg(feature = "time")]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::eval;
+
+fn float(result: Result<Value, EvalError>) -> f64 {
+    match result {
+        Ok(Value::Float(x)) => x,
+        other => panic!("expected a float, got {:?}", other),
+    }
+}
+
+#[test]
+fn now_is_seconds_since_the_epoch() {
+    let before = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    let now = float(eval("now()"));
+    let after = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    assert!(before.as_secs_f64() <= now && now <= after.as_secs_f64());
+}
+
+#[test]
+fn sleep_blocks_for_that_many_seconds() {
+    let start = Instant::now();
+    assert_eq!(eval("sleep(0.05)"), Ok(Value::Null));
+    assert!(start.elapsed().as_secs_f64() >= 0.05);
+    assert_eq!(eval("sleep(0)"), Ok(Value::Null));
+}
+
+#[test]
+fn elapsed_ms_counts_from_the_interpreters_creation() {
+    let elapsed = float(eval(
+        "let start = elapsed_ms(); sleep(0.02); elapsed_ms() - start",
+    ));
+    assert!(elapsed >= 20.0, "{}", elapsed);
+    assert!(float(eval("elapsed_ms()")) >= 0.0);
+}
+
+#[test]
+fn sleep_needs_a_duration() {
+    assert_eq!(
+        eval("sleep(-1)"),
+        Err(EvalError::InvalidArgument(
+            "sleep(): cannot sleep for -1 seconds".to_string()
+        ))
+    );
+    assert!(matches!(
+        eval("sleep(-0.5)"),
+        Err(EvalError::InvalidArgument(_))
+    ));
+    assert_eq!(
+        eval("sleep(\"1\")"),
+        Err(EvalError::InvalidArgument(
+            "invalid arguments to sleep".to_string()
+        ))
+    );
+}
@@ -0,0 +1,47 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use interpreter::error::LexErrorKind;
+use interpreter::lexer::{Lexer, Token};
+use interpreter::parser::Parser;
+
+#[test]
+fn tokens_come_one_at_a_time_until_eof() {
+    let mut lexer = Lexer::new("let x = 1".to_string());
+    let mut next = || lexer.next().map(|token| token.unwrap().token);
+    assert_eq!(next(), Some(Token::Let));
+    assert_eq!(next(), Some(Token::Ident("x".to_string())));
+    assert_eq!(next(), Some(Token::Assign));
+    assert_eq!(next(), Some(Token::Integer(1)));
+    assert_eq!(next(), Some(Token::EOF));
+    assert_eq!(next(), None);
+    assert_eq!(next(), None);
+}
+
+#[test]
+fn the_stream_ends_at_the_first_error() {
+    let mut lexer = Lexer::new("1 @ 2 3".to_string());
+    assert_eq!(lexer.next().unwrap().unwrap().token, Token::Integer(1));
+    let err = lexer.next().unwrap().unwrap_err();
+    assert_eq!(err.kind, LexErrorKind::UnexpectedChar('@'));
+    assert!(lexer.next().is_none());
+}
+
+#[test]
+fn empty_input_is_just_eof() {
+    let tokens: Vec<Token> = Lexer::new("  ".to_string())
+        .map(|token| token.unwrap().token)
+        .collect();
+    assert_eq!(tokens, [Token::EOF]);
+}
+
+// The parser pulls tokens only as it needs them, so it stops reading at a syntax error.
+#[test]
+fn the_parser_reads_no_further_than_it_must() {
+    let pulled = Rc::new(Cell::new(0));
+    let counter = pulled.clone();
+    let source = format!("1 ) {}", "2 ".repeat(10_000));
+    let tokens = Lexer::new(source).inspect(move |_| counter.set(counter.get() + 1));
+    assert!(Parser::new(tokens).parse().is_err());
+    assert!(pulled.get() <= 3, "{} tokens read", pulled.get());
+}
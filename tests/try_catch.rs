@@ -0,0 +1,188 @@
+use interpreter::error::EvalError;
+use interpreter::lexer::Lexer;
+use interpreter::parser::Parser;
+use interpreter::pretty::pretty_print;
+use interpreter::{Error, Value};
+
+mod common;
+use common::{eval, parse};
+
+fn string(s: &str) -> Result<Value, EvalError> {
+    Ok(Value::Str(s.to_string()))
+}
+
+#[test]
+fn an_error_in_the_body_runs_the_handler() {
+    assert_eq!(
+        eval("try { 1/0 } catch(e) { \"caught\" }"),
+        string("caught")
+    );
+    assert_eq!(eval("try { 1/0 } catch { 2 }"), Ok(Value::Integer(2)));
+    assert_eq!(eval("try { 1 } catch e { 2 }"), Ok(Value::Integer(1)));
+    assert_eq!(
+        eval("try { int(\"x\") } catch e { -1 }"),
+        Ok(Value::Integer(-1))
+    );
+}
+
+#[test]
+fn the_nearest_try_catches() {
+    let source = r#"
+        try {
+            try { 1 / 0 } catch e { "inner" }
+        } catch e { "outer" }
+    "#;
+    assert_eq!(eval(source), string("inner"));
+    let source = r#"
+        try {
+            try { 1 / 0 } catch e { throw "again" }
+        } catch e { f"outer saw {e.message}" }
+    "#;
+    assert_eq!(eval(source), string("outer saw again"));
+}
+
+#[test]
+fn errors_cross_function_calls() {
+    let source = r#"
+        fn inner(x) { 10 / x }
+        fn outer(x) { inner(x) + 1 };
+        try { outer(0) } catch e { e.kind }
+    "#;
+    assert_eq!(eval(source), string("DivisionByZero"));
+    let source = r#"
+        fn check(x) { if x > 1 { throw("too big") }; x };
+        try { check(1) + check(2) } catch e { e.message }
+    "#;
+    assert_eq!(eval(source), string("too big"));
+}
+
+#[test]
+fn the_handler_does_not_run_without_an_error() {
+    let source = "
+        let handled = 0;
+        let x = try { 5 } catch e { handled = handled + 1; 0 };
+        [x, handled]
+    ";
+    assert_eq!(eval(source).unwrap().to_string(), "[5, 0]");
+}
+
+#[test]
+fn the_caught_error_has_a_message_and_kind() {
+    assert_eq!(
+        eval("try { 1 / 0 } catch (e) { e }"),
+        Ok(Value::Error {
+            message: "division by zero in `1 / 0`".to_string(),
+            kind: "DivisionByZero".to_string(),
+        })
+    );
+    assert_eq!(
+        eval("try { nope } catch e { [e.kind, e.message, type(e)] }"),
+        Ok(Value::Array(vec![
+            Value::Str("UndefinedVariable".to_string()),
+            Value::Str("undefined variable: nope".to_string()),
+            Value::Str("error".to_string()),
+        ]))
+    );
+    assert_eq!(
+        eval("try { nope } catch e { f\"failed: {e}\" }"),
+        string("failed: undefined variable: nope")
+    );
+}
+
+#[test]
+fn throw_raises_any_value() {
+    assert_eq!(
+        eval("throw \"boom\""),
+        Err(EvalError::Thrown {
+            message: "boom".to_string(),
+            kind: "Thrown".to_string(),
+        })
+    );
+    assert_eq!(
+        eval("try { throw 42 } catch e { [e.kind, e.message] }"),
+        Ok(Value::Array(vec![
+            Value::Str("Thrown".to_string()),
+            Value::Str("42".to_string()),
+        ]))
+    );
+    // The value extends as far as an expression can.
+    assert_eq!(
+        eval("try { throw 1 + 2 } catch e { e.message }"),
+        string("3")
+    );
+    assert_eq!(
+        eval("fn f(x) { if x < 0 { throw \"negative\" }; x }; f(1)"),
+        Ok(Value::Integer(1))
+    );
+}
+
+#[test]
+fn a_caught_error_can_be_thrown_again() {
+    assert_eq!(
+        eval("try { try { 1 / 0 } catch e { throw e } } catch outer { outer.kind }"),
+        string("DivisionByZero")
+    );
+    let err = eval("try { [1][5] } catch e { throw e }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "index 5 out of bounds for list of length 1"
+    );
+}
+
+#[test]
+fn finally_always_runs() {
+    assert_eq!(
+        eval("let log = []; let r = try { 1 } finally { log = [\"done\"] }; [r, log]"),
+        eval("[1, [\"done\"]]")
+    );
+    assert_eq!(
+        eval("let n = 0; try { 1 / 0 } catch e { n = 1 } finally { n = n + 10 }; n"),
+        Ok(Value::Integer(11))
+    );
+    // Without a handler the error still propagates, after `finally` has run.
+    assert_eq!(
+        eval("let n = 0; try { try { 1 / 0 } finally { n = 5 } } catch e { n }"),
+        Ok(Value::Integer(5))
+    );
+    // The value of `finally` is dropped, but its errors are not.
+    assert_eq!(eval("try { 1 } finally { 2 }"), Ok(Value::Integer(1)));
+    assert_eq!(
+        eval("try { 1 } finally { throw \"late\" }"),
+        Err(EvalError::Thrown {
+            message: "late".to_string(),
+            kind: "Thrown".to_string(),
+        })
+    );
+}
+
+#[test]
+fn needs_a_catch_or_finally() {
+    for source in [
+        "try { 1 }",
+        "try { 1 } catch (e { 2 }",
+        "try { 1 } catch () { 2 }",
+        "try { 1 } finally 2",
+        "throw",
+    ] {
+        let result = Parser::new(Lexer::new(source.to_string())).parse();
+        assert!(matches!(result, Err(Error::Parse(_))), "{}", source);
+    }
+}
+
+#[test]
+fn pretty_printing_round_trips() {
+    for source in [
+        "try {\n    1\n} catch e {\n    2\n}",
+        "try {\n    1\n} finally {\n    2\n}",
+        "try {\n    1\n} catch {\n    2\n} finally {\n    3\n}",
+        "throw \"boom\"",
+        "f(throw 1)",
+    ] {
+        let ast = parse(source);
+        assert_eq!(pretty_print(&ast), source);
+    }
+    let ast = Parser::new(Lexer::new("try { 1 } catch (e) { 2 }".to_string()))
+        .parse()
+        .unwrap();
+    assert_eq!(pretty_print(&ast), "try {\n    1\n} catch e {\n    2\n}");
+}
@@ -0,0 +1,45 @@
+use interpreter::ast::NodeKind;
+use interpreter::error::EvalError;
+use interpreter::Value;
+
+mod common;
+use common::{eval, parse};
+
+fn ints(values: &[i64]) -> Value {
+    Value::Tuple(values.iter().copied().map(Value::Integer).collect())
+}
+
+#[test]
+fn two_or_more_parenthesized_values_are_a_tuple() {
+    assert_eq!(eval("(1, 2)"), Ok(ints(&[1, 2])));
+    assert_eq!(eval("(1, 2, 3)"), Ok(ints(&[1, 2, 3])));
+    assert_eq!(eval("(1)"), Ok(Value::Integer(1)));
+    assert_eq!(eval("(1 + 1, \"a\")").unwrap().to_string(), "(2, \"a\")");
+    assert!(matches!(parse("(1)").kind, NodeKind::Integer(1)));
+}
+
+#[test]
+fn let_destructures_a_tuple() {
+    assert_eq!(
+        eval("let (a, b) = (1, 2); a * 10 + b"),
+        Ok(Value::Integer(12))
+    );
+    let source = "fn divmod(a, b) { (a / b, a % b) }; let (q, r) = divmod(7, 2); r";
+    assert_eq!(eval(source), Ok(Value::Integer(1)));
+}
+
+#[test]
+fn destructuring_needs_as_many_names_as_values() {
+    assert_eq!(
+        eval("let (a, b) = (1, 2, 3)"),
+        Err(EvalError::DestructureArity(2, 3))
+    );
+    assert_eq!(
+        eval("let (a, b, c) = (1, 2)"),
+        Err(EvalError::DestructureArity(3, 2))
+    );
+    assert_eq!(
+        eval("let (a, b) = (1, 2, 3)").unwrap_err().to_string(),
+        "cannot destructure a tuple of 3 values into 2 names"
+    );
+}
@@ -0,0 +1,68 @@
+use interpreter::Value;
+
+mod common;
+use common::{eval, string};
+
+// An expression for a value of every variant, with its type name.
+const VALUES: &[(&str, &str)] = &[
+    ("1", "number"),
+    ("1.5", "number"),
+    (r#""a""#, "string"),
+    ("true", "bool"),
+    ("[1]", "list"),
+    ("(1, 2)", "tuple"),
+    (r#"{"a": 1}"#, "map"),
+    ("{ struct P { x }; P { x: 1 } }", "struct"),
+    ("|x| x", "function"),
+    ("len", "function"),
+    ("try { 1 / 0 } catch e { e }", "error"),
+    ("null", "null"),
+];
+
+#[test]
+fn type_names_every_kind_of_value() {
+    for (source, name) in VALUES {
+        assert_eq!(
+            eval(&format!("type({})", source)),
+            Ok(string(name)),
+            "{}",
+            source
+        );
+    }
+}
+
+#[test]
+fn type_agrees_with_type_name() {
+    for (source, name) in VALUES {
+        assert_eq!(eval(source).unwrap().type_name(), *name, "{}", source);
+    }
+}
+
+#[test]
+fn the_predicates_agree_with_type() {
+    for (source, name) in VALUES {
+        for (predicate, type_name) in [
+            ("is_number", "number"),
+            ("is_string", "string"),
+            ("is_list", "list"),
+            ("is_fn", "function"),
+        ] {
+            assert_eq!(
+                eval(&format!("{}({})", predicate, source)),
+                Ok(Value::Bool(name == &type_name)),
+                "{}({})",
+                predicate,
+                source
+            );
+        }
+    }
+}
+
+#[test]
+fn scripts_can_branch_on_the_type() {
+    let source = r#"
+        fn describe(x) { if type(x) == "string" { "text" } else { "other" } };
+        [describe("a"), describe(1)]
+    "#;
+    assert_eq!(eval(source).unwrap().to_string(), r#"["text", "other"]"#);
+}
@@ -0,0 +1,254 @@
+// The tree-walking interpreter and the bytecode VM run the same programs.
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::thread;
+
+use interpreter::compiler::{self, CompiledFunction, Instruction, OpCode};
+use interpreter::error::{CompileError, EvalError};
+use interpreter::interpreter::{Function, Interpreter};
+use interpreter::vm::{Compiled, Vm};
+use interpreter::Value;
+
+mod common;
+use common::parse;
+
+fn tree_walk(source: &str) -> Result<Value, EvalError> {
+    Interpreter::new().interpret(&parse(source))
+}
+
+fn bytecode(source: &str) -> Result<Value, EvalError> {
+    let code = compiler::compile(&parse(source)).unwrap();
+    Vm::new().run(&code)
+}
+
+const PROGRAMS: [&str; 16] = [
+    "42",
+    "1.5 * 4 - 2",
+    "-(3 + 4) * 2 % 5",
+    "let x = 7; let y = x * 2; y - x",
+    "let x = 1; x = x + 1; x",
+    "true and not false",
+    "1 < 2 or 1 / 0 == 0",
+    "if 1 < 2 { 10 } else { 20 }",
+    "if 1 > 2 { 10 }",
+    "let x = 5; if x == 1 { 1 } else if x == 5 { 5 } else { 0 }",
+    "let i = 0; let total = 0; while i < 10 { total = total + i; i = i + 1 }; total",
+    "while false { 1 }",
+    "fn square(n) { n * n }; square(3) + square(4)",
+    "fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }; fact(10)",
+    "fn fib(n) { if n < 2 { n } else { fib(n - 1) + fib(n - 2) } }; fib(15)",
+    "let x = 1; { let x = 2; x = 3 }; x",
+];
+
+#[test]
+fn both_give_the_same_values() {
+    for source in PROGRAMS {
+        let expected = tree_walk(source);
+        assert!(expected.is_ok(), "{}: {:?}", source, expected);
+        assert_eq!(bytecode(source), expected, "{}", source);
+    }
+}
+
+// The tree walker recurses for each call of a runaway function, which needs more stack
+// than a test thread has.
+#[test]
+fn both_give_the_same_errors() {
+    let test = || {
+        for source in [
+            "1 / 0",
+            "let x = 0; 10 % x",
+            "y + 1",
+            "true + 1",
+            "fn f(n) { n / (n - 1) }; f(2) + f(1)",
+            "fn f(a, b) { a }; f(1)",
+            "missing(1)",
+            "fn f(n) { f(n + 1) }; f(0)",
+        ] {
+            let expected = tree_walk(source);
+            assert!(expected.is_err(), "{}: {:?}", source, expected);
+            assert_eq!(bytecode(source), expected, "{}", source);
+        }
+    };
+    thread::Builder::new()
+        .stack_size(256 << 20)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn if_and_while_jump_to_back_patched_targets() {
+    let code = compiler::compile(&parse("if true { 1 } else { 2 }")).unwrap();
+    let jumps: Vec<_> = code
+        .iter()
+        .filter_map(|instruction| match instruction {
+            Instruction::Jump(to) | Instruction::JumpIfFalse(to) => Some(*to),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(jumps.len(), 2);
+    assert!(jumps.iter().all(|&to| to > 0 && to < code.len()));
+    assert_eq!(code.last(), Some(&Instruction::Return));
+}
+
+#[test]
+fn a_compiled_formula_evaluates_with_the_variables_given() {
+    let f = Compiled::new(&parse("3 * x ^ 2 - x / 2 + 1")).unwrap();
+    let mut env = HashMap::new();
+    for (x, expected) in [(0, 1.0), (2, 12.0), (-1, 4.5)] {
+        env.insert("x".to_string(), Value::Integer(x));
+        assert_eq!(f.eval(&env), Ok(expected), "x = {}", x);
+    }
+    env.clear();
+    assert_eq!(
+        f.eval(&env),
+        Err(EvalError::UndefinedVariable("x".to_string()))
+    );
+    let f = Compiled::new(&parse("x < 1")).unwrap();
+    env.insert("x".to_string(), Value::Integer(0));
+    assert_eq!(
+        f.eval(&env),
+        Err(EvalError::TypeMismatch(
+            "expected a number, found bool".to_string()
+        ))
+    );
+}
+
+#[test]
+fn globals_persist_from_one_run_to_the_next() {
+    let mut vm = Vm::new();
+    vm.run(&compiler::compile(&parse("let x = 20")).unwrap())
+        .unwrap();
+    assert_eq!(
+        vm.run(&compiler::compile(&parse("x + 1")).unwrap()),
+        Ok(Value::Integer(21))
+    );
+}
+
+#[test]
+fn unsupported_nodes_are_a_compile_error() {
+    assert_eq!(
+        compiler::compile(&parse("[1, 2]")),
+        Err(CompileError::Unsupported("Array"))
+    );
+}
+
+fn run(code: &[Instruction]) -> Result<Value, EvalError> {
+    Vm::new().run(code)
+}
+
+fn int(n: i64) -> Instruction {
+    Instruction::LoadConst(Value::Integer(n))
+}
+
+#[test]
+fn binary_ops_pop_the_right_operand_first() {
+    assert_eq!(
+        run(&[
+            int(7),
+            int(2),
+            Instruction::BinaryOp(OpCode::Sub),
+            Instruction::Return
+        ]),
+        Ok(Value::Integer(5))
+    );
+    assert_eq!(
+        run(&[
+            int(2),
+            Instruction::UnaryOp(OpCode::Neg),
+            Instruction::Return
+        ]),
+        Ok(Value::Integer(-2))
+    );
+}
+
+#[test]
+fn jumps_set_the_instruction_pointer() {
+    assert_eq!(
+        run(&[Instruction::Jump(2), int(1), int(2), Instruction::Return]),
+        Ok(Value::Integer(2))
+    );
+    for (condition, expected) in [(true, 1), (false, 2)] {
+        let code = [
+            Instruction::LoadConst(Value::Bool(condition)),
+            Instruction::JumpIfFalse(4),
+            int(1),
+            Instruction::Jump(5),
+            int(2),
+            Instruction::Return,
+        ];
+        assert_eq!(run(&code), Ok(Value::Integer(expected)));
+    }
+    assert_eq!(
+        run(&[int(0), Instruction::JumpIfFalse(0), Instruction::Return]),
+        Err(EvalError::TypeMismatch(
+            "condition must be a boolean, found 0".to_string()
+        ))
+    );
+}
+
+#[test]
+fn a_call_runs_in_its_own_frame_until_it_returns() {
+    let square = CompiledFunction {
+        name: Some("square".to_string()),
+        params: vec!["n".to_string()],
+        code: vec![
+            Instruction::LoadVar("n".to_string()),
+            Instruction::LoadVar("n".to_string()),
+            Instruction::BinaryOp(OpCode::Mul),
+            Instruction::Return,
+        ],
+    };
+    let code = [
+        Instruction::LoadConst(Value::Function(Function::Compiled(Rc::new(square)))),
+        int(6),
+        Instruction::Call(1),
+        int(1),
+        Instruction::BinaryOp(OpCode::Add),
+        Instruction::Return,
+    ];
+    let mut vm = Vm::new();
+    assert_eq!(vm.run(&code), Ok(Value::Integer(37)));
+    assert!(vm.call_stack.is_empty());
+    assert!(vm.stack.is_empty());
+    // The parameter was local to the call.
+    assert!(!vm.globals.contains_key("n"));
+}
+
+// Frames live on the VM's call stack, not Rust's, so deep recursion needs no native
+// stack, only a depth limit that allows it.
+#[test]
+fn deep_recursion_does_not_overflow() {
+    let source = "fn down(n) { if n == 0 { 0 } else { down(n - 1) } }; down(100000)";
+    let code = compiler::compile(&parse(source)).unwrap();
+    assert_eq!(
+        Vm::new().with_max_depth(100_001).run(&code),
+        Ok(Value::Integer(0))
+    );
+    assert_eq!(Vm::new().run(&code), Err(EvalError::TooDeep(2_000)));
+}
+
+#[test]
+fn malformed_code_is_an_error() {
+    let invalid = |msg: &str| Err(EvalError::InvalidBytecode(msg.to_string()));
+    assert_eq!(run(&[]), invalid("no instruction at 0"));
+    assert_eq!(run(&[int(1)]), invalid("no instruction at 1"));
+    assert_eq!(
+        run(&[Instruction::Jump(5), Instruction::Return]),
+        invalid("no instruction at 5")
+    );
+    assert_eq!(
+        run(&[int(1), Instruction::Pop, Instruction::Pop]),
+        invalid("stack underflow at 2")
+    );
+    assert_eq!(run(&[Instruction::Return]), invalid("stack underflow at 0"));
+    assert_eq!(
+        run(&[int(1), Instruction::Call(3), Instruction::Return]),
+        invalid("stack underflow at 1")
+    );
+    assert_eq!(
+        run(&[Instruction::StoreVar("x".to_string())]),
+        invalid("stack underflow at 0")
+    );
+}